@@ -0,0 +1,174 @@
+// Annotates replays with each player's chance of winning as the game goes, chess-engine
+// style, to show when a game was decided.
+//
+// The annotator reads a replay and, after the state recorded at the start of the game and of
+// every turn, inserts an evaluation: the share of fast rollouts from that state each player won.
+// Rollouts see the whole recorded state, including hidden preferences, but reshuffle the decks
+// so they do not follow the recorded draws. Every seat is played by the rollout strategy, and
+// the game rules must be the ones the replay was recorded under.
+//
+// Alongside the annotated events, each game gets a summary: the turn from which one player's
+// chance stayed above DECIDED, and the turn whose evaluation moved the most.
+use hedonica_engine::game::{GameResult, GameRules, GameState};
+use hedonica_engine::lookahead::{GameEngine, Snapshot};
+use hedonica_engine::replay::ReplayEvent;
+use rand::prelude::*;
+use serde::Serialize;
+
+const DECIDED: f64 = 0.9;
+
+pub struct Annotator<'a> {
+    rules: &'a GameRules,
+    rollouts: u32,
+    rollout_strategy: String,
+    rng: StdRng,
+    rollout_engine: Option<GameEngine>,
+}
+
+#[derive(Serialize)]
+pub struct Swing {
+    pub turn: i32,
+    pub player: usize,
+    // The change in the player's chance of winning since the previous evaluation.
+    pub change: f64,
+}
+
+#[derive(Serialize)]
+pub struct GameSummary {
+    pub game: u32,
+    pub decided_turn: Option<i32>,
+    pub biggest_swing: Option<Swing>,
+}
+
+impl<'a> Annotator<'a> {
+    pub fn new(
+        rules: &'a GameRules,
+        rollouts: u32,
+        rollout_strategy: &str,
+        seed: Option<u64>,
+    ) -> Annotator<'a> {
+        let rng = match seed {
+            None => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+            Some(seed) => SeedableRng::seed_from_u64(seed),
+        };
+        Annotator {
+            rules,
+            rollouts,
+            rollout_strategy: rollout_strategy.to_string(),
+            rng,
+            rollout_engine: None,
+        }
+    }
+
+    // The share of rollouts from this state each player wins.
+    pub fn win_probabilities(&mut self, state: &GameState) -> Vec<f64> {
+        let num_players = state.players.len();
+        if self
+            .rollout_engine
+            .as_ref()
+            .is_none_or(|engine| engine.state().players.len() != num_players)
+        {
+            // The command line checks the strategy is registered.
+            self.rollout_engine = Some(
+                GameEngine::with_policy(self.rules, &self.rollout_strategy, state.clone())
+                    .unwrap_or_else(|error| panic!("{}", error)),
+            );
+        }
+
+        let engine = self.rollout_engine.as_mut().unwrap();
+        let mut wins = vec![0; num_players];
+        for _ in 0..self.rollouts {
+            engine.reset(Snapshot::from(state.with_shuffled_decks(&mut self.rng)));
+            let outcome = engine.play_out().clone();
+            wins[GameResult::from_state(self.rules, outcome).winner] += 1;
+        }
+        wins.iter()
+            .map(|&w| w as f64 / self.rollouts.max(1) as f64)
+            .collect()
+    }
+
+    // The replay with evaluations inserted, and a summary of each game.
+    pub fn annotate(&mut self, events: Vec<ReplayEvent>) -> (Vec<ReplayEvent>, Vec<GameSummary>) {
+        let mut annotated = Vec::with_capacity(events.len());
+        let mut summaries = Vec::new();
+        let mut evaluations: Vec<(i32, Vec<f64>)> = Vec::new();
+        let mut game_number = 0;
+        for event in events {
+            let evaluated = match &event {
+                // From an earlier annotation pass.
+                ReplayEvent::Evaluation { .. } => continue,
+                ReplayEvent::GameStart { game, state } => {
+                    game_number = *game;
+                    evaluations.clear();
+                    Some((state.current_turn, self.win_probabilities(state)))
+                }
+                ReplayEvent::TurnStart { state } => {
+                    Some((state.current_turn, self.win_probabilities(state)))
+                }
+                ReplayEvent::GameEnd { winner, state, .. } => {
+                    let mut certain = vec![0.; state.players.len()];
+                    certain[*winner] = 1.;
+                    Some((state.current_turn, certain))
+                }
+                _ => None,
+            };
+            let game_ended = matches!(event, ReplayEvent::GameEnd { .. });
+            annotated.push(event);
+            if let Some((turn, win_probabilities)) = evaluated {
+                annotated.push(ReplayEvent::Evaluation {
+                    turn,
+                    rollouts: self.rollouts,
+                    win_probabilities: win_probabilities.clone(),
+                });
+                evaluations.push((turn, win_probabilities));
+            }
+            if game_ended {
+                summaries.push(summarize(game_number, &evaluations));
+            }
+        }
+        (annotated, summaries)
+    }
+}
+
+fn summarize(game: u32, evaluations: &[(i32, Vec<f64>)]) -> GameSummary {
+    let leader = |probabilities: &Vec<f64>| {
+        probabilities
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(player, &p)| (player, p))
+            .unwrap()
+    };
+    // The first evaluation from which the final winner's chance never drops below DECIDED.
+    let decided_turn = evaluations.last().and_then(|(_, last)| {
+        let (winner, _) = leader(last);
+        let undecided = evaluations
+            .iter()
+            .rposition(|(_, probabilities)| probabilities[winner] < DECIDED);
+        let first_decided = undecided.map_or(0, |i| i + 1);
+        evaluations.get(first_decided).map(|(turn, _)| *turn)
+    });
+
+    let biggest_swing = evaluations
+        .windows(2)
+        .flat_map(|pair| {
+            let (turn, after) = &pair[1];
+            let before = &pair[0].1;
+            after
+                .iter()
+                .zip(before)
+                .enumerate()
+                .map(move |(player, (a, b))| Swing {
+                    turn: *turn,
+                    player,
+                    change: a - b,
+                })
+        })
+        .max_by(|a, b| a.change.abs().partial_cmp(&b.change.abs()).unwrap());
+
+    GameSummary {
+        game,
+        decided_turn,
+        biggest_swing,
+    }
+}
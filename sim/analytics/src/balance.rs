@@ -0,0 +1,118 @@
+// Balance regression scenarios, for the balance-check subcommand.
+//
+// Each scenario is a seeded batch of games with tolerance bands on its key metrics. A rules or
+// engine change that moves a metric outside its band fails the check, so shifts in balance are
+// flagged instead of going unnoticed. The checked-in scenarios live in balance.json5, next to
+// Cargo.toml. When a change is meant to shift balance, rerun the check and update the bands.
+//
+// Scenarios are played with batch::play, so their nonzero seeds are offset by the run number.
+// Strategies that draw their own random numbers still vary between checks, which the bands
+// must allow for.
+//
+//   seat_skew  - The highest seat win rate minus the lowest.
+//   mean_turns - Mean game length, in turns.
+//   win_rates  - Win rate by seat, e.g. for a baseline bot against others.
+use crate::batch::{self, BatchMetrics};
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameRules, SimConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_SCENARIOS: &str = include_str!("../balance.json5");
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Band {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl Band {
+    pub(crate) fn contains(&self, x: f64) -> bool {
+        self.min.is_none_or(|min| x >= min) && self.max.is_none_or(|max| x <= max)
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct Bands {
+    #[serde(default)]
+    pub seat_skew: Option<Band>,
+    #[serde(default)]
+    pub mean_turns: Option<Band>,
+    #[serde(default)]
+    pub win_rates: BTreeMap<usize, Band>,
+}
+
+#[derive(Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub sim_config: SimConfig,
+    #[serde(default = "default_game_rules")]
+    pub game_rules: GameRules,
+    #[serde(default)]
+    pub bands: Bands,
+}
+
+fn default_game_rules() -> GameRules {
+    serde_json::from_str("{}").unwrap()
+}
+
+#[derive(Serialize)]
+pub struct Violation {
+    pub metric: String,
+    pub value: f64,
+    pub band: Band,
+}
+
+#[derive(Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub passed: bool,
+    pub metrics: BatchMetrics,
+    pub violations: Vec<Violation>,
+}
+
+pub fn parse_scenarios(text: &str) -> Result<Vec<Scenario>> {
+    // Go through serde_json, which reads the quoted player numbers in win_rates.
+    let value: serde_json::Value =
+        json5::from_str(text).map_err(|error| HedonicaError::parse("balance scenarios", error))?;
+    serde_json::from_value(value).map_err(|error| HedonicaError::parse("balance scenarios", error))
+}
+
+pub fn check(scenario: &mut Scenario) -> Result<ScenarioReport> {
+    let metrics = batch::play(&mut scenario.sim_config, &scenario.game_rules)?;
+    let bands = &scenario.bands;
+    let mut checks: Vec<(String, f64, &Band)> = Vec::new();
+    if let Some(band) = &bands.seat_skew {
+        checks.push((String::from("seat_skew"), metrics.seat_skew, band));
+    }
+    if let Some(band) = &bands.mean_turns {
+        checks.push((String::from("mean_turns"), metrics.mean_turns, band));
+    }
+    for (player, band) in &bands.win_rates {
+        let rate = *metrics.win_rates.get(player).ok_or_else(|| {
+            HedonicaError::parse(
+                &format!("scenario {}", scenario.name),
+                format!("it has no player {}", player),
+            )
+        })?;
+        checks.push((format!("win_rates.{}", player), rate, band));
+    }
+
+    let violations: Vec<Violation> = checks
+        .into_iter()
+        .filter(|(_, value, band)| !band.contains(*value))
+        .map(|(metric, value, band)| Violation {
+            metric,
+            value,
+            band: band.clone(),
+        })
+        .collect();
+    Ok(ScenarioReport {
+        name: scenario.name.clone(),
+        passed: violations.is_empty(),
+        metrics,
+        violations,
+    })
+}
@@ -0,0 +1,148 @@
+// Plays a batch of games outside the main simulator loop and summarizes it, for subcommands
+// that compare many batches, such as balance-check and sweep.
+//
+// Every run is dealt from its own seeds, from a SeedSchedule, so every game gets a different
+// but reproducible deal. The batch stops early if the config's early_stopping region says so.
+// Games that abort, e.g. because a strategy panicked, are counted in aborted and left out of
+// the other metrics.
+use hedonica_engine::advantage;
+use hedonica_engine::error::Result;
+use hedonica_engine::game::{self, GameRules, SimConfig};
+use hedonica_engine::player::load_strategies;
+use hedonica_engine::rng::SeedSchedule;
+use hedonica_engine::sampling::PreferenceDealer;
+use hedonica_engine::series::{MatchState, MatchTally};
+use hedonica_engine::stats::Stats;
+use hedonica_engine::timing;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize)]
+pub struct BatchMetrics {
+    pub runs: u64,
+    // The highest seat win rate minus the lowest.
+    pub seat_skew: f64,
+    pub mean_turns: f64,
+    pub turns_var: f64,
+    // The turns 90% of games finish within, for the long games the mean hides.
+    #[serde(default)]
+    pub p90_turns: f64,
+    // Trading rounds per turn, in standard turns.
+    #[serde(default)]
+    pub rounds_per_turn: f64,
+    // Completed trades per game.
+    #[serde(default)]
+    pub mean_trades: f64,
+    #[serde(default)]
+    pub trades_var: f64,
+    // The mean spread of players' advantage indices per deal. See advantage.rs in the engine.
+    #[serde(default)]
+    pub mean_advantage_spread: f64,
+    // With match_format, how often the match winner was behind after the first game.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comeback_rate: Option<f64>,
+    pub win_rates: BTreeMap<usize, f64>,
+    #[serde(default)]
+    pub aborted: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped_early: Option<String>,
+}
+
+impl BatchMetrics {
+    pub fn max_win_rate(&self) -> f64 {
+        self.win_rates.values().cloned().fold(0., f64::max)
+    }
+
+    // The share of attempted games that aborted.
+    pub fn abort_rate(&self) -> f64 {
+        self.aborted as f64 / (self.runs + self.aborted).max(1) as f64
+    }
+}
+
+pub fn play(config: &mut SimConfig, rules: &GameRules) -> Result<BatchMetrics> {
+    let seeds = SeedSchedule::new(config);
+
+    let mut players = load_strategies(config, rules)?;
+    timing::instrument(&mut players, config);
+    let mut dealer = PreferenceDealer::new(config, rules)?;
+    let mut wins_by_player: BTreeMap<usize, i32> =
+        (0..config.num_players).map(|p| (p, 0)).collect();
+    let mut turn_stats = Stats::with_quantiles();
+    let mut stopped_early = None;
+    let mut aborted = 0;
+    let mut trade_rounds = 0;
+    let mut trade_stats = Stats::default();
+    let mut advantage_spreads = Stats::default();
+    let mut match_state = MatchState::default();
+    let mut matches = MatchTally::default();
+    for hook in &config.hooks {
+        hook.on_batch_start(rules);
+    }
+    for run in 0..config.num_runs {
+        seeds.seeds(run).apply(config);
+        let mut game = match dealer
+            .deal(config, rules, run)
+            .and_then(|deal| game::generate_start_state(config, rules, deal.preferences))
+        {
+            Ok(game) => game,
+            Err(error) => {
+                seeds.restore(config);
+                return Err(error);
+            }
+        };
+        if config.match_format.is_some() {
+            match_state.start_game(&mut game);
+        }
+        let game_result = match game::try_play(config, rules, game, &mut players) {
+            Ok(game_result) => game_result,
+            Err(_) => {
+                aborted += 1;
+                continue;
+            }
+        };
+        *wins_by_player.entry(game_result.winner).or_insert(0) += 1;
+        turn_stats.add(game_result.turns as f64);
+        trade_rounds += game_result.pacing.trade_rounds;
+        trade_stats.add(game_result.liquidity.trades as f64);
+        advantage_spreads.add(advantage::spread(&game_result.advantage));
+        if let Some(format) = &config.match_format {
+            if let Some(match_result) = match_state.add(config, format, &game_result) {
+                matches.add(&match_result);
+            }
+        }
+
+        if let Some(early_stopping) = &config.early_stopping {
+            stopped_early = early_stopping.check(&turn_stats, &wins_by_player);
+            if stopped_early.is_some() {
+                break;
+            }
+        }
+    }
+    seeds.restore(config);
+
+    let runs = turn_stats.len();
+    let win_rates: BTreeMap<usize, f64> = wins_by_player
+        .into_iter()
+        .map(|(player, wins)| (player, wins as f64 / runs.max(1) as f64))
+        .collect();
+    let highest = win_rates.values().cloned().fold(f64::MIN, f64::max);
+    let lowest = win_rates.values().cloned().fold(f64::MAX, f64::min);
+    Ok(BatchMetrics {
+        runs,
+        seat_skew: highest - lowest,
+        mean_turns: turn_stats.mean(),
+        turns_var: turn_stats.var(),
+        p90_turns: turn_stats.p90().unwrap_or(f64::NAN),
+        rounds_per_turn: trade_rounds as f64 / (turn_stats.mean() * runs as f64).max(1.),
+        mean_trades: trade_stats.mean(),
+        trades_var: trade_stats.var(),
+        mean_advantage_spread: advantage_spreads.mean(),
+        comeback_rate: config
+            .match_format
+            .as_ref()
+            .map(|_| matches.comeback_rate()),
+        win_rates,
+        aborted,
+        stopped_early,
+    })
+}
@@ -0,0 +1,111 @@
+// A cache of batch results on disk, so repeated sweep cells and tournament seatings, and reruns
+// of the same command, reuse their metrics instead of playing the games again.
+//
+// Results are keyed by a hash of the sim config, including the player configs, the game rules
+// and the build of the simulator. The build is identified by the crate version and a hash of
+// the running executable, so any rebuild invalidates the cache. Batches without a master seed
+// whose deck or preference seed is 0 are seeded from the OS and never cached, since rerunning
+// them is meant to give new games. Strategies that draw their own random numbers are cached
+// as they played the first time.
+//
+// Each result is a JSON file named by its key in the cache directory. Deleting the directory
+// clears the cache.
+use crate::batch::{self, BatchMetrics};
+use hedonica_engine::error;
+use hedonica_engine::game::{GameRules, SimConfig};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_DIR: &str = ".sim-cache";
+
+pub struct ResultCache {
+    // None when caching is off.
+    dir: Option<PathBuf>,
+    build: String,
+}
+
+// 64-bit FNV-1a, which is stable between builds, unlike std's hasher.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn build_id() -> String {
+    let executable = std::env::current_exe()
+        .and_then(fs::read)
+        .map_or(0, |bytes| fnv1a(&bytes));
+    format!("{}-{:016x}", env!("CARGO_PKG_VERSION"), executable)
+}
+
+impl ResultCache {
+    pub fn disabled() -> ResultCache {
+        ResultCache {
+            dir: None,
+            build: String::new(),
+        }
+    }
+
+    pub fn open(dir: &Path) -> ResultCache {
+        ResultCache {
+            dir: Some(dir.to_path_buf()),
+            build: build_id(),
+        }
+    }
+
+    fn path(&self, kind: &str, key: &impl Serialize) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        // Values serialize with sorted keys, so equal inputs give equal text.
+        let key = serde_json::json!({ "kind": kind, "build": self.build, "key": key });
+        let hash = fnv1a(key.to_string().as_bytes());
+        Some(dir.join(format!("{}-{:016x}.json", kind, hash)))
+    }
+
+    // The cached result for the key, or the computed one, which is then cached unless it failed.
+    // A cache that cannot be read or written is skipped with a warning.
+    pub fn get_or_insert_with<T: Serialize + DeserializeOwned, E>(
+        &self,
+        kind: &str,
+        key: &impl Serialize,
+        compute: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        let path = match self.path(kind, key) {
+            Some(path) => path,
+            None => return compute(),
+        };
+        if let Ok(text) = fs::read_to_string(&path) {
+            match serde_json::from_str(&text) {
+                Ok(value) => return Ok(value),
+                Err(error) => {
+                    hedonica_engine::warn!("Ignoring cached result {}: {}", path.display(), error)
+                }
+            }
+        }
+        let value = compute()?;
+        let written = fs::create_dir_all(path.parent().unwrap())
+            .and_then(|_| fs::write(&path, serde_json::to_string(&value).unwrap()));
+        if let Err(error) = written {
+            hedonica_engine::warn!("Could not cache result in {}: {}", path.display(), error);
+        }
+        Ok(value)
+    }
+
+    // batch::play, reusing a cached result for the same config and rules.
+    pub fn play_batch(
+        &self,
+        config: &mut SimConfig,
+        rules: &GameRules,
+    ) -> error::Result<BatchMetrics> {
+        if config.seed.is_none() && (config.deck_shuffle_seed == 0 || config.preferences_seed == 0)
+        {
+            return batch::play(config, rules);
+        }
+        // Pacing and showing games don't change results, so they are left out of the key.
+        config.turn_pause_millis = None;
+        config.hide_game_state = true;
+        let key = serde_json::json!({ "sim_config": &*config, "game_rules": rules });
+        self.get_or_insert_with("batch", &key, || batch::play(config, rules))
+    }
+}
@@ -0,0 +1,178 @@
+// Outcomes grouped by starting conditions, with --cohort-by, to quantify setups that looked
+// unfair in playtests, e.g. "player 0 was dealt the art-heavy card", which also leads first.
+//
+// Games are grouped by a list of keys, comma-separated on the command line:
+//   top:<seat>        - The category the seat values most, e.g. top:0=art.
+//   card:<seat>       - The seat's whole preference card, most valued category first.
+//   favoring:<cat>    - The seats whose most valued category is cat, e.g. favoring:art=[0, 2].
+//   stratum           - The preference stratum of the deal, with stratified sampling.
+// A cohort is one combination of values, and the summary gives each cohort's sample count,
+// win rate by seat with its interval, mean turns and mean score by seat. Cohorts with few
+// games have wide intervals, which is the point of reporting them.
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameResult, GameState};
+use hedonica_engine::stats::{self, Stats};
+use hedonica_engine::types::{PlayerId, Preferences};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize)]
+pub enum CohortKey {
+    Top(PlayerId),
+    Card(PlayerId),
+    Favoring(String),
+    Stratum,
+}
+
+impl CohortKey {
+    fn name(&self) -> String {
+        match self {
+            CohortKey::Top(seat) => format!("top:{}", seat),
+            CohortKey::Card(seat) => format!("card:{}", seat),
+            CohortKey::Favoring(category) => format!("favoring:{}", category),
+            CohortKey::Stratum => String::from("stratum"),
+        }
+    }
+
+    fn value(&self, game: &GameState, stratum: Option<&str>) -> String {
+        match self {
+            CohortKey::Top(seat) => ranked(game.players[*seat].preferences())[0].clone(),
+            CohortKey::Card(seat) => {
+                let preferences = game.players[*seat].preferences();
+                let card: Vec<String> = ranked(preferences)
+                    .iter()
+                    .map(|category| format!("{}:{}", category, preferences[category]))
+                    .collect();
+                card.join(" ")
+            }
+            CohortKey::Favoring(category) => {
+                let seats: Vec<PlayerId> = (0..game.players.len())
+                    .filter(|&seat| ranked(game.players[seat].preferences())[0] == *category)
+                    .collect();
+                format!("{:?}", seats)
+            }
+            CohortKey::Stratum => stratum.unwrap_or("none").to_string(),
+        }
+    }
+}
+
+// The goods categories by value, most valued first, ties by name.
+fn ranked(preferences: &Preferences) -> Vec<String> {
+    let mut categories: Vec<&String> = preferences
+        .keys()
+        .filter(|category| *category != "money")
+        .collect();
+    categories.sort_by(|a, b| {
+        preferences[*b]
+            .partial_cmp(&preferences[*a])
+            .unwrap()
+            .then(a.cmp(b))
+    });
+    categories.into_iter().cloned().collect()
+}
+
+pub fn parse_keys(text: &str, num_players: usize) -> Result<Vec<CohortKey>> {
+    let invalid = |spec: &str, expected: &str| {
+        HedonicaError::parse(
+            &format!("cohort key {}", spec),
+            format!("expected {}", expected),
+        )
+    };
+    let seat = |spec: &str, seat: &str| -> Result<PlayerId> {
+        seat.parse()
+            .ok()
+            .filter(|&seat| seat < num_players)
+            .ok_or_else(|| invalid(spec, "the number of a seat in the game"))
+    };
+    text.split(',')
+        .map(str::trim)
+        .map(|spec| match spec.split_once(':') {
+            Some(("top", s)) => Ok(CohortKey::Top(seat(spec, s)?)),
+            Some(("card", s)) => Ok(CohortKey::Card(seat(spec, s)?)),
+            Some(("favoring", category)) => Ok(CohortKey::Favoring(category.to_string())),
+            None if spec == "stratum" => Ok(CohortKey::Stratum),
+            _ => Err(invalid(
+                spec,
+                "top:<seat>, card:<seat>, favoring:<category> or stratum",
+            )),
+        })
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Cohort {
+    games: u64,
+    wins: BTreeMap<PlayerId, u64>,
+    #[serde(with = "stats::state")]
+    turns: Stats,
+    #[serde(with = "stats::state_vec")]
+    scores: Vec<Stats>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Cohorts {
+    keys: Vec<CohortKey>,
+    cohorts: BTreeMap<String, Cohort>,
+}
+
+impl Cohorts {
+    pub fn new(keys: Vec<CohortKey>) -> Cohorts {
+        Cohorts {
+            keys,
+            cohorts: BTreeMap::new(),
+        }
+    }
+
+    // The cohort of a game, from its start state, before it is played.
+    pub fn label(&self, game: &GameState, stratum: Option<&str>) -> String {
+        let values: Vec<String> = self
+            .keys
+            .iter()
+            .map(|key| format!("{}={}", key.name(), key.value(game, stratum)))
+            .collect();
+        values.join(", ")
+    }
+
+    pub fn add(&mut self, label: String, result: &GameResult) {
+        let cohort = self.cohorts.entry(label).or_default();
+        cohort.games += 1;
+        *cohort.wins.entry(result.winner).or_insert(0) += 1;
+        cohort.turns.add(result.turns as f64);
+        cohort
+            .scores
+            .resize_with(result.scores.len(), Stats::default);
+        for (seat, &score) in result.scores.iter().enumerate() {
+            cohort.scores[seat].add(score);
+        }
+    }
+
+    pub fn summary(&self, level: f64) -> serde_json::Value {
+        let cohorts: BTreeMap<&String, serde_json::Value> = self
+            .cohorts
+            .iter()
+            .map(|(label, cohort)| {
+                let games = cohort.games as f64;
+                let win_rates: BTreeMap<PlayerId, serde_json::Value> = (0..cohort.scores.len())
+                    .map(|seat| {
+                        let wins = cohort.wins.get(&seat).copied().unwrap_or(0) as f64;
+                        let (low, high) = stats::rate_interval(wins, games, level);
+                        (
+                            seat,
+                            serde_json::json!({ "rate": wins / games, "low": low, "high": high }),
+                        )
+                    })
+                    .collect();
+                let mean_scores: Vec<f64> = cohort.scores.iter().map(Stats::mean).collect();
+                let summary = serde_json::json!({
+                    "games": cohort.games,
+                    "win_rates": win_rates,
+                    "mean_turns": cohort.turns.mean(),
+                    "mean_scores": mean_scores,
+                });
+                (label, summary)
+            })
+            .collect();
+        let keys: Vec<String> = self.keys.iter().map(CohortKey::name).collect();
+        serde_json::json!({ "cohorts": { "by": keys, "confidence": level, "groups": cohorts } })
+    }
+}
@@ -0,0 +1,104 @@
+// Side-by-side comparison of rule sets, for the compare-rules subcommand.
+//
+// Every rule set plays a batch of num_runs games with batch::play, with the same sim config and
+// strategies. Without a master seed in the sim config, one is drawn for the comparison, so the
+// nth game under every rule set is dealt from the same seeds and the rules are the only
+// difference. Batches already in the result cache are not played again.
+//
+// Every other rule set is compared with the first, the baseline, on:
+//   seat win rates - a chi-squared test of whether wins are spread over the seats the same way
+//                    under both, with the change in seat_skew.
+//   turns          - Welch's test of the difference in mean turns per game.
+//   trades         - Welch's test of the difference in mean completed trades per game.
+// Small p-values mean a difference is unlikely to be noise. With many rule sets, some small
+// p-values are expected by chance alone.
+use crate::batch::BatchMetrics;
+use crate::cache::ResultCache;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameRules, SimConfig};
+use hedonica_engine::stats;
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct RulesResult {
+    pub name: String,
+    pub metrics: BatchMetrics,
+}
+
+// How `variant` differs from `baseline`, as variant minus baseline.
+#[derive(Serialize)]
+pub struct RulesDiff {
+    pub baseline: String,
+    pub variant: String,
+    pub seat_skew_change: f64,
+    pub seat_wins_p_value: f64,
+    pub mean_turns_change: f64,
+    pub turns_p_value: f64,
+    pub mean_trades_change: f64,
+    pub trades_p_value: f64,
+}
+
+#[derive(Serialize)]
+pub struct RulesComparison {
+    pub seed: u64,
+    pub rules: Vec<RulesResult>,
+    pub diffs: Vec<RulesDiff>,
+}
+
+fn wins_by_seat(metrics: &BatchMetrics) -> Vec<u64> {
+    metrics
+        .win_rates
+        .values()
+        .map(|rate| (rate * metrics.runs as f64).round() as u64)
+        .collect()
+}
+
+fn diff(baseline: &RulesResult, variant: &RulesResult) -> RulesDiff {
+    let (a, b) = (&baseline.metrics, &variant.metrics);
+    RulesDiff {
+        baseline: baseline.name.clone(),
+        variant: variant.name.clone(),
+        seat_skew_change: b.seat_skew - a.seat_skew,
+        seat_wins_p_value: stats::homogeneity_p_value(&[wins_by_seat(a), wins_by_seat(b)]),
+        mean_turns_change: b.mean_turns - a.mean_turns,
+        turns_p_value: stats::mean_difference_p_value(
+            (a.mean_turns, a.turns_var, a.runs),
+            (b.mean_turns, b.turns_var, b.runs),
+        ),
+        mean_trades_change: b.mean_trades - a.mean_trades,
+        trades_p_value: stats::mean_difference_p_value(
+            (a.mean_trades, a.trades_var, a.runs),
+            (b.mean_trades, b.trades_var, b.runs),
+        ),
+    }
+}
+
+// Plays every named rule set, the baseline first.
+pub fn run(
+    config: &mut SimConfig,
+    rules: &[(String, GameRules)],
+    cache: &ResultCache,
+) -> Result<RulesComparison> {
+    if rules.len() < 2 {
+        return Err(HedonicaError::Unsupported {
+            message: String::from("comparing rules needs at least two rule sets"),
+        });
+    }
+    let seed = *config
+        .seed
+        .get_or_insert_with(|| rand::random::<u64>().max(1));
+    let rules = rules
+        .iter()
+        .map(|(name, rules)| {
+            Ok(RulesResult {
+                name: name.clone(),
+                metrics: cache.play_batch(config, rules)?,
+            })
+        })
+        .collect::<Result<Vec<RulesResult>>>()?;
+    let diffs = rules[1..]
+        .iter()
+        .map(|variant| diff(&rules[0], variant))
+        .collect();
+    Ok(RulesComparison { seed, rules, diffs })
+}
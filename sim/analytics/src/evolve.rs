@@ -0,0 +1,267 @@
+// Evolves a strategy's config toward winning more often, for the evolve subcommand.
+//
+// Genes are numeric config parameters with the range to search, e.g.
+//   {offer_fraction: [0, 1]}
+// checked against the strategy's config schema. Parameters the schema types as integers are
+// rounded. Every generation, each member of the population plays two-player matches of num_runs
+// games in each seating, as tournament.rs does, against `opponents` other members drawn at
+// random, and its fitness is its win rate over those games. The next generation keeps the
+// `elite` fittest members as they are, and fills the rest with children of two parents picked
+// by tournament selection of two, by uniform crossover and Gaussian mutation of a tenth of each
+// gene's range, clamped to the range. The first generation is drawn uniformly from the ranges.
+//
+// Members are only measured against each other, so a population can drift toward beating its
+// own kind. Check the best configs against other strategies with the tournament subcommand.
+use crate::cache::ResultCache;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameRules, SimConfig};
+use hedonica_engine::info;
+use hedonica_engine::player;
+use hedonica_engine::stats::normal_quantile;
+use rand::prelude::*;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+pub struct EvolveOptions {
+    pub population: usize,
+    pub generations: usize,
+    pub opponents: usize,
+    pub elite: usize,
+    // Seeds the evolution. 0 seeds from the OS.
+    pub seed: u64,
+}
+
+pub struct Gene {
+    name: String,
+    low: f64,
+    high: f64,
+    integer: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Member {
+    pub config: Value,
+    pub fitness: f64,
+}
+
+#[derive(Serialize)]
+pub struct GenerationSummary {
+    pub generation: usize,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    pub best_config: Value,
+}
+
+pub struct Evolution {
+    pub generations: Vec<GenerationSummary>,
+    // The last generation, fittest first.
+    pub population: Vec<Member>,
+}
+
+pub fn parse_genes(text: &str, strategy: &str) -> Result<Vec<Gene>> {
+    let invalid = |message: String| HedonicaError::parse("genes", message);
+    let ranges: Map<String, Value> = json5::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    let schema = player::config_schema(strategy)?;
+    ranges
+        .into_iter()
+        .map(|(name, range)| {
+            let property = &schema["properties"][&name];
+            let integer = match property["type"].as_str() {
+                Some("integer") => true,
+                Some("number") => false,
+                _ => {
+                    return Err(invalid(format!(
+                        "{} has no number parameter {}",
+                        strategy, name
+                    )))
+                }
+            };
+            let (low, high) = match range.as_array().map(Vec::as_slice) {
+                Some([low, high]) => match (low.as_f64(), high.as_f64()) {
+                    (Some(low), Some(high)) if low <= high => (low, high),
+                    _ => return Err(invalid(format!("{} needs a range [low, high]", name))),
+                },
+                _ => return Err(invalid(format!("{} needs a range [low, high]", name))),
+            };
+            let below = property["minimum"].as_f64().is_some_and(|min| low < min);
+            let above = property["maximum"].as_f64().is_some_and(|max| high > max);
+            if below || above {
+                return Err(invalid(format!(
+                    "the range of {} is outside what {} allows",
+                    name, strategy
+                )));
+            }
+            Ok(Gene {
+                name,
+                low,
+                high,
+                integer,
+            })
+        })
+        .collect()
+}
+
+fn seeded_rng(seed: u64) -> StdRng {
+    match seed {
+        0 => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+        seed => SeedableRng::seed_from_u64(seed),
+    }
+}
+
+fn to_config(genes: &[Gene], values: &[f64]) -> Value {
+    genes
+        .iter()
+        .zip(values)
+        .map(|(gene, &value)| {
+            let value = if gene.integer {
+                serde_json::json!(value.round() as i64)
+            } else {
+                serde_json::json!(value)
+            };
+            (gene.name.clone(), value)
+        })
+        .collect::<Map<String, Value>>()
+        .into()
+}
+
+fn seating_config(
+    base_config: &Value,
+    strategy: &str,
+    first: &Value,
+    second: &Value,
+) -> Result<SimConfig> {
+    let mut config = base_config.clone();
+    config["num_players"] = serde_json::json!(2);
+    config["player_configs"] = serde_json::json!([
+        { "player_type": strategy, "config": first },
+        { "player_type": strategy, "config": second },
+    ]);
+    serde_json::from_value(config).map_err(|e| HedonicaError::parse("sim config", e))
+}
+
+// Each member's win rate over matches against randomly drawn other members.
+fn evaluate(
+    base_config: &Value,
+    rules: &GameRules,
+    strategy: &str,
+    configs: &[Value],
+    opponents: usize,
+    rng: &mut StdRng,
+    cache: &ResultCache,
+) -> Result<Vec<f64>> {
+    (0..configs.len())
+        .map(|member| {
+            let others: Vec<usize> = (0..configs.len()).filter(|&o| o != member).collect();
+            let (mut wins, mut games) = (0., 0.);
+            for &other in others.choose_multiple(rng, opponents) {
+                for (seat, first, second) in [(0, member, other), (1, other, member)] {
+                    let mut config =
+                        seating_config(base_config, strategy, &configs[first], &configs[second])?;
+                    let metrics = cache.play_batch(&mut config, rules)?;
+                    let runs = metrics.runs as f64;
+                    wins += (metrics.win_rates[&seat] * runs).round();
+                    games += runs;
+                }
+            }
+            Ok(wins / games.max(1.))
+        })
+        .collect()
+}
+
+// The fitter of two members drawn at random.
+fn select<'a>(members: &'a [(Vec<f64>, f64)], rng: &mut StdRng) -> &'a [f64] {
+    let a = members.choose(rng).unwrap();
+    let b = members.choose(rng).unwrap();
+    if a.1 >= b.1 {
+        &a.0
+    } else {
+        &b.0
+    }
+}
+
+fn breed(genes: &[Gene], a: &[f64], b: &[f64], rng: &mut StdRng) -> Vec<f64> {
+    genes
+        .iter()
+        .enumerate()
+        .map(|(i, gene)| {
+            let inherited = if rng.gen_bool(0.5) { a[i] } else { b[i] };
+            let normal = normal_quantile(rng.gen::<f64>().max(f64::MIN_POSITIVE));
+            (inherited + normal * (gene.high - gene.low) / 10.).clamp(gene.low, gene.high)
+        })
+        .collect()
+}
+
+pub fn run(
+    base_config: &Value,
+    rules: &GameRules,
+    strategy: &str,
+    genes: &[Gene],
+    options: &EvolveOptions,
+    cache: &ResultCache,
+) -> Result<Evolution> {
+    if options.population < 2 || options.generations == 0 {
+        return Err(HedonicaError::Unsupported {
+            message: String::from(
+                "evolving needs a population of two or more, for a generation or more",
+            ),
+        });
+    }
+    let mut rng = seeded_rng(options.seed);
+    let mut population: Vec<Vec<f64>> = (0..options.population)
+        .map(|_| {
+            genes
+                .iter()
+                .map(|gene| gene.low + rng.gen::<f64>() * (gene.high - gene.low))
+                .collect()
+        })
+        .collect();
+    let mut generations = Vec::new();
+    loop {
+        let generation = generations.len();
+        let configs: Vec<Value> = population.iter().map(|v| to_config(genes, v)).collect();
+        let fitness = evaluate(
+            base_config,
+            rules,
+            strategy,
+            &configs,
+            options.opponents,
+            &mut rng,
+            cache,
+        )?;
+        let mut ranked: Vec<(Vec<f64>, f64)> = population.into_iter().zip(fitness).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let summary = GenerationSummary {
+            generation,
+            best_fitness: ranked[0].1,
+            mean_fitness: ranked.iter().map(|(_, f)| f).sum::<f64>() / ranked.len() as f64,
+            best_config: to_config(genes, &ranked[0].0),
+        };
+        info!(
+            "Generation {}: best win rate {:.3}, mean {:.3}, best config {}",
+            generation, summary.best_fitness, summary.mean_fitness, summary.best_config
+        );
+        generations.push(summary);
+        if generations.len() == options.generations {
+            let population = ranked
+                .iter()
+                .map(|(values, fitness)| Member {
+                    config: to_config(genes, values),
+                    fitness: *fitness,
+                })
+                .collect();
+            return Ok(Evolution {
+                generations,
+                population,
+            });
+        }
+        population = ranked
+            .iter()
+            .take(options.elite)
+            .map(|(values, _)| values.clone())
+            .collect();
+        while population.len() < options.population {
+            let (a, b) = (select(&ranked, &mut rng), select(&ranked, &mut rng));
+            population.push(breed(genes, a, b, &mut rng));
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// A long-running self-play league between registered strategies, for the league subcommand.
+// Unlike a tournament, which rates a fixed field from scratch, the league keeps its ratings in
+// a JSON file between runs, so bots can be entered as they are written and play their way up
+// the leaderboard.
+//
+// Each run plays some rounds. In a round every entrant plays at most one match, paired first
+// with the opponent they have met least, then the closest in rating, with the entrants who have
+// played fewest matches picked first. A match is two seatings of num_runs two-player games, as
+// in a tournament, each with a master seed of its own so no two matches replay the same deals.
+//
+// Ratings are on the Elo scale and start at 1500. After each match both ratings move by
+//
+//   K * (share of the games the first won - expected share)
+//
+// where the expected share is 1 / (1 + 10^((second's rating - first's) / 400)), so a match
+// counts as one game scored by how many games were won. Ratings depend on the order matches
+// are played in, as a leaderboard's do. Entrants with fewer than PROVISIONAL_MATCHES matches
+// are marked provisional in the standings.
+use crate::cache::ResultCache;
+use crate::tournament;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::GameRules;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const INITIAL_RATING: f64 = 1500.;
+const K: f64 = 32.;
+const PROVISIONAL_MATCHES: u32 = 5;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Entrant {
+    pub rating: f64,
+    pub matches: u32,
+    pub games: u64,
+    pub wins: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MatchRecord {
+    pub first: String,
+    pub second: String,
+    pub games: u64,
+    pub first_wins: f64,
+    // How far the first's rating moved, and the second's the other way.
+    pub rating_change: f64,
+}
+
+#[derive(Serialize)]
+pub struct Standing {
+    pub rank: usize,
+    pub strategy: String,
+    pub rating: f64,
+    pub matches: u32,
+    pub games: u64,
+    pub win_rate: f64,
+    pub provisional: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct League {
+    pub entrants: BTreeMap<String, Entrant>,
+    // Every match played, oldest first.
+    pub matches: Vec<MatchRecord>,
+}
+
+impl League {
+    // A missing file is an empty league.
+    pub fn load(path: &Path) -> io::Result<League> {
+        if !path.exists() {
+            return Ok(League::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    // Writes beside the path and renames into place, so an interrupted run leaves the last
+    // ratings whole.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&partial, path)
+    }
+
+    // Enters a strategy at the initial rating, unless it is already in the league.
+    pub fn enter(&mut self, strategy: &str) {
+        self.entrants
+            .entry(strategy.to_string())
+            .or_insert(Entrant {
+                rating: INITIAL_RATING,
+                matches: 0,
+                games: 0,
+                wins: 0.,
+            });
+    }
+
+    fn meetings(&self, a: &str, b: &str) -> usize {
+        self.matches
+            .iter()
+            .filter(|m| (m.first == a && m.second == b) || (m.first == b && m.second == a))
+            .count()
+    }
+
+    // The next round's pairings.
+    pub fn schedule(&self) -> Vec<(String, String)> {
+        let mut waiting: Vec<&String> = self.entrants.keys().collect();
+        waiting.sort_by_key(|name| self.entrants[*name].matches);
+        let mut pairs = Vec::new();
+        while waiting.len() >= 2 {
+            let first = waiting.remove(0);
+            let rating = self.entrants[first].rating;
+            let (index, _) = waiting
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    let key = |name: &String| {
+                        (
+                            self.meetings(first, name),
+                            (self.entrants[name].rating - rating).abs(),
+                        )
+                    };
+                    let (a, b) = (key(a), key(b));
+                    a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+                })
+                .unwrap();
+            let second = waiting.remove(index);
+            pairs.push((first.clone(), second.clone()));
+        }
+        pairs
+    }
+
+    // Plays one round of matches and updates the ratings, returning the matches.
+    pub fn play_round(
+        &mut self,
+        base_config: &serde_json::Value,
+        rules: &GameRules,
+        cache: &ResultCache,
+    ) -> Result<Vec<MatchRecord>> {
+        if self.entrants.len() < 2 {
+            return Err(HedonicaError::Unsupported {
+                message: String::from("a league needs at least two strategies to play"),
+            });
+        }
+        let base_seed = base_config["seed"].as_u64().unwrap_or(0);
+        let mut played = Vec::new();
+        for (first, second) in self.schedule() {
+            let (mut games, mut first_wins) = (0., 0.);
+            for (seating, (a, b)) in [(&first, &second), (&second, &first)].iter().enumerate() {
+                let mut config = base_config.clone();
+                config["seed"] = serde_json::json!(
+                    base_seed.wrapping_add(2 * self.matches.len() as u64 + seating as u64)
+                );
+                let mut config = tournament::seating_config(&config, a, b)?;
+                let metrics = cache.play_batch(&mut config, rules)?;
+                let runs = metrics.runs as f64;
+                let wins = (metrics.win_rates[&0] * runs).round();
+                games += runs;
+                first_wins += if *a == &first { wins } else { runs - wins };
+            }
+            let record = self.record(&first, &second, games, first_wins);
+            played.push(record);
+        }
+        Ok(played)
+    }
+
+    fn record(&mut self, first: &str, second: &str, games: f64, first_wins: f64) -> MatchRecord {
+        let (a, b) = (self.entrants[first].rating, self.entrants[second].rating);
+        let expected = 1. / (1. + 10f64.powf((b - a) / 400.));
+        let share = if games > 0. { first_wins / games } else { 0.5 };
+        let change = K * (share - expected);
+        for (name, delta, wins) in [
+            (first, change, first_wins),
+            (second, -change, games - first_wins),
+        ] {
+            let entrant = self.entrants.get_mut(name).unwrap();
+            entrant.rating += delta;
+            entrant.matches += 1;
+            entrant.games += games as u64;
+            entrant.wins += wins;
+        }
+        let record = MatchRecord {
+            first: first.to_string(),
+            second: second.to_string(),
+            games: games as u64,
+            first_wins,
+            rating_change: change,
+        };
+        self.matches.push(record.clone());
+        record
+    }
+
+    // Entrants by rating, highest first.
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut entrants: Vec<(&String, &Entrant)> = self.entrants.iter().collect();
+        entrants.sort_by(|(_, a), (_, b)| b.rating.total_cmp(&a.rating));
+        entrants
+            .into_iter()
+            .enumerate()
+            .map(|(index, (strategy, entrant))| Standing {
+                rank: index + 1,
+                strategy: strategy.clone(),
+                rating: entrant.rating,
+                matches: entrant.matches,
+                games: entrant.games,
+                win_rate: entrant.wins / entrant.games.max(1) as f64,
+                provisional: entrant.matches < PROVISIONAL_MATCHES,
+            })
+            .collect()
+    }
+}
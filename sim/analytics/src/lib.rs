@@ -0,0 +1,18 @@
+// Tools that play and summarize many games: batches and their cache, balance checks, parameter
+// sweeps, rules comparisons, tournaments and the league, strategy evolution, replay annotation,
+// trajectories, trade history, result files, and goal verdicts.
+pub mod annotate;
+pub mod balance;
+pub mod batch;
+pub mod cache;
+pub mod cohorts;
+pub mod compare_rules;
+pub mod evolve;
+pub mod league;
+pub mod output;
+pub mod sweep;
+pub mod tournament;
+pub mod trade_bias;
+pub mod trade_history;
+pub mod trajectories;
+pub mod verdict;
@@ -0,0 +1,177 @@
+// Per-game results written to a file with --output, for analysis outside the simulator.
+//
+// A path ending in .csv gets one row per game with a column per player score and place, per
+// player and category of final goods, and for the game's seeds. Tied players share the higher
+// place. Any other path gets JSON lines, one object per game, with the game's trajectory when
+// trajectories are collected, and on the last game of each match, the match's result.
+use hedonica_engine::game::{GameResult, GameRules, TurnSample};
+use hedonica_engine::rng::RunSeeds;
+use hedonica_engine::series::MatchResult;
+use hedonica_engine::types::{GoodsSet, PlayerId};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+#[derive(Serialize)]
+pub struct GameRecord<'a> {
+    pub game: i32,
+    pub winner: PlayerId,
+    pub turns: i32,
+    pub trades: u32,
+    pub scores: &'a [f64],
+    pub placements: &'a [Vec<PlayerId>],
+    pub final_goods: &'a [GoodsSet],
+    pub seeds: RunSeeds,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub trajectory: &'a [TurnSample],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_result: Option<&'a MatchResult>,
+}
+
+impl<'a> GameRecord<'a> {
+    pub fn new(game: i32, result: &'a GameResult) -> GameRecord<'a> {
+        GameRecord {
+            game,
+            winner: result.winner,
+            turns: result.turns,
+            trades: result.liquidity.trades,
+            scores: &result.scores,
+            placements: &result.placements,
+            final_goods: &result.final_goods,
+            seeds: result.seeds,
+            trajectory: &result.trajectory,
+            match_result: result.match_result.as_ref(),
+        }
+    }
+}
+
+pub trait OutputWriter {
+    fn write_game(&mut self, record: &GameRecord) -> std::io::Result<()>;
+
+    fn finish(&mut self) -> std::io::Result<()>;
+}
+
+struct JsonLinesWriter {
+    out: BufWriter<File>,
+}
+
+impl OutputWriter for JsonLinesWriter {
+    fn write_game(&mut self, record: &GameRecord) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.out, record)?;
+        writeln!(self.out)
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+struct CsvWriter {
+    out: BufWriter<File>,
+    num_players: usize,
+    categories: Vec<String>,
+}
+
+impl CsvWriter {
+    fn header(&self) -> Vec<String> {
+        let mut columns: Vec<String> = ["game", "winner", "turns", "trades"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        columns.extend((0..self.num_players).map(|p| format!("score_{}", p)));
+        columns.extend((0..self.num_players).map(|p| format!("place_{}", p)));
+        for p in 0..self.num_players {
+            columns.extend(self.categories.iter().map(|c| format!("goods_{}_{}", p, c)));
+        }
+        columns.push(String::from("deck_shuffle_seed"));
+        columns.push(String::from("preferences_seed"));
+        columns
+    }
+}
+
+impl OutputWriter for CsvWriter {
+    fn write_game(&mut self, record: &GameRecord) -> std::io::Result<()> {
+        let mut row = vec![
+            record.game.to_string(),
+            record.winner.to_string(),
+            record.turns.to_string(),
+            record.trades.to_string(),
+        ];
+        row.extend(record.scores.iter().map(|s| s.to_string()));
+        let mut places = vec![0; self.num_players];
+        let mut place = 1;
+        for group in record.placements {
+            group.iter().for_each(|&p| places[p] = place);
+            place += group.len();
+        }
+        row.extend(places.iter().map(|p| p.to_string()));
+        for goods in record.final_goods {
+            row.extend(
+                self.categories
+                    .iter()
+                    .map(|c| goods.get(c).copied().unwrap_or(0.).to_string()),
+            );
+        }
+        row.push(record.seeds.deck_shuffle_seed.to_string());
+        row.push(record.seeds.preferences_seed.to_string());
+        writeln!(self.out, "{}", row.join(","))
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// A writer for the path's format, starting with a CSV header if `header`.
+fn open(
+    out: BufWriter<File>,
+    path: &Path,
+    num_players: usize,
+    rules: &GameRules,
+    header: bool,
+) -> std::io::Result<Box<dyn OutputWriter>> {
+    if path.extension().is_some_and(|e| e == "csv") {
+        let categories = rules
+            .all_categories()
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let mut writer = CsvWriter {
+            out,
+            num_players,
+            categories,
+        };
+        if header {
+            let header = writer.header().join(",");
+            writeln!(writer.out, "{}", header)?;
+        }
+        Ok(Box::new(writer))
+    } else {
+        Ok(Box::new(JsonLinesWriter { out }))
+    }
+}
+
+pub fn create(
+    path: &Path,
+    num_players: usize,
+    rules: &GameRules,
+) -> std::io::Result<Box<dyn OutputWriter>> {
+    let out = BufWriter::new(File::create(path)?);
+    open(out, path, num_players, rules, true)
+}
+
+// Continues a file written by create, from its first `length` bytes, e.g. as of a checkpoint.
+// Games written after that are dropped, since the resumed batch plays them again.
+pub fn resume(
+    path: &Path,
+    num_players: usize,
+    rules: &GameRules,
+    length: u64,
+) -> std::io::Result<Box<dyn OutputWriter>> {
+    let file = OpenOptions::new().write(true).open(path)?;
+    file.set_len(length)?;
+    let mut out = BufWriter::new(file);
+    out.seek(SeekFrom::End(0))?;
+    open(out, path, num_players, rules, false)
+}
@@ -0,0 +1,141 @@
+// Parameter sweeps, for the sweep subcommand.
+//
+// A grid maps GameRules parameters to the values to try, e.g.
+//   {victory_threshold: [30, 40, 50, 60], start_money: [5, 10, 20]}
+// Every combination of values is one cell. Each cell plays a batch of num_runs games with
+// batch::play on the base rules with the cell's values set, and the sweep prints a table with a
+// row per cell. Cells already in the result cache are not played again. With early_stopping in
+// the sim config, cells that are confidently outside the region stop early and say why.
+use crate::batch::BatchMetrics;
+use crate::cache::ResultCache;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameRules, SimConfig};
+use serde_json::{Map, Value};
+
+pub struct Cell {
+    pub params: Vec<(String, Value)>,
+    pub metrics: BatchMetrics,
+}
+
+pub fn parse_grid(text: &str, base_rules: &Value) -> Result<Map<String, Value>> {
+    let grid: Map<String, Value> =
+        json5::from_str(text).map_err(|e| HedonicaError::parse("sweep grid", e))?;
+    for (name, values) in &grid {
+        if base_rules.get(name).is_none() {
+            return Err(HedonicaError::parse(
+                "sweep grid",
+                format!("GameRules has no parameter {}", name),
+            ));
+        }
+        if values.as_array().is_none_or(|values| values.is_empty()) {
+            return Err(HedonicaError::parse(
+                "sweep grid",
+                format!("parameter {} needs a list of values", name),
+            ));
+        }
+    }
+    Ok(grid)
+}
+
+// Every combination of grid values, with the first parameter varying slowest.
+fn combinations(grid: &Map<String, Value>) -> Vec<Vec<(String, Value)>> {
+    grid.iter().fold(vec![Vec::new()], |cells, (name, values)| {
+        cells
+            .iter()
+            .flat_map(|cell| {
+                values.as_array().unwrap().iter().map(move |value| {
+                    let mut cell = cell.clone();
+                    cell.push((name.clone(), value.clone()));
+                    cell
+                })
+            })
+            .collect()
+    })
+}
+
+pub fn run(
+    config: &mut SimConfig,
+    base_rules: &Value,
+    grid: &Map<String, Value>,
+    cache: &ResultCache,
+) -> Result<Vec<Cell>> {
+    combinations(grid)
+        .into_iter()
+        .map(|params| {
+            let mut rules = base_rules.clone();
+            for (name, value) in &params {
+                rules[name] = value.clone();
+            }
+            let rules: GameRules = serde_json::from_value(rules)
+                .map_err(|e| HedonicaError::parse(&format!("sweep values {:?}", params), e))?;
+            let metrics = cache.play_batch(config, &rules)?;
+            Ok(Cell { params, metrics })
+        })
+        .collect()
+}
+
+pub fn print_table(grid: &Map<String, Value>, cells: &[Cell]) {
+    print!("{}", format_table(grid, cells));
+}
+
+// The table of cells, one line per row with columns aligned.
+pub fn format_table(grid: &Map<String, Value>, cells: &[Cell]) -> String {
+    let mut header: Vec<String> = grid.keys().cloned().collect();
+    header.extend(
+        [
+            "runs",
+            "seat_skew",
+            "max_win_rate",
+            "mean_turns",
+            "turns_var",
+            "p90_turns",
+            "rounds_per_turn",
+            "mean_trades",
+            "advantage_spread",
+            "abort_rate",
+            "stopped_early",
+        ]
+        .iter()
+        .map(|c| c.to_string()),
+    );
+    let rows: Vec<Vec<String>> = cells
+        .iter()
+        .map(|cell| {
+            let mut row: Vec<String> = cell.params.iter().map(|(_, v)| v.to_string()).collect();
+            let m = &cell.metrics;
+            row.push(m.runs.to_string());
+            row.push(format!("{:.3}", m.seat_skew));
+            row.push(format!("{:.3}", m.max_win_rate()));
+            row.push(format!("{:.2}", m.mean_turns));
+            row.push(format!("{:.2}", m.turns_var));
+            row.push(format!("{:.1}", m.p90_turns));
+            row.push(format!("{:.2}", m.rounds_per_turn));
+            row.push(format!("{:.2}", m.mean_trades));
+            row.push(format!("{:.3}", m.mean_advantage_spread));
+            row.push(format!("{:.3}", m.abort_rate()));
+            row.push(m.stopped_early.clone().unwrap_or_default());
+            row
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain(std::iter::once(header[i].len()))
+                .max()
+                .unwrap()
+        })
+        .collect();
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, &width)| format!("{:width$}", value, width = width))
+            .collect();
+        table += line.join("  ").trim_end();
+        table.push('\n');
+    }
+    table
+}
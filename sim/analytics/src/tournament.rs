@@ -0,0 +1,177 @@
+// Round-robin tournaments between registered strategies, for the tournament subcommand.
+//
+// Every pair of strategies plays two-player matches of num_runs games in each seating, so
+// neither side keeps the first-player advantage. Matches are played with batch::play, so the
+// sim config's seeds and early_stopping apply to each seating, and seatings in the result
+// cache are not played again.
+//
+// Ratings are on the Elo scale, fit to all games at once with a Bradley-Terry model rather than
+// updated game by game, so they do not depend on the order matches are played in. Each pair is
+// credited half a win each way before fitting, so a strategy that never wins still gets a
+// finite rating. Ratings average 1500.
+//
+// Each pair's head-to-head win rate gets a Wilson confidence interval and an exact binomial
+// p-value for differing from an even split, so a 52% to 48% split can be told from noise.
+//
+// Only wins count, so a sim config with placement_points is rejected rather than have them
+// silently ignored.
+use crate::cache::ResultCache;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameRules, SimConfig};
+use hedonica_engine::stats;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub struct TournamentReport {
+    pub games_per_seating: i32,
+    // win_rates[a][b] is how often a beat b, over both seatings.
+    pub win_rates: BTreeMap<String, BTreeMap<String, f64>>,
+    pub ratings: BTreeMap<String, f64>,
+    pub confidence_level: f64,
+    pub pairs: Vec<PairTest>,
+}
+
+// How often `first` beat `second`, over both seatings.
+#[derive(Serialize)]
+pub struct PairTest {
+    pub first: String,
+    pub second: String,
+    pub games: u64,
+    pub win_rate: f64,
+    pub interval: (f64, f64),
+    pub p_value: f64,
+}
+
+const CONFIDENCE_LEVEL: f64 = 0.95;
+
+const PRIOR_WINS: f64 = 0.5;
+
+pub(crate) fn seating_config(
+    base_config: &serde_json::Value,
+    first: &str,
+    second: &str,
+) -> Result<SimConfig> {
+    if !base_config["placement_points"].is_null() {
+        return Err(HedonicaError::Unsupported {
+            message: String::from(
+                "matches are rated by wins alone, so placement_points cannot be set",
+            ),
+        });
+    }
+    let mut config = base_config.clone();
+    config["num_players"] = serde_json::json!(2);
+    config["player_configs"] = serde_json::json!([
+        { "player_type": first },
+        { "player_type": second },
+    ]);
+    serde_json::from_value(config).map_err(|e| HedonicaError::parse("sim config", e))
+}
+
+// Bradley-Terry strengths by minorization-maximization, where wins[i][j] counts i beating j.
+fn strengths(wins: &[Vec<f64>]) -> Vec<f64> {
+    let n = wins.len();
+    let mut strength = vec![1.; n];
+    for _ in 0..1000 {
+        let next: Vec<f64> = (0..n)
+            .map(|i| {
+                let total_wins: f64 = wins[i].iter().sum();
+                let denominator: f64 = (0..n)
+                    .filter(|&j| j != i)
+                    .map(|j| (wins[i][j] + wins[j][i]) / (strength[i] + strength[j]))
+                    .sum();
+                total_wins / denominator
+            })
+            .collect();
+        let log_mean = next.iter().map(|s| s.ln()).sum::<f64>() / n as f64;
+        strength = next.iter().map(|s| s / log_mean.exp()).collect();
+    }
+    strength
+}
+
+pub fn play(
+    base_config: &serde_json::Value,
+    rules: &GameRules,
+    strategies: &[String],
+    cache: &ResultCache,
+) -> Result<TournamentReport> {
+    let n = strategies.len();
+    if n < 2 {
+        return Err(HedonicaError::Unsupported {
+            message: String::from("a tournament needs at least two strategies"),
+        });
+    }
+    for (i, strategy) in strategies.iter().enumerate() {
+        if strategies[..i].contains(strategy) {
+            return Err(HedonicaError::Unsupported {
+                message: format!("{} is in the tournament twice", strategy),
+            });
+        }
+    }
+
+    let mut wins = vec![vec![0.; n]; n];
+    let mut games = vec![vec![0.; n]; n];
+    let mut games_per_seating = 0;
+    for a in 0..n {
+        for b in 0..n {
+            if a == b {
+                continue;
+            }
+            let mut config = seating_config(base_config, &strategies[a], &strategies[b])?;
+            games_per_seating = config.num_runs;
+            let metrics = cache.play_batch(&mut config, rules)?;
+            let runs = metrics.runs as f64;
+            let first_wins = (metrics.win_rates[&0] * runs).round();
+            wins[a][b] += first_wins;
+            wins[b][a] += runs - first_wins;
+            games[a][b] += runs;
+            games[b][a] += runs;
+        }
+    }
+
+    let win_rates = (0..n)
+        .map(|a| {
+            let row = (0..n)
+                .filter(|&b| b != a)
+                .map(|b| (strategies[b].clone(), wins[a][b] / games[a][b].max(1.)))
+                .collect();
+            (strategies[a].clone(), row)
+        })
+        .collect();
+
+    let mut pairs = Vec::new();
+    for a in 0..n {
+        for b in a + 1..n {
+            let (first_wins, games) = (wins[a][b] as u64, games[a][b] as u64);
+            pairs.push(PairTest {
+                first: strategies[a].clone(),
+                second: strategies[b].clone(),
+                games,
+                win_rate: first_wins as f64 / games.max(1) as f64,
+                interval: stats::rate_interval(first_wins as f64, games as f64, CONFIDENCE_LEVEL),
+                p_value: stats::binomial_p_value(first_wins, games, 0.5),
+            });
+        }
+    }
+
+    let smoothed: Vec<Vec<f64>> = (0..n)
+        .map(|a| {
+            (0..n)
+                .map(|b| if a == b { 0. } else { wins[a][b] + PRIOR_WINS })
+                .collect()
+        })
+        .collect();
+    let ratings = strengths(&smoothed)
+        .iter()
+        .zip(strategies)
+        .map(|(s, strategy)| (strategy.clone(), 1500. + 400. * s.log10()))
+        .collect();
+
+    Ok(TournamentReport {
+        games_per_seating,
+        win_rates,
+        ratings,
+        confidence_level: CONFIDENCE_LEVEL,
+        pairs,
+    })
+}
@@ -0,0 +1,80 @@
+// Trade bias: how the value of the trades in a batch flows between strategies.
+//
+// The report sums each side's gain from every trade, as computed in trade_flows.rs, for each
+// ordered pair of strategy labels, so a strategy that systematically comes out ahead of another
+// shows a positive net against it.
+use hedonica_engine::trade_flows::TradeFlow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+struct PairFlow {
+    trades: u32,
+    // Points gained by the first strategy of the pair, and by the second.
+    gain: f64,
+    partner_gain: f64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct TradeBias {
+    // Kept as a list of pairs, since JSON keys are strings.
+    #[serde(with = "pair_list")]
+    pairs: BTreeMap<(String, String), PairFlow>,
+}
+
+impl TradeBias {
+    pub fn add(&mut self, flows: &[TradeFlow], labels: &[String]) {
+        for flow in flows {
+            let (a, b) = (&labels[flow.proposer], &labels[flow.accepter]);
+            let mut add = |first: &String, second: &String, gain: f64, partner_gain: f64| {
+                let pair = self
+                    .pairs
+                    .entry((first.clone(), second.clone()))
+                    .or_default();
+                pair.gain += gain;
+                pair.partner_gain += partner_gain;
+            };
+            add(a, b, flow.proposer_gain, flow.accepter_gain);
+            add(b, a, flow.accepter_gain, flow.proposer_gain);
+            // A trade between seats with the same label is one trade, entered both ways.
+            self.pairs.get_mut(&(a.clone(), b.clone())).unwrap().trades += 1;
+            if a != b {
+                self.pairs.get_mut(&(b.clone(), a.clone())).unwrap().trades += 1;
+            }
+        }
+    }
+
+    pub fn summary(&self) -> serde_json::Value {
+        let mut by_label: BTreeMap<&str, BTreeMap<&str, serde_json::Value>> = BTreeMap::new();
+        for ((a, b), flow) in &self.pairs {
+            let trades = flow.trades.max(1) as f64;
+            by_label.entry(a).or_default().insert(
+                b,
+                serde_json::json!({
+                    "trades": flow.trades,
+                    "gain": flow.gain,
+                    "partner_gain": flow.partner_gain,
+                    "net_per_trade": (flow.gain - flow.partner_gain) / trades,
+                }),
+            );
+        }
+        serde_json::json!({ "trade_bias": by_label })
+    }
+}
+
+mod pair_list {
+    use super::PairFlow;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    type Pairs = BTreeMap<(String, String), PairFlow>;
+
+    pub fn serialize<S: Serializer>(pairs: &Pairs, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(pairs.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pairs, D::Error> {
+        let pairs = Vec::<((String, String), PairFlow)>::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
@@ -0,0 +1,63 @@
+// Trading activity over a batch, to see whether trading strategies trade their way ahead of
+// PlayerNoTrades or just trade.
+//
+// The summary gives per-game Stats of each seat's trades, the goods volume and the trades per
+// turn, and the mean money each seat paid each other seat per game, from the trade history in
+// every GameResult.
+use hedonica_engine::game::TradeHistory;
+use hedonica_engine::stats::{self, Stats};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct TradeHistoryTally {
+    games: u64,
+    #[serde(with = "stats::state_vec")]
+    trades_by_player: Vec<Stats>,
+    #[serde(with = "stats::state")]
+    goods_volume: Stats,
+    #[serde(with = "stats::state")]
+    trades_per_turn: Stats,
+    money_flows: Vec<Vec<f64>>,
+}
+
+impl TradeHistoryTally {
+    pub fn add(&mut self, history: &TradeHistory) {
+        let num_players = history.trades_by_player.len();
+        self.games += 1;
+        self.trades_by_player
+            .resize_with(num_players, Stats::default);
+        self.money_flows.resize_with(num_players, Vec::new);
+        for (stats, &trades) in self
+            .trades_by_player
+            .iter_mut()
+            .zip(&history.trades_by_player)
+        {
+            stats.add(trades as f64);
+        }
+        self.goods_volume.add(history.goods_volume);
+        self.trades_per_turn.add(history.trades_per_turn);
+        for (totals, flows) in self.money_flows.iter_mut().zip(&history.money_flows) {
+            totals.resize(num_players, 0.);
+            for (total, flow) in totals.iter_mut().zip(flows) {
+                *total += flow;
+            }
+        }
+    }
+
+    pub fn summary(&self) -> serde_json::Value {
+        let games = self.games.max(1) as f64;
+        let money_flows: Vec<Vec<f64>> = self
+            .money_flows
+            .iter()
+            .map(|flows| flows.iter().map(|total| total / games).collect())
+            .collect();
+        serde_json::json!({
+            "trade_history": {
+                "trades_by_player": self.trades_by_player,
+                "goods_volume": self.goods_volume,
+                "trades_per_turn": self.trades_per_turn,
+                "money_flows_per_game": money_flows,
+            }
+        })
+    }
+}
@@ -0,0 +1,101 @@
+// Score, goods and money trajectories over a batch, with --collect-trajectories, to show
+// whether games drag in the midgame (goal G1).
+//
+// For every turn, the summary gives each seat's mean and standard deviation at the start of the
+// turn, over the games that reached it, and how many games that was. Games that end early drop
+// out of later turns, so the count shrinks as the turns go on. The end of every game is
+// summarized separately.
+use hedonica_engine::game::TurnSample;
+use hedonica_engine::stats::{self, Stats};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Default)]
+struct SeatSeries {
+    #[serde(with = "stats::state")]
+    scores: Stats,
+    #[serde(with = "stats::state")]
+    goods: Stats,
+    #[serde(with = "stats::state")]
+    money: Stats,
+}
+
+#[derive(Serialize)]
+struct Spread {
+    mean: f64,
+    std: f64,
+}
+
+impl Spread {
+    fn of(stats: &Stats) -> Spread {
+        Spread {
+            mean: stats.mean(),
+            std: stats.var().sqrt(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SeatSummary {
+    score: Spread,
+    goods: Spread,
+    money: Spread,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Samples {
+    games: u64,
+    seats: Vec<SeatSeries>,
+}
+
+impl Samples {
+    fn add(&mut self, sample: &TurnSample) {
+        self.games += 1;
+        self.seats
+            .resize_with(sample.scores.len(), SeatSeries::default);
+        for (seat, series) in self.seats.iter_mut().enumerate() {
+            series.scores.add(sample.scores[seat]);
+            series.goods.add(sample.goods[seat]);
+            series.money.add(sample.money[seat]);
+        }
+    }
+
+    fn summary(&self) -> serde_json::Value {
+        let seats: Vec<SeatSummary> = self
+            .seats
+            .iter()
+            .map(|series| SeatSummary {
+                score: Spread::of(&series.scores),
+                goods: Spread::of(&series.goods),
+                money: Spread::of(&series.money),
+            })
+            .collect();
+        serde_json::json!({ "games": self.games, "seats": seats })
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Trajectories {
+    turns: BTreeMap<i32, Samples>,
+    end: Samples,
+}
+
+impl Trajectories {
+    pub fn add(&mut self, trajectory: &[TurnSample]) {
+        if let Some((end, turns)) = trajectory.split_last() {
+            for sample in turns {
+                self.turns.entry(sample.turn).or_default().add(sample);
+            }
+            self.end.add(end);
+        }
+    }
+
+    pub fn summary(&self) -> serde_json::Value {
+        let turns: BTreeMap<i32, serde_json::Value> = self
+            .turns
+            .iter()
+            .map(|(&turn, samples)| (turn, samples.summary()))
+            .collect();
+        serde_json::json!({ "trajectories": { "turns": turns, "end": self.end.summary() } })
+    }
+}
@@ -0,0 +1,239 @@
+// A plain-language verdict on a batch against goals G1-G4, printed at the end of every batch so
+// results can be read without the statistics.
+//
+// Each check compares an estimate from the batch with a threshold from the goals file, by
+// default the checked-in goals.json5 next to Cargo.toml. A check passes when the estimate's
+// whole 95% interval is within the goal, fails when it is wholly outside, and is inconclusive
+// otherwise, which means more runs are needed to tell. Checks that need seats with the same
+// strategy, or different ones, are left out of batches without them.
+//
+//   game_length     - G1. The mean game length in turns, and its standard deviation.
+//   seat_fairness   - G2. Each seat's win rate, within max_deviation of a fair share, when
+//                     every seat plays the same strategy.
+//   lame_strategies - G3. The win rate of the seats playing each of these easy strategies,
+//                     at most max_edge above a fair share.
+//   dominance       - G4. The win rate of each strategy's seats, at most max_edge above a
+//                     fair share.
+use crate::balance::Band;
+use hedonica_engine::error::{self, HedonicaError};
+use hedonica_engine::stats::{self, Stats};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+pub const DEFAULT_GOALS: &str = include_str!("../goals.json5");
+
+const CONFIDENCE_LEVEL: f64 = 0.95;
+
+#[derive(Deserialize)]
+pub struct GameLengthGoal {
+    #[serde(default)]
+    pub mean_turns: Option<Band>,
+    #[serde(default)]
+    pub max_std: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct SeatFairnessGoal {
+    pub max_deviation: f64,
+}
+
+#[derive(Deserialize)]
+pub struct LameStrategiesGoal {
+    pub labels: Vec<String>,
+    pub max_edge: f64,
+}
+
+#[derive(Deserialize)]
+pub struct DominanceGoal {
+    pub max_edge: f64,
+}
+
+#[derive(Deserialize, Default)]
+pub struct Goals {
+    #[serde(default)]
+    pub game_length: Option<GameLengthGoal>,
+    #[serde(default)]
+    pub seat_fairness: Option<SeatFairnessGoal>,
+    #[serde(default)]
+    pub lame_strategies: Option<LameStrategiesGoal>,
+    #[serde(default)]
+    pub dominance: Option<DominanceGoal>,
+}
+
+pub fn parse_goals(text: &str) -> error::Result<Goals> {
+    json5::from_str(text).map_err(|e| HedonicaError::parse("goals", e))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Inconclusive,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Outcome::Pass => "PASS",
+            Outcome::Fail => "FAIL",
+            Outcome::Inconclusive => "INCONCLUSIVE, more runs needed",
+        })
+    }
+}
+
+// One line of the verdict, e.g. "G2 seat 1 win rate 58%, goal 45% to 55%: FAIL".
+pub struct Verdict {
+    pub goal: &'static str,
+    pub metric: String,
+    pub goal_text: String,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, goal {}: {}",
+            self.goal, self.metric, self.goal_text, self.outcome
+        )
+    }
+}
+
+fn judge((low, high): (f64, f64), band: &Band) -> Outcome {
+    if band.contains(low) && band.contains(high) {
+        Outcome::Pass
+    } else if band.max.is_some_and(|max| low > max) || band.min.is_some_and(|min| high < min) {
+        Outcome::Fail
+    } else {
+        Outcome::Inconclusive
+    }
+}
+
+fn describe(band: &Band, show: impl Fn(f64) -> String) -> String {
+    match (band.min, band.max) {
+        (Some(min), Some(max)) => format!("{} to {}", show(min), show(max)),
+        (Some(min), None) => format!("at least {}", show(min)),
+        (None, Some(max)) => format!("at most {}", show(max)),
+        (None, None) => String::from("anything"),
+    }
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.0}%", rate * 100.)
+}
+
+fn turns(count: f64) -> String {
+    format!("{:.1}", count)
+}
+
+// A win rate check, of `wins` out of `trials`.
+fn rate_verdict(goal: &'static str, what: String, wins: f64, trials: f64, band: Band) -> Verdict {
+    Verdict {
+        goal,
+        metric: format!("{} win rate {}", what, percent(wins / trials.max(1.))),
+        goal_text: describe(&band, percent),
+        outcome: judge(stats::rate_interval(wins, trials, CONFIDENCE_LEVEL), &band),
+    }
+}
+
+// The verdict on a batch with these game lengths and wins by seat, played by the strategies
+// with these labels.
+pub fn verdicts(
+    goals: &Goals,
+    turn_stats: &Stats,
+    wins_by_player: &BTreeMap<usize, i32>,
+    labels: &[String],
+) -> Vec<Verdict> {
+    let mut verdicts = Vec::new();
+    if turn_stats.is_empty() {
+        return verdicts;
+    }
+    let runs = turn_stats.len() as f64;
+    let fair = 1. / labels.len() as f64;
+    let wins = |seat: usize| wins_by_player.get(&seat).copied().unwrap_or(0) as f64;
+
+    if let Some(goal) = &goals.game_length {
+        if let Some(band) = &goal.mean_turns {
+            verdicts.push(Verdict {
+                goal: "G1",
+                metric: format!("mean game length {} turns", turns(turn_stats.mean())),
+                goal_text: describe(band, turns),
+                outcome: judge(turn_stats.mean_interval(CONFIDENCE_LEVEL), band),
+            });
+        }
+        if let Some(max_std) = goal.max_std {
+            let std = turn_stats.var().sqrt();
+            verdicts.push(Verdict {
+                goal: "G1",
+                metric: format!("game length spread ±{} turns", turns(std)),
+                goal_text: format!("at most ±{}", turns(max_std)),
+                outcome: if std <= max_std {
+                    Outcome::Pass
+                } else {
+                    Outcome::Fail
+                },
+            });
+        }
+    }
+
+    let strategies: BTreeSet<&String> = labels.iter().collect();
+    if let (Some(goal), 1) = (&goals.seat_fairness, strategies.len()) {
+        let band = Band {
+            min: Some(fair - goal.max_deviation),
+            max: Some(fair + goal.max_deviation),
+        };
+        for seat in 0..labels.len() {
+            verdicts.push(rate_verdict(
+                "G2",
+                format!("seat {}", seat),
+                wins(seat),
+                runs,
+                band.clone(),
+            ));
+        }
+    }
+
+    if strategies.len() > 1 {
+        // Seats with the same label are pooled, as one strategy's share of the wins.
+        let by_strategy = |label: &String| {
+            let seats: Vec<usize> = (0..labels.len())
+                .filter(|&seat| labels[seat] == *label)
+                .collect();
+            let wins: f64 = seats.iter().map(|&seat| wins(seat)).sum();
+            (wins, runs * seats.len() as f64)
+        };
+        let at_most = |max_edge: f64| Band {
+            min: None,
+            max: Some(fair + max_edge),
+        };
+        if let Some(goal) = &goals.lame_strategies {
+            for label in strategies
+                .iter()
+                .filter(|label| goal.labels.contains(label))
+            {
+                let (wins, trials) = by_strategy(label);
+                verdicts.push(rate_verdict(
+                    "G3",
+                    label.to_string(),
+                    wins,
+                    trials,
+                    at_most(goal.max_edge),
+                ));
+            }
+        }
+        if let Some(goal) = &goals.dominance {
+            for label in &strategies {
+                let (wins, trials) = by_strategy(label);
+                verdicts.push(rate_verdict(
+                    "G4",
+                    label.to_string(),
+                    wins,
+                    trials,
+                    at_most(goal.max_edge),
+                ));
+            }
+        }
+    }
+    verdicts
+}
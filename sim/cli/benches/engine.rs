@@ -0,0 +1,79 @@
+// Benchmarks of the engine's hot paths. See bench.rs in the engine for how they are run and
+// compared. Pass a name, e.g. `cargo bench -p hedonica-cli --features bench -- game`, to run
+// only the benchmarks whose names contain it.
+use hedonica_engine::bench::{self, Bench};
+use hedonica_engine::game::{self, GameRules, GameState, SimConfig};
+use hedonica_engine::player::load_strategies;
+use hedonica_engine::types::{GoodsSet, Money, Trade};
+use std::path::Path;
+
+// Links the strategies in, which register themselves.
+use hedonica_strategies as _;
+
+fn sim_config(player_type: &str) -> SimConfig {
+    let players: Vec<_> = (0..4)
+        .map(|_| serde_json::json!({ "player_type": player_type }))
+        .collect();
+    let mut config: SimConfig = serde_json::from_value(serde_json::json!({
+        "num_players": 4,
+        "deck_shuffle_seed": 1000,
+        "preferences_seed": 2000,
+        "player_configs": players,
+    }))
+    .unwrap();
+    config.hide_game_state = true;
+    config
+}
+
+fn start_state(config: &SimConfig, rules: &GameRules) -> GameState {
+    let preferences = game::generate_preferences_deck(config, rules).unwrap();
+    game::generate_start_state(config, rules, preferences).unwrap()
+}
+
+// The start state with a proposal waiting: player 1 offers the lead 2 money for 1 art.
+fn state_with_proposal(config: &SimConfig, rules: &GameRules) -> GameState {
+    let mut game = start_state(config, rules);
+    *game.players[0].num_goods.entry("art").or_default() += 1.;
+    let trade = Trade {
+        proposer: 1,
+        accepter: 0,
+        from_proposor: GoodsSet::new(),
+        from_acceptor: [("art", 1.)].iter().copied().collect(),
+        money: Money(2.),
+        counter_offers: 0,
+    };
+    game.current_trade_proposals.insert(1, trade);
+    game
+}
+
+fn main() {
+    let mut bench = Bench::from_args();
+    let rules: GameRules = serde_json::from_str("{}").unwrap();
+
+    let config = sim_config("PlayerNoTrades");
+    bench.run("generate_start_state", || start_state(&config, &rules));
+
+    let waiting = state_with_proposal(&config, &rules);
+    bench.run("clone_game_state", || waiting.clone());
+    bench.run("end_round (with clone)", || {
+        let mut game = waiting.clone();
+        bench::end_round(&config, &mut game, &rules, vec![true]);
+        game
+    });
+
+    for player_type in ["PlayerNoTrades", "PlayerGreedy"].iter() {
+        let config = sim_config(player_type);
+        let mut players = load_strategies(&config, &rules).unwrap();
+        let start = start_state(&config, &rules);
+        bench.run(&format!("full_game/{}", player_type), || {
+            game::try_play(&config, &rules, start.clone(), &mut players)
+                .map(|result| result.turns)
+                .unwrap_or(0)
+        });
+    }
+
+    bench.finish(Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../target/hedonica-bench.json"
+    )));
+}
@@ -0,0 +1,175 @@
+// Checkpoints of a running batch, with --checkpoint, so a batch of hundreds of thousands of runs
+// that is interrupted continues with --resume instead of starting over.
+//
+// Every --checkpoint-every runs, the batch writes the next run to play, everything it has
+// tallied so far and how much of the --output file it has written, replacing the last
+// checkpoint. Each run is dealt from its own seeds, from the run number and the config's seeds
+// as in rng.rs, so a resumed batch with a master seed plays the same games it would have played
+// uninterrupted. Strategies start over from their configs and profile store, as they do at the
+// start of any batch, so strategies that learn within a batch or draw their own random numbers
+// may play differently.
+//
+// A checkpoint is only resumed with the sim config, game rules and flags it was written with,
+// and the same build of the simulator. Replays recorded with --record cannot be resumed, and
+// decision times cover only the runs played since the batch resumed.
+use hedonica_analytics::cohorts::Cohorts;
+use hedonica_analytics::trade_bias::TradeBias;
+use hedonica_analytics::trade_history::TradeHistoryTally;
+use hedonica_analytics::trajectories::Trajectories;
+use hedonica_engine::ablation::{AblationTally, ReplayedDeals};
+use hedonica_engine::advantage::AdvantageTally;
+use hedonica_engine::bidding::BidRecord;
+use hedonica_engine::blitz::Pacing;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::events::EventLedger;
+use hedonica_engine::game::{
+    AbortTally, ActionCounts, GameEnding, GameState, Liquidity, MilestoneTally, PlacementTally,
+};
+use hedonica_engine::series::{MatchState, MatchTally};
+use hedonica_engine::stats::{self, Histogram, SeatFairness, SeatFairnessState, Stats};
+use hedonica_engine::stats::{WeightedResults, WeightedResultsState};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Everything a batch tallies over its runs for the report at the end.
+#[derive(Serialize, Deserialize)]
+pub struct Tallies {
+    pub wins_by_player: BTreeMap<usize, i32>,
+    #[serde(with = "stats::state")]
+    pub turn_stats: Stats,
+    pub turn_histogram: Option<Histogram>,
+    #[serde(with = "SeatFairnessState")]
+    pub seat_fairness: SeatFairness,
+    #[serde(with = "WeightedResultsState")]
+    pub weighted_results: WeightedResults,
+    pub eliminations_by_player: BTreeMap<usize, i32>,
+    pub bailouts_by_player: BTreeMap<usize, u32>,
+    pub loans_by_player: BTreeMap<usize, u32>,
+    pub loan_defaults_by_player: BTreeMap<usize, u32>,
+    pub events_by_player: BTreeMap<usize, EventLedger>,
+    pub action_counts: ActionCounts,
+    pub pacing: Pacing,
+    pub liquidity: Liquidity,
+    pub spreads: Vec<f64>,
+    pub bid_history: Vec<BidRecord>,
+    pub milestones: MilestoneTally,
+    pub placements: PlacementTally,
+    pub trade_bias: TradeBias,
+    pub trade_history: TradeHistoryTally,
+    pub trajectories: Trajectories,
+    pub advantage: AdvantageTally,
+    // Games by how they ended.
+    pub endings: BTreeMap<GameEnding, u32>,
+    // The match being played, with match_format, and the matches played.
+    pub match_state: MatchState,
+    pub matches: MatchTally,
+    pub trade_errors_by_player: BTreeMap<usize, u32>,
+    pub aborted_games: u32,
+    pub abort_tally: AbortTally,
+    pub ablation_tally: AblationTally,
+    // Baseline deals waiting for their variant replay, with their weights and strata.
+    pub replayed_deals: ReplayedDeals<(GameState, f64, Option<String>)>,
+    pub cohorts: Option<Cohorts>,
+}
+
+impl Tallies {
+    pub fn new(
+        num_players: usize,
+        turn_histogram: Option<Histogram>,
+        cohorts: Option<Cohorts>,
+    ) -> Tallies {
+        Tallies {
+            wins_by_player: BTreeMap::new(),
+            turn_stats: Stats::with_quantiles(),
+            turn_histogram,
+            seat_fairness: SeatFairness::new(num_players),
+            weighted_results: WeightedResults::default(),
+            eliminations_by_player: BTreeMap::new(),
+            bailouts_by_player: BTreeMap::new(),
+            loans_by_player: BTreeMap::new(),
+            loan_defaults_by_player: BTreeMap::new(),
+            events_by_player: BTreeMap::new(),
+            action_counts: ActionCounts::default(),
+            pacing: Pacing::default(),
+            liquidity: Liquidity::default(),
+            spreads: Vec::new(),
+            bid_history: Vec::new(),
+            milestones: MilestoneTally::default(),
+            placements: PlacementTally::default(),
+            trade_bias: TradeBias::default(),
+            trade_history: TradeHistoryTally::default(),
+            trajectories: Trajectories::default(),
+            advantage: AdvantageTally::default(),
+            endings: BTreeMap::new(),
+            match_state: MatchState::default(),
+            matches: MatchTally::default(),
+            trade_errors_by_player: BTreeMap::new(),
+            aborted_games: 0,
+            abort_tally: AbortTally::default(),
+            ablation_tally: AblationTally::default(),
+            replayed_deals: ReplayedDeals::default(),
+            cohorts,
+        }
+    }
+}
+
+// Tallies is saved by reference and loaded owned.
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint<T> {
+    pub version: String,
+    pub sim_config: Value,
+    pub game_rules: Value,
+    pub next_run: i32,
+    pub elapsed_seconds: f64,
+    // How many bytes of the --output file were written, if any.
+    pub output_length: Option<u64>,
+    pub tallies: T,
+}
+
+// Where and how often a batch checkpoints, and the checkpoint it resumes from, if any.
+pub struct Checkpointing {
+    pub path: PathBuf,
+    pub every: i32,
+    pub resumed: Option<Checkpoint<Tallies>>,
+}
+
+impl<T> Checkpoint<T> {
+    // Fails unless the checkpoint was written by this build for the same config and rules.
+    pub fn check(&self, sim_config: &Value, game_rules: &Value) -> Result<()> {
+        let mismatch = if self.version != env!("CARGO_PKG_VERSION") {
+            Some("another version of the simulator")
+        } else if self.sim_config != *sim_config {
+            Some("another sim config")
+        } else if self.game_rules != *game_rules {
+            Some("other game rules")
+        } else {
+            None
+        };
+        match mismatch {
+            Some(mismatch) => Err(HedonicaError::Unsupported {
+                message: format!("the checkpoint is of a batch with {}", mismatch),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+// Writes the checkpoint, or a saved game, beside the path and renames it into place, so an
+// interruption while writing leaves the last one whole.
+pub fn save(path: &Path, checkpoint: &impl Serialize) -> Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let text = serde_json::to_string(checkpoint).unwrap();
+    fs::write(&partial, text).map_err(|e| HedonicaError::io(partial.display(), e))?;
+    fs::rename(&partial, path).map_err(|e| HedonicaError::io(path.display(), e))
+}
+
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<Checkpoint<T>> {
+    let text = fs::read_to_string(path).map_err(|e| HedonicaError::io(path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| HedonicaError::parse(&path.display().to_string(), e))
+}
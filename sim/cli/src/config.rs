@@ -0,0 +1,129 @@
+// The sim config and game rules from the command line. Each is built from layers, with later
+// layers overriding earlier ones:
+//   1. --sim-config-file or --game-rules-file, JSON5 read from a path.
+//   2. --sim-config or --game-rules, JSON5 on the command line. Without a file, this is the
+//      default when not given.
+//   3. --set sim.<key>=<value> or --set rules.<key>=<value>, once per value, in order.
+// Objects are merged key by key, recursively, and any other value replaces the one before it,
+// so a list of player_configs is replaced whole. A --set key can be a dotted path into nested
+// objects, e.g. rules.auction.increment=2. Its value is JSON5, or a string when it does not
+// parse as JSON5, so sim.profile_store=profiles.json needs no quotes.
+use hedonica_engine::error::{HedonicaError, Result};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fs;
+
+pub struct Layers {
+    // What it is called in errors.
+    pub name: &'static str,
+    // The command line value, e.g. sim-config.
+    pub arg: &'static str,
+    // The path of a file of it, e.g. sim-config-file.
+    pub file_arg: &'static str,
+    // What --set keys for it start with, e.g. sim.
+    pub prefix: &'static str,
+}
+
+pub const SIM_CONFIG: Layers = Layers {
+    name: "sim config",
+    arg: "sim-config",
+    file_arg: "sim-config-file",
+    prefix: "sim",
+};
+
+pub const GAME_RULES: Layers = Layers {
+    name: "game rules",
+    arg: "game-rules",
+    file_arg: "game-rules-file",
+    prefix: "rules",
+};
+
+// The prefixes --set keys may start with.
+pub const SET_PREFIXES: [&str; 2] = [SIM_CONFIG.prefix, GAME_RULES.prefix];
+
+// Merges overrides into base, key by key for objects.
+pub fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+fn parse_json5(text: &str, what: &str) -> Result<Value> {
+    json5::from_str(text).map_err(|e| HedonicaError::parse(what, e))
+}
+
+// The --set override, e.g. rules.victory_threshold=40, as an object to merge, or None if it
+// is for the other layers.
+fn set_override(text: &str, prefix: &str) -> Result<Option<Value>> {
+    let invalid = |message: &str| HedonicaError::parse(&format!("--set {}", text), message);
+    let (path, value) = text
+        .split_once('=')
+        .ok_or_else(|| invalid("expected <key>=<value>"))?;
+    let mut keys = path.trim().split('.');
+    let section = keys.next().unwrap();
+    if !SET_PREFIXES.contains(&section) {
+        return Err(invalid("keys start with sim. or rules."));
+    }
+    if section != prefix {
+        return Ok(None);
+    }
+    let keys: Vec<&str> = keys.collect();
+    if keys.is_empty() || keys.iter().any(|key| key.is_empty()) {
+        return Err(invalid("expected a key after the prefix"));
+    }
+    let value =
+        json5::from_str(value.trim()).unwrap_or_else(|_| Value::String(value.trim().to_string()));
+    Ok(Some(keys.iter().rev().fold(value, |value, key| {
+        let mut object = Map::new();
+        object.insert(key.to_string(), value);
+        Value::Object(object)
+    })))
+}
+
+// The layers merged, as JSON.
+pub fn load_value(matches: &clap::ArgMatches, layers: &Layers) -> Result<Value> {
+    let mut value = Value::Object(Map::new());
+    if let Some(path) = matches.value_of(layers.file_arg) {
+        let text = fs::read_to_string(path).map_err(|e| HedonicaError::io(path, e))?;
+        merge(&mut value, parse_json5(&text, path)?);
+    }
+    if matches.value_of(layers.file_arg).is_none() || matches.occurrences_of(layers.arg) > 0 {
+        let text = matches.value_of(layers.arg).unwrap();
+        merge(&mut value, parse_json5(text, &format!("--{}", layers.arg))?);
+    }
+    for text in matches.values_of("set").into_iter().flatten() {
+        if let Some(overrides) = set_override(text, layers.prefix)? {
+            merge(&mut value, overrides);
+        }
+    }
+    Ok(value)
+}
+
+// The layers merged and parsed, e.g. as a SimConfig.
+pub fn load<T: DeserializeOwned>(matches: &clap::ArgMatches, layers: &Layers) -> Result<T> {
+    serde_json::from_value(load_value(matches, layers)?)
+        .map_err(|e| HedonicaError::parse(layers.name, e))
+}
+
+// The layers with a JSON5 file merged over them, e.g. one of several rule sets compared with
+// the same --game-rules and --set overrides underneath.
+pub fn load_over<T: DeserializeOwned>(
+    matches: &clap::ArgMatches,
+    layers: &Layers,
+    path: &str,
+) -> Result<T> {
+    let mut value = load_value(matches, layers)?;
+    let text = fs::read_to_string(path).map_err(|e| HedonicaError::io(path, e))?;
+    merge(&mut value, parse_json5(&text, path)?);
+    serde_json::from_value(value).map_err(|e| HedonicaError::parse(path, e))
+}
@@ -0,0 +1,289 @@
+// A live view of a batch in the browser, with --dashboard <port>, for long batches and sweeps
+// that would otherwise be watched by tailing stdout. While games play, the simulator answers
+// HTTP on 127.0.0.1:<port>:
+//
+//   GET /         - A page that draws the status below as it changes.
+//   GET /status   - The status now, as JSON.
+//   GET /events   - The status as server-sent events, once every STREAM_INTERVAL.
+//
+// The status holds the games played, the batch playing now and how far through it is, every
+// seat's win rate over the last WINDOW games, and the histogram of turns per game in the batch.
+// A sweep or rule comparison plays one batch per set of rules, and the status starts over with
+// each batch. Games served from the result cache are not played, so they are not shown. At most
+// MAX_CONNECTIONS requests are served at once, each on its own thread, and the rest are turned
+// away with 503 Service Unavailable. The server stops with the simulator.
+use hedonica_engine::game::{GameResult, GameRules};
+use hedonica_engine::observer::Observer;
+use hedonica_engine::{debug, info, warn};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const WINDOW: usize = 200;
+const STREAM_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_CONNECTIONS: usize = 16;
+
+#[derive(Serialize)]
+struct TurnBin {
+    turns: i32,
+    games: u64,
+}
+
+#[derive(Serialize)]
+struct Status {
+    elapsed_seconds: f64,
+    games: u64,
+    games_per_second: f64,
+    // Starting from 0.
+    batch: usize,
+    batch_rules: Value,
+    batch_games: u64,
+    // None for batches with a time budget rather than a number of runs.
+    batch_runs: Option<u64>,
+    window: usize,
+    rolling_win_rates: Vec<f64>,
+    mean_turns: Option<f64>,
+    turn_histogram: Vec<TurnBin>,
+}
+
+struct Board {
+    start: Instant,
+    runs: Option<u64>,
+    games: u64,
+    batch: Option<usize>,
+    batch_rules: Value,
+    batch_games: u64,
+    winners: VecDeque<usize>,
+    num_players: usize,
+    turns: BTreeMap<i32, u64>,
+}
+
+impl Board {
+    fn start_batch(&mut self, rules: &GameRules) {
+        self.batch = Some(self.batch.map_or(0, |batch| batch + 1));
+        self.batch_rules = serde_json::to_value(rules).unwrap();
+        self.batch_games = 0;
+        self.winners.clear();
+        self.turns.clear();
+    }
+
+    fn add(&mut self, result: &GameResult) {
+        self.games += 1;
+        self.batch_games += 1;
+        self.num_players = result.scores.len();
+        if self.winners.len() == WINDOW {
+            self.winners.pop_front();
+        }
+        self.winners.push_back(result.winner);
+        *self.turns.entry(result.turns).or_insert(0) += 1;
+    }
+
+    fn status(&self) -> Status {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut wins = vec![0; self.num_players];
+        for &winner in &self.winners {
+            if let Some(wins) = wins.get_mut(winner) {
+                *wins += 1;
+            }
+        }
+        let window = self.winners.len();
+        let total_turns: f64 = self.turns.iter().map(|(&t, &n)| t as f64 * n as f64).sum();
+        Status {
+            elapsed_seconds: elapsed,
+            games: self.games,
+            games_per_second: self.games as f64 / elapsed.max(1e-9),
+            batch: self.batch.unwrap_or(0),
+            batch_rules: self.batch_rules.clone(),
+            batch_games: self.batch_games,
+            batch_runs: self.runs,
+            window,
+            rolling_win_rates: wins
+                .into_iter()
+                .map(|w| w as f64 / window.max(1) as f64)
+                .collect(),
+            mean_turns: Some(total_turns / self.batch_games as f64)
+                .filter(|_| self.batch_games > 0),
+            turn_histogram: self
+                .turns
+                .iter()
+                .map(|(&turns, &games)| TurnBin { turns, games })
+                .collect(),
+        }
+    }
+}
+
+// The hook that feeds the dashboard. It shares the board with the server's threads.
+pub struct Dashboard {
+    board: Arc<Mutex<Board>>,
+}
+
+impl Observer for Dashboard {
+    fn on_batch_start(&self, rules: &GameRules) {
+        self.board.lock().unwrap().start_batch(rules);
+    }
+
+    fn on_game_end(&self, _rules: &GameRules, result: &GameResult) {
+        self.board.lock().unwrap().add(result);
+    }
+}
+
+// Counts a connection as open until it is dropped.
+struct Open(Arc<AtomicUsize>);
+
+impl Drop for Open {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Starts serving the dashboard on the port, for batches of `runs` games each.
+pub fn start(port: u16, runs: Option<u64>) -> io::Result<Dashboard> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    info!("Dashboard at http://{}/", listener.local_addr()?);
+    let board = Arc::new(Mutex::new(Board {
+        start: Instant::now(),
+        runs,
+        games: 0,
+        batch: None,
+        batch_rules: Value::Null,
+        batch_games: 0,
+        winners: VecDeque::new(),
+        num_players: 0,
+        turns: BTreeMap::new(),
+    }));
+    let served = board.clone();
+    let open = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Could not accept a dashboard connection: {}", e);
+                    continue;
+                }
+            };
+            if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                open.fetch_sub(1, Ordering::SeqCst);
+                let busy = "Too many dashboard connections";
+                if let Err(e) = send(&mut stream, "503 Service Unavailable", "text/plain", busy) {
+                    debug!("Dropped a dashboard connection: {}", e);
+                }
+                continue;
+            }
+            let open = Open(open.clone());
+            let board = served.clone();
+            thread::spawn(move || {
+                let _open = open;
+                if let Err(e) = respond(stream, &board) {
+                    debug!("Dropped a dashboard connection: {}", e);
+                }
+            });
+        }
+    });
+    Ok(Dashboard { board })
+}
+
+fn status_json(board: &Mutex<Board>) -> String {
+    serde_json::to_string(&board.lock().unwrap().status()).unwrap()
+}
+
+fn respond(stream: TcpStream, board: &Mutex<Board>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // The headers are not needed, but are read so the client sees its request taken.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    let mut writer = stream;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+    match path {
+        "/" => send(&mut writer, "200 OK", "text/html; charset=utf-8", PAGE),
+        "/status" => send(
+            &mut writer,
+            "200 OK",
+            "application/json",
+            &status_json(board),
+        ),
+        "/events" => {
+            write!(
+                writer,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+            )?;
+            loop {
+                write!(writer, "data: {}\n\n", status_json(board))?;
+                writer.flush()?;
+                thread::sleep(STREAM_INTERVAL);
+            }
+        }
+        _ => send(&mut writer, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+fn send(writer: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Hedonica batch</title>
+<style>
+  body { font-family: sans-serif; margin: 2em; }
+  .bar { background: #48c; height: 1em; display: inline-block; vertical-align: middle; }
+  td { padding: 0.1em 0.6em; }
+  pre { background: #eee; padding: 0.5em; }
+</style>
+</head>
+<body>
+<h1>Hedonica batch</h1>
+<p id="progress">Waiting for the first game...</p>
+<h2>Win rates over the last <span id="window">0</span> games</h2>
+<table id="wins"></table>
+<h2>Turns per game</h2>
+<p id="turns"></p>
+<table id="histogram"></table>
+<h2>Rules</h2>
+<pre id="rules"></pre>
+<script>
+function bars(table, rows, scale) {
+  table.innerHTML = rows.map(([label, value, text]) =>
+    `<tr><td>${label}</td><td><span class="bar" style="width:${value * scale}px"></span> ${text}</td></tr>`
+  ).join("");
+}
+new EventSource("/events").onmessage = (event) => {
+  const s = JSON.parse(event.data);
+  const runs = s.batch_runs === null ? "" : ` of ${s.batch_runs}`;
+  document.getElementById("progress").textContent =
+    `Batch ${s.batch}: ${s.batch_games}${runs} games. ${s.games} games in ` +
+    `${s.elapsed_seconds.toFixed(0)}s, ${s.games_per_second.toFixed(1)} a second.`;
+  document.getElementById("window").textContent = s.window;
+  bars(document.getElementById("wins"),
+       s.rolling_win_rates.map((rate, seat) => [`Seat ${seat}`, rate, rate.toFixed(3)]), 400);
+  document.getElementById("turns").textContent =
+    s.mean_turns === null ? "" : `Mean ${s.mean_turns.toFixed(2)}`;
+  const most = Math.max(1, ...s.turn_histogram.map((bin) => bin.games));
+  bars(document.getElementById("histogram"),
+       s.turn_histogram.map((bin) => [bin.turns, bin.games / most, bin.games]), 400);
+  document.getElementById("rules").textContent = JSON.stringify(s.batch_rules, null, 2);
+};
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,198 @@
+// A curated set of end-to-end analyses, for the examples subcommand. They double as executable
+// documentation of the analysis tools and as a smoke test of the whole pipeline on a new
+// machine: if `sim examples` finishes, strategies load, games play, and every analysis can
+// write its results.
+//
+// Each example writes into its own directory under the output directory:
+//
+//   sim_config.json, game_rules.json - The inputs, so the example can be rerun by hand.
+//   metrics.json                     - The batch summary, for batch examples.
+//   report.json                      - The ratings and win rates, for tournament examples.
+//   cells.json, table.txt            - Every cell of the grid, for sweep examples.
+//
+// index.json in the output directory lists the examples that were run, with their files and
+// how long they took. Every example is seeded, so its results only change with the engine or
+// the strategies. Examples never use the result cache, since they are meant to play.
+use hedonica_analytics::cache::ResultCache;
+use hedonica_analytics::{batch, sweep, tournament};
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::{GameRules, SimConfig};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+enum Analysis {
+    Batch,
+    Tournament(&'static [&'static str]),
+    // A grid of game rules values, as for the sweep subcommand.
+    Sweep(&'static str),
+}
+
+pub struct Example {
+    pub name: &'static str,
+    pub about: &'static str,
+    sim_config: &'static str,
+    game_rules: &'static str,
+    analysis: Analysis,
+}
+
+pub const EXAMPLES: &[Example] = &[
+    Example {
+        name: "baseline_batch",
+        about: "Three greedy players, for the seat skew and game length of the default rules",
+        sim_config: r#"{
+            num_players: 3,
+            num_runs: 500,
+            deck_shuffle_seed: 1000,
+            preferences_seed: 2000,
+            player_configs: [
+                { player_type: "PlayerGreedy" },
+                { player_type: "PlayerGreedy" },
+                { player_type: "PlayerGreedy" },
+            ],
+        }"#,
+        game_rules: "{}",
+        analysis: Analysis::Batch,
+    },
+    Example {
+        name: "tournament",
+        about: "Round-robin ratings of the built-in strategies that need no outside player",
+        sim_config: "{ num_runs: 100, deck_shuffle_seed: 3000, preferences_seed: 4000 }",
+        game_rules: "{}",
+        analysis: Analysis::Tournament(&[
+            "PlayerNoTrades",
+            "PlayerGreedy",
+            "PlayerExpectedUtility",
+        ]),
+    },
+    Example {
+        name: "victory_threshold_sweep",
+        about: "How the victory threshold changes game length and seat skew for greedy players",
+        sim_config: r#"{
+            num_players: 3,
+            num_runs: 200,
+            deck_shuffle_seed: 5000,
+            preferences_seed: 6000,
+            player_configs: [
+                { player_type: "PlayerGreedy" },
+                { player_type: "PlayerGreedy" },
+                { player_type: "PlayerGreedy" },
+            ],
+        }"#,
+        game_rules: "{}",
+        analysis: Analysis::Sweep("{ victory_threshold: [30.0, 40.0, 50.0] }"),
+    },
+];
+
+#[derive(Serialize)]
+struct IndexEntry {
+    name: &'static str,
+    about: &'static str,
+    files: Vec<String>,
+    seconds: f64,
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    let text = serde_json::to_string_pretty(value)
+        .map_err(|e| HedonicaError::io(path.display(), e.into()))?;
+    fs::write(path, text + "\n").map_err(|e| HedonicaError::io(path.display(), e))
+}
+
+// Plays the example and writes its files into dir, returning their names.
+fn run(example: &Example, dir: &Path) -> Result<Vec<&'static str>> {
+    fs::create_dir_all(dir).map_err(|e| HedonicaError::io(dir.display(), e))?;
+    let config_name = format!("the {} example's sim config", example.name);
+    let rules_name = format!("the {} example's game rules", example.name);
+    let sim_config: Value =
+        json5::from_str(example.sim_config).map_err(|e| HedonicaError::parse(&config_name, e))?;
+    let game_rules: Value =
+        json5::from_str(example.game_rules).map_err(|e| HedonicaError::parse(&rules_name, e))?;
+    let rules: GameRules =
+        serde_json::from_value(game_rules).map_err(|e| HedonicaError::parse(&rules_name, e))?;
+    // Written with every default filled in, so the inputs are complete.
+    let game_rules = serde_json::to_value(&rules).expect("GameRules always serialize");
+    write_json(&dir.join("game_rules.json"), &game_rules)?;
+
+    match example.analysis {
+        Analysis::Batch => {
+            let mut config: SimConfig = serde_json::from_value(sim_config)
+                .map_err(|e| HedonicaError::parse(&config_name, e))?;
+            write_json(&dir.join("sim_config.json"), &config)?;
+            let metrics = batch::play(&mut config, &rules)?;
+            write_json(&dir.join("metrics.json"), &metrics)?;
+            Ok(vec!["sim_config.json", "game_rules.json", "metrics.json"])
+        }
+        Analysis::Tournament(strategies) => {
+            write_json(&dir.join("sim_config.json"), &sim_config)?;
+            let strategies: Vec<String> = strategies.iter().map(|s| s.to_string()).collect();
+            let report =
+                tournament::play(&sim_config, &rules, &strategies, &ResultCache::disabled())?;
+            write_json(&dir.join("report.json"), &report)?;
+            Ok(vec!["sim_config.json", "game_rules.json", "report.json"])
+        }
+        Analysis::Sweep(grid) => {
+            let mut config: SimConfig = serde_json::from_value(sim_config)
+                .map_err(|e| HedonicaError::parse(&config_name, e))?;
+            write_json(&dir.join("sim_config.json"), &config)?;
+            let grid = sweep::parse_grid(grid, &game_rules)?;
+            let cells = sweep::run(&mut config, &game_rules, &grid, &ResultCache::disabled())?;
+            let rows: Vec<Value> = cells
+                .iter()
+                .map(|cell| {
+                    let params: Map<String, Value> = cell.params.iter().cloned().collect();
+                    serde_json::json!({ "params": params, "metrics": cell.metrics })
+                })
+                .collect();
+            write_json(&dir.join("cells.json"), &rows)?;
+            let table = dir.join("table.txt");
+            fs::write(&table, sweep::format_table(&grid, &cells))
+                .map_err(|e| HedonicaError::io(table.display(), e))?;
+            Ok(vec![
+                "sim_config.json",
+                "game_rules.json",
+                "cells.json",
+                "table.txt",
+            ])
+        }
+    }
+}
+
+// Runs the named examples, or all of them, printing each as it finishes.
+pub fn run_all(output_dir: &Path, names: &[&str]) -> Result<()> {
+    if let Some(name) = names
+        .iter()
+        .find(|&name| !EXAMPLES.iter().any(|example| example.name == *name))
+    {
+        return Err(HedonicaError::parse(
+            "example name",
+            format!("there is no example named {}", name),
+        ));
+    }
+    let mut index = Vec::new();
+    for example in EXAMPLES
+        .iter()
+        .filter(|example| names.is_empty() || names.contains(&example.name))
+    {
+        let start = Instant::now();
+        let files = run(example, &output_dir.join(example.name))?;
+        let seconds = start.elapsed().as_secs_f64();
+        println!(
+            "{:<24} {:>7.2}s  {}",
+            example.name,
+            seconds,
+            output_dir.join(example.name).display()
+        );
+        index.push(IndexEntry {
+            name: example.name,
+            about: example.about,
+            files: files
+                .iter()
+                .map(|file| format!("{}/{}", example.name, file))
+                .collect(),
+            seconds,
+        });
+    }
+    write_json(&output_dir.join("index.json"), &index)
+}
@@ -0,0 +1,72 @@
+// `sim gym` serves a HedonicaEnv over stdin and stdout, one JSON object per line, so training
+// code in another language can use the env without bindings. Requests and their responses:
+//
+//   {"type": "spec"}               - {"observation_size": 58, "action_size": 13}
+//   {"type": "reset"}              - {"observation": [...], "legal_actions": [0, 3, ...],
+//                                     "reward": 0, "done": false}
+//   {"type": "step", "action": 3}  - The same, for the agent's next decision. Once "done" is
+//                                    true, the response also has the game's "winner" and
+//                                    "scores".
+//
+// legal_actions lists the indices that make sense for the decision. See gym.rs in the engine
+// for the encodings.
+use hedonica_engine::gym::{HedonicaEnv, Step};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+fn response(env: &HedonicaEnv, step: Step) -> Value {
+    let legal_actions: Vec<usize> = env.legal_actions().map_or_else(Vec::new, |legal| {
+        (0..legal.len()).filter(|&action| legal[action]).collect()
+    });
+    let mut response = json!({
+        "observation": step.observation,
+        "legal_actions": legal_actions,
+        "reward": step.reward,
+        "done": step.done,
+    });
+    if let Some(result) = env.last_result() {
+        response["winner"] = json!(result.winner);
+        response["scores"] = json!(result.scores);
+    }
+    response
+}
+
+pub fn serve(mut env: HedonicaEnv) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Value = serde_json::from_str(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let reply = match request["type"].as_str() {
+            Some("spec") => json!({
+                "observation_size": env.observation_size(),
+                "action_size": env.action_size(),
+            }),
+            Some("reset") => {
+                let observation = env.reset();
+                let step = Step {
+                    observation,
+                    reward: 0.,
+                    done: env.is_done(),
+                };
+                response(&env, step)
+            }
+            Some("step") if !env.is_done() => match request["action"].as_u64() {
+                Some(action) => {
+                    let step = env.step(action as usize);
+                    response(&env, step)
+                }
+                None => json!({ "error": "step needs an action" }),
+            },
+            Some("step") => json!({ "error": "the game is over, reset to start another" }),
+            _ => json!({ "error": format!("unknown request: {}", line) }),
+        };
+        writeln!(out, "{}", reply)?;
+        out.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1605 @@
+extern crate clap;
+
+mod checkpoint;
+mod config;
+mod dashboard;
+mod examples;
+mod gym;
+mod pacing;
+mod profiles;
+mod progress;
+mod real_player_cli;
+mod report;
+mod saves;
+mod sdk;
+mod serve;
+mod theme;
+mod tui;
+
+use crate::pacing::Pacer;
+use crate::profiles::ProfileStore;
+use crate::progress::ProgressBar;
+use clap::{App, AppSettings, Arg, SubCommand};
+use hedonica_analytics::cache::{self, ResultCache};
+use hedonica_analytics::league::League;
+use hedonica_analytics::{
+    annotate, balance, cohorts, compare_rules, evolve, output, sweep, tournament, verdict,
+};
+use hedonica_engine::error::HedonicaError;
+use hedonica_engine::game::*;
+use hedonica_engine::log::{self, Level};
+use hedonica_engine::narrate::Narrator;
+use hedonica_engine::observer::{self, ObserverConfig};
+use hedonica_engine::player::*;
+use hedonica_engine::{
+    ablation, bidding, debug, error, game, info, replay, rng, sampling, stats, timing, validate,
+    warn,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Serialize)]
+struct StoppedEarly {
+    runs: u64,
+    reason: String,
+}
+
+const CONFIDENCE_LEVEL: f64 = 0.95;
+
+// Parses a duration such as 90s, 5m or 2h. A bare number is in seconds.
+fn parse_duration(text: &str) -> error::Result<Duration> {
+    let invalid = || {
+        HedonicaError::parse(
+            &format!("duration {}", text),
+            "expected a positive number of s, m or h",
+        )
+    };
+    let (number, unit) = match text.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => text.split_at(i),
+        None => (text, "s"),
+    };
+    let number: f64 = number.trim().parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number * 60.,
+        "h" => number * 3600.,
+        _ => return Err(invalid()),
+    };
+    if seconds <= 0. {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+// A number from the command line.
+fn number_arg<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> error::Result<T> {
+    matches
+        .value_of(name)
+        .unwrap()
+        .parse()
+        .map_err(|_| HedonicaError::parse(&format!("--{}", name), "expected a number"))
+}
+
+// The sample size a time-boxed batch reached and how precise its estimates are.
+fn time_budget_report(
+    budget: Duration,
+    elapsed: Duration,
+    turn_stats: &stats::Stats,
+    wins_by_player: &BTreeMap<usize, i32>,
+    num_players: usize,
+) -> serde_json::Value {
+    let runs = turn_stats.len() as f64;
+    let win_rates: BTreeMap<usize, (f64, f64)> = (0..num_players)
+        .map(|player| {
+            let wins = wins_by_player.get(&player).copied().unwrap_or(0) as f64;
+            (player, stats::rate_interval(wins, runs, CONFIDENCE_LEVEL))
+        })
+        .collect();
+    serde_json::json!({
+        "time_budget_seconds": budget.as_secs_f64(),
+        "elapsed_seconds": elapsed.as_secs_f64(),
+        "runs": turn_stats.len(),
+        "confidence_level": CONFIDENCE_LEVEL,
+        "mean_turns_interval": turn_stats.mean_interval(CONFIDENCE_LEVEL),
+        "win_rate_intervals": win_rates,
+    })
+}
+
+// What a batch writes, reports and resumes from, from the flags and observers.
+struct BatchOptions<'a> {
+    output_path: Option<&'a Path>,
+    time_budget: Option<Duration>,
+    cohorts: Option<cohorts::Cohorts>,
+    goals: verdict::Goals,
+    checkpointing: Option<checkpoint::Checkpointing>,
+    saving: Option<saves::Saving>,
+}
+
+// Plays num_runs games, or with a time budget as many games as fit in it, and prints the
+// results as one report. See report.rs.
+fn run_sim(mut config: SimConfig, rules: GameRules, options: BatchOptions) -> error::Result<()> {
+    let BatchOptions {
+        output_path,
+        time_budget,
+        cohorts,
+        goals,
+        mut checkpointing,
+        saving,
+    } = options;
+    validate::playable(&config, &rules)?;
+    if time_budget.is_some() {
+        config.num_runs = i32::MAX;
+    }
+    let started_at = SystemTime::now();
+    let sim_config = serde_json::to_value(&config).unwrap();
+    let game_rules = serde_json::to_value(&rules).unwrap();
+    let (mut tallies, first_run, elapsed, output_length) =
+        match checkpointing.as_mut().and_then(|c| c.resumed.take()) {
+            Some(resumed) => {
+                resumed.check(&sim_config, &game_rules)?;
+                if resumed.tallies.cohorts.is_some() != cohorts.is_some() {
+                    return Err(HedonicaError::Unsupported {
+                        message: String::from("the checkpoint is of a batch with other flags"),
+                    });
+                }
+                info!("Resuming at run {}", resumed.next_run);
+                (
+                    resumed.tallies,
+                    resumed.next_run,
+                    Duration::from_secs_f64(resumed.elapsed_seconds),
+                    resumed.output_length,
+                )
+            }
+            None => {
+                let turn_histogram = config
+                    .turn_histogram
+                    .clone()
+                    .map(stats::Histogram::new)
+                    .transpose()?;
+                (
+                    checkpoint::Tallies::new(config.num_players, turn_histogram, cohorts),
+                    0,
+                    Duration::ZERO,
+                    None,
+                )
+            }
+        };
+    let mut loaded_game = match saving {
+        Some(saving) => {
+            if let Some(path) = &saving.path {
+                config.hooks.push(Box::new(saves::GameSaver::new(
+                    path,
+                    &sim_config,
+                    &game_rules,
+                )));
+            }
+            if let Some(loaded) = &saving.loaded {
+                loaded.check(&sim_config, &game_rules)?;
+                info!(
+                    "Continuing the saved game at turn {}",
+                    loaded.state.current_turn
+                );
+            }
+            saving.loaded.map(|loaded| loaded.state)
+        }
+        None => None,
+    };
+    config.hooks.extend(
+        Pacer::for_config(&config).map(|pacer| Box::new(pacer) as Box<dyn observer::Observer>),
+    );
+    let start = Instant::now()
+        .checked_sub(elapsed)
+        .unwrap_or_else(Instant::now);
+    let mut players: Vec<Box<dyn PlayerStrategy>> = load_strategies(&config, &rules)?;
+    let decision_times = timing::instrument(&mut players, &config);
+    let mut output = output_path
+        .map(|path| {
+            match output_length {
+                Some(length) => output::resume(path, config.num_players, &rules, length),
+                None => output::create(path, config.num_players, &rules),
+            }
+            .map_err(|e| HedonicaError::io(path.display(), e))
+        })
+        .transpose()?;
+    let labels = strategy_labels(&config);
+    let profile_path = config.profile_store.clone().map(PathBuf::from);
+    let mut profile_store = match profile_path.as_deref() {
+        Some(path) => ProfileStore::load(path).map_err(|e| HedonicaError::io(path.display(), e))?,
+        None => ProfileStore::default(),
+    };
+    profile_store.restore(&mut players, &labels);
+    let mut stopped_early = None;
+    let seeds = rng::SeedSchedule::new(&config);
+    let mut dealer = sampling::PreferenceDealer::new(&config, &rules)?;
+    let mut variant_strategy = match &config.ablation {
+        Some(ablation) if ablation.seat >= config.num_players => {
+            return Err(HedonicaError::parse(
+                "sim config",
+                format!("the ablation seat {} is not in the game", ablation.seat),
+            ));
+        }
+        Some(ablation) => {
+            let variant = load_strategy(
+                &ablation.player_config,
+                ablation.seat,
+                &rules,
+                config.strict_capabilities,
+            )?;
+            check_players(
+                ablation.seat,
+                ablation.player_config.player_type(),
+                variant.as_ref(),
+                config.num_players,
+            )?;
+            Some(variant)
+        }
+        None => None,
+    };
+    // The variant is held to the budget, but its times are not reported.
+    if let Some(variant) = &mut variant_strategy {
+        timing::instrument(std::slice::from_mut(variant), &config);
+    }
+    let mut progress = match time_budget {
+        Some(budget) => ProgressBar::timed(budget, config.hide_game_state),
+        None => ProgressBar::new(config.num_runs as u64, config.hide_game_state),
+    }
+    .filter(|_| observer::shows_progress(&config.observers));
+
+    for hook in &config.hooks {
+        hook.on_batch_start(&rules);
+    }
+    for run in first_run..config.num_runs {
+        if let Some(checkpointing) = checkpointing
+            .as_ref()
+            .filter(|c| run > first_run && run % c.every == 0)
+        {
+            let output_length = match (&mut output, output_path) {
+                (Some(output), Some(path)) => {
+                    output
+                        .finish()
+                        .map_err(|e| HedonicaError::io("the output file", e))?;
+                    Some(
+                        std::fs::metadata(path)
+                            .map_err(|e| HedonicaError::io(path.display(), e))?
+                            .len(),
+                    )
+                }
+                _ => None,
+            };
+            let checkpoint = checkpoint::Checkpoint {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                sim_config: sim_config.clone(),
+                game_rules: game_rules.clone(),
+                next_run: run,
+                elapsed_seconds: start.elapsed().as_secs_f64(),
+                output_length,
+                tallies: &tallies,
+            };
+            checkpoint::save(&checkpointing.path, &checkpoint)?;
+            debug!("Checkpointed before run {}", run);
+        }
+        if time_budget.is_some_and(|budget| start.elapsed() >= budget) {
+            break;
+        }
+        let arm = config
+            .ablation
+            .as_ref()
+            .map(|ablation| ablation.arm(run, config.num_runs));
+        let (mut game, weight, stratum) = match arm.and_then(|arm| tallies.replayed_deals.take(arm))
+        {
+            Some(replayed) => replayed,
+            None => match loaded_game.take() {
+                Some(game) => (game, 1., None),
+                None => {
+                    seeds.seeds(run).apply(&mut config);
+                    let deal = dealer.deal(&config, &rules, run)?;
+                    (
+                        game::generate_start_state(&config, &rules, deal.preferences)?,
+                        deal.weight,
+                        deal.stratum,
+                    )
+                }
+            },
+        };
+        if let Some(arm) = arm {
+            tallies
+                .replayed_deals
+                .keep(arm, run, (game.clone(), weight, stratum.clone()));
+        }
+        let swap_seat = match (&config.ablation, arm) {
+            (Some(ablation), Some(ablation::Arm::Variant { .. })) => Some(ablation.seat),
+            _ => None,
+        };
+        if let Some(seat) = swap_seat {
+            std::mem::swap(&mut players[seat], variant_strategy.as_mut().unwrap());
+        }
+
+        if config.match_format.is_some() && game.current_turn == 0 {
+            tallies.match_state.start_game(&mut game);
+        }
+        let cohort = tallies
+            .cohorts
+            .as_ref()
+            .map(|cohorts| cohorts.label(&game, stratum.as_deref()));
+        let played = game::try_play(&config, &rules, game, &mut players);
+        if let Some(seat) = swap_seat {
+            std::mem::swap(&mut players[seat], variant_strategy.as_mut().unwrap());
+        }
+        if let Some(progress) = &mut progress {
+            progress.set(run as u64 + 1);
+        }
+        let mut game_result = match played {
+            Ok(game_result) => game_result,
+            Err(aborted) => {
+                warn!("Game {} aborted: {}", run, aborted.reason);
+                tallies.abort_tally.add(&aborted);
+                continue;
+            }
+        };
+        if let Some(format) = &config.match_format {
+            game_result.match_result = tallies.match_state.add(&config, format, &game_result);
+            if let Some(match_result) = &game_result.match_result {
+                tallies.matches.add(match_result);
+            }
+        }
+        if let (Some(ablation), Some(arm)) = (&config.ablation, arm) {
+            tallies.ablation_tally.add(ablation, arm, run, &game_result);
+        }
+        if let Some(output) = &mut output {
+            output
+                .write_game(&output::GameRecord::new(run, &game_result))
+                .map_err(|e| HedonicaError::io("the output file", e))?;
+        }
+        debug!(
+            "Game {}: player {} won after {} turns",
+            run, game_result.winner, game_result.turns
+        );
+        *tallies
+            .wins_by_player
+            .entry(game_result.winner)
+            .or_insert(0) += 1;
+        tallies.turn_stats.add(game_result.turns as f64);
+        if let Some(histogram) = &mut tallies.turn_histogram {
+            histogram.add(game_result.turns as f64);
+        }
+        tallies
+            .seat_fairness
+            .add(game_result.winner, &game_result.scores);
+        for &player in &game_result.eliminated {
+            *tallies.eliminations_by_player.entry(player).or_insert(0) += 1;
+        }
+        for (player, &bailouts) in game_result.bailouts.iter().enumerate() {
+            *tallies.bailouts_by_player.entry(player).or_insert(0) += bailouts;
+        }
+        for (player, &loans) in game_result.loans_taken.iter().enumerate() {
+            *tallies.loans_by_player.entry(player).or_insert(0) += loans;
+        }
+        for (player, &defaults) in game_result.loan_defaults.iter().enumerate() {
+            *tallies.loan_defaults_by_player.entry(player).or_insert(0) += defaults;
+        }
+        for (player, ledger) in game_result.events.iter().enumerate() {
+            tallies
+                .events_by_player
+                .entry(player)
+                .or_default()
+                .add(ledger);
+        }
+        tallies.action_counts.draws += game_result.action_counts.draws;
+        tallies.action_counts.trade_rounds += game_result.action_counts.trade_rounds;
+        tallies.action_counts.converts += game_result.action_counts.converts;
+        tallies.pacing.add(&game_result.pacing);
+        tallies.liquidity.add(&game_result.liquidity);
+        tallies.spreads.extend(game_result.liquidity.mean_spread);
+        tallies.milestones.add(&game_result);
+        tallies
+            .placements
+            .add(&game_result.placements, config.placement_points.as_deref());
+        tallies.trade_bias.add(&game_result.trade_flows, &labels);
+        tallies.trade_history.add(&game_result.trade_history);
+        tallies.trajectories.add(&game_result.trajectory);
+        tallies
+            .advantage
+            .add(&game_result.advantage, game_result.winner);
+        *tallies.endings.entry(game_result.ending).or_insert(0) += 1;
+        if let (Some(cohorts), Some(cohort)) = (&mut tallies.cohorts, cohort) {
+            cohorts.add(cohort, &game_result);
+        }
+        for record in &game_result.trade_errors {
+            *tallies
+                .trade_errors_by_player
+                .entry(record.player)
+                .or_insert(0) += 1;
+        }
+        if game_result.aborted {
+            tallies.aborted_games += 1;
+        }
+        tallies.bid_history.extend(game_result.bids);
+        tallies.weighted_results.add(
+            game_result.winner,
+            game_result.turns as f64,
+            weight,
+            stratum.as_deref(),
+        );
+
+        if let Some(early_stopping) = &config.early_stopping {
+            if let Some(reason) = early_stopping.check(&tallies.turn_stats, &tallies.wins_by_player)
+            {
+                stopped_early = Some(StoppedEarly {
+                    runs: tallies.turn_stats.len(),
+                    reason,
+                });
+                break;
+            }
+        }
+    }
+
+    if let Some(progress) = &mut progress {
+        progress.finish();
+    }
+    if config.match_format.is_some() && tallies.match_state.unfinished_games() > 0 {
+        warn!("The batch ended partway through a match; its {} games are left out of the match statistics", tallies.match_state.unfinished_games());
+    }
+    if let Some(output) = &mut output {
+        output
+            .finish()
+            .map_err(|e| HedonicaError::io("the output file", e))?;
+    }
+    if let Some(path) = &profile_path {
+        profile_store.record(&players, &labels);
+        profile_store
+            .save(path)
+            .map_err(|e| HedonicaError::io(path.display(), e))?;
+    }
+
+    let runs = tallies.turn_stats.len();
+    let wins: Vec<u64> = (0..config.num_players)
+        .map(|player| tallies.wins_by_player.get(&player).copied().unwrap_or(0) as u64)
+        .collect();
+    let intervals: Vec<(f64, f64)> = wins
+        .iter()
+        .map(|&wins| stats::rate_interval(wins as f64, runs as f64, CONFIDENCE_LEVEL))
+        .collect();
+    let decision_times: Vec<_> = decision_times
+        .iter()
+        .map(|times| times.lock().unwrap())
+        .collect();
+    let players = (0..config.num_players)
+        .map(|seat| report::PlayerReport {
+            seat,
+            strategy: labels[seat].clone(),
+            wins: tallies.wins_by_player.get(&seat).copied().unwrap_or(0),
+            win_rate_interval: Some(intervals[seat]).filter(|_| runs > 0),
+            decision_times: &decision_times[seat],
+            eliminations: Some(
+                tallies
+                    .eliminations_by_player
+                    .get(&seat)
+                    .copied()
+                    .unwrap_or(0),
+            )
+            .filter(|_| rules.bankruptcy != BankruptcyRule::Ignore),
+            bailouts: Some(tallies.bailouts_by_player.get(&seat).copied().unwrap_or(0))
+                .filter(|_| rules.bankruptcy != BankruptcyRule::Ignore),
+            loans: Some(tallies.loans_by_player.get(&seat).copied().unwrap_or(0))
+                .filter(|_| rules.loans.is_some()),
+            loan_defaults: Some(
+                tallies
+                    .loan_defaults_by_player
+                    .get(&seat)
+                    .copied()
+                    .unwrap_or(0),
+            )
+            .filter(|_| rules.loans.is_some()),
+            insurance: tallies
+                .events_by_player
+                .get(&seat)
+                .map(|ledger| ledger.summary())
+                .filter(|_| rules.events.is_some()),
+            invalid_trades: Some(
+                tallies
+                    .trade_errors_by_player
+                    .get(&seat)
+                    .copied()
+                    .unwrap_or(0),
+            )
+            .filter(|_| !tallies.trade_errors_by_player.is_empty()),
+        })
+        .collect();
+    let total_turns = tallies.turn_stats.mean() * runs as f64;
+    let per_turn = |count: u32| count as f64 / total_turns;
+    let sections = report::Sections {
+        // With two players, placements say no more than the wins do.
+        placements: Some(tallies.placements.summary())
+            .filter(|_| config.num_players > 2 || config.placement_points.is_some()),
+        // Only seats with different strategies have anything to compare.
+        trade_bias: Some(tallies.trade_bias.summary())
+            .filter(|_| labels.iter().any(|label| *label != labels[0])),
+        bidding: Some(bidding::summary(&tallies.bid_history))
+            .filter(|_| rules.contested.is_some() || rules.auction.is_some()),
+        milestones: Some(tallies.milestones.summary(&rules))
+            .filter(|_| !rules.milestones.is_empty()),
+        aborted_games: Some(tallies.aborted_games)
+            .filter(|_| !tallies.trade_errors_by_player.is_empty()),
+        pacing: rules
+            .blitz
+            .as_ref()
+            .map(|_| tallies.pacing.summary(total_turns)),
+        action_points: rules.action_points.as_ref().map(|_| {
+            serde_json::json!({
+                "draws_per_turn": per_turn(tallies.action_counts.draws),
+                "trade_rounds_per_turn": per_turn(tallies.action_counts.trade_rounds),
+                "converts_per_turn": per_turn(tallies.action_counts.converts),
+            })
+        }),
+        liquidity: tallies.liquidity.summary(runs, &tallies.spreads),
+        trade_history: tallies.trade_history.summary(),
+        advantage: tallies.advantage.summary(),
+        endings: tallies
+            .endings
+            .iter()
+            .map(|(ending, &games)| (*ending, games as f64 / runs as f64))
+            .collect(),
+        matches: config
+            .match_format
+            .as_ref()
+            .map(|_| tallies.matches.summary(&tallies.match_state)),
+        weighted_results: Some(serde_json::to_value(&tallies.weighted_results).unwrap())
+            .filter(|_| config.preference_sampling != sampling::PreferenceSampling::Random),
+        ablation: config
+            .ablation
+            .as_ref()
+            .map(|ablation| tallies.ablation_tally.summary(ablation)),
+        trajectories: Some(tallies.trajectories.summary()).filter(|_| config.collect_trajectories),
+        cohorts: tallies
+            .cohorts
+            .as_ref()
+            .map(|cohorts| cohorts.summary(CONFIDENCE_LEVEL)),
+        aborts: Some(tallies.abort_tally.summary(runs)).filter(|_| !tallies.abort_tally.is_empty()),
+        time_budget: time_budget.map(|budget| {
+            time_budget_report(
+                budget,
+                start.elapsed(),
+                &tallies.turn_stats,
+                &tallies.wins_by_player,
+                config.num_players,
+            )
+        }),
+        stopped_early: stopped_early
+            .map(|stopped_early| serde_json::to_value(stopped_early).unwrap()),
+    };
+    let report = report::SimReport {
+        version: report::VERSION,
+        metadata: report::Metadata::new(
+            started_at,
+            start.elapsed().as_secs_f64(),
+            Some(first_run).filter(|&run| run > 0),
+            runs,
+        ),
+        sim_config: &sim_config,
+        game_rules: &game_rules,
+        confidence_level: CONFIDENCE_LEVEL,
+        players,
+        turns: &tallies.turn_stats,
+        turn_histogram: tallies
+            .turn_histogram
+            .as_ref()
+            .map(stats::Histogram::summary),
+        seat_fairness: &tallies.seat_fairness,
+        sections,
+        verdicts: verdict::verdicts(
+            &goals,
+            &tallies.turn_stats,
+            &tallies.wins_by_player,
+            &labels,
+        )
+        .iter()
+        .map(ToString::to_string)
+        .collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    Ok(())
+}
+
+// Plays a pilot batch and reports how many runs would detect the given effects.
+fn run_power(
+    mut config: SimConfig,
+    rules: GameRules,
+    matches: &clap::ArgMatches,
+) -> error::Result<()> {
+    let analysis = stats::PowerAnalysis {
+        alpha: number_arg(matches, "alpha")?,
+        power: number_arg(matches, "power")?,
+    };
+    let effect = number_arg(matches, "effect")?;
+    let turns_effect: Option<f64> = matches
+        .value_of("turns-effect")
+        .map(|_| number_arg(matches, "turns-effect"))
+        .transpose()?;
+    config.hide_game_state = true;
+
+    let mut players = load_strategies(&config, &rules)?;
+    let mut dealer = sampling::PreferenceDealer::new(&config, &rules)?;
+    let mut wins_by_player: BTreeMap<usize, i32> =
+        (0..config.num_players).map(|p| (p, 0)).collect();
+    let mut turn_stats = stats::Stats::default();
+    let mut progress = ProgressBar::new(config.num_runs as u64, config.hide_game_state)
+        .filter(|_| observer::shows_progress(&config.observers));
+    let seeds = rng::SeedSchedule::new(&config);
+    for run in 0..config.num_runs {
+        seeds.seeds(run).apply(&mut config);
+        let deal = dealer.deal(&config, &rules, run)?;
+        let game = game::generate_start_state(&config, &rules, deal.preferences)?;
+        let game_result = match game::try_play(&config, &rules, game, &mut players) {
+            Ok(game_result) => game_result,
+            Err(aborted) => {
+                warn!("Game {} aborted: {}", run, aborted.reason);
+                continue;
+            }
+        };
+        *wins_by_player.entry(game_result.winner).or_insert(0) += 1;
+        turn_stats.add(game_result.turns as f64);
+        debug!(
+            "Game {}: player {} won after {} turns",
+            run, game_result.winner, game_result.turns
+        );
+        if let Some(progress) = &mut progress {
+            progress.set(run as u64 + 1);
+        }
+    }
+    if let Some(progress) = &mut progress {
+        progress.finish();
+    }
+
+    let runs = turn_stats.len() as f64;
+    let win_rates: BTreeMap<usize, serde_json::Value> = wins_by_player
+        .iter()
+        .map(|(&player, &wins)| {
+            let rate = wins as f64 / runs;
+            (player, serde_json::json!({ "win_rate": rate, "required": analysis.for_rate(rate, effect) }))
+        })
+        .collect();
+    let mut report = serde_json::json!({
+        "pilot_runs": turn_stats.len(),
+        "alpha": analysis.alpha,
+        "power": analysis.power,
+        "win_rate_effect": effect,
+        "players": win_rates,
+    });
+    if let Some(turns_effect) = turns_effect {
+        report["mean_turns"] = serde_json::json!({
+            "mean": turn_stats.mean(),
+            "var": turn_stats.var(),
+            "effect": turns_effect,
+            "required": analysis.for_mean(turn_stats.var(), turns_effect),
+        });
+    }
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    Ok(())
+}
+
+// Plays num_runs games headless and reports how fast they went, as a quick check that a change
+// did not slow the engine down. The benches behind the bench feature measure more precisely.
+// Rates count only the games that finished, over the time spent playing them, since an aborted
+// game stops early; aborted games are counted on their own.
+fn run_bench(mut config: SimConfig, rules: GameRules) -> error::Result<()> {
+    config.hide_game_state = true;
+    let mut players = load_strategies(&config, &rules)?;
+    let mut dealer = sampling::PreferenceDealer::new(&config, &rules)?;
+    let seeds = rng::SeedSchedule::new(&config);
+    let (mut games, mut aborted, mut turns) = (0u64, 0u64, 0u64);
+    let mut playing = Duration::ZERO;
+    let start = Instant::now();
+    for run in 0..config.num_runs {
+        seeds.seeds(run).apply(&mut config);
+        let deal = dealer.deal(&config, &rules, run)?;
+        let game = game::generate_start_state(&config, &rules, deal.preferences)?;
+        let game_start = Instant::now();
+        match game::try_play(&config, &rules, game, &mut players) {
+            Ok(game_result) => {
+                games += 1;
+                turns += game_result.turns as u64;
+                playing += game_start.elapsed();
+            }
+            Err(_) => aborted += 1,
+        }
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    let playing = playing.as_secs_f64();
+    let report = serde_json::json!({
+        "games": games,
+        "aborted": aborted,
+        "seconds": seconds,
+        "games_per_second": games as f64 / playing,
+        "turns_per_second": turns as f64 / playing,
+    });
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    Ok(())
+}
+
+// Plays every balance scenario and exits with an error if any metric is out of its band.
+fn run_balance_check(matches: &clap::ArgMatches) -> error::Result<()> {
+    let text = match matches.value_of("scenarios") {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| HedonicaError::io(path, e))?,
+        None => balance::DEFAULT_SCENARIOS.to_string(),
+    };
+    let mut scenarios = balance::parse_scenarios(&text)?;
+    if let Some(name) = matches.value_of("scenario") {
+        scenarios.retain(|scenario| scenario.name == name);
+        if scenarios.is_empty() {
+            return Err(HedonicaError::parse(
+                "--scenario",
+                format!("there is no balance scenario named {}", name),
+            ));
+        }
+    }
+    let reports: Vec<balance::ScenarioReport> = scenarios
+        .iter_mut()
+        .map(balance::check)
+        .collect::<error::Result<_>>()?;
+    println!("{}", serde_json::to_string_pretty(&reports).unwrap());
+    let failed: Vec<&str> = reports
+        .iter()
+        .filter(|report| !report.passed)
+        .map(|report| report.name.as_str())
+        .collect();
+    if !failed.is_empty() {
+        error!("Balance check failed: {}", failed.join(", "));
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "plugins", unix))]
+fn load_plugins(dir: &Path) -> error::Result<()> {
+    let names = hedonica_strategies::plugins::load_dir(dir)
+        .map_err(|e| HedonicaError::io(dir.display(), e))?;
+    info!("Loaded plugin strategies: {}", names.join(", "));
+    Ok(())
+}
+
+#[cfg(not(all(feature = "plugins", unix)))]
+fn load_plugins(_dir: &Path) -> error::Result<()> {
+    Err(HedonicaError::Unsupported {
+        message: String::from("this build of the simulator cannot load plugins"),
+    })
+}
+
+#[cfg(all(feature = "plugins", unix))]
+fn load_strategy_lib(path: &Path) -> error::Result<()> {
+    let names = hedonica_strategies::strategy_lib::load(path)
+        .map_err(|e| HedonicaError::io(path.display(), e))?;
+    info!(
+        "Loaded strategies from {}: {}",
+        path.display(),
+        names.join(", ")
+    );
+    Ok(())
+}
+
+#[cfg(not(all(feature = "plugins", unix)))]
+fn load_strategy_lib(_path: &Path) -> error::Result<()> {
+    Err(HedonicaError::Unsupported {
+        message: String::from("this build of the simulator cannot load strategy libraries"),
+    })
+}
+
+// The flags every subcommand takes: where the sim config and game rules come from, how much
+// is logged and which strategies are registered besides the built-in ones. They may be given
+// before or after the subcommand.
+fn global_args<'a, 'b>(
+    app: App<'a, 'b>,
+    default_sim_config: &'a str,
+    default_game_rules: &'a str,
+) -> App<'a, 'b> {
+    app.arg(Arg::with_name("sim-config").long("sim-config").help("JSON of sim config").default_value(default_sim_config).takes_value(true).global(true))
+        .arg(Arg::with_name("game-rules").long("game-rules").help("JSON of game rules").default_value(default_game_rules).takes_value(true).global(true))
+        .arg(Arg::with_name("sim-config-file").long("sim-config-file").help("JSON5 file of sim config, which --sim-config and --set override").takes_value(true).global(true))
+        .arg(Arg::with_name("game-rules-file").long("game-rules-file").help("JSON5 file of game rules, which --game-rules and --set override").takes_value(true).global(true))
+        .arg(
+            Arg::with_name("set")
+                .long("set")
+                .help("Overrides one sim config or game rules value, e.g. rules.victory_threshold=40 or sim.num_runs=100")
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true)
+                .global(true),
+        )
+        .arg(Arg::with_name("verbose").short("v").long("verbose").help("Also logs a line per game played").conflicts_with("quiet").global(true))
+        .arg(Arg::with_name("quiet").short("q").long("quiet").help("Only logs errors: no warnings, game states or progress bar").global(true))
+        .arg(Arg::with_name("plugins").long("plugins").help("Registers the strategies of every plugin library (.so, .dylib) in this directory").takes_value(true).global(true))
+        .arg(
+            Arg::with_name("strategy-lib")
+                .long("strategy-lib")
+                .help("Registers the strategies of a Rust cdylib built with export_strategies!, once per library")
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true)
+                .global(true),
+        )
+}
+
+// The flags of the subcommands that play batches through the result cache.
+fn cache_args<'a, 'b>(command: App<'a, 'b>) -> App<'a, 'b> {
+    command
+        .arg(
+            Arg::with_name("cache-dir")
+                .long("cache-dir")
+                .help("Where batch results are cached")
+                .default_value(cache::DEFAULT_DIR)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("no-cache")
+                .long("no-cache")
+                .help("Plays every batch again instead of reusing cached results"),
+        )
+}
+
+fn open_cache(matches: &clap::ArgMatches) -> ResultCache {
+    if matches.is_present("no-cache") {
+        ResultCache::disabled()
+    } else {
+        ResultCache::open(Path::new(matches.value_of("cache-dir").unwrap()))
+    }
+}
+
+// The flags of the subcommands that play games and report on them: what is recorded, written,
+// logged and saved as they are played.
+fn game_output_args<'a, 'b>(command: App<'a, 'b>) -> App<'a, 'b> {
+    command
+        .arg(Arg::with_name("record").long("record").help("Records every game to this JSON lines replay file").takes_value(true))
+        .arg(Arg::with_name("output").long("output").help("Writes per-game results to this file, as CSV if it ends in .csv and JSON lines otherwise").takes_value(true))
+        .arg(Arg::with_name("log-events").long("log-events").help("Logs every turn, proposal, trade and game end as games are played"))
+        .arg(Arg::with_name("narrate").long("narrate").help("Narrates games as they are played, a plain line per draw, offer and game end, in place of the game state"))
+        .arg(
+            Arg::with_name("save-game")
+                .long("save-game")
+                .help("Saves the game to this path before every turn, to finish later with --load-game; needs num_runs 1")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("load-game")
+                .long("load-game")
+                .help("Continues a game saved with --save-game, given the same config and rules; it keeps saving there unless --save-game says otherwise")
+                .takes_value(true),
+        )
+}
+
+fn main() {
+    let default_sim_config =
+        serde_json::to_string_pretty(&json5::from_str::<SimConfig>("{}").unwrap()).unwrap();
+    let default_game_rules =
+        serde_json::to_string_pretty(&json5::from_str::<GameRules>("{}").unwrap()).unwrap();
+
+    let app = App::new("Hedonica Simulator")
+        .version("0.1")
+        .author("Michael Graczyk <michael@mgraczyk.com>")
+        .about("Simulates the Hedonica board game")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands);
+    let matches = global_args(app, &default_sim_config, &default_game_rules)
+        .subcommand(
+            game_output_args(SubCommand::with_name("simulate"))
+                .about("Plays num_runs games between the sim config's strategies and reports the results")
+                .arg(
+                    Arg::with_name("collect-trajectories")
+                        .long("collect-trajectories")
+                        .help("Records every player's score, goods and money each turn and reports mean trajectories"),
+                )
+                .arg(
+                    Arg::with_name("cohort-by")
+                        .long("cohort-by")
+                        .help("Reports outcomes by starting conditions, e.g. top:0,favoring:art (keys: top:<seat>, card:<seat>, favoring:<category>, stratum)")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("goals")
+                        .long("goals")
+                        .help("JSON file of the goal thresholds for the verdict at the end of a batch [default: the checked-in goals.json5]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("time-budget")
+                        .long("time-budget")
+                        .help("Plays as many games as fit in this wall-clock time, e.g. 90s, 5m or 2h, instead of num_runs")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("dashboard")
+                        .long("dashboard")
+                        .value_name("port")
+                        .help("Serves a live dashboard of the batch's progress, win rates and turns on http://127.0.0.1:<port>/ while it plays")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("checkpoint")
+                        .long("checkpoint")
+                        .help("Writes a checkpoint of the batch to this path every --checkpoint-every runs, to continue with --resume")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("checkpoint-every")
+                        .long("checkpoint-every")
+                        .help("How many runs to play between checkpoints")
+                        .default_value("1000")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .help("Continues an interrupted batch from its checkpoint, given the same config, rules and flags; it keeps checkpointing there unless --checkpoint says otherwise")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            game_output_args(SubCommand::with_name("play"))
+                .about("Plays one game at the terminal against the sim config's strategies")
+                .arg(
+                    Arg::with_name("seat")
+                        .long("seat")
+                        .help("A seat you play, replacing the sim config's strategy there [default: 0, unless the sim config seats RealPlayerCLI]")
+                        .takes_value(true)
+                        .number_of_values(1)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Plays back a replay file recorded with --record")
+                .arg(Arg::with_name("replay").help("The replay file").required(true)),
+        )
+        .subcommand(SubCommand::with_name("bench").about("Plays num_runs games headless and reports games per second, as a performance smoke test"))
+        .subcommand(SubCommand::with_name("strategies").about("Prints every registered strategy with the schema of its config"))
+        .subcommand(SubCommand::with_name("validate-config").about("Checks that the sim config and game rules can be played, and prints the settings to change if not"))
+        .subcommand(
+            SubCommand::with_name("generate-sdk")
+                .about("Generates bot client stubs for the strategy protocol")
+                .arg(
+                    Arg::with_name("language")
+                        .required(true)
+                        .possible_values(sdk::LANGUAGES),
+                )
+                .arg(
+                    Arg::with_name("output-dir")
+                        .long("output-dir")
+                        .help("Directory to write the sdk into [default: sdk/<language>]")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("examples")
+                .about("Runs the curated end-to-end analyses, writing each one's inputs and results into its own directory")
+                .arg(
+                    Arg::with_name("names")
+                        .help("Only runs the examples with these names")
+                        .possible_values(&examples::EXAMPLES.iter().map(|example| example.name).collect::<Vec<_>>())
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("output-dir")
+                        .long("output-dir")
+                        .help("Directory to write the examples into")
+                        .default_value("examples-output")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("gym")
+                .about("Serves a reinforcement learning env over stdin and stdout, one JSON request per line")
+                .arg(
+                    Arg::with_name("seat")
+                        .long("seat")
+                        .help("The seat the agent plays; the other seats play the sim config's strategies")
+                        .default_value("0")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Hosts games over TCP, speaking the JSON line bot protocol rather than gRPC: clients create or join lobbies, then play their seats")
+                .arg(
+                    Arg::with_name("listen")
+                        .long("listen")
+                        .help("The address to accept clients on")
+                        .default_value("127.0.0.1:7878")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("seats")
+                        .long("seats")
+                        .help("How many seats of each game clients take; the rest play the sim config's strategies. Defaults to every seat")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("action-timeout")
+                        .long("action-timeout")
+                        .help("How long a client has to answer each request before its game is aborted, e.g. 30s or 5m")
+                        .default_value("60s")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("power")
+                .about("Plays a pilot batch of num_runs games and reports the runs needed to detect an effect")
+                .arg(
+                    Arg::with_name("effect")
+                        .long("effect")
+                        .help("Win rate difference to detect, e.g. 0.03")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("turns-effect")
+                        .long("turns-effect")
+                        .help("Difference in mean game length, in turns, to detect")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("alpha")
+                        .long("alpha")
+                        .help("Significance level of the two-sided test")
+                        .default_value("0.05")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("power")
+                        .long("power")
+                        .help("Chance of detecting the effect when it is real")
+                        .default_value("0.8")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("balance-check")
+                .about("Plays the balance scenarios and fails if any metric moves outside its tolerance band")
+                .arg(
+                    Arg::with_name("scenarios")
+                        .long("scenarios")
+                        .help("JSON file of balance scenarios [default: the checked-in balance.json5]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("scenario")
+                        .long("scenario")
+                        .help("Only plays the scenario with this name")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            cache_args(SubCommand::with_name("sweep"))
+                .about("Plays num_runs games for every combination of game rules values in a grid")
+                .arg(
+                    Arg::with_name("grid")
+                        .long("grid")
+                        .help("JSON of game rules parameters to lists of values, e.g. {victory_threshold: [30, 40, 50]}")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            cache_args(SubCommand::with_name("compare-rules"))
+                .about("Plays num_runs games with the same seeds and strategies under each of two or more game rules and tests the differences from the first")
+                .arg(
+                    Arg::with_name("rules")
+                        .help("Paths of game rules JSON5, each merged over --game-rules")
+                        .required(true)
+                        .min_values(2)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            cache_args(SubCommand::with_name("tournament"))
+                .about("Plays round-robin two-player matches of num_runs games per seating between strategies and rates them")
+                .arg(
+                    Arg::with_name("strategies")
+                        .help("Registered strategy names, e.g. PlayerGreedy PlayerNoTrades")
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
+        .subcommand(
+            cache_args(SubCommand::with_name("league"))
+                .about("Plays rounds of a league between strategies, keeping Elo ratings on disk between runs, and prints the standings")
+                .arg(Arg::with_name("strategies").help("Registered strategy names to enter in the league, if not already in it").multiple(true))
+                .arg(
+                    Arg::with_name("db")
+                        .long("db")
+                        .help("The league's ratings file, created if missing")
+                        .default_value("league.json")
+                        .takes_value(true),
+                )
+                .arg(Arg::with_name("rounds").long("rounds").help("Rounds to play, each a match for every entrant but one if they are odd").default_value("1").takes_value(true)),
+        )
+        .subcommand(
+            cache_args(SubCommand::with_name("evolve"))
+                .about("Evolves a strategy's config parameters toward a higher win rate in two-player self-play")
+                .arg(Arg::with_name("strategy").help("Registered strategy name, e.g. PlayerGreedy").required(true))
+                .arg(
+                    Arg::with_name("genes")
+                        .long("genes")
+                        .help("JSON of the strategy's number config parameters to ranges, e.g. {offer_fraction: [0, 1]}")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .help("Where to write the last generation's configs, fittest first")
+                        .default_value("evolved.json")
+                        .takes_value(true),
+                )
+                .arg(Arg::with_name("population").long("population").help("Configs in each generation").default_value("12").takes_value(true))
+                .arg(Arg::with_name("generations").long("generations").default_value("10").takes_value(true))
+                .arg(
+                    Arg::with_name("opponents")
+                        .long("opponents")
+                        .help("Other configs each config plays a match against, each generation")
+                        .default_value("3")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("elite")
+                        .long("elite")
+                        .help("Fittest configs kept unchanged in the next generation")
+                        .default_value("2")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .help("Seed for the evolution, or 0 to seed from the OS")
+                        .default_value("0")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("annotate")
+                .about("Adds each player's chance of winning after every recorded turn to a replay, using rollouts under --game-rules")
+                .arg(Arg::with_name("replay").required(true))
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .help("Where to write the annotated replay [default: the replay path with .annotated before the extension]")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("rollouts")
+                        .long("rollouts")
+                        .help("Rollouts per evaluation")
+                        .default_value("200")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("rollout-strategy")
+                        .long("rollout-strategy")
+                        .help("Registered strategy that plays every seat in rollouts")
+                        .default_value("PlayerGreedy")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .help("Seed for reshuffling decks in rollouts, or 0 to seed from the OS")
+                        .default_value("0")
+                        .takes_value(true),
+                ),
+        )
+        .get_matches();
+
+    let (command, matches) = matches.subcommand();
+    let matches = matches.unwrap();
+    if matches.is_present("quiet") {
+        log::set_level(Level::Error);
+    } else if matches.is_present("verbose") {
+        log::set_level(Level::Debug);
+    }
+    if let Err(e) = run(command, matches) {
+        error!("{}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(command: &str, matches: &clap::ArgMatches) -> error::Result<()> {
+    if let Some(dir) = matches.value_of("plugins") {
+        load_plugins(Path::new(dir))?;
+    }
+    for path in matches.values_of("strategy-lib").into_iter().flatten() {
+        load_strategy_lib(Path::new(path))?;
+    }
+    match command {
+        "simulate" => run_simulate(config::load(matches, &config::SIM_CONFIG)?, matches),
+        "play" => {
+            let mut config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            config.num_runs = 1;
+            let seats: Vec<usize> = match matches.values_of("seat") {
+                Some(seats) => seats
+                    .map(|seat| {
+                        seat.parse()
+                            .map_err(|_| HedonicaError::parse("--seat", "expected a seat number"))
+                    })
+                    .collect::<error::Result<_>>()?,
+                None if real_player_cli::plays_in(&config) => Vec::new(),
+                None => vec![0],
+            };
+            for seat in seats {
+                real_player_cli::take_seat(&mut config, seat)?;
+            }
+            run_simulate(config, matches)
+        }
+        "replay" => {
+            let config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            let path = matches.value_of("replay").unwrap();
+            let events = replay::read(Path::new(path)).map_err(|e| HedonicaError::io(path, e))?;
+            replay::play_back(
+                &events,
+                config
+                    .turn_pause_millis
+                    .unwrap_or(pacing::DEFAULT_PAUSE_MILLIS),
+                config.hide_game_state,
+            );
+            Ok(())
+        }
+        "bench" => run_bench(
+            config::load(matches, &config::SIM_CONFIG)?,
+            config::load(matches, &config::GAME_RULES)?,
+        ),
+        "strategies" => {
+            let strategies: serde_json::Map<String, serde_json::Value> = registered_strategies()
+                .into_iter()
+                .map(|name| config_schema(&name).map(|schema| (name, schema)))
+                .collect::<error::Result<_>>()?;
+            println!("{}", serde_json::to_string_pretty(&strategies).unwrap());
+            Ok(())
+        }
+        "validate-config" => {
+            let config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let diagnostics = validate::check(&config, &rules);
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic);
+            }
+            if diagnostics
+                .iter()
+                .any(|d| d.severity == validate::Severity::Error)
+            {
+                return Err(HedonicaError::Unsupported {
+                    message: String::from(
+                        "the sim config and game rules cannot be played; see the errors above",
+                    ),
+                });
+            }
+            if diagnostics.is_empty() {
+                println!("The sim config and game rules are valid");
+            } else {
+                println!("The sim config and game rules are valid, but see the warnings above");
+            }
+            Ok(())
+        }
+        "generate-sdk" => {
+            let language = matches.value_of("language").unwrap();
+            let output_dir = matches.value_of("output-dir").map_or_else(
+                || Path::new("sdk").join(language),
+                |d| Path::new(d).to_path_buf(),
+            );
+            sdk::write(language, &output_dir)
+                .map_err(|e| HedonicaError::io(output_dir.display(), e))?;
+            println!("Wrote {} sdk to {}", language, output_dir.display());
+            Ok(())
+        }
+        "examples" => {
+            let output_dir = Path::new(matches.value_of("output-dir").unwrap());
+            let names: Vec<&str> = matches
+                .values_of("names")
+                .map_or_else(Vec::new, |names| names.collect());
+            examples::run_all(output_dir, &names)?;
+            println!("Wrote examples to {}", output_dir.display());
+            Ok(())
+        }
+        "balance-check" => run_balance_check(matches),
+        "annotate" => {
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let replay_path = Path::new(matches.value_of("replay").unwrap());
+            let output_path = matches.value_of("output").map_or_else(
+                || {
+                    replay_path.with_extension(format!(
+                        "annotated.{}",
+                        replay_path
+                            .extension()
+                            .and_then(|e| e.to_str())
+                            .unwrap_or("jsonl")
+                    ))
+                },
+                |path| Path::new(path).to_path_buf(),
+            );
+            let rollouts: u32 = number_arg(matches, "rollouts")?;
+            let seed: u64 = number_arg(matches, "seed")?;
+            let rollout_strategy = matches.value_of("rollout-strategy").unwrap();
+            check_registered(rollout_strategy)?;
+            // The replay's goods get the ids the annotator's games use.
+            rules.start_categories();
+            let events = replay::read(replay_path)
+                .map_err(|e| HedonicaError::io(replay_path.display(), e))?;
+            let mut annotator = annotate::Annotator::new(
+                &rules,
+                rollouts,
+                rollout_strategy,
+                rng::config_seed(seed),
+            );
+            let (annotated, summaries) = annotator.annotate(events);
+            replay::write(&output_path, &annotated)
+                .map_err(|e| HedonicaError::io(output_path.display(), e))?;
+            println!("{}", serde_json::to_string_pretty(&summaries).unwrap());
+            Ok(())
+        }
+        "tournament" => {
+            let base_config = config::load_value(matches, &config::SIM_CONFIG)?;
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let strategies: Vec<String> = matches
+                .values_of("strategies")
+                .unwrap()
+                .map(String::from)
+                .collect();
+            for strategy in &strategies {
+                check_registered(strategy)?;
+            }
+            let report = tournament::play(&base_config, &rules, &strategies, &open_cache(matches))?;
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            Ok(())
+        }
+        "league" => {
+            let base_config = config::load_value(matches, &config::SIM_CONFIG)?;
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let cache = open_cache(matches);
+            let path = Path::new(matches.value_of("db").unwrap());
+            let mut league =
+                League::load(path).map_err(|e| HedonicaError::io(path.display(), e))?;
+            for strategy in matches.values_of("strategies").into_iter().flatten() {
+                check_registered(strategy)?;
+                league.enter(strategy);
+            }
+            let rounds: usize = number_arg(matches, "rounds")?;
+            let mut played = Vec::new();
+            for _ in 0..rounds {
+                played.extend(league.play_round(&base_config, &rules, &cache)?);
+                league
+                    .save(path)
+                    .map_err(|e| HedonicaError::io(path.display(), e))?;
+            }
+            league
+                .save(path)
+                .map_err(|e| HedonicaError::io(path.display(), e))?;
+            let report = serde_json::json!({ "matches": played, "standings": league.standings() });
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            Ok(())
+        }
+        "evolve" => {
+            let base_config = config::load_value(matches, &config::SIM_CONFIG)?;
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let strategy = matches.value_of("strategy").unwrap();
+            check_registered(strategy)?;
+            let genes = evolve::parse_genes(matches.value_of("genes").unwrap(), strategy)?;
+            let options = evolve::EvolveOptions {
+                population: number_arg(matches, "population")?,
+                generations: number_arg(matches, "generations")?,
+                opponents: number_arg(matches, "opponents")?,
+                elite: number_arg(matches, "elite")?,
+                seed: number_arg(matches, "seed")?,
+            };
+            let evolution = evolve::run(
+                &base_config,
+                &rules,
+                strategy,
+                &genes,
+                &options,
+                &open_cache(matches),
+            )?;
+            let output_path = matches.value_of("output").unwrap();
+            let configs = serde_json::to_string_pretty(&evolution.population).unwrap();
+            std::fs::write(output_path, configs).map_err(|e| HedonicaError::io(output_path, e))?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&evolution.generations).unwrap()
+            );
+            Ok(())
+        }
+        "gym" => {
+            let config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let seat: usize = number_arg(matches, "seat")?;
+            gym::serve(hedonica_engine::gym::HedonicaEnv::new(
+                &config, &rules, seat,
+            ))
+            .map_err(|e| HedonicaError::io("the gym env", e))
+        }
+        "serve" => {
+            let config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let seats = match matches.value_of("seats") {
+                Some(_) => number_arg(matches, "seats")?,
+                None => config.num_players,
+            };
+            if seats == 0 || seats > config.num_players {
+                return Err(HedonicaError::Unsupported {
+                    message: format!(
+                        "--seats must be from 1 to the {} players of the sim config",
+                        config.num_players
+                    ),
+                });
+            }
+            let options = serve::ServeOptions {
+                seats,
+                action_timeout: parse_duration(matches.value_of("action-timeout").unwrap())?,
+            };
+            let address = matches.value_of("listen").unwrap();
+            serve::serve(address, &config, rules, options)
+                .map_err(|e| HedonicaError::io(address, e))
+        }
+        "power" => run_power(
+            config::load(matches, &config::SIM_CONFIG)?,
+            config::load(matches, &config::GAME_RULES)?,
+            matches,
+        ),
+        "sweep" => {
+            let mut config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            let base_rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+            let base_rules = serde_json::to_value(base_rules).unwrap();
+            let grid = sweep::parse_grid(matches.value_of("grid").unwrap(), &base_rules)?;
+            let cells = sweep::run(&mut config, &base_rules, &grid, &open_cache(matches))?;
+            sweep::print_table(&grid, &cells);
+            Ok(())
+        }
+        "compare-rules" => {
+            let mut config: SimConfig = config::load(matches, &config::SIM_CONFIG)?;
+            let rules = matches
+                .values_of("rules")
+                .unwrap()
+                .map(|path| {
+                    Ok((
+                        path.to_string(),
+                        config::load_over(matches, &config::GAME_RULES, path)?,
+                    ))
+                })
+                .collect::<error::Result<Vec<(String, GameRules)>>>()?;
+            let comparison = compare_rules::run(&mut config, &rules, &open_cache(matches))?;
+            println!("{}", serde_json::to_string_pretty(&comparison).unwrap());
+            Ok(())
+        }
+        _ => unreachable!("clap only matches the subcommands above"),
+    }
+}
+
+// Plays the games of the simulate and play subcommands, with the observers their flags and the
+// config ask for.
+fn run_simulate(mut config: SimConfig, matches: &clap::ArgMatches) -> error::Result<()> {
+    if matches.is_present("dashboard") {
+        let port: u16 = number_arg(matches, "dashboard")?;
+        let runs = Some(config.num_runs as u64).filter(|_| !matches.is_present("time-budget"));
+        config
+            .hooks
+            .push(Box::new(dashboard::start(port, runs).map_err(|e| {
+                HedonicaError::io(format!("port {}", port), e)
+            })?));
+    }
+    // Flags add observers to the config's pipeline, replacing any of the same type.
+    let observers = config.observers.clone();
+    let configured =
+        |path_of: fn(&ObserverConfig) -> Option<&str>| observers.iter().find_map(path_of);
+    if matches.is_present("collect-trajectories")
+        || observers.contains(&ObserverConfig::Trajectories)
+    {
+        config.collect_trajectories = true;
+    }
+    if matches.is_present("log-events") || observers.contains(&ObserverConfig::Log) {
+        config.hooks.push(Box::new(observer::EventLog));
+        if !config.observers.contains(&ObserverConfig::Log) {
+            config.observers.push(ObserverConfig::Log);
+        }
+    }
+    if matches.is_present("narrate") || observers.contains(&ObserverConfig::Narrate) {
+        config.hide_game_state = true;
+        config.hooks.push(Box::new(Narrator::new()));
+        if !config.observers.contains(&ObserverConfig::Narrate) {
+            config.observers.push(ObserverConfig::Narrate);
+        }
+    }
+    let record_path = matches.value_of("record").or_else(|| {
+        configured(|o| match o {
+            ObserverConfig::Replay { path } => Some(path),
+            _ => None,
+        })
+    });
+    if let Some(path) = record_path {
+        config.recorder = Some(
+            replay::Recorder::create(Path::new(path)).map_err(|e| HedonicaError::io(path, e))?,
+        );
+    }
+    let output_path = matches.value_of("output").or_else(|| {
+        configured(|o| match o {
+            ObserverConfig::Output { path } => Some(path),
+            _ => None,
+        })
+    });
+    let cohort_keys = matches.value_of("cohort-by").or_else(|| {
+        configured(|o| match o {
+            ObserverConfig::Cohorts { by } => Some(by),
+            _ => None,
+        })
+    });
+    let rules: GameRules = config::load(matches, &config::GAME_RULES)?;
+    // Saved games and checkpoints name categories, which must get the ids the games will use.
+    rules.start_categories();
+    let time_budget = matches
+        .value_of("time-budget")
+        .map(parse_duration)
+        .transpose()?;
+    let cohorts = cohort_keys
+        .map(|keys| cohorts::parse_keys(keys, config.num_players).map(cohorts::Cohorts::new))
+        .transpose()?;
+    let goals = match matches.value_of("goals") {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| HedonicaError::io(path, e))?,
+        None => verdict::DEFAULT_GOALS.to_string(),
+    };
+    let checkpoint_path = matches
+        .value_of("checkpoint")
+        .or_else(|| matches.value_of("resume"));
+    let checkpointing = match checkpoint_path {
+        Some(path) => {
+            if matches.is_present("resume") && record_path.is_some() {
+                return Err(HedonicaError::Unsupported {
+                    message: String::from("a batch recording a replay cannot be resumed"),
+                });
+            }
+            let every: i32 = number_arg(matches, "checkpoint-every")?;
+            if every <= 0 {
+                return Err(HedonicaError::parse(
+                    "--checkpoint-every",
+                    "expected a positive number of runs",
+                ));
+            }
+            let resumed = matches
+                .value_of("resume")
+                .map(|path| checkpoint::load(Path::new(path)))
+                .transpose()?;
+            Some(checkpoint::Checkpointing {
+                path: PathBuf::from(path),
+                every,
+                resumed,
+            })
+        }
+        None => None,
+    };
+    let save_path = matches
+        .value_of("save-game")
+        .or_else(|| matches.value_of("load-game"));
+    let saving = match save_path {
+        Some(path) => {
+            if config.num_runs != 1 || time_budget.is_some() {
+                return Err(HedonicaError::Unsupported {
+                    message: String::from(
+                        "only a single game can be saved or loaded; set num_runs to 1",
+                    ),
+                });
+            }
+            let loaded = matches
+                .value_of("load-game")
+                .map(|path| saves::load(Path::new(path)))
+                .transpose()?;
+            Some(saves::Saving {
+                path: Some(PathBuf::from(path)),
+                loaded,
+            })
+        }
+        None => None,
+    };
+    let options = BatchOptions {
+        output_path: output_path.map(Path::new),
+        time_budget,
+        cohorts,
+        goals: verdict::parse_goals(&goals)?,
+        checkpointing,
+        saving,
+    };
+    run_sim(config, rules, options)
+}
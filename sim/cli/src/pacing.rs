@@ -0,0 +1,64 @@
+// Paces and shows games played on the command line, for people watching or playing them.
+//
+// The engine plays at full speed, so run_sim attaches a Pacer as a hook when there is anyone
+// to pace for. It pauses before every round for turn_pause_millis, or when that is unset for
+// DEFAULT_PAUSE_MILLIS in games a human plays in and not at all otherwise. Unless
+// hide_game_state is set or a human plays, who has the screen in tui.rs, it also shows the
+// game state before every round and the scores at the end of games scored in secret.
+use crate::real_player_cli;
+use hedonica_engine::game::{GameResult, GameRules, GameState, ScoreVisibility, SimConfig};
+use hedonica_engine::info;
+use hedonica_engine::observer::Observer;
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_PAUSE_MILLIS: u64 = 500;
+
+pub struct Pacer {
+    pause: Duration,
+    show_game_state: bool,
+}
+
+impl Pacer {
+    // None when there is nothing to pause for or show.
+    pub fn for_config(config: &SimConfig) -> Option<Pacer> {
+        let human = real_player_cli::plays_in(config);
+        let default_pause = if human { DEFAULT_PAUSE_MILLIS } else { 0 };
+        let pause = Duration::from_millis(config.turn_pause_millis.unwrap_or(default_pause));
+        // A human sees the game on their own screen, which the state would scroll away.
+        let show_game_state = !config.hide_game_state && !human;
+        if pause.is_zero() && !show_game_state {
+            return None;
+        }
+        Some(Pacer {
+            pause,
+            show_game_state,
+        })
+    }
+}
+
+impl Observer for Pacer {
+    fn on_round_start(&self, game: &GameState) {
+        if !self.pause.is_zero() {
+            thread::sleep(self.pause);
+        }
+        if self.show_game_state {
+            info!(
+                "{}",
+                serde_json::to_string_pretty(&game.table_view()).unwrap()
+            );
+        }
+    }
+
+    fn on_game_end(&self, rules: &GameRules, result: &GameResult) {
+        if self.show_game_state && rules.score_visibility == ScoreVisibility::Hidden {
+            info!(
+                "{}",
+                serde_json::to_string_pretty(
+                    &serde_json::json!({ "revealed_scores": result.scores })
+                )
+                .unwrap()
+            );
+        }
+    }
+}
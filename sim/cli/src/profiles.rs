@@ -0,0 +1,47 @@
+// Strategy profiles that persist between batches, so strategies that model their opponents
+// can carry what they learned from one tournament stage into the next.
+//
+// The store is a JSON object keyed by strategy label. Each value is opaque to the simulator
+// and belongs to the strategy with that label. When several seats share a label, the last
+// seat's profile is the one saved.
+use hedonica_engine::player::PlayerStrategy;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Default)]
+pub struct ProfileStore {
+    profiles: BTreeMap<String, serde_json::Value>,
+}
+
+impl ProfileStore {
+    // A missing file is an empty store.
+    pub fn load(path: &Path) -> io::Result<ProfileStore> {
+        if !path.exists() {
+            return Ok(ProfileStore::default());
+        }
+        let profiles = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok(ProfileStore { profiles })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(&self.profiles)?)
+    }
+
+    pub fn restore(&self, players: &mut [Box<dyn PlayerStrategy>], labels: &[String]) {
+        for (player, label) in players.iter_mut().zip(labels) {
+            if let Some(profile) = self.profiles.get(label) {
+                player.load_profile(profile, labels);
+            }
+        }
+    }
+
+    pub fn record(&mut self, players: &[Box<dyn PlayerStrategy>], labels: &[String]) {
+        for (player, label) in players.iter().zip(labels) {
+            if let Some(profile) = player.save_profile() {
+                self.profiles.insert(label.clone(), profile);
+            }
+        }
+    }
+}
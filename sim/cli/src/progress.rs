@@ -0,0 +1,93 @@
+// A progress bar over the runs of a batch, or over its time budget, drawn on stderr.
+//
+// It is only drawn when stderr is a terminal and nothing else is logging while games play: not
+// with -q, with -v, or when game states are shown, since those lines would tear the bar.
+use hedonica_engine::log::{self, Level};
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 30;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+enum Goal {
+    Runs(u64),
+    Time(Duration),
+}
+
+pub struct ProgressBar {
+    goal: Goal,
+    start: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl ProgressBar {
+    // None when the bar should not be drawn.
+    pub fn new(total: u64, hide_game_state: bool) -> Option<ProgressBar> {
+        ProgressBar::start(Goal::Runs(total), hide_game_state)
+    }
+
+    // A bar that fills over the time budget rather than the runs.
+    pub fn timed(budget: Duration, hide_game_state: bool) -> Option<ProgressBar> {
+        ProgressBar::start(Goal::Time(budget), hide_game_state)
+    }
+
+    fn start(goal: Goal, hide_game_state: bool) -> Option<ProgressBar> {
+        let show = log::enabled(Level::Info)
+            && !log::enabled(Level::Debug)
+            && hide_game_state
+            && io::stderr().is_terminal();
+        if !show {
+            return None;
+        }
+        Some(ProgressBar {
+            goal,
+            start: Instant::now(),
+            last_draw: None,
+        })
+    }
+
+    pub fn set(&mut self, done: u64) {
+        let now = Instant::now();
+        let elapsed = now - self.start;
+        let fraction = match self.goal {
+            Goal::Runs(total) => done as f64 / total.max(1) as f64,
+            Goal::Time(budget) => (elapsed.as_secs_f64() / budget.as_secs_f64()).min(1.),
+        };
+        if fraction < 1.
+            && self
+                .last_draw
+                .is_some_and(|last| now - last < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.last_draw = Some(now);
+
+        let filled = (fraction * WIDTH as f64) as usize;
+        let elapsed = elapsed.as_secs_f64();
+        let counts = match self.goal {
+            Goal::Runs(total) => format!("{}/{}", done, total),
+            Goal::Time(_) => format!("{} games", done),
+        };
+        let eta = if done > 0 {
+            format!("{:.0}s left", elapsed / fraction - elapsed)
+        } else {
+            String::from("")
+        };
+        eprint!(
+            "\r[{}{}] {} {:3.0}% {:.0}s {}\x1b[K",
+            "#".repeat(filled),
+            "-".repeat(WIDTH - filled),
+            counts,
+            fraction * 100.,
+            elapsed,
+            eta
+        );
+        io::stderr().flush().ok();
+    }
+
+    // Erases the bar.
+    pub fn finish(&mut self) {
+        eprint!("\r\x1b[K");
+        io::stderr().flush().ok();
+    }
+}
@@ -0,0 +1,233 @@
+use ctor::ctor;
+use dialoguer::{Checkboxes, Confirmation, Select};
+
+use crate::theme::Theme;
+use crate::tui::TableScreen;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::SimConfig;
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::pricing;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+use std::collections::HashMap;
+use std::path::Path;
+
+const PLAYER_TYPE: &str = "RealPlayerCLI";
+
+// What a trade would do to your score and, as far as you can tell, the other side's.
+fn print_trade_preview(my_id: PlayerId, game_state: &Observation, trade: &Trade) {
+    let view = game_state.view();
+    let (proposer_gain, accepter_gain) = pricing::estimated_gains(&view, trade);
+    let (given, taken, other, other_gain) = if trade.proposer == my_id {
+        (
+            trade.proposer_gives(),
+            trade.accepter_gives(),
+            trade.accepter,
+            accepter_gain,
+        )
+    } else {
+        (
+            trade.accepter_gives(),
+            trade.proposer_gives(),
+            trade.proposer,
+            proposer_gain,
+        )
+    };
+    // Exact for you, from your own card and holdings.
+    let gain = game_state.player_state(my_id).score_change(&taken, &given);
+    let score = view.score(my_id).unwrap();
+    println!(
+        "Your score would go from {} to {} ({:+}), and player {}'s would change by {}{:+}",
+        score,
+        score + gain,
+        gain,
+        other,
+        if view.preferences(other).is_some() {
+            ""
+        } else {
+            "about "
+        },
+        other_gain
+    );
+}
+
+fn ask_yes_no_question(prompt: &str) -> bool {
+    Confirmation::new().with_text(prompt).interact().unwrap()
+}
+
+fn ask_goods_list(prompt: &str, goods: &GoodsSet, theme: &Theme) -> GoodsSet {
+    let mut dialog = Checkboxes::new();
+    dialog.with_prompt(prompt);
+
+    let mut prompt_items = Vec::<&str>::new();
+    let labels: Vec<String> = goods.keys().map(|category| theme.label(category)).collect();
+    goods
+        .iter()
+        .zip(&labels)
+        .for_each(|((category, &count), label)| {
+            for _ in 0..(count as u64) {
+                dialog.item(label);
+                prompt_items.push(category);
+            }
+        });
+
+    let mut result = GoodsSet::new();
+    if let Ok(selected) = dialog.interact() {
+        selected.into_iter().for_each(|i| {
+            *result.entry(prompt_items[i]).or_insert(0.0) += 1.0;
+        })
+    }
+
+    result
+}
+
+// Config:
+//   theme - Path of a theme file for showing categories. See theme.rs.
+struct RealPlayerCLI {
+    my_id: PlayerId,
+    theme: Theme,
+    screen: TableScreen,
+}
+
+impl PlayerStrategy for RealPlayerCLI {
+    fn init(
+        &mut self,
+        player_id: PlayerId,
+        value: &serde_json::Value,
+    ) -> std::result::Result<(), String> {
+        self.my_id = player_id;
+        if let Some(path) = value.get("theme").and_then(|v| v.as_str()) {
+            self.theme = Theme::load(Path::new(path))
+                .map_err(|e| format!("could not read theme file {}: {}", path, e))?;
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.my_id = 0;
+        self.screen.reset();
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "theme": { "type": "string", "description": "Path of a theme file" },
+            },
+        })
+    }
+
+    fn choose_preferences(&mut self, hand: &[Preferences]) -> usize {
+        let cards: Vec<String> = hand
+            .iter()
+            .map(|card| self.theme.terminal_goods(card))
+            .collect();
+        Select::new()
+            .with_prompt("Which preference card do you keep?")
+            .items(&cards)
+            .default(0)
+            .interact()
+            .unwrap()
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        self.screen.draw(self.my_id, game_state, &self.theme);
+        HashMap::new()
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        self.screen.draw(self.my_id, game_state, &self.theme);
+
+        if !ask_yes_no_question(&format!(
+            "Do you want to trade with player {}?",
+            game_state.lead
+        )) {
+            return None;
+        }
+
+        let from_acceptor = ask_goods_list(
+            "Which goods do you want?",
+            &game_state.lead_player_state().num_goods,
+            &self.theme,
+        );
+        let from_proposor = ask_goods_list(
+            "Which goods will you give?",
+            &game_state.player_state(self.my_id).num_goods,
+            &self.theme,
+        );
+
+        if from_acceptor.is_empty() && from_proposor.is_empty() {
+            return None;
+        }
+
+        let trade = Trade::new(self.my_id, game_state.lead, from_proposor, from_acceptor);
+        print_trade_preview(self.my_id, game_state, &trade);
+        if !ask_yes_no_question("Do you want to propose the trade?") {
+            return None;
+        }
+        Some(trade)
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        self.screen.draw(self.my_id, game_state, &self.theme);
+        vec![false; game_state.current_trade_proposals.len()]
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        self.screen.draw(self.my_id, game_state, &self.theme);
+        println!(
+            "Player {} offers you {} for {}",
+            trade.proposer,
+            self.theme.terminal_goods(&trade.proposer_gives()),
+            self.theme.terminal_goods(&trade.accepter_gives())
+        );
+        print_trade_preview(self.my_id, game_state, trade);
+        ask_yes_no_question("Do you want to make the trade? [y/n]")
+    }
+}
+
+// Whether a human plays in games with this config.
+pub fn plays_in(config: &SimConfig) -> bool {
+    config
+        .player_configs
+        .iter()
+        .any(|player_config| player_config.player_type() == PLAYER_TYPE)
+}
+
+// Seats a human in `seat`, for the play subcommand. Seats before it without a player config
+// keep playing the default strategy.
+pub fn take_seat(config: &mut SimConfig, seat: PlayerId) -> Result<()> {
+    if seat >= config.num_players {
+        return Err(HedonicaError::Unsupported {
+            message: format!(
+                "there is no seat {} in a game of {} players",
+                seat, config.num_players
+            ),
+        });
+    }
+    let player_config = |player_type: &str| {
+        serde_json::from_value(serde_json::json!({ "player_type": player_type })).unwrap()
+    };
+    while config.player_configs.len() <= seat {
+        config
+            .player_configs
+            .push(player_config(player::DEFAULT_PLAYER_TYPE));
+    }
+    config.player_configs[seat] = player_config(PLAYER_TYPE);
+    Ok(())
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(RealPlayerCLI {
+        my_id: 0,
+        theme: Theme::default(),
+        screen: TableScreen::default(),
+    })
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy(PLAYER_TYPE, create)
+}
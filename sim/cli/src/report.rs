@@ -0,0 +1,157 @@
+// The report a batch prints when it ends: one JSON document with everything the batch found,
+// the config and rules it was played with, and where it came from, so downstream tools read
+// one artifact with a stable schema.
+//
+// VERSION is bumped whenever a field is renamed, removed or changes meaning. Adding a field
+// does not bump it, so readers should ignore fields they do not know. Sections that only apply
+// to some rules, e.g. loans without loan rules, are left out rather than null.
+use hedonica_engine::game::GameEnding;
+use hedonica_engine::stats::{HistogramBin, SeatFairness, Stats};
+use hedonica_engine::timing::DecisionTimes;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+pub struct Metadata {
+    pub crate_version: &'static str,
+    // None for builds outside a git checkout.
+    pub git_commit: Option<&'static str>,
+    // When this process started and finished the batch, in UTC.
+    pub started_at: String,
+    pub finished_at: String,
+    // Includes the time before a checkpoint the batch resumed from.
+    pub elapsed_seconds: f64,
+    pub resumed_at_run: Option<i32>,
+    pub runs: u64,
+}
+
+impl Metadata {
+    pub fn new(
+        started_at: SystemTime,
+        elapsed_seconds: f64,
+        resumed_at_run: Option<i32>,
+        runs: u64,
+    ) -> Metadata {
+        Metadata {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            git_commit: Some(env!("HEDONICA_GIT_COMMIT")).filter(|commit| !commit.is_empty()),
+            started_at: rfc3339(started_at),
+            finished_at: rfc3339(SystemTime::now()),
+            elapsed_seconds,
+            resumed_at_run,
+            runs,
+        }
+    }
+}
+
+// One seat's results. Counts that only apply to some rules are left out without them.
+#[derive(Serialize)]
+pub struct PlayerReport<'a> {
+    pub seat: usize,
+    pub strategy: String,
+    pub wins: i32,
+    pub win_rate_interval: Option<(f64, f64)>,
+    pub decision_times: &'a DecisionTimes,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eliminations: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bailouts: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loans: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub loan_defaults: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insurance: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invalid_trades: Option<u32>,
+}
+
+#[derive(Serialize, Default)]
+pub struct Sections {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placements: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trade_bias: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bidding: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestones: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aborted_games: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pacing: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_points: Option<Value>,
+    pub liquidity: Value,
+    pub trade_history: Value,
+    pub advantage: Value,
+    // The share of games that ended each way.
+    pub endings: BTreeMap<GameEnding, f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weighted_results: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ablation: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trajectories: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cohorts: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aborts: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_budget: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stopped_early: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct SimReport<'a> {
+    pub version: u32,
+    pub metadata: Metadata,
+    pub sim_config: &'a Value,
+    pub game_rules: &'a Value,
+    pub confidence_level: f64,
+    pub players: Vec<PlayerReport<'a>>,
+    pub turns: &'a Stats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_histogram: Option<Vec<HistogramBin>>,
+    pub seat_fairness: &'a SeatFairness,
+    #[serde(flatten)]
+    pub sections: Sections,
+    // The plain-language verdicts on the goals, one line each.
+    pub verdicts: Vec<String>,
+}
+
+// The time as e.g. 2024-05-01T12:34:56Z, by the days-to-civil algorithm of Howard Hinnant.
+fn rfc3339(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+    let (days, second_of_day) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        second_of_day / 3600,
+        second_of_day / 60 % 60,
+        second_of_day % 60
+    )
+}
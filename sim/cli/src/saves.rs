@@ -0,0 +1,94 @@
+// Saved games, with --save-game, so a game with people at the table can be put away and
+// finished later with --load-game.
+//
+// Before every turn, the game is written to the save path, replacing the last save. The game
+// state holds everything the engine draws on for the rest of the game: the deck in its dealt
+// order, the event deck, and the seeds reshuffles are drawn from. So a loaded game plays on
+// from the start of the saved turn exactly as it would have. Strategies start over from their
+// configs, as at the start of any game, so bots that remember earlier turns or draw their own
+// random numbers may play differently.
+//
+// A save is only loaded with the sim config and game rules it was written with, and only into
+// a run of one game. Saving and loading work for any seats, not just RealPlayerCLI ones.
+use crate::checkpoint;
+use hedonica_engine::error::{HedonicaError, Result};
+use hedonica_engine::game::GameState;
+use hedonica_engine::observer::Observer;
+use hedonica_engine::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The game is saved by reference and loaded owned.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame<G> {
+    pub version: String,
+    pub sim_config: Value,
+    pub game_rules: Value,
+    pub state: G,
+}
+
+impl<G> SavedGame<G> {
+    // Fails unless the game was saved by this build with the same config and rules.
+    pub fn check(&self, sim_config: &Value, game_rules: &Value) -> Result<()> {
+        let mismatch = if self.version != env!("CARGO_PKG_VERSION") {
+            Some("another version of the simulator")
+        } else if self.sim_config != *sim_config {
+            Some("another sim config")
+        } else if self.game_rules != *game_rules {
+            Some("other game rules")
+        } else {
+            None
+        };
+        match mismatch {
+            Some(mismatch) => Err(HedonicaError::Unsupported {
+                message: format!("the saved game was played with {}", mismatch),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+// Where a game is saved, if anywhere, and the save it continues from, if any.
+pub struct Saving {
+    pub path: Option<PathBuf>,
+    pub loaded: Option<SavedGame<GameState>>,
+}
+
+pub fn load(path: &Path) -> Result<SavedGame<GameState>> {
+    let text = fs::read_to_string(path).map_err(|e| HedonicaError::io(path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| HedonicaError::parse(&path.display().to_string(), e))
+}
+
+// Saves the game before every turn. A save that cannot be written is skipped with a warning,
+// leaving the last one.
+pub struct GameSaver {
+    path: PathBuf,
+    sim_config: Value,
+    game_rules: Value,
+}
+
+impl GameSaver {
+    pub fn new(path: &Path, sim_config: &Value, game_rules: &Value) -> GameSaver {
+        GameSaver {
+            path: path.to_path_buf(),
+            sim_config: sim_config.clone(),
+            game_rules: game_rules.clone(),
+        }
+    }
+}
+
+impl Observer for GameSaver {
+    fn on_turn_start(&self, game: &GameState) {
+        let saved = SavedGame {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            sim_config: self.sim_config.clone(),
+            game_rules: self.game_rules.clone(),
+            state: game,
+        };
+        if let Err(error) = checkpoint::save(&self.path, &saved) {
+            warn!("Could not save the game: {}", error);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// Generates client stubs for the bot protocol from its JSON schema, so strategies can be
+// written in other languages.
+mod python;
+mod typescript;
+
+use hedonica_strategies::protocol;
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const LANGUAGES: &[&str] = &["python", "typescript"];
+
+// Returns the files making up the SDK for the given language, as (file name, contents).
+pub fn generate(language: &str) -> Vec<(&'static str, String)> {
+    let schema = protocol::schema();
+    let mut files = match language {
+        "python" => python::generate(&schema),
+        "typescript" => typescript::generate(&schema),
+        _ => panic!("unsupported sdk language \"{}\"", language),
+    };
+    files.push((
+        "protocol.schema.json",
+        serde_json::to_string_pretty(&schema).unwrap() + "\n",
+    ));
+    files
+}
+
+pub fn write(language: &str, output_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    for (name, contents) in generate(language) {
+        fs::write(output_dir.join(name), contents)?;
+    }
+    Ok(())
+}
+
+fn definitions(schema: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    schema["definitions"].as_object().unwrap().iter()
+}
+
+// The name of the definition referenced by a {"$ref": ...} schema.
+fn ref_name(schema: &Value) -> Option<&str> {
+    schema["$ref"]
+        .as_str()
+        .map(|r| r.trim_start_matches("#/definitions/"))
+}
+
+// Object definitions with named properties become classes or interfaces. Everything else
+// becomes a type alias.
+fn is_record(schema: &Value) -> bool {
+    schema["type"] == "object" && schema.get("properties").is_some()
+}
+
+fn properties(schema: &Value) -> impl Iterator<Item = (&String, &Value)> {
+    schema["properties"].as_object().unwrap().iter()
+}
+
+// Splits {"oneOf": [X, {"type": "null"}]} into Some(X).
+fn nullable_inner(schema: &Value) -> Option<&Value> {
+    match schema["oneOf"].as_array() {
+        Some(options) if options.len() == 2 && options[1]["type"] == "null" => Some(&options[0]),
+        _ => None,
+    }
+}
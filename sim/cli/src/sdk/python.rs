@@ -0,0 +1,225 @@
+use serde_json::Value;
+
+use crate::sdk::*;
+
+fn type_expr(schema: &Value) -> String {
+    if let Some(name) = ref_name(schema) {
+        return name.to_string();
+    }
+    if let Some(inner) = nullable_inner(schema) {
+        return format!("Optional[{}]", type_expr(inner));
+    }
+    if let Some(options) = schema["oneOf"].as_array() {
+        let options: Vec<String> = options.iter().map(type_expr).collect();
+        return format!("Union[{}]", options.join(", "));
+    }
+    if let Some(value) = schema.get("const") {
+        return format!("Literal[{}]", value);
+    }
+    match schema["type"].as_str() {
+        Some("string") => String::from("str"),
+        Some("number") => String::from("float"),
+        Some("integer") => String::from("int"),
+        Some("boolean") => String::from("bool"),
+        Some("null") => String::from("None"),
+        Some("array") => format!("List[{}]", type_expr(&schema["items"])),
+        Some("object") => format!("Dict[str, {}]", type_expr(&schema["additionalProperties"])),
+        _ => String::from("Any"),
+    }
+}
+
+const HEADER: &str = r#"# Generated by `sim generate-sdk python`. Do not edit.
+#
+# Types and a stdin/stdout runner for the Hedonica strategy protocol.
+# Subclass Bot, override the decisions you care about, and call run(YourBot()).
+from __future__ import annotations
+
+import json
+import sys
+from typing import Any, Dict, List, Literal, Optional, TypedDict, Union
+
+"#;
+
+const RUNTIME: &str = r#"
+
+class Bot:
+    player_id: PlayerId = 0
+
+    def init(self, player_id: PlayerId, config: Any) -> None:
+        self.player_id = player_id
+
+    def reset(self) -> None:
+        pass
+
+    def choose_preferences(self, hand: List[Preferences]) -> int:
+        return 0
+
+    def propose_trades_as_lead(self, game_state: GameState) -> Dict[str, Trade]:
+        return {}
+
+    def propose_trade_as_non_lead(self, game_state: GameState) -> Optional[Trade]:
+        return None
+
+    def accept_trades_as_lead(self, game_state: GameState) -> List[bool]:
+        return [False] * len(game_state["current_trade_proposals"])
+
+    def accept_trades_as_non_lead(self, game_state: GameState, trade: Trade) -> bool:
+        return False
+
+    def answer_trades_as_lead(self, game_state: GameState) -> List[Acceptance]:
+        return ["accept" if a else "reject" for a in self.accept_trades_as_lead(game_state)]
+
+    def answer_trade_as_non_lead(self, game_state: GameState, trade: Trade) -> Acceptance:
+        return "accept" if self.accept_trades_as_non_lead(game_state, trade) else "reject"
+
+    def counter_offer_as_lead(self, game_state: GameState, trade: Trade) -> Optional[Trade]:
+        return None
+
+    def counter_offer_as_non_lead(self, game_state: GameState, trade: Trade) -> Optional[Trade]:
+        return None
+
+    def loan_action(self, game_state: GameState) -> LoanAction:
+        return "none"
+
+    def buy_insurance(self, game_state: GameState) -> bool:
+        return False
+
+    def choose_action(self, game_state: GameState) -> TurnAction:
+        if not game_state["turn_actions"]:
+            return {"type": "draw"}
+        return {"type": "trade_round"}
+
+    def place_orders(self, game_state: GameState) -> Optional[List[Order]]:
+        return None
+
+    def bid(self, game_state: GameState, good: Good) -> float:
+        return 0.0
+
+    def raise_bid(self, game_state: GameState, good: Good, price: float) -> bool:
+        return price <= self.bid(game_state, good)
+
+    def name_wild_category(self, game_state: GameState, good: Good) -> str:
+        preferences = game_state["players"][self.player_id]["preferences"]
+        return max((c for c in preferences if c != "money"), key=lambda c: preferences[c])
+
+    def choose_steal(self, game_state: GameState) -> Optional[Steal]:
+        return None
+
+
+def handle(bot: Bot, request: Request) -> Dict[str, Any]:
+    kind = request["type"]
+    if kind == "init":
+        bot.init(request["player_id"], request["config"])
+        return {}
+    if kind == "reset":
+        bot.reset()
+        return {}
+    if kind == "choose_preferences":
+        return {"choice": bot.choose_preferences(request["hand"])}
+    if kind == "propose_trades_as_lead":
+        return {"trades": bot.propose_trades_as_lead(request["game_state"])}
+    if kind == "propose_trade_as_non_lead":
+        return {"trade": bot.propose_trade_as_non_lead(request["game_state"])}
+    if kind == "accept_trades_as_lead":
+        return {"acceptances": bot.accept_trades_as_lead(request["game_state"])}
+    if kind == "accept_trades_as_non_lead":
+        return {"accept": bot.accept_trades_as_non_lead(request["game_state"], request["trade"])}
+    if kind == "answer_trades_as_lead":
+        return {"answers": bot.answer_trades_as_lead(request["game_state"])}
+    if kind == "answer_trade_as_non_lead":
+        return {"answer": bot.answer_trade_as_non_lead(request["game_state"], request["trade"])}
+    if kind == "counter_offer_as_lead":
+        return {"trade": bot.counter_offer_as_lead(request["game_state"], request["trade"])}
+    if kind == "counter_offer_as_non_lead":
+        return {"trade": bot.counter_offer_as_non_lead(request["game_state"], request["trade"])}
+    if kind == "loan_action":
+        return {"action": bot.loan_action(request["game_state"])}
+    if kind == "buy_insurance":
+        return {"buy": bot.buy_insurance(request["game_state"])}
+    if kind == "choose_action":
+        return {"action": bot.choose_action(request["game_state"])}
+    if kind == "place_orders":
+        return {"orders": bot.place_orders(request["game_state"])}
+    if kind == "bid":
+        return {"bid": bot.bid(request["game_state"], request["good"])}
+    if kind == "raise_bid":
+        return {"raise_price": bot.raise_bid(request["game_state"], request["good"], request["price"])}
+    if kind == "name_wild_category":
+        return {"category": bot.name_wild_category(request["game_state"], request["good"])}
+    if kind == "choose_steal":
+        return {"steal": bot.choose_steal(request["game_state"])}
+    raise ValueError("unknown request type " + repr(kind))
+
+
+def run(bot: Bot) -> None:
+    for line in sys.stdin:
+        line = line.strip()
+        if not line:
+            continue
+        response = handle(bot, json.loads(line))
+        sys.stdout.write(json.dumps(response) + "\n")
+        sys.stdout.flush()
+"#;
+
+const RANDOM_BOT: &str = r#"# A bot that trades at random. Run it with `python3 random_bot.py`.
+import random
+from typing import List, Optional
+
+from hedonica_protocol import Bot, GameState, Trade, run
+
+
+def owned(game_state: GameState, player_id: int) -> List[str]:
+    goods = game_state["players"][player_id]["num_goods"]
+    return [category for category, count in goods.items() if count >= 1]
+
+
+class RandomBot(Bot):
+    def propose_trade_as_non_lead(self, game_state: GameState) -> Optional[Trade]:
+        lead = game_state["lead"]
+        mine, theirs = owned(game_state, self.player_id), owned(game_state, lead)
+        if random.random() < 0.5 or not mine or not theirs:
+            return None
+        return {
+            "proposer": self.player_id,
+            "accepter": lead,
+            "from_proposor": {random.choice(mine): 1.0},
+            "from_acceptor": {random.choice(theirs): 1.0},
+        }
+
+    def accept_trades_as_lead(self, game_state: GameState) -> List[bool]:
+        return [random.random() < 0.5 for _ in game_state["current_trade_proposals"]]
+
+    def accept_trades_as_non_lead(self, game_state: GameState, trade: Trade) -> bool:
+        return random.random() < 0.5
+
+
+if __name__ == "__main__":
+    run(RandomBot())
+"#;
+
+pub fn generate(schema: &Value) -> Vec<(&'static str, String)> {
+    let mut out = String::from(HEADER);
+
+    // Class annotations are evaluated lazily, aliases are not, so aliases go last.
+    for (name, definition) in definitions(schema).filter(|(_, d)| is_record(d)) {
+        out += &format!("\nclass {}(TypedDict):\n", name);
+        let mut any_fields = false;
+        for (field, field_schema) in properties(definition) {
+            out += &format!("    {}: {}\n", field, type_expr(field_schema));
+            any_fields = true;
+        }
+        if !any_fields {
+            out += "    pass\n";
+        }
+        out += "\n";
+    }
+    for (name, definition) in definitions(schema).filter(|(_, d)| !is_record(d)) {
+        out += &format!("\n{} = {}\n", name, type_expr(definition));
+    }
+    out += RUNTIME;
+
+    vec![
+        ("hedonica_protocol.py", out),
+        ("random_bot.py", String::from(RANDOM_BOT)),
+    ]
+}
@@ -0,0 +1,265 @@
+use serde_json::Value;
+
+use crate::sdk::*;
+
+fn type_expr(schema: &Value) -> String {
+    if let Some(name) = ref_name(schema) {
+        return name.to_string();
+    }
+    if let Some(inner) = nullable_inner(schema) {
+        return format!("{} | null", type_expr(inner));
+    }
+    if let Some(options) = schema["oneOf"].as_array() {
+        let options: Vec<String> = options.iter().map(type_expr).collect();
+        return options.join(" | ");
+    }
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    match schema["type"].as_str() {
+        Some("string") => String::from("string"),
+        Some("number") | Some("integer") => String::from("number"),
+        Some("boolean") => String::from("boolean"),
+        Some("null") => String::from("null"),
+        Some("array") => format!("Array<{}>", type_expr(&schema["items"])),
+        Some("object") => format!(
+            "{{ [key: string]: {} }}",
+            type_expr(&schema["additionalProperties"])
+        ),
+        _ => String::from("unknown"),
+    }
+}
+
+const HEADER: &str = r#"// Generated by `sim generate-sdk typescript`. Do not edit.
+//
+// Types and a stdin/stdout runner for the Hedonica strategy protocol.
+// Extend Bot, override the decisions you care about, and call run(new YourBot()).
+import * as readline from "readline";
+"#;
+
+const RUNTIME: &str = r#"
+export class Bot {
+  playerId: PlayerId = 0;
+
+  init(playerId: PlayerId, _config: unknown): void {
+    this.playerId = playerId;
+  }
+
+  reset(): void {}
+
+  choosePreferences(_hand: Array<Preferences>): number {
+    return 0;
+  }
+
+  proposeTradesAsLead(_gameState: GameState): { [key: string]: Trade } {
+    return {};
+  }
+
+  proposeTradeAsNonLead(_gameState: GameState): Trade | null {
+    return null;
+  }
+
+  acceptTradesAsLead(gameState: GameState): Array<boolean> {
+    return Object.keys(gameState.current_trade_proposals).map(() => false);
+  }
+
+  acceptTradesAsNonLead(_gameState: GameState, _trade: Trade): boolean {
+    return false;
+  }
+
+  answerTradesAsLead(gameState: GameState): Array<Acceptance> {
+    return this.acceptTradesAsLead(gameState).map((accept) => (accept ? "accept" : "reject"));
+  }
+
+  answerTradeAsNonLead(gameState: GameState, trade: Trade): Acceptance {
+    return this.acceptTradesAsNonLead(gameState, trade) ? "accept" : "reject";
+  }
+
+  counterOfferAsLead(_gameState: GameState, _trade: Trade): Trade | null {
+    return null;
+  }
+
+  counterOfferAsNonLead(_gameState: GameState, _trade: Trade): Trade | null {
+    return null;
+  }
+
+  loanAction(_gameState: GameState): LoanAction {
+    return "none";
+  }
+
+  buyInsurance(_gameState: GameState): boolean {
+    return false;
+  }
+
+  chooseAction(gameState: GameState): TurnAction {
+    return gameState.turn_actions.length === 0 ? { type: "draw" } : { type: "trade_round" };
+  }
+
+  placeOrders(_gameState: GameState): Array<Order> | null {
+    return null;
+  }
+
+  bid(_gameState: GameState, _good: Good): number {
+    return 0;
+  }
+
+  raiseBid(gameState: GameState, good: Good, price: number): boolean {
+    return price <= this.bid(gameState, good);
+  }
+
+  nameWildCategory(gameState: GameState, _good: Good): string {
+    const preferences = gameState.players[this.playerId].preferences;
+    const categories = Object.keys(preferences).filter((category) => category !== "money");
+    return categories.reduce((a, b) => (preferences[b] > preferences[a] ? b : a));
+  }
+
+  chooseSteal(_gameState: GameState): Steal | null {
+    return null;
+  }
+}
+
+export function handle(bot: Bot, request: Request): object {
+  switch (request.type) {
+    case "init":
+      bot.init(request.player_id, request.config);
+      return {};
+    case "reset":
+      bot.reset();
+      return {};
+    case "choose_preferences":
+      return { choice: bot.choosePreferences(request.hand) };
+    case "propose_trades_as_lead":
+      return { trades: bot.proposeTradesAsLead(request.game_state) };
+    case "propose_trade_as_non_lead":
+      return { trade: bot.proposeTradeAsNonLead(request.game_state) };
+    case "accept_trades_as_lead":
+      return { acceptances: bot.acceptTradesAsLead(request.game_state) };
+    case "accept_trades_as_non_lead":
+      return { accept: bot.acceptTradesAsNonLead(request.game_state, request.trade) };
+    case "answer_trades_as_lead":
+      return { answers: bot.answerTradesAsLead(request.game_state) };
+    case "answer_trade_as_non_lead":
+      return { answer: bot.answerTradeAsNonLead(request.game_state, request.trade) };
+    case "counter_offer_as_lead":
+      return { trade: bot.counterOfferAsLead(request.game_state, request.trade) };
+    case "counter_offer_as_non_lead":
+      return { trade: bot.counterOfferAsNonLead(request.game_state, request.trade) };
+    case "loan_action":
+      return { action: bot.loanAction(request.game_state) };
+    case "buy_insurance":
+      return { buy: bot.buyInsurance(request.game_state) };
+    case "choose_action":
+      return { action: bot.chooseAction(request.game_state) };
+    case "place_orders":
+      return { orders: bot.placeOrders(request.game_state) };
+    case "bid":
+      return { bid: bot.bid(request.game_state, request.good) };
+    case "raise_bid":
+      return { raise_price: bot.raiseBid(request.game_state, request.good, request.price) };
+    case "name_wild_category":
+      return { category: bot.nameWildCategory(request.game_state, request.good) };
+    case "choose_steal":
+      return { steal: bot.chooseSteal(request.game_state) };
+  }
+}
+
+export function run(bot: Bot): void {
+  const lines = readline.createInterface({ input: process.stdin, terminal: false });
+  lines.on("line", (line: string) => {
+    if (line.trim().length === 0) {
+      return;
+    }
+    const response = handle(bot, JSON.parse(line) as Request);
+    process.stdout.write(JSON.stringify(response) + "\n");
+  });
+}
+"#;
+
+const RANDOM_BOT: &str = r#"// A bot that trades at random. Run it with `npm install && npm start`.
+import { Bot, GameState, PlayerId, Trade, run } from "./protocol";
+
+function owned(gameState: GameState, playerId: PlayerId): Array<string> {
+  const goods = gameState.players[playerId].num_goods;
+  return Object.keys(goods).filter((category) => goods[category] >= 1);
+}
+
+function choice<T>(items: Array<T>): T {
+  return items[Math.floor(Math.random() * items.length)];
+}
+
+class RandomBot extends Bot {
+  proposeTradeAsNonLead(gameState: GameState): Trade | null {
+    const lead = gameState.lead;
+    const mine = owned(gameState, this.playerId);
+    const theirs = owned(gameState, lead);
+    if (Math.random() < 0.5 || mine.length === 0 || theirs.length === 0) {
+      return null;
+    }
+    return {
+      proposer: this.playerId,
+      accepter: lead,
+      from_proposor: { [choice(mine)]: 1 },
+      from_acceptor: { [choice(theirs)]: 1 },
+    };
+  }
+
+  acceptTradesAsLead(gameState: GameState): Array<boolean> {
+    return Object.keys(gameState.current_trade_proposals).map(() => Math.random() < 0.5);
+  }
+
+  acceptTradesAsNonLead(_gameState: GameState, _trade: Trade): boolean {
+    return Math.random() < 0.5;
+  }
+}
+
+run(new RandomBot());
+"#;
+
+const PACKAGE_JSON: &str = r#"{
+  "name": "hedonica-bot",
+  "private": true,
+  "scripts": {
+    "build": "tsc",
+    "start": "tsc && node dist/random_bot.js"
+  },
+  "devDependencies": {
+    "@types/node": "^20.0.0",
+    "typescript": "^5.0.0"
+  }
+}
+"#;
+
+const TSCONFIG_JSON: &str = r#"{
+  "compilerOptions": {
+    "target": "es2019",
+    "module": "commonjs",
+    "strict": true,
+    "outDir": "dist"
+  },
+  "files": ["protocol.ts", "random_bot.ts"]
+}
+"#;
+
+pub fn generate(schema: &Value) -> Vec<(&'static str, String)> {
+    let mut out = String::from(HEADER);
+
+    for (name, definition) in definitions(schema) {
+        if is_record(definition) {
+            out += &format!("\nexport interface {} {{\n", name);
+            for (field, field_schema) in properties(definition) {
+                out += &format!("  {}: {};\n", field, type_expr(field_schema));
+            }
+            out += "}\n";
+        } else {
+            out += &format!("\nexport type {} = {};\n", name, type_expr(definition));
+        }
+    }
+    out += RUNTIME;
+
+    vec![
+        ("protocol.ts", out),
+        ("random_bot.ts", String::from(RANDOM_BOT)),
+        ("package.json", String::from(PACKAGE_JSON)),
+        ("tsconfig.json", String::from(TSCONFIG_JSON)),
+    ]
+}
@@ -0,0 +1,344 @@
+// `sim serve` hosts games over TCP, so remote humans and bots can playtest against each other
+// and the built-in strategies.
+//
+// This is not a gRPC server. The serve mode was specified as gRPC with tonic, but tonic, prost
+// and protoc are not dependencies of the workspace and cannot be fetched where it is built, so
+// the server speaks the JSON line protocol remote strategies already use instead: one JSON
+// object per line over a plain TCP socket. gRPC clients cannot connect to it. Seats are played
+// through remote strategies' Transport, so a tonic front end would need its own Transport and
+// lobby handling once the dependency is available, but not a game loop of its own.
+//
+// A client first creates or joins a lobby:
+//
+//   {"type": "create"}               -> {"type": "joined", "lobby": "3", "seat": 0, "seats": 2}
+//   {"type": "join", "lobby": "3"}   -> {"type": "joined", "lobby": "3", "seat": 1, "seats": 2}
+//
+// Either may list the optional mechanics the client implements, as "capabilities" does for
+// remote strategies. Clients take the first `seats` seats in the order they arrive, and the
+// other seats play the sim config's strategies. Once every client seat is taken the game
+// starts, and the server sends each client the requests of protocol.rs, with game_state as
+// its seat observes it. A client answers each with one line, as PlayerSubprocess bots do, so
+// a bot built with `sim generate-sdk` can play over the socket. A client that disconnects while
+// its lobby waits closes the lobby, and the others in it are sent {"type": "error"}. A client
+// that does not answer within the action timeout, or disconnects, once the game has started
+// aborts the game. When the game ends, every client is sent one of
+//
+//   {"type": "game_over", "winner": 1, "scores": [...]}
+//   {"type": "game_over", "aborted": reason}
+//
+// and disconnected. A first request the server cannot use is answered with {"type": "error"}.
+use hedonica_engine::game::{self, GameResult, GameRules, SimConfig};
+use hedonica_engine::player::{self, PlayerStrategy};
+use hedonica_engine::rng::SeedSchedule;
+use hedonica_engine::{error, info, warn};
+use hedonica_strategies::remote::{RemoteStrategy, Transport};
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub struct ServeOptions {
+    // How many seats of each game clients take.
+    pub seats: usize,
+    // How long a client has to answer each request.
+    pub action_timeout: Duration,
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Connection> {
+        Ok(Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    fn send(&mut self, message: &Value) -> io::Result<()> {
+        writeln!(self.writer, "{}", message)?;
+        self.writer.flush()
+    }
+
+    fn receive(&mut self) -> io::Result<Value> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = self
+                .reader
+                .read_line(&mut line)
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "no response within the action timeout",
+                    ),
+                    _ => e,
+                })?;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the client disconnected",
+                ));
+            }
+            if !line.trim().is_empty() {
+                return serde_json::from_str(&line).map_err(io::Error::from);
+            }
+        }
+    }
+}
+
+impl Transport for Connection {
+    fn exchange(&mut self, request: &Value) -> io::Result<Value> {
+        self.send(request)?;
+        self.receive()
+    }
+}
+
+struct Client {
+    seat: usize,
+    connection: Connection,
+    capabilities: Value,
+}
+
+#[derive(Default)]
+struct Lobby {
+    // Seats handed out, counting clients still being told theirs.
+    seated: usize,
+    clients: Vec<Client>,
+}
+
+#[derive(Default)]
+struct Lobbies {
+    // Lobbies still waiting for clients, by name.
+    waiting: HashMap<String, Lobby>,
+    created: u64,
+}
+
+struct Server {
+    // The sim config as JSON, parsed again for each game so each gets its own seeds.
+    config: Value,
+    rules: GameRules,
+    options: ServeOptions,
+    lobbies: Mutex<Lobbies>,
+}
+
+fn error_message(message: &str) -> Value {
+    json!({ "type": "error", "message": message })
+}
+
+impl Server {
+    // Claims a seat in the lobby the request creates or joins.
+    fn claim_seat(&self, request: &Value) -> Result<(String, usize), &'static str> {
+        let mut lobbies = self.lobbies.lock().unwrap();
+        let name = match (request["type"].as_str(), request["lobby"].as_str()) {
+            (Some("create"), _) => {
+                lobbies.created += 1;
+                let name = lobbies.created.to_string();
+                lobbies.waiting.insert(name.clone(), Lobby::default());
+                name
+            }
+            (Some("join"), Some(name))
+                if lobbies
+                    .waiting
+                    .get(name)
+                    .is_some_and(|lobby| lobby.seated < self.options.seats) =>
+            {
+                name.to_string()
+            }
+            (Some("join"), _) => return Err("no lobby of that name is waiting"),
+            _ => return Err("expected create or join"),
+        };
+        let lobby = lobbies.waiting.get_mut(&name).unwrap();
+        lobby.seated += 1;
+        Ok((name, lobby.seated - 1))
+    }
+
+    // Closes a waiting lobby, telling the clients already in it.
+    fn close(&self, name: &str, reason: &str) {
+        let lobby = self.lobbies.lock().unwrap().waiting.remove(name);
+        if let Some(lobby) = lobby {
+            info!("Closed lobby {}: {}", name, reason);
+            for mut client in lobby.clients {
+                // The client may already be gone.
+                let _ = client.connection.send(&error_message(reason));
+            }
+        }
+    }
+
+    // Waits with a seated client while its lobby fills, closing the lobby if the client
+    // disconnects first. Nothing reads the client's socket until the game starts, so a peek
+    // sees the disconnect.
+    fn watch(&self, name: &str, stream: TcpStream) {
+        let mut byte = [0; 1];
+        loop {
+            let disconnected = match stream.peek(&mut byte) {
+                // The client is talking, so the game has started.
+                Ok(read) if read > 0 => return,
+                Ok(_) => true,
+                Err(e) => !matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ),
+            };
+            if disconnected {
+                break;
+            }
+            if !self.lobbies.lock().unwrap().waiting.contains_key(name) {
+                return;
+            }
+        }
+        self.close(name, "a client left the lobby before the game started");
+    }
+
+    // Seats a new client, and plays the game if that fills its lobby.
+    fn admit(&self, stream: TcpStream) -> io::Result<()> {
+        let mut connection = Connection::new(stream)?;
+        connection
+            .writer
+            .set_read_timeout(Some(self.options.action_timeout))?;
+        let request = connection.receive()?;
+        let (name, seat) = match self.claim_seat(&request) {
+            Ok(claimed) => claimed,
+            Err(message) => return connection.send(&error_message(message)),
+        };
+        // The client hears its seat before the lobby can fill and the game write to it.
+        let joined = connection.send(&json!({
+            "type": "joined",
+            "lobby": name,
+            "seat": seat,
+            "seats": self.options.seats,
+        }));
+        if let Err(e) = joined {
+            self.close(&name, "a client left the lobby before the game started");
+            return Err(e);
+        }
+        let watched = connection.writer.try_clone()?;
+        let mut lobbies = self.lobbies.lock().unwrap();
+        let lobby = match lobbies.waiting.get_mut(&name) {
+            Some(lobby) => lobby,
+            None => {
+                drop(lobbies);
+                return connection.send(&error_message("the lobby closed before the game started"));
+            }
+        };
+        lobby.clients.push(Client {
+            seat,
+            connection,
+            capabilities: request["capabilities"].clone(),
+        });
+        if lobby.clients.len() < self.options.seats {
+            drop(lobbies);
+            self.watch(&name, watched);
+            return Ok(());
+        }
+        let mut clients = lobbies.waiting.remove(&name).unwrap().clients;
+        drop(lobbies);
+        clients.sort_by_key(|client| client.seat);
+        let game_number: u64 = name.parse().unwrap();
+
+        info!("Lobby {} is full, starting its game", name);
+        let mut writers = clients
+            .iter()
+            .map(|client| client.connection.writer.try_clone())
+            .collect::<io::Result<Vec<_>>>()?;
+        let message = match self.play(game_number, clients) {
+            Ok(Ok(result)) => {
+                info!("Lobby {} finished, player {} won", name, result.winner);
+                json!({ "type": "game_over", "winner": result.winner, "scores": result.scores })
+            }
+            Ok(Err(aborted)) => {
+                warn!("Lobby {} aborted: {}", name, aborted.reason);
+                json!({ "type": "game_over", "aborted": aborted.reason })
+            }
+            Err(e) => {
+                warn!("Lobby {} could not start: {}", name, e);
+                json!({ "type": "game_over", "aborted": e.to_string() })
+            }
+        };
+        for writer in &mut writers {
+            // The client may already be gone.
+            let _ = writeln!(writer, "{}", message);
+        }
+        Ok(())
+    }
+
+    fn play(
+        &self,
+        game_number: u64,
+        clients: Vec<Client>,
+    ) -> error::Result<Result<GameResult, game::AbortedGame>> {
+        let mut config: SimConfig = serde_json::from_value(self.config.clone()).unwrap();
+        SeedSchedule::new(&config)
+            .seeds(game_number as i32)
+            .apply(&mut config);
+        let rules = &self.rules;
+        let mut players = player::load_strategies(&config, rules)?;
+        for (seat, client) in clients.into_iter().enumerate() {
+            let connection = Cell::new(Some(client.connection));
+            let mut strategy = RemoteStrategy::new("client", move |_: &Value| {
+                let connection = connection.take().expect("a client seat connects once");
+                Ok(Box::new(connection) as Box<dyn Transport>)
+            });
+            strategy
+                .init(seat, &json!({ "capabilities": client.capabilities }))
+                .map_err(|message| error::HedonicaError::Unsupported { message })?;
+            strategy.set_rules(rules);
+            player::check_capabilities(
+                seat,
+                "client",
+                &strategy,
+                rules,
+                config.strict_capabilities,
+            )?;
+            players[seat] = Box::new(strategy);
+        }
+        let preferences = game::generate_preferences_deck(&config, rules)?;
+        let game = game::generate_start_state(&config, rules, preferences)?;
+        Ok(game::try_play(&config, rules, game, &mut players))
+    }
+}
+
+// Accepts clients on the address until the process is stopped, playing each lobby's game on the
+// thread of the client that filled it.
+pub fn serve(
+    address: &str,
+    config: &SimConfig,
+    rules: GameRules,
+    options: ServeOptions,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    info!(
+        "Serving games of {} players, {} for clients, on {}",
+        config.num_players,
+        options.seats,
+        listener.local_addr()?
+    );
+    let server = Arc::new(Server {
+        config: serde_json::to_value(config).unwrap(),
+        rules,
+        options,
+        lobbies: Mutex::new(Lobbies::default()),
+    });
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Could not accept a client: {}", e);
+                continue;
+            }
+        };
+        let server = server.clone();
+        thread::spawn(move || {
+            if let Err(e) = server.admit(stream) {
+                warn!("Dropped a client: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
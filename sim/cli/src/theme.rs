@@ -0,0 +1,89 @@
+// Display names, emoji and colors for categories, so playtest materials can try different
+// theming without touching the rules. Rules and strategies only ever see category ids. A theme
+// file maps ids to how they are shown, e.g.
+//   {categories: {cars: {name: "Yachts", emoji: "🛥", color: "#1e90ff"}}}
+// Colors are CSS-style "#rrggbb" or one of the eight basic terminal color names. Categories
+// the theme leaves out are shown by id. See themes/ for examples.
+use hedonica_engine::types::GoodsSet;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Deserialize, Clone, Default)]
+pub struct CategoryTheme {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub emoji: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+pub struct Theme {
+    #[serde(default)]
+    pub categories: BTreeMap<String, CategoryTheme>,
+}
+
+const TERMINAL_COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// The ANSI escape that starts text in this color, if it is one we know.
+fn ansi_color(color: &str) -> Option<String> {
+    if let Some(index) = TERMINAL_COLORS.iter().position(|&c| c == color) {
+        return Some(format!("\x1b[{}m", 30 + index));
+    }
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(format!(
+        "\x1b[38;2;{};{};{}m",
+        channel(0)?,
+        channel(2)?,
+        channel(4)?
+    ))
+}
+
+impl Theme {
+    pub fn load(path: &Path) -> std::io::Result<Theme> {
+        let text = std::fs::read_to_string(path)?;
+        json5::from_str(&text)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    pub fn name<'a>(&'a self, category: &'a str) -> &'a str {
+        self.categories
+            .get(category)
+            .and_then(|theme| theme.name.as_deref())
+            .unwrap_or(category)
+    }
+
+    pub fn color(&self, category: &str) -> Option<&str> {
+        self.categories.get(category)?.color.as_deref()
+    }
+
+    // The name with its emoji, for plain text.
+    pub fn label(&self, category: &str) -> String {
+        match self.categories.get(category).and_then(|t| t.emoji.as_ref()) {
+            Some(emoji) => format!("{} {}", emoji, self.name(category)),
+            None => self.name(category).to_string(),
+        }
+    }
+
+    // The label in the category's color, for terminals.
+    pub fn terminal_label(&self, category: &str) -> String {
+        match self.color(category).and_then(ansi_color) {
+            Some(escape) => format!("{}{}\x1b[0m", escape, self.label(category)),
+            None => self.label(category),
+        }
+    }
+
+    // e.g. "🛥 Yachts: 2, Money: 5", for goods or preference values.
+    pub fn terminal_goods(&self, goods: &GoodsSet) -> String {
+        goods
+            .iter()
+            .map(|(category, count)| format!("{}: {}", self.terminal_label(category), count))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
@@ -0,0 +1,154 @@
+// A full-screen view of the game for RealPlayerCLI, cleared and redrawn whenever the human is
+// asked to act, so it keeps up with every round they take part in. From the top, it shows:
+//   Table          - Every player's goods, with the lead and you marked, and scores where
+//                    visible.
+//   Your card      - Your private preference values.
+//   Scores         - Each visible score at the start of every turn so far, as a sparkline.
+//   Trade log      - The most recent completed trades.
+//   Suggestions    - Trades that might work for both sides, from suggest.
+// The prompts are asked below it. Drawing uses ANSI escapes, as theme.rs does, so it needs no
+// terminal library and leaves the last screen of a game on the terminal.
+use crate::theme::Theme;
+use hedonica_engine::game::GameState;
+use hedonica_engine::suggest;
+use hedonica_engine::types::*;
+use std::io::{self, Write};
+
+const CLEAR: &str = "\x1b[2J\x1b[H";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_TURNS: usize = 40;
+const TRADE_LOG_LINES: usize = 8;
+const SUGGESTIONS: usize = 3;
+
+#[derive(Default)]
+pub struct TableScreen {
+    // Every player's score, where visible, when each turn was last drawn.
+    scores_by_turn: Vec<Vec<Option<f64>>>,
+}
+
+fn heading(title: &str) -> String {
+    format!(
+        "\n{}── {} {}{}",
+        BOLD,
+        title,
+        "─".repeat(60 - title.len()),
+        RESET
+    )
+}
+
+// Scores as bars from the lowest to the highest shown, blank where hidden.
+fn sparkline(scores: &[Option<f64>], low: f64, high: f64) -> String {
+    scores
+        .iter()
+        .map(|score| match score {
+            Some(score) if high > low => {
+                let level = (score - low) / (high - low) * (SPARKS.len() - 1) as f64;
+                SPARKS[level.round() as usize]
+            }
+            Some(_) => SPARKS[0],
+            None => ' ',
+        })
+        .collect()
+}
+
+impl TableScreen {
+    pub fn reset(&mut self) {
+        self.scores_by_turn.clear();
+    }
+
+    pub fn draw(&mut self, my_id: PlayerId, game: &GameState, theme: &Theme) {
+        let view = game.view(my_id);
+        let num_players = game.players.len();
+        let turn = game.current_turn.max(0) as usize;
+        if self.scores_by_turn.len() <= turn {
+            self.scores_by_turn.resize(turn + 1, Vec::new());
+        }
+        self.scores_by_turn[turn] = (0..num_players).map(|i| view.score(i)).collect();
+
+        let mut lines = vec![format!(
+            "{}Turn {}, round {}. Player {} leads.",
+            CLEAR, game.current_turn, game.current_round, game.lead
+        )];
+
+        lines.push(heading("Table"));
+        for (i, player) in game.players.iter().enumerate() {
+            let marker = if i == game.lead && i == my_id {
+                "[lead, you]"
+            } else if i == game.lead {
+                "[lead]"
+            } else if i == my_id {
+                "[you]"
+            } else {
+                ""
+            };
+            let score = view
+                .score(i)
+                .map_or_else(|| String::from("?"), |score| score.to_string());
+            lines.push(format!(
+                "Player {} {:<11} score {:>5}  {}",
+                i,
+                marker,
+                score,
+                theme.terminal_goods(&player.num_goods)
+            ));
+            if player.eliminated {
+                lines.push(String::from("  eliminated"));
+            } else if player.face_down_goods > 0. {
+                lines.push(format!("  {} goods in all, face down", view.goods_count(i)));
+            }
+        }
+
+        lines.push(heading("Your card"));
+        lines.push(theme.terminal_goods(view.preferences(my_id).unwrap()));
+
+        lines.push(heading("Scores"));
+        let shown =
+            &self.scores_by_turn[self.scores_by_turn.len().saturating_sub(SPARKLINE_TURNS)..];
+        let visible = shown.iter().flatten().flatten();
+        let low = visible.clone().fold(f64::INFINITY, |a, &b| a.min(b));
+        let high = visible.fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+        for i in 0..num_players {
+            let scores: Vec<Option<f64>> = shown
+                .iter()
+                .map(|turn| turn.get(i).copied().flatten())
+                .collect();
+            lines.push(format!("Player {} {}", i, sparkline(&scores, low, high)));
+        }
+
+        lines.push(heading("Trade log"));
+        let trades: Vec<&Trade> = game.completed_trades().collect();
+        if trades.is_empty() {
+            lines.push(String::from("No trades yet"));
+        }
+        for trade in &trades[trades.len().saturating_sub(TRADE_LOG_LINES)..] {
+            lines.push(format!(
+                "Player {} gave player {} {} for {}",
+                trade.proposer,
+                trade.accepter,
+                theme.terminal_goods(&trade.proposer_gives()),
+                theme.terminal_goods(&trade.accepter_gives())
+            ));
+        }
+
+        let suggestions = suggest::suggest_trades(&view, SUGGESTIONS);
+        if !suggestions.is_empty() {
+            lines.push(heading("Suggestions"));
+            for suggestion in suggestions {
+                lines.push(format!(
+                    "With player {}: give {}, get {} (you +{}, them +{})",
+                    suggestion.trade.accepter,
+                    theme.terminal_goods(&suggestion.trade.proposer_gives()),
+                    theme.terminal_goods(&suggestion.trade.accepter_gives()),
+                    suggestion.gain,
+                    suggestion.partner_gain
+                ));
+            }
+        }
+
+        let mut stdout = io::stdout();
+        writeln!(stdout, "{}\n", lines.join("\n")).unwrap();
+        stdout.flush().unwrap();
+    }
+}
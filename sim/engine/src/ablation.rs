@@ -0,0 +1,177 @@
+// Ablation studies within one batch: one seat's strategy is swapped for a variant on some runs,
+// and each variant game replays the start state (deck and preference deal) of a baseline game,
+// so the two arms are compared game for game instead of across separately seeded batches.
+//
+//   alternate - Even runs are baseline games and each odd run replays the run before it.
+//   switch_at - Runs before `run` are baseline games. From then on the seat plays the variant,
+//               and run `run + i` replays run i. Variant runs past the end of the baseline
+//               runs get fresh deals and are left out of the paired comparison.
+use crate::game::GameResult;
+use crate::player::PlayerConfig;
+use crate::stats::{self, Stats};
+use crate::types::PlayerId;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AblationSchedule {
+    #[default]
+    Alternate,
+    SwitchAt {
+        run: i32,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Ablation {
+    pub seat: PlayerId,
+    // The strategy the seat plays in the variant arm.
+    pub player_config: PlayerConfig,
+    #[serde(default)]
+    pub schedule: AblationSchedule,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Arm {
+    // Some(run) when a later variant game will replay this one.
+    Baseline { replayed_by: Option<i32> },
+    // The baseline run whose start state this game replays, if any.
+    Variant { replays: Option<i32> },
+}
+
+impl Ablation {
+    pub fn arm(&self, run: i32, num_runs: i32) -> Arm {
+        match self.schedule {
+            AblationSchedule::Alternate if run % 2 == 0 => Arm::Baseline {
+                replayed_by: Some(run + 1).filter(|&r| r < num_runs),
+            },
+            AblationSchedule::Alternate => Arm::Variant {
+                replays: Some(run - 1),
+            },
+            AblationSchedule::SwitchAt { run: switch } if run < switch => Arm::Baseline {
+                replayed_by: Some(run + switch).filter(|&r| r < num_runs),
+            },
+            AblationSchedule::SwitchAt { run: switch } => Arm::Variant {
+                replays: Some(run - switch).filter(|&r| r < switch),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ArmTally {
+    runs: u32,
+    wins: u32,
+    #[serde(with = "stats::state")]
+    scores: Stats,
+}
+
+impl ArmTally {
+    fn add(&mut self, won: bool, score: f64) {
+        self.runs += 1;
+        self.wins += won as u32;
+        self.scores.add(score);
+    }
+
+    fn summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "runs": self.runs,
+            "win_rate": self.wins as f64 / self.runs.max(1) as f64,
+            "mean_score": self.scores.mean(),
+        })
+    }
+}
+
+// The ablated seat's results in each arm, and over the pairs of games that share a deal.
+#[derive(Serialize, Deserialize, Default)]
+pub struct AblationTally {
+    baseline: ArmTally,
+    variant: ArmTally,
+    // The seat's win and score in baseline games waiting for their variant replay.
+    pending: BTreeMap<i32, (bool, f64)>,
+    #[serde(with = "stats::state")]
+    score_differences: Stats,
+    pairs: u32,
+    variant_only_wins: u32,
+    baseline_only_wins: u32,
+}
+
+impl AblationTally {
+    pub fn add(&mut self, ablation: &Ablation, arm: Arm, run: i32, result: &GameResult) {
+        let won = result.winner == ablation.seat;
+        let score = result.scores[ablation.seat];
+        match arm {
+            Arm::Baseline { replayed_by } => {
+                self.baseline.add(won, score);
+                if replayed_by.is_some() {
+                    self.pending.insert(run, (won, score));
+                }
+            }
+            Arm::Variant { replays } => {
+                self.variant.add(won, score);
+                if let Some((baseline_won, baseline_score)) =
+                    replays.and_then(|r| self.pending.remove(&r))
+                {
+                    self.pairs += 1;
+                    self.score_differences.add(score - baseline_score);
+                    self.variant_only_wins += (won && !baseline_won) as u32;
+                    self.baseline_only_wins += (baseline_won && !won) as u32;
+                }
+            }
+        }
+    }
+
+    pub fn summary(&self, ablation: &Ablation) -> serde_json::Value {
+        let pairs = self.pairs.max(1) as f64;
+        let differences = &self.score_differences;
+        serde_json::json!({
+            "ablation": {
+                "seat": ablation.seat,
+                "baseline": self.baseline.summary(),
+                "variant": self.variant.summary(),
+                "paired": {
+                    "pairs": self.pairs,
+                    "mean_score_difference": differences.mean(),
+                    "score_difference_error": (differences.var() / pairs).sqrt(),
+                    "win_rate_difference":
+                        (self.variant_only_wins as f64 - self.baseline_only_wins as f64) / pairs,
+                    "variant_only_wins": self.variant_only_wins,
+                    "baseline_only_wins": self.baseline_only_wins,
+                },
+            }
+        })
+    }
+}
+
+// What a baseline game's variant replay needs, such as its start state, kept until then.
+#[derive(Serialize, Deserialize)]
+pub struct ReplayedDeals<T> {
+    deals: BTreeMap<i32, T>,
+}
+
+impl<T> Default for ReplayedDeals<T> {
+    fn default() -> ReplayedDeals<T> {
+        ReplayedDeals {
+            deals: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> ReplayedDeals<T> {
+    pub fn keep(&mut self, arm: Arm, run: i32, deal: T) {
+        if let Arm::Baseline {
+            replayed_by: Some(_),
+        } = arm
+        {
+            self.deals.insert(run, deal);
+        }
+    }
+
+    pub fn take(&mut self, arm: Arm) -> Option<T> {
+        match arm {
+            Arm::Variant { replays: Some(run) } => self.deals.remove(&run),
+            _ => None,
+        }
+    }
+}
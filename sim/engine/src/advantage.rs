@@ -0,0 +1,142 @@
+// Goal G2 measured directly: how much better one player's preferences suit the deck than
+// another's, before a card is drawn.
+//
+// A player's advantage index is the score they could expect per card of the deck if every
+// category's goods ended up with the players in proportion to how much each values it, as
+// trading tends to send goods to whoever values them most. So for player p,
+//
+//   advantage(p) = sum over c of supply(c) * value(p, c)^2 / sum over q of value(q, c)
+//
+// divided by the deck's total supply of goods, where supply counts the goods the rules put in
+// the deck: expected counts for weighted decks, and special cards by their amounts. A player
+// who values a plentiful category nobody else wants has a high index; players who want the
+// same thing split it. Money is left out, as everyone values it alike.
+//
+// A deal's spread is its highest index minus its lowest. Every game's indices are in
+// GameResult.advantage, and the batch report gives the spread across runs, each seat's mean
+// index, and how often the player with the highest index won. With the house rule
+// max_advantage_spread, preference cards are redealt until the spread is within it.
+use crate::cards::SpecialCards;
+use crate::deck::DeckSpec;
+use crate::error::Result;
+use crate::game::GameRules;
+use crate::stats::{self, Stats};
+use crate::types::{GoodsSet, Preferences};
+use serde::{Deserialize, Serialize};
+
+// How many goods of each category the rules put in the deck, on average.
+pub fn expected_supply(rules: &GameRules) -> Result<GoodsSet> {
+    let mut supply = GoodsSet::new();
+    match rules.deck_spec() {
+        DeckSpec::Counts { counts } => {
+            for (category, count) in counts {
+                *supply.entry(category).or_default() += count as f64;
+            }
+        }
+        DeckSpec::Weighted { size, weights } => {
+            let total: f64 = weights.values().sum();
+            for (category, weight) in weights {
+                *supply.entry(category).or_default() += size as f64 * weight / total;
+            }
+        }
+        DeckSpec::Cards { cards } => {
+            for spec in &cards {
+                add_cards(rules, &mut supply, spec)?;
+            }
+        }
+    }
+    for spec in &rules.special_cards {
+        add_cards(rules, &mut supply, spec)?;
+    }
+    Ok(supply)
+}
+
+fn add_cards(rules: &GameRules, supply: &mut GoodsSet, spec: &SpecialCards) -> Result<()> {
+    let card = spec.card(rules)?;
+    if card.effect.is_none() {
+        *supply.entry(card.category).or_default() += card.amount * spec.count as f64;
+    }
+    Ok(())
+}
+
+// Every player's advantage index, by seat.
+pub fn indices(supply: &GoodsSet, preferences: &[&Preferences]) -> Vec<f64> {
+    let total: f64 = supply.values().sum();
+    preferences
+        .iter()
+        .map(|mine| {
+            supply
+                .iter()
+                .filter(|(_, &count)| count > 0.)
+                .map(|(category, &count)| {
+                    let value = mine.get(category).copied().unwrap_or(0.);
+                    let everyone: f64 = preferences
+                        .iter()
+                        .map(|theirs| theirs.get(category).copied().unwrap_or(0.))
+                        .sum();
+                    if everyone > 0. {
+                        count * value * value / everyone
+                    } else {
+                        0.
+                    }
+                })
+                .sum::<f64>()
+                / total.max(1.)
+        })
+        .collect()
+}
+
+pub fn spread(indices: &[f64]) -> f64 {
+    let highest = indices.iter().cloned().fold(f64::MIN, f64::max);
+    let lowest = indices.iter().cloned().fold(f64::MAX, f64::min);
+    (highest - lowest).max(0.)
+}
+
+// Advantage indices over a batch.
+#[derive(Serialize, Deserialize)]
+pub struct AdvantageTally {
+    #[serde(with = "stats::state")]
+    spreads: Stats,
+    #[serde(with = "stats::state_vec")]
+    seats: Vec<Stats>,
+    // Games won by a player with the highest index, counting ties.
+    top_wins: u64,
+}
+
+impl Default for AdvantageTally {
+    fn default() -> AdvantageTally {
+        AdvantageTally {
+            spreads: Stats::with_quantiles(),
+            seats: Vec::new(),
+            top_wins: 0,
+        }
+    }
+}
+
+impl AdvantageTally {
+    pub fn add(&mut self, advantage: &[f64], winner: usize) {
+        if advantage.is_empty() {
+            return;
+        }
+        self.spreads.add(spread(advantage));
+        self.seats.resize_with(advantage.len(), Stats::default);
+        for (stats, &index) in self.seats.iter_mut().zip(advantage) {
+            stats.add(index);
+        }
+        let highest = advantage.iter().cloned().fold(f64::MIN, f64::max);
+        if advantage.get(winner) == Some(&highest) {
+            self.top_wins += 1;
+        }
+    }
+
+    pub fn summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "mean_spread": self.spreads.mean(),
+            "spread_std": self.spreads.var().sqrt(),
+            "median_spread": self.spreads.median(),
+            "p90_spread": self.spreads.p90(),
+            "mean_index_by_seat": self.seats.iter().map(Stats::mean).collect::<Vec<f64>>(),
+            "top_advantage_win_rate": self.top_wins as f64 / self.spreads.len().max(1) as f64,
+        })
+    }
+}
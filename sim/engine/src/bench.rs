@@ -0,0 +1,148 @@
+// A small benchmark harness for the engine's hot paths, with the bench feature, for tracking
+// performance regressions as the engine grows. The benches themselves are in the cli crate,
+// where strategies are available, and run with
+//
+//   cargo bench -p hedonica-cli --features bench
+//
+// Each benchmark warms up, then takes a few samples, each running the routine in batches that
+// double in size until a batch takes long enough to time, and reports the time per iteration of
+// the fastest sample. Results are saved to target/hedonica-bench.json, and each run reports its
+// change from the last saved run, so a regression shows as a positive change. Timings are noisy
+// on a busy machine; changes of a few percent are not meaningful.
+//
+// This stands in for criterion, which the workspace does not vendor and builds without a
+// registry cannot fetch. It keeps to criterion's shape, a named routine timed per iteration, so
+// the benches can move over unchanged once criterion is vendored; only the saved history would
+// be lost.
+use crate::game::{GameRules, GameState, SimConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const WARM_UP: Duration = Duration::from_millis(300);
+const MEASUREMENT: Duration = Duration::from_secs(1);
+const SAMPLES: usize = 5;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Measurement {
+    pub nanos_per_iter: f64,
+    pub iterations: u64,
+}
+
+#[derive(Default)]
+pub struct Bench {
+    results: BTreeMap<String, Measurement>,
+    filter: Option<String>,
+}
+
+impl Bench {
+    // Benchmarks whose names contain the first command line argument that is not a flag, or
+    // all of them.
+    pub fn from_args() -> Bench {
+        Bench {
+            results: BTreeMap::new(),
+            filter: std::env::args().skip(1).find(|arg| !arg.starts_with('-')),
+        }
+    }
+
+    // Times `routine`, which should return what it computes so it is not optimized away.
+    pub fn run<T>(&mut self, name: &str, mut routine: impl FnMut() -> T) {
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !name.contains(filter.as_str()))
+        {
+            return;
+        }
+        let start = Instant::now();
+        let mut batch = 1u64;
+        while start.elapsed() < WARM_UP {
+            for _ in 0..batch {
+                black_box(routine());
+            }
+            batch *= 2;
+        }
+        let mut best = f64::MAX;
+        let mut iterations = 0;
+        for _ in 0..SAMPLES {
+            let (elapsed, size) = loop {
+                let start = Instant::now();
+                for _ in 0..batch {
+                    black_box(routine());
+                }
+                let elapsed = start.elapsed();
+                if elapsed >= MEASUREMENT / SAMPLES as u32 {
+                    break (elapsed, batch);
+                }
+                batch *= 2;
+            };
+            iterations += size;
+            best = best.min(elapsed.as_nanos() as f64 / size as f64);
+        }
+        self.results.insert(
+            name.to_string(),
+            Measurement {
+                nanos_per_iter: best,
+                iterations,
+            },
+        );
+    }
+
+    // Prints every result with its change from the saved results, then saves these.
+    pub fn finish(self, saved: &Path) {
+        let last: BTreeMap<String, Measurement> = fs::read_to_string(saved)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        for (name, measurement) in &self.results {
+            let change = match last.get(name) {
+                Some(last) => format!(
+                    "{:+.1}%",
+                    (measurement.nanos_per_iter / last.nanos_per_iter - 1.) * 100.
+                ),
+                None => String::from("new"),
+            };
+            println!(
+                "{:<32} {:>14} per iter {:>10}  ({} iterations)",
+                name,
+                format_nanos(measurement.nanos_per_iter),
+                change,
+                measurement.iterations
+            );
+        }
+        let mut merged = last;
+        merged.extend(self.results);
+        if let Err(e) = fs::write(saved, serde_json::to_string_pretty(&merged).unwrap()) {
+            crate::warn!(
+                "Could not save benchmark results to {}: {}",
+                saved.display(),
+                e
+            );
+        }
+    }
+}
+
+fn format_nanos(nanos: f64) -> String {
+    if nanos < 1e3 {
+        format!("{:.1} ns", nanos)
+    } else if nanos < 1e6 {
+        format!("{:.2} us", nanos / 1e3)
+    } else if nanos < 1e9 {
+        format!("{:.2} ms", nanos / 1e6)
+    } else {
+        format!("{:.2} s", nanos / 1e9)
+    }
+}
+
+// GameState::end_round, which play_round calls once every proposal is answered.
+pub fn end_round(
+    config: &SimConfig,
+    game: &mut GameState,
+    rules: &GameRules,
+    trade_acceptances: Vec<bool>,
+) {
+    game.end_round(config, rules, trade_acceptances);
+}
@@ -0,0 +1,141 @@
+// Auctions for drawn goods: sealed bids for contested goods, and the auction variant.
+//
+// When contested goods are enabled and the lead draws a good in a contested category, every
+// active player submits a sealed money bid for it. The highest bid wins the good and is paid
+// to the bank. Ties go to the first tied player in turn order starting from the lead, so the
+// lead keeps the good when nobody outbids them. Bids are clamped to what the bidder can pay.
+//
+// With auction rules, every drawn good is auctioned that way instead of going to the lead,
+// either by sealed bids or in an English auction. An English auction starts at a price of 0
+// with the lead holding the good. Going around in turn order from the lead, every active player
+// still in the auction is asked whether to outbid the standing bid by the increment, and drops
+// out if they do not or cannot pay. The last bidder left wins and pays their bid to the bank.
+// Each player's record in the BidRecord is the highest bid they made.
+use crate::types::{CategoryId, PlayerId};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ContestRules {
+    #[serde(default = "default_contested_categories")]
+    pub categories: Vec<String>,
+}
+
+fn default_contested_categories() -> Vec<String> {
+    vec![String::from("art")]
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AuctionFormat {
+    #[default]
+    SealedBid,
+    English,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AuctionRules {
+    #[serde(default)]
+    pub format: AuctionFormat,
+
+    // How much each bid in an English auction raises the price.
+    #[serde(
+        default = "default_increment",
+        deserialize_with = "deserialize_increment"
+    )]
+    pub increment: f64,
+}
+
+fn default_increment() -> f64 {
+    1.
+}
+
+// Bids that raise the price by nothing would go around forever.
+fn deserialize_increment<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    match f64::deserialize(deserializer)? {
+        increment if increment > 0. => Ok(increment),
+        _ => Err(serde::de::Error::custom(
+            "auction.increment must be positive",
+        )),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BidRecord {
+    pub turn: i32,
+    pub lead: PlayerId,
+    pub category: CategoryId,
+    // Indexed by player. Eliminated players bid nothing.
+    pub bids: Vec<Option<f64>>,
+    pub winner: PlayerId,
+}
+
+impl BidRecord {
+    pub fn price(&self) -> f64 {
+        self.bids[self.winner].unwrap_or(0.)
+    }
+}
+
+// The winning player, given bids indexed by player.
+pub fn winner(lead: PlayerId, bids: &[Option<f64>]) -> PlayerId {
+    let num_players = bids.len();
+    let mut best = lead;
+    for offset in 1..num_players {
+        let player_id = (lead + offset) % num_players;
+        if bids[player_id].unwrap_or(-1.) > bids[best].unwrap_or(-1.) {
+            best = player_id;
+        }
+    }
+    best
+}
+
+#[derive(Serialize, Default)]
+struct PlayerBids {
+    contests: u32,
+    wins: u32,
+    wins_as_lead: u32,
+    mean_bid: f64,
+    paid: f64,
+}
+
+#[derive(Serialize, Default)]
+struct CategoryBids {
+    contests: u32,
+    mean_price: f64,
+    lead_kept: u32,
+}
+
+// How often each player won contests and what contested goods sold for.
+pub fn summary(history: &[BidRecord]) -> serde_json::Value {
+    let mut players: BTreeMap<PlayerId, PlayerBids> = BTreeMap::new();
+    let mut categories: BTreeMap<&str, CategoryBids> = BTreeMap::new();
+    for record in history {
+        for (player_id, bid) in record.bids.iter().enumerate() {
+            if let Some(bid) = bid {
+                let player = players.entry(player_id).or_default();
+                player.contests += 1;
+                player.mean_bid += bid;
+            }
+        }
+        let winner = players.entry(record.winner).or_default();
+        winner.wins += 1;
+        winner.paid += record.price();
+        if record.winner == record.lead {
+            winner.wins_as_lead += 1;
+        }
+
+        let category = categories.entry(record.category.name()).or_default();
+        category.contests += 1;
+        category.mean_price += record.price();
+        if record.winner == record.lead {
+            category.lead_kept += 1;
+        }
+    }
+    for player in players.values_mut() {
+        player.mean_bid /= player.contests.max(1) as f64;
+    }
+    for category in categories.values_mut() {
+        category.mean_price /= category.contests.max(1) as f64;
+    }
+    serde_json::json!({ "bids_by_player": players, "bids_by_category": categories })
+}
@@ -0,0 +1,60 @@
+// The blitz variant, which simulates the "speed round" endgame: the number of trading rounds
+// allowed in a standard turn shrinks as the game goes on, following a schedule. Once a turn has
+// used its rounds, it ends as if nobody had anything more to propose. A round is one batch of
+// proposals, so two rounds let the lead propose and everyone else answer with proposals once.
+//
+// The schedule is a list of stages, e.g.
+//   {schedule: [{from_turn: 20, rounds: 4}, {from_turn: 30, rounds: 2},
+//               {from_turn: 40, rounds: 0}]}
+// Each stage applies from its turn until the next stage's. Turns before the first stage trade
+// without a limit, as in the standard game.
+//
+// Every game also keeps pacing counts, so a blitz schedule can be compared with the standard
+// structure, e.g. by sweeping blitz over null and a schedule.
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlitzStage {
+    pub from_turn: i32,
+    pub rounds: i32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlitzRules {
+    pub schedule: Vec<BlitzStage>,
+}
+
+impl BlitzRules {
+    // The trading rounds allowed on the turn, or None if there is no limit yet.
+    pub fn rounds_allowed(&self, turn: i32) -> Option<i32> {
+        self.schedule
+            .iter()
+            .filter(|stage| stage.from_turn <= turn)
+            .max_by_key(|stage| stage.from_turn)
+            .map(|stage| stage.rounds.max(0))
+    }
+}
+
+// How much trading standard turns had over a game.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Pacing {
+    pub trade_rounds: u32,
+    // Turns whose trading was ended by the blitz schedule.
+    pub cut_short: u32,
+}
+
+impl Pacing {
+    pub fn add(&mut self, other: &Pacing) {
+        self.trade_rounds += other.trade_rounds;
+        self.cut_short += other.cut_short;
+    }
+
+    // Per-turn means over a batch of games that lasted `turns` turns in all.
+    pub fn summary(&self, turns: f64) -> serde_json::Value {
+        let turns = turns.max(1.);
+        serde_json::json!({
+            "trade_rounds_per_turn": self.trade_rounds as f64 / turns,
+            "turns_cut_short": self.cut_short as f64 / turns,
+        })
+    }
+}
@@ -0,0 +1,143 @@
+// Special cards in the goods deck, as in the physical prototype: goods worth more or less than
+// one of their category, wild goods, and action cards. GameRules.special_cards adds them to the
+// deck of plain goods, e.g.
+//   special_cards: [
+//     {count: 10, category: "art", amount: 2},
+//     {count: 5, effect: "wild"},
+//     {count: 3, effect: "steal"},
+//     {count: 3, effect: "draw_two"},
+//   ]
+// A card's amount is what it adds to its holder's goods of its category, so 2 is a double and
+// 1.5 a fine good. Effects are resolved as the lead draws the card, before any auction or
+// contest:
+//   wild     - The lead names its category with PlayerStrategy::name_wild_category, and it is
+//              then drawn as a good of that category, with the card's amount.
+//   steal    - The lead takes one good from another player, chosen with
+//              PlayerStrategy::choose_steal. A choice the other player cannot give steals
+//              nothing.
+//   draw_two - The lead draws two more cards in its place.
+// Steal and draw_two cards are discarded once resolved, and cards with an effect drawn as a
+// milestone reward are discarded unresolved. Strategies without the SpecialCards capability
+// name the category they value most, and steal it from whoever shows the most.
+use crate::category::{CategoryId, MONEY};
+use crate::error::{HedonicaError, Result};
+use crate::game::GameRules;
+use crate::types::*;
+use crate::view::Observation;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GoodEffect {
+    Wild,
+    Steal,
+    DrawTwo,
+}
+
+impl GoodEffect {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GoodEffect::Wild => "wild",
+            GoodEffect::Steal => "steal",
+            GoodEffect::DrawTwo => "draw_two",
+        }
+    }
+
+    // Whether resolving it asks the lead's strategy for a decision.
+    pub fn needs_decision(&self) -> bool {
+        matches!(self, GoodEffect::Wild | GoodEffect::Steal)
+    }
+}
+
+// `count` copies of one special card.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SpecialCards {
+    pub count: usize,
+    // The category of a goods card. Cards with an effect have none.
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default = "default_amount")]
+    pub amount: f64,
+    #[serde(default)]
+    pub effect: Option<GoodEffect>,
+}
+
+fn default_amount() -> f64 {
+    1.
+}
+
+impl SpecialCards {
+    pub(crate) fn card(&self, rules: &GameRules) -> Result<Good> {
+        let invalid = |message: String| HedonicaError::parse("special_cards", message);
+        if self.amount <= 0. {
+            return Err(invalid(format!("amount {} is not positive", self.amount)));
+        }
+        let category = match (&self.category, self.effect) {
+            (Some(category), None) if rules.categories.contains(category) => {
+                CategoryId::intern(category)
+            }
+            (Some(category), None) => {
+                return Err(invalid(format!("{} is not a category", category)));
+            }
+            (None, Some(effect)) => CategoryId::intern(effect.name()),
+            _ => {
+                return Err(invalid(String::from(
+                    "each card needs a category or an effect",
+                )))
+            }
+        };
+        Ok(Good {
+            category,
+            amount: self.amount,
+            effect: self.effect,
+        })
+    }
+}
+
+// Every special card the rules add to the deck, unshuffled.
+pub fn generate_special_cards(rules: &GameRules) -> Result<Vec<Good>> {
+    let mut cards = Vec::new();
+    for spec in &rules.special_cards {
+        let card = spec.card(rules)?;
+        cards.extend(std::iter::repeat_n(card, spec.count));
+    }
+    Ok(cards)
+}
+
+// Goods categories from the most valued, by name on a tie.
+fn categories_by_value(preferences: &Preferences) -> Vec<CategoryId> {
+    let mut categories: Vec<(CategoryId, f64)> = preferences
+        .ids()
+        .filter(|&(category, _)| category != MONEY)
+        .collect();
+    categories.sort_by(|(a, a_value), (b, b_value)| {
+        b_value.total_cmp(a_value).then(a.name().cmp(b.name()))
+    });
+    categories
+        .into_iter()
+        .map(|(category, _)| category)
+        .collect()
+}
+
+// The category these preferences value most.
+pub fn favorite_category(preferences: &Preferences) -> CategoryId {
+    categories_by_value(preferences)[0]
+}
+
+// The good of the category the viewer values most, from the other player showing the most of
+// it, falling back to less valued categories when nobody shows any.
+pub fn default_steal(game_state: &Observation) -> Option<(PlayerId, CategoryId)> {
+    let me = game_state.viewer();
+    let view = game_state.view();
+    let preferences = game_state.player_state(me).preferences();
+    categories_by_value(preferences)
+        .into_iter()
+        .find_map(|category| {
+            (0..game_state.players.len())
+                .filter(|&p| p != me && !game_state.players[p].eliminated)
+                .map(|p| (p, view.inventory(p).get_id(category).unwrap_or(0.)))
+                .filter(|&(_, held)| held >= 1.)
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(p, _)| (p, category))
+        })
+}
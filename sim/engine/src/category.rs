@@ -0,0 +1,514 @@
+// Categories of goods as small interned ids, so goods sets and preference cards are vectors
+// indexed by category rather than maps keyed by name. Scoring, affordability checks and
+// transfers run in every decision of every game, and indexing a vector is far cheaper there
+// than hashing a string.
+//
+// Each game has its own interner, set up by start_game as the game starts: money is id 0, and
+// the rules' categories follow in the order the rules list them, so every game with the same
+// rules numbers them alike. The interner belongs to the thread playing the game, which plays
+// one at a time, and stays in place after the game so its results can still be read. Other
+// names, such as those in a strategy's own code, are added as they are first used, until a game
+// with other categories starts over. Input from outside the process, such as a bot's
+// responses, is read under known_only, where a name the game does not have yet is an error: a
+// bot cannot add categories, or have a misspelled category become a new one.
+//
+// CategoryMap keeps the map interface the code had when goods sets were HashMaps keyed by name:
+// names go in and come out, and a category may be absent as well as zero. Looking up a name
+// scans the game's handful of categories, so the engine's per-decision paths hold ids instead:
+// goods, cooldowns, trade checks and transfers. Serialized, a CategoryMap is a map of names and
+// a CategoryId is a name, so names only matter at the serialization boundary. Iteration is in
+// id order, which unlike a HashMap's is the same on every run.
+//
+// Names are handed out as &'static Strings, each distinct name kept once for the life of the
+// process.
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, Serializer};
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Index, IndexMut};
+use std::sync::Mutex;
+
+static NAMES: Mutex<BTreeMap<String, &'static String>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    // The names of the categories of the game this thread is playing, by id.
+    static CATEGORIES: RefCell<Vec<&'static String>> = RefCell::new(vec![keep_name("money")]);
+    // Whether categories deserialized on this thread may only be ones the game has.
+    static KNOWN_ONLY: Cell<bool> = const { Cell::new(false) };
+}
+
+// The process's one copy of the name.
+fn keep_name(name: &str) -> &'static String {
+    let mut names = NAMES.lock().unwrap();
+    if let Some(&name) = names.get(name) {
+        return name;
+    }
+    let kept: &'static String = Box::leak(Box::new(name.to_string()));
+    names.insert(name.to_string(), kept);
+    kept
+}
+
+// Sets up the interner for a game with these categories: money, then each category in order.
+// The interner is kept if it already starts that way, as it does for every game after the
+// first with the same rules, so ids held from earlier games stay valid.
+pub fn start_game(categories: &[String]) {
+    CATEGORIES.with(|names| {
+        let mut names = names.borrow_mut();
+        let same = names.len() > categories.len()
+            && names[1..=categories.len()]
+                .iter()
+                .zip(categories)
+                .all(|(name, category)| *name == category);
+        if !same {
+            *names = std::iter::once("money")
+                .chain(categories.iter().map(String::as_str))
+                .map(keep_name)
+                .collect();
+        }
+    });
+}
+
+// Runs f, which reads input from outside the process, with every CategoryMap and CategoryId it
+// deserializes limited to the game's categories.
+pub fn known_only<T>(f: impl FnOnce() -> T) -> T {
+    let known_only = KNOWN_ONLY.with(|flag| flag.replace(true));
+    let result = f();
+    KNOWN_ONLY.with(|flag| flag.set(known_only));
+    result
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct CategoryId(u16);
+
+pub const MONEY: CategoryId = CategoryId(0);
+
+impl CategoryId {
+    // The id of an interned name. There are only ever a handful of categories, so a scan of
+    // the names beats hashing the name.
+    pub fn lookup(name: &str) -> Option<CategoryId> {
+        CATEGORIES.with(|names| {
+            names
+                .borrow()
+                .iter()
+                .position(|interned| interned.as_str() == name)
+                .map(|i| CategoryId(i as u16))
+        })
+    }
+
+    // The id of the name, interning it if it is new.
+    pub fn intern(name: &str) -> CategoryId {
+        CategoryId::try_intern(name)
+            .unwrap_or_else(|| panic!("more than {} categories in one game", u16::MAX))
+    }
+
+    // The id of the name, interning it if it is new and there is an id left for it.
+    pub fn try_intern(name: &str) -> Option<CategoryId> {
+        if let Some(id) = CategoryId::lookup(name) {
+            return Some(id);
+        }
+        CATEGORIES.with(|names| {
+            let mut names = names.borrow_mut();
+            let id = u16::try_from(names.len()).ok()?;
+            names.push(keep_name(name));
+            Some(CategoryId(id))
+        })
+    }
+
+    // The id of the name, or under known_only an error if the game has no such category.
+    fn read<E: Error>(name: &str) -> Result<CategoryId, E> {
+        if KNOWN_ONLY.with(Cell::get) {
+            CategoryId::lookup(name).ok_or_else(|| E::custom(format!("unknown category {}", name)))
+        } else {
+            CategoryId::try_intern(name)
+                .ok_or_else(|| E::custom(format!("more than {} categories in one game", u16::MAX)))
+        }
+    }
+
+    pub fn name(self) -> &'static String {
+        CATEGORIES.with(|names| {
+            *names
+                .borrow()
+                .get(self.index())
+                .unwrap_or_else(|| panic!("no category {} in this game", self.0))
+        })
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for CategoryId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for CategoryId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for CategoryId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CategoryId, D::Error> {
+        CategoryId::read(&String::deserialize(deserializer)?)
+    }
+}
+
+// A category by name or by id, as CategoryMap takes them.
+pub trait CategoryKey {
+    // The category's id, if it has one.
+    fn find(&self) -> Option<CategoryId>;
+    // The category's id, interning a new name.
+    fn intern(&self) -> CategoryId;
+    fn name(&self) -> &str;
+}
+
+impl CategoryKey for CategoryId {
+    fn find(&self) -> Option<CategoryId> {
+        Some(*self)
+    }
+
+    fn intern(&self) -> CategoryId {
+        *self
+    }
+
+    fn name(&self) -> &str {
+        CategoryId::name(*self)
+    }
+}
+
+impl CategoryKey for str {
+    fn find(&self) -> Option<CategoryId> {
+        CategoryId::lookup(self)
+    }
+
+    fn intern(&self) -> CategoryId {
+        CategoryId::intern(self)
+    }
+
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+impl CategoryKey for String {
+    fn find(&self) -> Option<CategoryId> {
+        CategoryKey::find(self.as_str())
+    }
+
+    fn intern(&self) -> CategoryId {
+        CategoryKey::intern(self.as_str())
+    }
+
+    fn name(&self) -> &str {
+        self
+    }
+}
+
+impl<K: CategoryKey + ?Sized> CategoryKey for &K {
+    fn find(&self) -> Option<CategoryId> {
+        (**self).find()
+    }
+
+    fn intern(&self) -> CategoryId {
+        (**self).intern()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+}
+
+// An amount for some categories, indexed by category id.
+#[derive(Clone, Default)]
+pub struct CategoryMap {
+    values: Vec<Option<f64>>,
+}
+
+pub struct Entry<'a> {
+    value: &'a mut Option<f64>,
+}
+
+impl<'a> Entry<'a> {
+    pub fn or_insert(self, default: f64) -> &'a mut f64 {
+        self.value.get_or_insert(default)
+    }
+
+    pub fn or_default(self) -> &'a mut f64 {
+        self.or_insert(0.)
+    }
+
+    pub fn and_modify(self, f: impl FnOnce(&mut f64)) -> Entry<'a> {
+        if let Some(value) = self.value.as_mut() {
+            f(value);
+        }
+        self
+    }
+}
+
+impl CategoryMap {
+    pub fn new() -> CategoryMap {
+        CategoryMap::default()
+    }
+
+    pub fn get_id(&self, id: CategoryId) -> Option<f64> {
+        self.values.get(id.index()).copied().flatten()
+    }
+
+    pub fn get_id_mut(&mut self, id: CategoryId) -> Option<&mut f64> {
+        self.values.get_mut(id.index()).and_then(Option::as_mut)
+    }
+
+    pub fn insert_id(&mut self, id: CategoryId, value: f64) -> Option<f64> {
+        if self.values.len() <= id.index() {
+            self.values.resize(id.index() + 1, None);
+        }
+        self.values[id.index()].replace(value)
+    }
+
+    pub fn entry_id(&mut self, id: CategoryId) -> Entry<'_> {
+        if self.values.len() <= id.index() {
+            self.values.resize(id.index() + 1, None);
+        }
+        Entry {
+            value: &mut self.values[id.index()],
+        }
+    }
+
+    // Every category present, with its amount, in id order.
+    pub fn ids(&self) -> impl Iterator<Item = (CategoryId, f64)> + '_ {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| value.map(|value| (CategoryId(i as u16), value)))
+    }
+
+    pub fn ids_mut(&mut self) -> impl Iterator<Item = (CategoryId, &mut f64)> {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, value)| value.as_mut().map(|value| (CategoryId(i as u16), value)))
+    }
+
+    pub fn get<K: CategoryKey + ?Sized>(&self, category: &K) -> Option<&f64> {
+        let id = category.find()?;
+        self.values.get(id.index()).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut<K: CategoryKey + ?Sized>(&mut self, category: &K) -> Option<&mut f64> {
+        self.get_id_mut(category.find()?)
+    }
+
+    pub fn contains_key<K: CategoryKey + ?Sized>(&self, category: &K) -> bool {
+        self.get(category).is_some()
+    }
+
+    pub fn insert(&mut self, category: impl CategoryKey, value: f64) -> Option<f64> {
+        self.insert_id(category.intern(), value)
+    }
+
+    pub fn remove<K: CategoryKey + ?Sized>(&mut self, category: &K) -> Option<f64> {
+        let id = category.find()?;
+        self.values.get_mut(id.index()).and_then(Option::take)
+    }
+
+    pub fn entry(&mut self, category: impl CategoryKey) -> Entry<'_> {
+        self.entry_id(category.intern())
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.iter().filter(|value| value.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(Option::is_none)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            values: self.values.iter().enumerate(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            values: self.values.iter_mut().enumerate(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &'static String> + '_ {
+        self.iter().map(|(category, _)| category)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &f64> {
+        self.values.iter().flatten()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut f64> {
+        self.values.iter_mut().flatten()
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&'static String, &mut f64) -> bool) {
+        for (i, slot) in self.values.iter_mut().enumerate() {
+            if let Some(value) = slot {
+                if !keep(CategoryId(i as u16).name(), value) {
+                    *slot = None;
+                }
+            }
+        }
+    }
+}
+
+pub struct Iter<'a> {
+    values: std::iter::Enumerate<std::slice::Iter<'a, Option<f64>>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'static String, &'a f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values.find_map(|(i, value)| {
+            value
+                .as_ref()
+                .map(|value| (CategoryId(i as u16).name(), value))
+        })
+    }
+}
+
+pub struct IterMut<'a> {
+    values: std::iter::Enumerate<std::slice::IterMut<'a, Option<f64>>>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'static String, &'a mut f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.values.find_map(|(i, value)| {
+            value
+                .as_mut()
+                .map(|value| (CategoryId(i as u16).name(), value))
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a CategoryMap {
+    type Item = (&'static String, &'a f64);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut CategoryMap {
+    type Item = (&'static String, &'a mut f64);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for CategoryMap {
+    type Item = (String, f64);
+    type IntoIter = std::vec::IntoIter<(String, f64)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ids()
+            .map(|(id, value)| (id.name().clone(), value))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<K: CategoryKey> FromIterator<(K, f64)> for CategoryMap {
+    fn from_iter<I: IntoIterator<Item = (K, f64)>>(iter: I) -> CategoryMap {
+        let mut map = CategoryMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: CategoryKey> Extend<(K, f64)> for CategoryMap {
+    fn extend<I: IntoIterator<Item = (K, f64)>>(&mut self, iter: I) {
+        for (category, value) in iter {
+            self.insert(category, value);
+        }
+    }
+}
+
+impl<K: CategoryKey + ?Sized> Index<&K> for CategoryMap {
+    type Output = f64;
+
+    fn index(&self, category: &K) -> &f64 {
+        self.get(category)
+            .unwrap_or_else(|| panic!("no category {}", category.name()))
+    }
+}
+
+impl<K: CategoryKey + ?Sized> IndexMut<&K> for CategoryMap {
+    fn index_mut(&mut self, category: &K) -> &mut f64 {
+        let name = category.name();
+        self.get_mut(category)
+            .unwrap_or_else(|| panic!("no category {}", name))
+    }
+}
+
+impl Index<CategoryId> for CategoryMap {
+    type Output = f64;
+
+    fn index(&self, id: CategoryId) -> &f64 {
+        self.values
+            .get(id.index())
+            .and_then(Option::as_ref)
+            .unwrap_or_else(|| panic!("no category {}", id))
+    }
+}
+
+impl IndexMut<CategoryId> for CategoryMap {
+    fn index_mut(&mut self, id: CategoryId) -> &mut f64 {
+        self.get_id_mut(id)
+            .unwrap_or_else(|| panic!("no category {}", id))
+    }
+}
+
+// Equal when the same categories are present with the same amounts.
+impl PartialEq for CategoryMap {
+    fn eq(&self, other: &CategoryMap) -> bool {
+        let len = self.values.len().max(other.values.len());
+        (0..len).all(|i| {
+            self.values.get(i).copied().flatten() == other.values.get(i).copied().flatten()
+        })
+    }
+}
+
+impl fmt::Debug for CategoryMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Serialize for CategoryMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for CategoryMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CategoryMap, D::Error> {
+        let mut map = CategoryMap::new();
+        for (name, value) in BTreeMap::<String, f64>::deserialize(deserializer)? {
+            map.insert_id(CategoryId::read(&name)?, value);
+        }
+        Ok(map)
+    }
+}
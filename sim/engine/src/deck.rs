@@ -0,0 +1,125 @@
+// What the goods deck holds, as GameRules.deck specifies it. generate_deck builds the deck
+// from the spec, adds the rules' special_cards and shuffles it. A spec is one of
+//
+//   {type: "counts", counts: {cars: 120, art: 40}}
+//       That many plain goods of each category, and none of categories left out.
+//   {type: "weighted", size: 500, weights: {cars: 3, art: 1}}
+//       `size` plain goods, each of a category drawn at random in proportion to its weight, so
+//       the composition differs from game to game.
+//   {type: "cards", cards: [{count: 100, category: "cars"}, {count: 20, category: "art",
+//                            amount: 2}, {count: 4, effect: "wild"}]}
+//       Exactly the listed cards, written as special_cards are. See cards.rs.
+//
+// Without a deck spec, the deck holds deck_counts goods of each category, and categories left
+// out get an even share of deck_size.
+//
+// GameRules.deck_exhaustion says what happens when the deck runs out:
+//
+//   end          - The game ends after the turn that drew the last card.
+//   final_turns  - Every player still in the game, from the next lead on, plays one more turn
+//                  without a draw, trading as usual, and then the game ends.
+//   reshuffle    - Cards that leave play (action cards once resolved, and goods converted to
+//                  money) go to a discard pile, which is shuffled to become the deck when the
+//                  deck runs out. The game then only runs out of goods once both are empty.
+//
+// reshuffle_discards is the older spelling of reshuffle. The turn the deck ran out and how the
+// game ended are in GameResult.
+use crate::cards::SpecialCards;
+use crate::category::CategoryId;
+use crate::error::{HedonicaError, Result};
+use crate::game::GameRules;
+use crate::types::Good;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DeckExhaustion {
+    #[default]
+    End,
+    FinalTurns,
+    Reshuffle,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeckSpec {
+    Counts {
+        counts: BTreeMap<String, usize>,
+    },
+    Weighted {
+        size: usize,
+        weights: BTreeMap<String, f64>,
+    },
+    Cards {
+        cards: Vec<SpecialCards>,
+    },
+}
+
+fn invalid(message: String) -> HedonicaError {
+    HedonicaError::parse("deck", message)
+}
+
+fn check_category(rules: &GameRules, category: &str) -> Result<()> {
+    if rules.categories.iter().any(|c| c == category) {
+        Ok(())
+    } else {
+        Err(invalid(format!("{} is not a category", category)))
+    }
+}
+
+impl DeckSpec {
+    // The deck's cards, unshuffled, with plain goods in the order of the rules' categories,
+    // drawing weighted compositions from the rng.
+    pub fn cards(&self, rules: &GameRules, rng: &mut impl Rng) -> Result<Vec<Good>> {
+        let plain = |counts: &[usize]| {
+            rules
+                .categories
+                .iter()
+                .zip(counts)
+                .flat_map(|(category, &count)| {
+                    std::iter::repeat_n(Good::from(CategoryId::intern(category)), count)
+                })
+                .collect::<Vec<Good>>()
+        };
+        let mut cards = Vec::new();
+        match self {
+            DeckSpec::Counts { counts } => {
+                for category in counts.keys() {
+                    check_category(rules, category)?;
+                }
+                let counts: Vec<usize> = rules
+                    .categories
+                    .iter()
+                    .map(|category| counts.get(category).copied().unwrap_or(0))
+                    .collect();
+                cards.extend(plain(&counts));
+            }
+            DeckSpec::Weighted { size, weights } => {
+                for category in weights.keys() {
+                    check_category(rules, category)?;
+                }
+                let weights = rules
+                    .categories
+                    .iter()
+                    .map(|category| weights.get(category).copied().unwrap_or(0.));
+                let index =
+                    WeightedIndex::new(weights).map_err(|e| invalid(format!("weights: {}", e)))?;
+                let mut counts = vec![0; rules.categories.len()];
+                for _ in 0..*size {
+                    counts[index.sample(rng)] += 1;
+                }
+                cards.extend(plain(&counts));
+            }
+            DeckSpec::Cards { cards: specs } => {
+                for spec in specs {
+                    let card = spec.card(rules)?;
+                    cards.extend(std::iter::repeat_n(card, spec.count));
+                }
+            }
+        }
+        Ok(cards)
+    }
+}
@@ -0,0 +1,185 @@
+// Errors in what the simulator was asked to do, as opposed to bugs: configs, rules and files
+// that cannot be used, and strategies that cannot play the game they were given. Each says
+// what went wrong in words a user can act on. The command line prints them and exits with
+// exit_code. Bugs and broken invariants still panic, and a game that panics is aborted as
+// try_play describes.
+use crate::player::Capability;
+use crate::types::PlayerId;
+use std::fmt;
+use std::io;
+
+// Exit codes, from sysexits.h.
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+
+#[derive(Debug)]
+pub enum HedonicaError {
+    // A config, rules file or command line value that cannot be parsed.
+    Parse {
+        what: String,
+        message: String,
+    },
+    // A file that cannot be read or written.
+    Io {
+        what: String,
+        source: io::Error,
+    },
+    UnknownStrategy {
+        player_type: String,
+    },
+    InvalidStrategyConfig {
+        player: PlayerId,
+        player_type: String,
+        errors: Vec<String>,
+    },
+    MissingCapability {
+        player: PlayerId,
+        player_type: String,
+        capability: Capability,
+    },
+    TooManyPlayerConfigs {
+        configs: usize,
+        num_players: usize,
+    },
+    // A strategy seated in a game larger than it can play.
+    TooManyPlayers {
+        player: PlayerId,
+        player_type: String,
+        max_players: usize,
+        num_players: usize,
+    },
+    // A strategy seated in a game smaller than it can play.
+    TooFewPlayers {
+        player: PlayerId,
+        player_type: String,
+        min_players: usize,
+        num_players: usize,
+    },
+    // The rules leave no goods to draw.
+    EmptyDeck,
+    // A strategy answered outside the choices it was given.
+    InvalidChoice {
+        player: PlayerId,
+        what: &'static str,
+        choice: usize,
+        choices: usize,
+    },
+    // Settings that cannot be used together, or an unsupported build.
+    Unsupported {
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, HedonicaError>;
+
+impl HedonicaError {
+    pub fn parse(what: &str, error: impl fmt::Display) -> HedonicaError {
+        HedonicaError::Parse {
+            what: what.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn io(what: impl fmt::Display, source: io::Error) -> HedonicaError {
+        HedonicaError::Io {
+            what: what.to_string(),
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HedonicaError::Parse { .. } | HedonicaError::Unsupported { .. } => EX_USAGE,
+            HedonicaError::Io { .. } => EX_IOERR,
+            HedonicaError::InvalidChoice { .. } => EX_SOFTWARE,
+            _ => EX_DATAERR,
+        }
+    }
+}
+
+impl fmt::Display for HedonicaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HedonicaError::Parse { what, message } => {
+                write!(f, "could not parse {}: {}", what, message)
+            }
+            HedonicaError::Io { what, source } => write!(f, "{}: {}", what, source),
+            HedonicaError::UnknownStrategy { player_type } => write!(
+                f,
+                "unknown player_type \"{}\", see the strategies subcommand",
+                player_type
+            ),
+            HedonicaError::InvalidStrategyConfig {
+                player,
+                player_type,
+                errors,
+            } => write!(
+                f,
+                "player {} ({}) has an invalid config: {}",
+                player,
+                player_type,
+                errors.join(", ")
+            ),
+            HedonicaError::MissingCapability {
+                player,
+                player_type,
+                capability,
+            } => write!(
+                f,
+                "player {} ({}) does not support {:?}, which the game rules require",
+                player, player_type, capability
+            ),
+            HedonicaError::TooManyPlayerConfigs {
+                configs,
+                num_players,
+            } => write!(
+                f,
+                "{} player configs for a game of {} players",
+                configs, num_players
+            ),
+            HedonicaError::TooManyPlayers {
+                player,
+                player_type,
+                max_players,
+                num_players,
+            } => write!(
+                f,
+                "player {} ({}) only plays games of up to {} players, not {}",
+                player, player_type, max_players, num_players
+            ),
+            HedonicaError::TooFewPlayers {
+                player,
+                player_type,
+                min_players,
+                num_players,
+            } => write!(
+                f,
+                "player {} ({}) only plays games of at least {} players, not {}",
+                player, player_type, min_players, num_players
+            ),
+            HedonicaError::EmptyDeck => write!(f, "the game rules leave no goods in the deck"),
+            HedonicaError::InvalidChoice {
+                player,
+                what,
+                choice,
+                choices,
+            } => write!(
+                f,
+                "player {} chose {} {} of {}",
+                player, what, choice, choices
+            ),
+            HedonicaError::Unsupported { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for HedonicaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HedonicaError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,122 @@
+// Escrowed trading, with the house rule trade_settlement: "escrow". By default a round's
+// accepted trades are carried out one after another, so goods a player receives in one trade
+// can pay for the next, and a player who accepts two trades they can afford one at a time but
+// not together loses whichever comes second, in no particular order.
+//
+// In escrow, proposals are commitments. When proposals are made, the proposer's side of each
+// is reserved from their holdings, in seat order of the other party, and a proposal that their
+// unreserved holdings cannot cover is invalid, so a lead cannot offer the same good to two
+// players. When the round ends, every accepted trade is settled at once against the holdings
+// the players had when the round's trades were answered: trades are taken in seat order of
+// the non-lead party, each reserving both sides, and one that a party cannot cover alongside
+// the trades before it is rejected as unaffordable for that party. What is left is carried
+// out together, so nothing received in the round pays for anything else in it.
+use crate::game::{GameState, TradeError};
+use crate::types::{GoodsSet, PlayerId, Trade};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeSettlement {
+    // Accepted trades are carried out one at a time.
+    #[default]
+    Sequential,
+    // Proposals reserve goods and accepted trades settle together.
+    Escrow,
+}
+
+// Goods each player has committed to trades this round.
+pub struct Escrow {
+    reserved: Vec<GoodsSet>,
+}
+
+impl Escrow {
+    pub fn new(num_players: usize) -> Escrow {
+        Escrow {
+            reserved: vec![GoodsSet::new(); num_players],
+        }
+    }
+
+    // Checks that `player` can cover `owed` on top of what they have reserved. An unaffordable
+    // trade's `held` is what they had left unreserved.
+    fn check(&self, game: &GameState, player: PlayerId, owed: &GoodsSet) -> Result<(), TradeError> {
+        for (category, needed) in owed.ids() {
+            let held = game.players[player]
+                .num_goods
+                .get_id(category)
+                .unwrap_or(0.);
+            let free = held - self.reserved[player].get_id(category).unwrap_or(0.);
+            if free < needed {
+                return Err(TradeError::Unaffordable {
+                    player,
+                    category,
+                    needed,
+                    held: free,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self, player: PlayerId, owed: &GoodsSet) {
+        for (category, &amount) in owed {
+            *self.reserved[player].entry(category).or_insert(0.) += amount;
+        }
+    }
+
+    // Reserves the proposer's side of a proposal.
+    pub fn reserve_proposal(&mut self, game: &GameState, trade: &Trade) -> Result<(), TradeError> {
+        let owed = trade.owed_by(trade.proposer);
+        self.check(game, trade.proposer, &owed)?;
+        self.commit(trade.proposer, &owed);
+        Ok(())
+    }
+
+    // Reserves both sides of an accepted trade, or neither, naming the party who cannot cover
+    // their side.
+    fn reserve_trade(
+        &mut self,
+        game: &GameState,
+        trade: &Trade,
+    ) -> Result<(), (PlayerId, TradeError)> {
+        let sides = [
+            (trade.proposer, trade.owed_by(trade.proposer)),
+            (trade.accepter, trade.owed_by(trade.accepter)),
+        ];
+        for (player, owed) in &sides {
+            self.check(game, *player, owed)
+                .map_err(|error| (*player, error))?;
+        }
+        for (player, owed) in &sides {
+            self.commit(*player, owed);
+        }
+        Ok(())
+    }
+}
+
+// Splits a round's accepted trades, keyed by their non-lead party, into those that settle
+// together and those rejected for conflicting with earlier ones, with the party who could not
+// cover their side and why. Trades past the turn's trade limit are left out of both, as they
+// are when trades are sequential.
+pub fn settle(
+    game: &GameState,
+    mut accepted: Vec<(PlayerId, Trade)>,
+) -> (Vec<Trade>, Vec<(PlayerId, TradeError)>) {
+    accepted.sort_unstable_by_key(|&(player_id, _)| player_id);
+    let mut escrow = Escrow::new(game.players.len());
+    let mut trades_left = game.trades_left();
+    let (mut settled, mut rejected) = (Vec::new(), Vec::new());
+    for (_, trade) in accepted {
+        if trades_left == Some(0) {
+            break;
+        }
+        match escrow.reserve_trade(game, &trade) {
+            Ok(()) => {
+                settled.push(trade);
+                trades_left = trades_left.map(|left| left - 1);
+            }
+            Err(rejection) => rejected.push(rejection),
+        }
+    }
+    (settled, rejected)
+}
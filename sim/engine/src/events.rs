@@ -0,0 +1,162 @@
+// Event cards and insurance against them.
+//
+// When event cards are enabled, the lead turns over an event card at the start of each turn,
+// after drawing a good. Negative events cost the lead points, unless they hold insurance,
+// which absorbs the next negative event. The event deck is shuffled once per game and
+// cycled through without reshuffling.
+use crate::category::MONEY;
+use crate::game::*;
+use crate::rng;
+use crate::rng::{AuditedRng, RngPurpose};
+use crate::shuffle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    // Nothing happens.
+    Calm,
+    // The lead pays tax_amount money, or all their money if they have less.
+    Tax,
+    // The lead loses one good from their most valuable category.
+    Spoilage,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EventRules {
+    #[serde(default = "default_calm_cards")]
+    pub calm_cards: usize,
+    #[serde(default = "default_tax_cards")]
+    pub tax_cards: usize,
+    #[serde(default = "default_spoilage_cards")]
+    pub spoilage_cards: usize,
+
+    #[serde(default = "default_tax_amount")]
+    pub tax_amount: f64,
+
+    // Money paid for cover against the next negative event.
+    #[serde(default = "default_insurance_price")]
+    pub insurance_price: f64,
+}
+
+fn default_calm_cards() -> usize {
+    20
+}
+fn default_tax_cards() -> usize {
+    5
+}
+fn default_spoilage_cards() -> usize {
+    5
+}
+fn default_tax_amount() -> f64 {
+    5.
+}
+fn default_insurance_price() -> f64 {
+    2.
+}
+
+// Each player's history with events and insurance. Values are in points.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct EventLedger {
+    pub insured: bool,
+    pub negative_events: u32,
+    pub losses: f64,
+
+    // Turns on which the player could have bought insurance.
+    pub insurance_offers: u32,
+    pub insurance_bought: u32,
+    pub premiums: f64,
+    pub claims: u32,
+    pub losses_avoided: f64,
+}
+
+impl EventLedger {
+    pub fn add(&mut self, other: &EventLedger) {
+        self.negative_events += other.negative_events;
+        self.losses += other.losses;
+        self.insurance_offers += other.insurance_offers;
+        self.insurance_bought += other.insurance_bought;
+        self.premiums += other.premiums;
+        self.claims += other.claims;
+        self.losses_avoided += other.losses_avoided;
+    }
+
+    // Purchase rate and net payoff of insurance, for reporting.
+    pub fn summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "negative_events": self.negative_events,
+            "losses": self.losses,
+            "insurance_bought": self.insurance_bought,
+            "purchase_rate": self.insurance_bought as f64 / self.insurance_offers.max(1) as f64,
+            "claims": self.claims,
+            "premiums": self.premiums,
+            "losses_avoided": self.losses_avoided,
+            "payoff": self.losses_avoided - self.premiums,
+        })
+    }
+}
+
+pub fn generate_event_deck(config: &SimConfig, rules: &EventRules) -> Vec<Event> {
+    let seed = rng::config_seed(config.deck_shuffle_seed).map(|seed| seed.wrapping_add(1));
+    let mut rng = AuditedRng::new(config, RngPurpose::EventDeck, seed);
+
+    let mut deck = Vec::new();
+    deck.extend(std::iter::repeat_n(Event::Calm, rules.calm_cards));
+    deck.extend(std::iter::repeat_n(Event::Tax, rules.tax_cards));
+    deck.extend(std::iter::repeat_n(Event::Spoilage, rules.spoilage_cards));
+    shuffle::shuffle(&mut deck, &config.shuffle, &mut rng);
+    rng.record(config);
+    deck
+}
+
+pub fn buy_insurance(player: &mut PlayerState, rules: &EventRules) {
+    if player.events.insured || player.num_goods[MONEY] < rules.insurance_price {
+        return;
+    }
+    *player.money_mut() -= rules.insurance_price;
+    player.events.insured = true;
+    player.events.insurance_bought += 1;
+    player.events.premiums += rules.insurance_price * player.preferences()[MONEY];
+}
+
+// The good category and points a spoilage event would take from the player.
+fn most_valuable_good(player: &PlayerState) -> Option<(String, f64)> {
+    player
+        .num_goods
+        .iter()
+        .filter(|(category, &count)| category.as_str() != "money" && count >= 1.)
+        .map(|(category, _)| (category.clone(), player.preferences()[category]))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then_with(|| b.0.cmp(&a.0)))
+}
+
+pub fn resolve(event: Event, player: &mut PlayerState, rules: &EventRules) {
+    let loss = match event {
+        Event::Calm => return,
+        Event::Tax => {
+            rules.tax_amount.min(player.num_goods[MONEY]).max(0.) * player.preferences()[MONEY]
+        }
+        Event::Spoilage => most_valuable_good(player).map_or(0., |(_, points)| points),
+    };
+
+    player.events.negative_events += 1;
+    if player.events.insured {
+        player.events.insured = false;
+        player.events.claims += 1;
+        player.events.losses_avoided += loss;
+        return;
+    }
+
+    player.events.losses += loss;
+    match event {
+        Event::Calm => {}
+        Event::Tax => {
+            let money = player.money_mut();
+            *money -= rules.tax_amount.min(*money).max(0.);
+        }
+        Event::Spoilage => {
+            if let Some((category, _)) = most_valuable_good(player) {
+                *player.num_goods.get_mut(&category).unwrap() -= 1.;
+            }
+        }
+    }
+}
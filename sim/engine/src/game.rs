@@ -0,0 +1,3440 @@
+// Rules of the game.
+// R1. Players draw preferences cards which give them utility functions,
+//     a mapping between "goods" and points.
+// R2. Players receive an initial cash payment.
+// R3. Players take turns in a pre-defined order until some player earns enough points to win.
+// R4. On his turn, a player draws a good and trades with as many other players as he would like.
+//     When he is done trading, his turn ends and the next player's turn begins.
+//
+// Variables in the game.
+// victory_threshold - The number of points needed to win the game.
+// win_condition - How the game is won, when not by reaching victory_threshold. See WinCondition.
+// start_money - The amount of money that players start with.
+// { categories } - The set of categories of goods.
+// { preferences } - The set of preferences that a player can be given.
+//                   Each preference is a map from category to point value.
+// { goods } - The set of goods a player can draw from the deck.
+//             Each good has a category. Special cards may count as more than one good, or
+//             have an effect when drawn. See cards.rs.
+//
+// Goals of simulation:
+//  G0. The game should be fun to play and easy to learn.
+//  G1. Ensure that the total game time is reasonable and has low variance.
+//  G2. Ensure that the distribution of preferences is "fair", in that
+//      the subset of preferences selected by the players do not typically
+//      conspire to advantage any one player by too much.
+//  G3. Ensure that there are no lame strategies that are easy to execute
+//      and greatly outperform many other simple strategies.
+//  G4. Ensure that there are no "dominant" strategies. We do not want every player
+//      to be forced to execute the same strategy.
+//
+// We aim to find values of the variables that will achieve these goals, and to find convincing
+// evidence that the goals have been achieved.
+//
+// Glossary:
+//   Lead: The player whose turn it currently is.
+//   Deck: The set of goods that has not yet been taken by any player.
+//         In the board game, this is a deck of cards.
+//
+//
+// This first simulator simplifies the game by making all actions synchronous,
+// and by restricting the structure of trading.
+// Trading is modeled as proposed trades that can be either accepted or rejected.
+//
+// With max_negotiation_rounds, the recipient of a rejected proposal may answer with a
+// counter-offer, which the other side accepts, counters in turn, or lets drop.
+//
+// A player must always be able to fulfill all his outstanding proposals, and no player
+// can accept any proposals that he cannot fulfill.
+//
+// Proposals must be accepted or rejected when they are received.
+// The flow is like this.
+//
+//  T1. The lead creates a set of proposed trades and broadcasts them.
+//  T2. Each non-lead accepts or rejects any trades directed at him.
+//  T3. Each non-lead prepares a set of trade proposal, which are gathered and broadcast to all
+//      playes
+use crate::ablation::Ablation;
+use crate::advantage;
+use crate::bidding;
+use crate::bidding::{AuctionFormat, AuctionRules, BidRecord, ContestRules};
+use crate::blitz::{BlitzRules, Pacing};
+use crate::cards::{self, GoodEffect, SpecialCards};
+use crate::category::{self, CategoryId, MONEY};
+use crate::deck::{DeckExhaustion, DeckSpec};
+use crate::error::{self, HedonicaError};
+use crate::escrow::{self, Escrow, TradeSettlement};
+use crate::events;
+use crate::events::{Event, EventLedger, EventRules};
+use crate::non_nan::NonNan;
+use crate::observer::{self, Observer, ObserverConfig};
+use crate::orderbook::{Fill, Order, OrderBook};
+use crate::player;
+
+use crate::player::*;
+use crate::preferences::{PreferenceGenerator, PreferenceModel};
+use crate::replay::{Decision, Recorder, ReplayEvent};
+use crate::rng;
+use crate::rng::{AuditedRng, RngPurpose, RunSeeds};
+use crate::sampling::PreferenceSampling;
+use crate::scoring::ScoringModel;
+use crate::series::{MatchFormat, MatchResult};
+use crate::shuffle;
+use crate::shuffle::ShuffleModel;
+use crate::stats::{EarlyStopping, HistogramConfig};
+use crate::timing::ResignRules;
+use crate::trade_flows::{self, TradeFlow};
+use crate::types::*;
+use crate::view::{Observation, PlayerView};
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlayerState {
+    preferences: Preferences,
+    pub num_goods: GoodsSet,
+
+    // Eliminated players take no further part in the game and cannot win. Players who resign
+    // are eliminated too.
+    #[serde(default)]
+    pub eliminated: bool,
+    #[serde(default)]
+    pub resigned: bool,
+
+    // Points lost to bailouts and defaulted loans.
+    #[serde(default)]
+    pub penalty: f64,
+    #[serde(default)]
+    pub bailouts: u32,
+
+    #[serde(default)]
+    pub loans: Vec<Loan>,
+    #[serde(default)]
+    pub loans_taken: u32,
+    #[serde(default)]
+    pub loan_defaults: u32,
+
+    #[serde(default)]
+    pub events: EventLedger,
+
+    // The turn on which the player may next trade each category, with trade cooldowns on.
+    #[serde(default)]
+    pub cooldowns: HashMap<CategoryId, i32>,
+
+    // In a copy of the game shown to another player under face-down inventories, how many
+    // goods this player holds whose categories are hidden. They are left out of num_goods.
+    #[serde(default)]
+    pub face_down_goods: f64,
+
+    // The rules' utility curve and scorer, kept with the player so scoring needs nothing else.
+    #[serde(default)]
+    pub utility_curve: UtilityCurve,
+    #[serde(default)]
+    pub scoring: ScoringModel,
+
+    // In a copy of the game shown to another player, with this player's preference card
+    // removed, their score if scores are public.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shown_score: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Loan {
+    pub owed: f64,
+    // How many more of the borrower's turns as lead before repayment is due.
+    pub turns_left: i32,
+}
+
+impl PlayerState {
+    pub fn preferences(&self) -> &Preferences {
+        &self.preferences
+    }
+
+    // Strategies should use PlayerView::score, which respects hidden scoring.
+    pub fn score(&self) -> f64 {
+        self.worth(&self.num_goods) - self.penalty - self.debt() * self.preferences[MONEY]
+    }
+
+    // Points for holding these goods, by this player's card and the rules' scorer.
+    pub fn worth(&self, goods: &GoodsSet) -> f64 {
+        self.scoring
+            .scorer()
+            .worth(&self.preferences, self.utility_curve, goods)
+    }
+
+    // How this player's score would change on receiving one set of goods for another. Under a
+    // utility curve this depends on what they hold, unlike player::score_delta.
+    pub fn score_change(&self, received: &GoodsSet, given: &GoodsSet) -> f64 {
+        let mut after = self.num_goods.clone();
+        for (id, count) in received.ids() {
+            *after.entry_id(id).or_insert(0.) += count;
+        }
+        for (id, count) in given.ids() {
+            *after.entry_id(id).or_insert(0.) -= count;
+        }
+        self.worth(&after) - self.worth(&self.num_goods)
+    }
+
+    // Outstanding debt counts against the score, so borrowing is only worth it for what the
+    // money can buy.
+    pub fn debt(&self) -> f64 {
+        self.loans.iter().map(|loan| loan.owed).sum()
+    }
+
+    pub fn money_mut(&mut self) -> &mut f64 {
+        self.num_goods.get_id_mut(MONEY).unwrap()
+    }
+
+    pub fn can_trade(&self, category: CategoryId, turn: i32) -> bool {
+        self.cooldowns
+            .get(&category)
+            .is_none_or(|&next| turn >= next)
+    }
+
+    pub fn is_bankrupt(&self) -> bool {
+        self.num_goods[MONEY] <= 0.
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameState {
+    deck: Vec<Good>,
+
+    // Cards that have left play, face up. See deck.rs.
+    #[serde(default)]
+    pub discards: Vec<Good>,
+
+    pub players: Vec<PlayerState>,
+
+    // It is this player's turn.
+    pub lead: PlayerId,
+
+    // Starts at 0, increments each time the lead changes.
+    pub current_turn: i32,
+
+    // Starts at 0, increments each time trades are proposed.
+    // The lead proposes trades on even rounds.
+    pub current_round: i32,
+    pub current_trade_proposals: HashMap<PlayerId, Trade>,
+
+    current_trades: Vec<Trade>,
+    past_trades: HashMap<i32, Vec<Trade>>,
+
+    // Players in the order they were eliminated.
+    pub eliminations: Vec<PlayerId>,
+
+    event_deck: Vec<Event>,
+    events_drawn: usize,
+
+    // With action points, what the lead has done and can still afford this turn.
+    pub action_points_left: u32,
+    pub turn_actions: Vec<TurnAction>,
+    pub action_counts: ActionCounts,
+
+    #[serde(default)]
+    pub pacing: Pacing,
+
+    // With the order book protocol, the standing orders and every fill so far.
+    pub order_book: OrderBook,
+    pub fills: Vec<Fill>,
+    spread_total: f64,
+    spread_samples: u32,
+
+    // Every sealed-bid contest for a drawn good so far.
+    pub bid_history: Vec<BidRecord>,
+
+    pub milestone_claims: Vec<MilestoneClaim>,
+
+    // Invalid trades so far, the players who have forfeited the rest of this turn for making
+    // them, and whether one aborted the game.
+    pub trade_errors: Vec<TradeErrorRecord>,
+    pub forfeited: Vec<PlayerId>,
+    pub aborted: bool,
+
+    // The lead, if they met the win condition and so ended the game.
+    #[serde(default)]
+    pub victor: Option<PlayerId>,
+
+    // The turn the deck ran out, and with deck_exhaustion final_turns, how many turns are left
+    // to play once it has. See deck.rs.
+    #[serde(default)]
+    pub deck_ran_out: Option<i32>,
+    #[serde(default)]
+    pub final_turns_left: Option<usize>,
+
+    // The seeds the game was dealt with.
+    #[serde(default)]
+    pub seeds: RunSeeds,
+
+    // The preference cards dealt to each player, with preference_hand_size above 1, until
+    // they choose which to keep at the start of play.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub preference_hands: Vec<Vec<Preferences>>,
+
+    pub score_visibility: ScoreVisibility,
+    #[serde(default)]
+    pub preference_visibility: PreferenceVisibility,
+    pub inventory_visibility: InventoryVisibility,
+    #[serde(default)]
+    pub trade_partners: TradePartners,
+    #[serde(default)]
+    pub trade_limits: TradeLimits,
+}
+
+// How often each action point action was taken over a game.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ActionCounts {
+    pub draws: u32,
+    pub trade_rounds: u32,
+    pub converts: u32,
+}
+
+//fn diff_vector<T>(before: Vec<T>, after: Vec<T>) {
+//for i in 0..min(before.len(), after.len()) {
+//}
+//}
+
+//fn diff_game_state(before: &GameState, after: &GameState) -> HashMap<String, String> {
+//let mut result = HashMap::new();
+//let insert_if = |k, v| {
+//if v { result.insert(k, v) };
+//};
+
+//insert_if("diff", diff_vector(before.deck, after.deck));
+
+//return result
+//}
+
+// Why a trade cannot be carried out.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TradeError {
+    UnknownPlayer {
+        player: PlayerId,
+    },
+    EliminatedPlayer {
+        player: PlayerId,
+    },
+    SelfTrade,
+    // The trade was proposed by or to the wrong player for the round.
+    WrongParties,
+    UnknownCategory {
+        category: CategoryId,
+    },
+    InvalidAmount {
+        category: CategoryId,
+        amount: f64,
+    },
+    Unaffordable {
+        player: PlayerId,
+        category: CategoryId,
+        needed: f64,
+        held: f64,
+    },
+    OnCooldown {
+        category: CategoryId,
+    },
+    // The rules' trade_partners do not let these two players trade.
+    NotPartners,
+    // The trade breaks one of the rules' trade_limits.
+    TooManyGoods {
+        goods: f64,
+        max: u32,
+    },
+    TooManyTrades {
+        max: u32,
+    },
+    TooManyProposals {
+        max: u32,
+    },
+    // A partial acceptance took more of a category than the proposer offered.
+    NotOffered {
+        category: CategoryId,
+        taken: f64,
+        offered: f64,
+    },
+}
+
+impl std::fmt::Display for TradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TradeError::UnknownPlayer { player } => write!(f, "no player {}", player),
+            TradeError::EliminatedPlayer { player } => {
+                write!(f, "player {} is eliminated", player)
+            }
+            TradeError::SelfTrade => write!(f, "a player cannot trade with themself"),
+            TradeError::WrongParties => write!(f, "the trade is not between this round's players"),
+            TradeError::UnknownCategory { category } => write!(f, "no category {}", category),
+            TradeError::InvalidAmount { category, amount } => {
+                write!(f, "{} is not a valid amount of {}", amount, category)
+            }
+            TradeError::Unaffordable {
+                player,
+                category,
+                needed,
+                held,
+            } => write!(
+                f,
+                "player {} must give {} {} but holds {}",
+                player, needed, category, held
+            ),
+            TradeError::OnCooldown { category } => write!(f, "{} is on cooldown", category),
+            TradeError::NotPartners => write!(f, "the rules do not let these players trade"),
+            TradeError::TooManyGoods { goods, max } => write!(
+                f,
+                "the trade moves {} goods but at most {} may change hands",
+                goods, max
+            ),
+            TradeError::TooManyTrades { max } => {
+                write!(f, "{} trades have already been made this turn", max)
+            }
+            TradeError::TooManyProposals { max } => {
+                write!(f, "at most {} proposals may be made in a round", max)
+            }
+            TradeError::NotOffered {
+                category,
+                taken,
+                offered,
+            } => write!(
+                f,
+                "the accepter took {} {} but {} were offered",
+                taken, category, offered
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TradeErrorRecord {
+    pub turn: i32,
+    pub round: i32,
+    // The player at fault: the proposer of an invalid proposal, or whoever could not pay for
+    // an accepted one.
+    pub player: PlayerId,
+    pub error: TradeError,
+}
+
+// Whether a trade could be carried out now. Each side must hold everything it gives: its
+// share of the payment, the positive amounts on its side of the trade and the negated
+// negative amounts on the other, so no balance can go negative.
+pub fn validate_trade(game: &GameState, trade: &Trade) -> Result<(), TradeError> {
+    for &player in &[trade.proposer, trade.accepter] {
+        if player >= game.players.len() {
+            return Err(TradeError::UnknownPlayer { player });
+        }
+        if game.players[player].eliminated {
+            return Err(TradeError::EliminatedPlayer { player });
+        }
+    }
+    if trade.proposer == trade.accepter {
+        return Err(TradeError::SelfTrade);
+    }
+    if !game.may_trade(trade.proposer, trade.accepter) {
+        return Err(TradeError::NotPartners);
+    }
+    if game.trades_left() == Some(0) {
+        return Err(TradeError::TooManyTrades {
+            max: game.trade_limits.max_trades_per_turn.unwrap(),
+        });
+    }
+    if !trade.money.0.is_finite() {
+        return Err(TradeError::InvalidAmount {
+            category: MONEY,
+            amount: trade.money.0,
+        });
+    }
+    for (category, amount) in trade.from_proposor.ids().chain(trade.from_acceptor.ids()) {
+        if game.players[trade.proposer]
+            .num_goods
+            .get_id(category)
+            .is_none()
+        {
+            return Err(TradeError::UnknownCategory { category });
+        }
+        if !amount.is_finite() {
+            return Err(TradeError::InvalidAmount { category, amount });
+        }
+    }
+    if let Some(max) = game.trade_limits.max_goods_per_trade {
+        if !game.trade_size_allowed(trade) {
+            return Err(TradeError::TooManyGoods {
+                goods: trade.goods_count(),
+                max,
+            });
+        }
+    }
+
+    for &player in &[trade.proposer, trade.accepter] {
+        for (category, needed) in trade.owed_by(player).ids() {
+            let held = game.players[player].num_goods[category];
+            if held < needed {
+                return Err(TradeError::Unaffordable {
+                    player,
+                    category,
+                    needed,
+                    held,
+                });
+            }
+        }
+    }
+
+    if let Some(category) = trade.goods_categories().find(|category| {
+        !game.players[trade.proposer].can_trade(*category, game.current_turn)
+            || !game.players[trade.accepter].can_trade(*category, game.current_turn)
+    }) {
+        return Err(TradeError::OnCooldown { category });
+    }
+    Ok(())
+}
+
+// Moves goods from one player to another. Negative amounts move the other way.
+fn transfer(players: &mut [PlayerState], from: PlayerId, to: PlayerId, goods: &GoodsSet) {
+    goods.ids().for_each(|(id, amount)| {
+        if amount > 0.0 {
+            assert!(players[from].num_goods[id] >= amount);
+        } else {
+            assert!(players[to].num_goods[id] >= -amount);
+        }
+        *players[from].num_goods.get_id_mut(id).unwrap() -= amount;
+        *players[to].num_goods.get_id_mut(id).unwrap() += amount;
+    });
+}
+
+impl GameState {
+    pub fn lead_player_state(&self) -> &PlayerState {
+        &self.players[self.lead]
+    }
+
+    pub fn player_state(&self, player_id: PlayerId) -> &PlayerState {
+        &self.players[player_id]
+    }
+
+    pub fn view(&self, viewer: PlayerId) -> PlayerView<'_> {
+        PlayerView::new(self, viewer)
+    }
+
+    // The next player around the table after this one who is still in the game, if any.
+    fn next_seat(&self, player_id: PlayerId) -> Option<PlayerId> {
+        let num_players = self.players.len();
+        (1..num_players)
+            .map(|offset| (player_id + offset) % num_players)
+            .find(|&p| !self.players[p].eliminated)
+    }
+
+    // Whether the two players may trade with each other now, under the rules' trade_partners.
+    pub fn may_trade(&self, a: PlayerId, b: PlayerId) -> bool {
+        match self.trade_partners {
+            TradePartners::Anyone => true,
+            TradePartners::Lead => a == self.lead || b == self.lead,
+            TradePartners::Adjacent => self.next_seat(a) == Some(b) || self.next_seat(b) == Some(a),
+        }
+    }
+
+    // How many more trades may be completed this turn under the rules' trade_limits, if
+    // they cap them.
+    pub fn trades_left(&self) -> Option<u32> {
+        self.trade_limits
+            .max_trades_per_turn
+            .map(|max| max.saturating_sub(self.current_trades.len() as u32))
+    }
+
+    // How many proposals a player may make in one round under the rules' trade_limits.
+    pub fn max_proposals(&self) -> usize {
+        self.trade_limits
+            .max_proposals_per_round
+            .map_or(usize::MAX, |max| max as usize)
+    }
+
+    // Whether the trade moves few enough goods for the rules' trade_limits.
+    pub fn trade_size_allowed(&self, trade: &Trade) -> bool {
+        self.trade_limits
+            .max_goods_per_trade
+            .is_none_or(|max| trade.goods_count() <= max as f64)
+    }
+
+    // The players still in the game this player may trade with now.
+    pub fn trade_partners_of(&self, player_id: PlayerId) -> Vec<PlayerId> {
+        (0..self.players.len())
+            .filter(|&p| p != player_id && !self.players[p].eliminated)
+            .filter(|&p| self.may_trade(player_id, p))
+            .collect()
+    }
+
+    // Whether every player may see every preference card: only when the rules reveal them and
+    // scores are public.
+    pub fn preferences_revealed(&self) -> bool {
+        self.score_visibility == ScoreVisibility::Public
+            && self.preference_visibility == PreferenceVisibility::Public
+    }
+
+    // The game as the viewer may see it, or the whole game with viewer None. Preference cards
+    // the viewer may not see are removed, leaving the players' scores if they are public.
+    // Under face-down inventories, other players' goods are turned face down.
+    pub fn observed_by(&self, config: &SimConfig, viewer: Option<PlayerId>) -> Cow<'_, GameState> {
+        if viewer.is_none()
+            || (self.preferences_revealed()
+                && self.inventory_visibility == InventoryVisibility::Public)
+        {
+            return Cow::Borrowed(self);
+        }
+        Cow::Owned(self.masked(Some(config), viewer))
+    }
+
+    // The game as a spectator at the table sees it, with no preference cards but those the
+    // rules reveal. Nothing records what a spectator is shown, so its redeal is not audited.
+    pub fn table_view(&self) -> GameState {
+        self.masked(None, None)
+    }
+
+    // The game as the viewer sees it, with the redeal of a face-down table audited with the
+    // config.
+    fn masked(&self, config: Option<&SimConfig>, viewer: Option<PlayerId>) -> GameState {
+        let mut game = self.clone();
+        if !self.preferences_revealed() {
+            for (player_id, player) in game.players.iter_mut().enumerate() {
+                if Some(player_id) != viewer {
+                    if self.score_visibility == ScoreVisibility::Public {
+                        player.shown_score = Some(player.score());
+                    }
+                    player.preferences.clear();
+                }
+            }
+        }
+        if self.inventory_visibility == InventoryVisibility::CountsOnly {
+            game.turn_goods_face_down(config, viewer);
+        }
+        game
+    }
+
+    // The game as the viewer is shown it when asked for a decision. See view.rs.
+    pub fn observation(&self, config: &SimConfig, viewer: PlayerId) -> Observation<'_> {
+        Observation::new(self.observed_by(config, Some(viewer)), viewer)
+    }
+
+    // A copy with both decks in a fixed order, hiding the order but not the contents.
+    pub(crate) fn with_sorted_decks(&self) -> GameState {
+        let mut game = self.clone();
+        game.deck.sort_by_key(|good| good.category.name());
+        game.event_deck.sort_by_key(|event| *event as u8);
+        game
+    }
+
+    // Moves other players' goods out of num_goods and into face_down_goods. The remaining deck
+    // would give away what they hold, so it is redealt from every good the viewer cannot see.
+    // The redeal is seeded by the game's deck seed and the turn, round and viewer, keeping
+    // simulations reproducible.
+    fn turn_goods_face_down(&mut self, config: Option<&SimConfig>, viewer: Option<PlayerId>) {
+        let deck_size = self.deck.len();
+        let mut unseen = std::mem::take(&mut self.deck);
+        for (player_id, player) in self.players.iter_mut().enumerate() {
+            if Some(player_id) == viewer {
+                continue;
+            }
+            for (category, count) in player.num_goods.ids_mut() {
+                if category == MONEY {
+                    continue;
+                }
+                let good = Good::from(category);
+                unseen.extend(std::iter::repeat_n(good, *count as usize));
+                player.face_down_goods += *count;
+                *count = 0.;
+            }
+        }
+        let seed = self.seeds.deck_shuffle_seed
+            ^ (self.current_turn as u64) << 32
+            ^ (self.current_round as u64) << 16
+            ^ viewer.map_or(0xffff, |v| v as u64);
+        let mut rng = match config {
+            Some(config) => AuditedRng::new(config, RngPurpose::Redeal, Some(seed)),
+            None => AuditedRng::unaudited(RngPurpose::Redeal, Some(seed)),
+        };
+        unseen.shuffle(&mut rng);
+        if let Some(config) = config {
+            rng.record(config);
+        }
+        unseen.truncate(deck_size);
+        self.deck = unseen;
+    }
+
+    // Every trade completed so far, not counting order book fills.
+    pub fn completed_trades(&self) -> impl Iterator<Item = &Trade> {
+        self.past_trades
+            .values()
+            .flatten()
+            .chain(&self.current_trades)
+    }
+
+    // How many goods of each category remain in the deck.
+    pub fn deck_composition(&self) -> GoodsSet {
+        let mut composition = GoodsSet::new();
+        for good in self.deck.iter().filter(|good| good.effect.is_none()) {
+            *composition.entry_id(good.category).or_insert(0.) += good.amount;
+        }
+        composition
+    }
+
+    pub fn num_active_players(&self) -> usize {
+        self.players.iter().filter(|p| !p.eliminated).count()
+    }
+
+    fn give_good(&mut self, player_id: PlayerId, good: &Good) {
+        self.players[player_id].num_goods[good.category] += good.amount;
+    }
+
+    // The lead takes one good of the category from the victim, if the victim is another
+    // player still in the game with one to give.
+    fn steal(&mut self, victim: PlayerId, category: CategoryId) {
+        let lead = self.lead;
+        let valid = victim != lead
+            && victim < self.players.len()
+            && !self.players[victim].eliminated
+            && category != MONEY
+            && self.players[victim]
+                .num_goods
+                .get_id(category)
+                .is_some_and(|held| held >= 1.);
+        if !valid {
+            crate::warn!(
+                "Player {} cannot steal {} from player {}",
+                lead,
+                category,
+                victim
+            );
+            return;
+        }
+        self.players[victim].num_goods[category] -= 1.;
+        self.players[lead].num_goods[category] += 1.;
+    }
+
+    // Ends the lead's turn where it stands, dropping any open proposals.
+    pub fn end_turn(&mut self) {
+        self.current_trade_proposals.clear();
+        self.end_lead_turn();
+    }
+
+    // A copy of the game with both decks reshuffled, for playing out futures that do not
+    // depend on the recorded draw order.
+    pub fn with_shuffled_decks(&self, rng: &mut impl Rng) -> GameState {
+        let mut game = self.clone();
+        game.deck.shuffle(rng);
+        game.event_deck.shuffle(rng);
+        game
+    }
+
+    // A copy of the game for the viewer to simulate with: the decks are reshuffled, since
+    // the viewer cannot know their order, preference cards the viewer cannot see are replaced
+    // with random ones, and face-down goods are dealt from PlayerView::estimated_inventory.
+    // Nothing in the copy is hidden from the simulated players.
+    pub fn determinize(
+        &self,
+        rules: &GameRules,
+        viewer: PlayerId,
+        rng: &mut impl Rng,
+    ) -> GameState {
+        let mut game = self.with_shuffled_decks(rng);
+        game.score_visibility = ScoreVisibility::Public;
+        game.preference_visibility = PreferenceVisibility::Public;
+        game.inventory_visibility = InventoryVisibility::Public;
+        for player_id in 0..game.players.len() {
+            if self.players[player_id].face_down_goods > 0. {
+                let hand = self.view(viewer).estimated_inventory(player_id);
+                let categories: Vec<(CategoryId, f64)> =
+                    hand.ids().filter(|&(c, _)| c != MONEY).collect();
+                let player = &mut game.players[player_id];
+                if let Ok(weights) = WeightedIndex::new(categories.iter().map(|&(_, w)| w)) {
+                    for _ in 0..player.face_down_goods as usize {
+                        let category = categories[weights.sample(rng)].0;
+                        player.num_goods[category] += 1.;
+                    }
+                }
+                player.face_down_goods = 0.;
+            }
+        }
+        for (player_id, player) in game.players.iter_mut().enumerate() {
+            if player_id != viewer && player.preferences.is_empty() {
+                player.preferences = rules
+                    .random_preference_card(rng)
+                    .expect("the rules dealt this game's cards, so they make cards");
+                player.shown_score = None;
+            }
+        }
+        game
+    }
+
+    // Passes the lead to the next player still in the game.
+    fn pass_lead(&mut self) {
+        for _ in 0..self.players.len() {
+            self.lead = (self.lead + 1) % self.players.len();
+            if !self.players[self.lead].eliminated {
+                break;
+            }
+        }
+    }
+
+    fn end_lead_turn(&mut self) {
+        self.pass_lead();
+        assert_eq!(self.current_trade_proposals.len(), 0);
+        if !self.current_trades.is_empty() {
+            self.past_trades
+                .insert(self.current_turn, std::mem::take(&mut self.current_trades));
+        }
+
+        self.current_turn += 1;
+        self.current_round = 0;
+        self.turn_actions.clear();
+        self.forfeited.clear();
+    }
+
+    // Notes when the deck has run out, starting the final turns if the rules have them, or
+    // counts down a final turn just played.
+    fn advance_endgame(&mut self, rules: &GameRules) {
+        if let Some(left) = &mut self.final_turns_left {
+            *left = left.saturating_sub(1);
+        } else if self.deck.is_empty() && self.deck_ran_out.is_none() {
+            self.deck_ran_out = Some(self.current_turn);
+            if rules.deck_exhaustion() == DeckExhaustion::FinalTurns {
+                self.final_turns_left = Some(self.num_active_players());
+            }
+        }
+    }
+
+    // Records an invalid trade by `player` and applies the rules' policy for them.
+    fn invalid_trade(&mut self, rules: &GameRules, player: PlayerId, error: TradeError) {
+        self.trade_errors.push(TradeErrorRecord {
+            turn: self.current_turn,
+            round: self.current_round,
+            player,
+            error,
+        });
+        match rules.invalid_trades {
+            InvalidTradePolicy::Reject => {}
+            InvalidTradePolicy::ForfeitTurn => self.forfeited.push(player),
+            InvalidTradePolicy::AbortGame => self.aborted = true,
+        }
+    }
+
+    // Turns a round's answers to its proposals into acceptances, putting the pro-rated trade in
+    // place of each proposal accepted in part, and returns those trades by proposal. A partial
+    // acceptance of goods that were not offered, or of part of a good, is an invalid trade by
+    // the accepter, and one that takes nothing is a rejection.
+    fn accept_answers(
+        &mut self,
+        rules: &GameRules,
+        answers: Vec<Acceptance>,
+    ) -> (Vec<bool>, BTreeMap<PlayerId, Trade>) {
+        let keys: Vec<PlayerId> = self.current_trade_proposals.keys().copied().collect();
+        let mut partial = BTreeMap::new();
+        let mut acceptances = Vec::with_capacity(answers.len());
+        for (key, answer) in keys.into_iter().zip(answers) {
+            let taken = match answer {
+                Acceptance::Accept => {
+                    acceptances.push(true);
+                    continue;
+                }
+                Acceptance::Reject => {
+                    acceptances.push(false);
+                    continue;
+                }
+                Acceptance::Partial(taken) => taken,
+            };
+            let trade = &self.current_trade_proposals[&key];
+            let trade = match trade.partial(&taken, rules.mean_good_value()) {
+                Ok(trade) => trade,
+                Err(error) => {
+                    let accepter = trade.accepter;
+                    self.invalid_trade(rules, accepter, error);
+                    acceptances.push(false);
+                    continue;
+                }
+            };
+            if taken.values().all(|&amount| amount == 0.) {
+                acceptances.push(false);
+                continue;
+            }
+            partial.insert(key, trade.clone());
+            self.current_trade_proposals.insert(key, trade);
+            acceptances.push(true);
+        }
+        (acceptances, partial)
+    }
+
+    // Sells one of the lead's goods back to the bank.
+    fn convert(&mut self, config: &SimConfig, rules: &GameRules, category: CategoryId, price: f64) {
+        let player = &mut self.players[self.lead];
+        match player.num_goods.get_id_mut(category) {
+            Some(count) if category != MONEY && *count >= 1. => *count -= 1.,
+            _ => return,
+        }
+        *player.money_mut() += price;
+        self.discard(config, rules, Good::from(category));
+    }
+
+    // Draws the top card of the deck, replacing a deck that runs out with the discard pile
+    // when the rules reshuffle discards.
+    fn draw_card(&mut self, config: &SimConfig, rules: &GameRules) -> Option<Good> {
+        let card = self.deck.pop();
+        self.refill_deck(config, rules);
+        card
+    }
+
+    fn discard(&mut self, config: &SimConfig, rules: &GameRules, card: Good) {
+        self.discards.push(card);
+        self.refill_deck(config, rules);
+    }
+
+    // The reshuffle is seeded by the game's deck seed and the turn and round, keeping
+    // simulations reproducible, and shuffled as the config's shuffle model says.
+    fn refill_deck(&mut self, config: &SimConfig, rules: &GameRules) {
+        if rules.deck_exhaustion() != DeckExhaustion::Reshuffle
+            || !self.deck.is_empty()
+            || self.discards.is_empty()
+        {
+            return;
+        }
+        self.deck = std::mem::take(&mut self.discards);
+        let seed = rng::config_seed(self.seeds.deck_shuffle_seed).map(|seed| {
+            seed ^ (self.current_turn as u64) << 32 ^ (self.current_round as u64) << 16
+        });
+        let mut rng = AuditedRng::new(config, RngPurpose::Reshuffle, seed);
+        shuffle::shuffle(&mut self.deck, &config.shuffle, &mut rng);
+        rng.record(config);
+    }
+
+    fn start_cooldowns(
+        &mut self,
+        rules: &GameRules,
+        categories: impl Iterator<Item = CategoryId>,
+        player_ids: &[PlayerId],
+    ) {
+        if rules.trade_cooldown_turns <= 0 {
+            return;
+        }
+        let next = self.current_turn + rules.trade_cooldown_turns;
+        for category in categories {
+            for &player_id in player_ids {
+                self.players[player_id].cooldowns.insert(category, next);
+            }
+        }
+    }
+
+    // Moves the goods and money for a trade both sides have agreed to, and records it as
+    // completed this turn.
+    pub fn apply_trade(&mut self, rules: &GameRules, trade: &Trade) {
+        let players = &mut self.players;
+        *players[trade.proposer].money_mut() -= trade.money.0;
+        *players[trade.accepter].money_mut() += trade.money.0;
+        transfer(
+            players,
+            trade.proposer,
+            trade.accepter,
+            &trade.from_proposor,
+        );
+        transfer(
+            players,
+            trade.accepter,
+            trade.proposer,
+            &trade.from_acceptor,
+        );
+        self.start_cooldowns(
+            rules,
+            trade.goods_categories(),
+            &[trade.proposer, trade.accepter],
+        );
+        self.current_trades.push(trade.clone());
+    }
+
+    pub(crate) fn end_round(
+        &mut self,
+        config: &SimConfig,
+        rules: &GameRules,
+        trade_acceptances: Vec<bool>,
+    ) {
+        // Move goods for accepted trades.
+        let accepted_trades = trade_acceptances
+            .into_iter()
+            .zip(std::mem::take(&mut self.current_trade_proposals))
+            .filter(|(accepted, (_, ___))| *accepted)
+            .map(|(_, proposal)| proposal);
+
+        if rules.trade_settlement == TradeSettlement::Escrow {
+            let (settled, rejected) = escrow::settle(self, accepted_trades.collect());
+            for (offender, error) in rejected {
+                self.invalid_trade(rules, offender, error);
+            }
+            if !self.aborted {
+                for trade in &settled {
+                    self.apply_trade(rules, trade);
+                }
+            }
+        } else {
+            // Earlier trades in the round may leave a player unable to pay for a later one, or
+            // use up the turn's trades, which is nobody's fault.
+            for (_, trade) in accepted_trades {
+                if self.aborted {
+                    break;
+                }
+                match validate_trade(self, &trade) {
+                    Ok(()) => self.apply_trade(rules, &trade),
+                    Err(TradeError::TooManyTrades { .. }) => {}
+                    Err(error) => {
+                        let offender = match error {
+                            TradeError::Unaffordable { player, .. } => player,
+                            _ => trade.accepter,
+                        };
+                        self.invalid_trade(rules, offender, error);
+                    }
+                }
+            }
+        }
+        self.current_round += 1;
+        self.claim_milestones(config, rules);
+        self.apply_bankruptcy_rule(rules);
+    }
+
+    // Lets a player replace their standing orders, matching them against the book.
+    fn place_orders(&mut self, rules: &GameRules, player_id: PlayerId, mut orders: Vec<Order>) {
+        let turn = self.current_turn;
+        orders.retain(|order| self.players[player_id].can_trade(order.category, turn));
+        let partners = self.trade_partners_of(player_id);
+        let fills = self
+            .order_book
+            .replace_orders(&mut self.players, player_id, &partners, orders);
+        for fill in &fills {
+            self.start_cooldowns(
+                rules,
+                std::iter::once(fill.category),
+                &[fill.buyer, fill.seller],
+            );
+        }
+        self.fills.extend(fills);
+    }
+
+    fn end_order_round(&mut self, config: &SimConfig, rules: &GameRules) {
+        for spread in self.order_book.spreads() {
+            self.spread_total += spread;
+            self.spread_samples += 1;
+        }
+        self.current_round += 1;
+        self.claim_milestones(config, rules);
+        self.apply_bankruptcy_rule(rules);
+
+        // Orders are withdrawn while their category is cooling down or their owner is out.
+        let (players, turn) = (&self.players, self.current_turn);
+        self.order_book.orders.retain(|resting| {
+            let owner = &players[resting.player];
+            !owner.eliminated && owner.can_trade(resting.order.category, turn)
+        });
+    }
+
+    // Awards each unclaimed milestone to the first player, in turn order from the lead, whose
+    // score has reached it. Extra draws can reach further milestones, so this repeats.
+    // Nobody knows who has reached a milestone while scores are hidden.
+    fn claim_milestones(&mut self, config: &SimConfig, rules: &GameRules) {
+        if rules.score_visibility == ScoreVisibility::Hidden {
+            return;
+        }
+        let num_players = self.players.len();
+        let mut claimed_any = true;
+        while claimed_any {
+            claimed_any = false;
+            for (index, milestone) in rules.milestones.iter().enumerate() {
+                if self.milestone_claims.iter().any(|c| c.milestone == index) {
+                    continue;
+                }
+                let claimant = (0..num_players)
+                    .map(|offset| (self.lead + offset) % num_players)
+                    .find(|&p| {
+                        !self.players[p].eliminated && self.players[p].score() >= milestone.points
+                    });
+                let player = match claimant {
+                    Some(player) => player,
+                    None => continue,
+                };
+                match milestone.reward {
+                    MilestoneReward::Money { amount } => {
+                        *self.players[player].money_mut() += amount
+                    }
+                    MilestoneReward::ExtraDraw => {
+                        // Action cards drawn here are discarded. See cards.rs.
+                        match self.draw_card(config, rules) {
+                            Some(good) if good.effect.is_none() => self.give_good(player, &good),
+                            Some(card) => self.discard(config, rules, card),
+                            None => {}
+                        }
+                    }
+                }
+                self.milestone_claims.push(MilestoneClaim {
+                    milestone: index,
+                    player,
+                    turn: self.current_turn,
+                });
+                claimed_any = true;
+            }
+        }
+    }
+
+    // Takes a player out of the game who resigns, passing on their goods and money as the
+    // rules' resigned_goods says. Goods returned to the deck are shuffled in as refill_deck
+    // shuffles discards.
+    pub fn resign(&mut self, config: &SimConfig, rules: &GameRules, player_id: PlayerId) {
+        let player = &mut self.players[player_id];
+        if player.eliminated {
+            return;
+        }
+        player.eliminated = true;
+        player.resigned = true;
+        self.eliminations.push(player_id);
+        let held = player.num_goods.clone();
+        player.num_goods.values_mut().for_each(|count| *count = 0.);
+
+        match rules.resigned_goods {
+            ResignedGoods::Removed => {}
+            ResignedGoods::Redistributed => {
+                let num_players = self.players.len();
+                let heirs: Vec<PlayerId> = (1..num_players)
+                    .map(|offset| (player_id + offset) % num_players)
+                    .filter(|&p| !self.players[p].eliminated)
+                    .collect();
+                if heirs.is_empty() {
+                    return;
+                }
+                let mut turn = 0;
+                for (category, count) in held.ids() {
+                    if category == MONEY {
+                        for &heir in &heirs {
+                            *self.players[heir].money_mut() += count / heirs.len() as f64;
+                        }
+                        continue;
+                    }
+                    let mut left = count;
+                    while left > 0. {
+                        let heir = heirs[turn % heirs.len()];
+                        *self.players[heir]
+                            .num_goods
+                            .entry_id(category)
+                            .or_insert(0.) += left.min(1.);
+                        left -= 1.;
+                        turn += 1;
+                    }
+                }
+            }
+            ResignedGoods::ReturnedToDeck => {
+                for (category, count) in held.ids().filter(|&(category, _)| category != MONEY) {
+                    let good = Good::from(category);
+                    self.deck.extend(std::iter::repeat_n(good, count as usize));
+                }
+                let seed = rng::config_seed(self.seeds.deck_shuffle_seed)
+                    .map(|seed| seed ^ (self.current_turn as u64) << 32 ^ player_id as u64);
+                let mut rng = AuditedRng::new(config, RngPurpose::Reshuffle, seed);
+                shuffle::shuffle(&mut self.deck, &config.shuffle, &mut rng);
+                rng.record(config);
+            }
+        }
+    }
+
+    fn apply_bankruptcy_rule(&mut self, rules: &GameRules) {
+        for player_id in 0..self.players.len() {
+            let player = &mut self.players[player_id];
+            if player.eliminated || !player.is_bankrupt() {
+                continue;
+            }
+            match rules.bankruptcy {
+                BankruptcyRule::Ignore | BankruptcyRule::Skip => {}
+                BankruptcyRule::Eliminate => {
+                    player.eliminated = true;
+                    self.eliminations.push(player_id);
+                }
+                BankruptcyRule::Bailout => {
+                    *player.money_mut() += rules.bailout_money;
+                    player.penalty += rules.bailout_penalty;
+                    player.bailouts += 1;
+                }
+            }
+        }
+    }
+}
+
+impl GameState {
+    // Loans come due at the start of the borrower's turn. A due loan is repaid in full if
+    // the borrower can afford it, and otherwise written off for default_penalty points.
+    fn settle_loans(&mut self, rules: &LoanRules) {
+        let player = &mut self.players[self.lead];
+        player
+            .loans
+            .iter_mut()
+            .for_each(|loan| loan.turns_left -= 1);
+        let (due, outstanding): (Vec<Loan>, Vec<Loan>) = std::mem::take(&mut player.loans)
+            .into_iter()
+            .partition(|loan| loan.turns_left <= 0);
+        player.loans = outstanding;
+        for loan in due {
+            if *player.money_mut() >= loan.owed {
+                *player.money_mut() -= loan.owed;
+            } else {
+                player.penalty += rules.default_penalty;
+                player.loan_defaults += 1;
+            }
+        }
+    }
+
+    fn draw_event(&mut self, rules: &EventRules) {
+        if self.event_deck.is_empty() {
+            return;
+        }
+        let event = self.event_deck[self.events_drawn % self.event_deck.len()];
+        self.events_drawn += 1;
+        events::resolve(event, &mut self.players[self.lead], rules);
+    }
+
+    fn apply_loan_action(&mut self, rules: &LoanRules, action: LoanAction) {
+        let player = &mut self.players[self.lead];
+        match action {
+            LoanAction::None => {}
+            LoanAction::Borrow => {
+                if player.loans.len() < rules.max_outstanding {
+                    *player.money_mut() += rules.amount;
+                    player.loans.push(Loan {
+                        owed: rules.amount * (1. + rules.interest_rate),
+                        turns_left: rules.term_turns,
+                    });
+                    player.loans_taken += 1;
+                }
+            }
+            LoanAction::Repay => {
+                if let Some(owed) = player.loans.first().map(|loan| loan.owed) {
+                    if *player.money_mut() >= owed {
+                        *player.money_mut() -= owed;
+                        player.loans.remove(0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// An optional bank that lends the lead money at the start of their turn.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LoanRules {
+    #[serde(default = "default_loan_amount")]
+    pub amount: f64,
+    #[serde(default = "default_loan_interest_rate")]
+    pub interest_rate: f64,
+    // Loans are due after this many of the borrower's turns as lead.
+    #[serde(default = "default_loan_term_turns")]
+    pub term_turns: i32,
+    #[serde(default = "default_loan_default_penalty")]
+    pub default_penalty: f64,
+    #[serde(default = "default_loan_max_outstanding")]
+    pub max_outstanding: usize,
+}
+
+fn default_loan_amount() -> f64 {
+    10.
+}
+fn default_loan_interest_rate() -> f64 {
+    0.2
+}
+fn default_loan_term_turns() -> i32 {
+    5
+}
+fn default_loan_default_penalty() -> f64 {
+    10.
+}
+fn default_loan_max_outstanding() -> usize {
+    1
+}
+
+// A bonus for the first player to reach some number of points.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Milestone {
+    pub points: f64,
+    pub reward: MilestoneReward,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MilestoneReward {
+    Money { amount: f64 },
+    // The claimant draws a good from the deck.
+    ExtraDraw,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MilestoneClaim {
+    // Index into GameRules.milestones.
+    pub milestone: usize,
+    pub player: PlayerId,
+    pub turn: i32,
+}
+
+// Who claims each milestone over a batch, how early, and how often they go on to win.
+#[derive(Serialize, Deserialize, Default)]
+pub struct MilestoneTally {
+    games: u32,
+    claims: BTreeMap<usize, MilestoneClaims>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct MilestoneClaims {
+    by_player: BTreeMap<PlayerId, u32>,
+    count: u32,
+    turn_total: f64,
+    claimant_won: u32,
+}
+
+impl MilestoneTally {
+    pub fn add(&mut self, result: &GameResult) {
+        self.games += 1;
+        for claim in &result.milestones {
+            let claims = self.claims.entry(claim.milestone).or_default();
+            *claims.by_player.entry(claim.player).or_insert(0) += 1;
+            claims.count += 1;
+            claims.turn_total += claim.turn as f64;
+            if claim.player == result.winner {
+                claims.claimant_won += 1;
+            }
+        }
+    }
+
+    pub fn summary(&self, rules: &GameRules) -> serde_json::Value {
+        let empty = MilestoneClaims::default();
+        let milestones: Vec<serde_json::Value> = rules
+            .milestones
+            .iter()
+            .enumerate()
+            .map(|(index, milestone)| {
+                let claims = self.claims.get(&index).unwrap_or(&empty);
+                let count = claims.count.max(1) as f64;
+                serde_json::json!({
+                    "points": milestone.points,
+                    "reward": milestone.reward,
+                    "claim_rate": claims.count as f64 / self.games.max(1) as f64,
+                    "claims_by_player": claims.by_player,
+                    "mean_turn_claimed": claims.turn_total / count,
+                    "claimant_win_rate": claims.claimant_won as f64 / count,
+                })
+            })
+            .collect();
+        serde_json::json!({ "milestones": milestones })
+    }
+}
+
+// Final placements, first to last, as groups of players who tied. Players who can still win
+// are placed by score, and equal scores tie. Eliminated players come after them, the last
+// eliminated first.
+fn placements(game: &GameState, scores: &[f64]) -> Vec<Vec<PlayerId>> {
+    let everyone_out = game.num_active_players() == 0;
+    let mut contenders: Vec<PlayerId> = (0..game.players.len())
+        .filter(|&pi| everyone_out || !game.players[pi].eliminated)
+        .collect();
+    contenders.sort_by_key(|&pi| std::cmp::Reverse(NonNan::new(scores[pi]).unwrap()));
+
+    let mut groups: Vec<Vec<PlayerId>> = Vec::new();
+    for pi in contenders {
+        match groups.last_mut() {
+            Some(group) if scores[group[0]] == scores[pi] => group.push(pi),
+            _ => groups.push(vec![pi]),
+        }
+    }
+    if !everyone_out {
+        groups.extend(game.eliminations.iter().rev().map(|&pi| vec![pi]));
+    }
+    groups
+}
+
+// Placement points over a batch. Each place is worth the matching entry of points, or Borda
+// points (one per player placed below) by default. Tied players share the points of the places
+// they cover, and are placed at the mean of those places.
+#[derive(Serialize, Deserialize, Default)]
+pub struct PlacementTally {
+    games: u32,
+    place_total: BTreeMap<PlayerId, f64>,
+    points_total: BTreeMap<PlayerId, f64>,
+    // How often each player shared in each place, by the group's first place.
+    place_counts: BTreeMap<PlayerId, BTreeMap<usize, u32>>,
+}
+
+impl PlacementTally {
+    pub fn add(&mut self, placements: &[Vec<PlayerId>], points: Option<&[f64]>) {
+        let num_players: usize = placements.iter().map(Vec::len).sum();
+        let place_points = |place: usize| match points {
+            Some(points) => points.get(place).copied().unwrap_or(0.),
+            None => (num_players - 1 - place) as f64,
+        };
+        self.games += 1;
+        let mut place = 0;
+        for group in placements {
+            let places = place..place + group.len();
+            let mean_points = places.clone().map(place_points).sum::<f64>() / group.len() as f64;
+            let mean_place = place as f64 + (group.len() + 1) as f64 / 2.;
+            for &pi in group {
+                *self.place_total.entry(pi).or_insert(0.) += mean_place;
+                *self.points_total.entry(pi).or_insert(0.) += mean_points;
+                *self
+                    .place_counts
+                    .entry(pi)
+                    .or_default()
+                    .entry(place + 1)
+                    .or_insert(0) += 1;
+            }
+            place = places.end;
+        }
+    }
+
+    pub fn summary(&self) -> serde_json::Value {
+        let games = self.games.max(1) as f64;
+        let players: BTreeMap<PlayerId, serde_json::Value> = self
+            .points_total
+            .iter()
+            .map(|(&pi, &points)| {
+                (
+                    pi,
+                    serde_json::json!({
+                        "points": points,
+                        "mean_points": points / games,
+                        "mean_placement": self.place_total[&pi] / games,
+                        "placements": self.place_counts[&pi],
+                    }),
+                )
+            })
+            .collect();
+        serde_json::json!({ "placements": players })
+    }
+}
+
+// A variant turn structure where the lead spends action points instead of drawing once and
+// then trading.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ActionPointRules {
+    #[serde(default = "default_points_per_turn")]
+    pub points_per_turn: u32,
+    #[serde(default = "default_action_cost")]
+    pub draw_cost: u32,
+    #[serde(default = "default_action_cost")]
+    pub trade_round_cost: u32,
+    #[serde(default = "default_action_cost")]
+    pub convert_cost: u32,
+    // Money the bank pays for a converted good.
+    #[serde(default = "default_convert_price")]
+    pub convert_price: f64,
+}
+
+impl ActionPointRules {
+    fn cost(&self, action: &TurnAction) -> u32 {
+        match action {
+            TurnAction::Draw => self.draw_cost,
+            TurnAction::TradeRound => self.trade_round_cost,
+            TurnAction::Convert { .. } => self.convert_cost,
+            TurnAction::EndTurn => 0,
+        }
+    }
+}
+
+fn default_points_per_turn() -> u32 {
+    3
+}
+fn default_action_cost() -> u32 {
+    1
+}
+fn default_convert_price() -> f64 {
+    2.
+}
+
+// How players trade with each other.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TradingProtocol {
+    // Rounds of proposals between the lead and everyone else, accepted or rejected.
+    #[default]
+    Proposals,
+    // Standing buy and sell orders on a public book, matched as they are placed. Each turn
+    // has one order round in which every player, lead first, may replace their orders.
+    OrderBook,
+}
+
+// How the points for a category grow with the goods held in it, as a multiple of the card's
+// value. Money always counts at face value.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UtilityCurve {
+    // Every good is worth the card's value.
+    #[default]
+    Constant,
+    // Each good is worth this fraction of the card's value less than the one before, down to
+    // nothing, e.g. 0.25 values the first four goods at 1, 0.75, 0.5 and 0.25.
+    Linear(f64),
+    // n goods are worth sqrt(n) times the card's value.
+    Sqrt,
+    // n goods are worth log2(1 + n) times the card's value.
+    Log,
+}
+
+impl UtilityCurve {
+    // How many goods at the card's value `count` goods of the category are worth.
+    pub fn value(&self, category: CategoryId, count: f64) -> f64 {
+        if category == MONEY || count <= 0. {
+            return count;
+        }
+        match *self {
+            UtilityCurve::Constant => count,
+            // The discrete sum of 1 - decay * (k - 1), continued smoothly for fractional
+            // counts and flat past the good that adds nothing.
+            UtilityCurve::Linear(decay) if decay > 0. => {
+                let count = count.min(1. / decay + 0.5);
+                count - decay * count * (count - 1.) / 2.
+            }
+            UtilityCurve::Linear(_) => count,
+            UtilityCurve::Sqrt => count.sqrt(),
+            UtilityCurve::Log => (1. + count).log2(),
+        }
+    }
+}
+
+// Whether players can see each other's scores during play.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreVisibility {
+    #[default]
+    Public,
+    // Players see inventories but only their own preferences. Nobody can claim victory or
+    // milestones, so games run until the deck or max_turns runs out, and scores are revealed
+    // at the end.
+    Hidden,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidTradePolicy {
+    // The trade is dropped.
+    #[default]
+    Reject,
+    // The trade is dropped and the player makes no more proposals this turn. A lead's turn
+    // ends.
+    ForfeitTurn,
+    // The game ends; it is scored as it stands and marked aborted.
+    AbortGame,
+}
+
+// Whether players can see each other's preference cards, when scores are public. Hidden, the
+// default, shows them each other's scores but not the cards behind them, as in the physical
+// game. Under hidden scoring, preference cards are always hidden.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PreferenceVisibility {
+    #[default]
+    Hidden,
+    Public,
+}
+
+// Whether players can see which goods each other holds.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InventoryVisibility {
+    #[default]
+    Public,
+    // Goods are held face down, as cards in hand. Players see how many goods and how much
+    // money each other holds, but not which categories.
+    CountsOnly,
+}
+
+// Whom players may trade with. The engine rejects proposals and counter-offers between other
+// players as invalid trades, and the order book only matches orders between partners.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TradePartners {
+    #[default]
+    Anyone,
+    // Only the player whose turn it is, as the physical game is considering. Proposal rounds
+    // are always with the lead, so this only narrows the order book.
+    Lead,
+    // Only the players seated either side, passing over eliminated players.
+    Adjacent,
+}
+
+// Caps on trading, to keep turns short. None leaves a cap off. The caps apply to proposals
+// and counter-offers, not to order book fills.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct TradeLimits {
+    // Goods changing hands in one trade, counting both sides but not money.
+    #[serde(default)]
+    pub max_goods_per_trade: Option<u32>,
+    // Trades completed in one turn. Trading ends for the turn once this many are done, and
+    // trades accepted in the same round beyond it are dropped.
+    #[serde(default)]
+    pub max_trades_per_turn: Option<u32>,
+    // Proposals one player makes in a round. Only the lead, who proposes to everyone else,
+    // can make more than one. Proposals beyond the cap are invalid trades.
+    #[serde(default)]
+    pub max_proposals_per_round: Option<u32>,
+}
+
+// How a game is won, for comparing game designs. Every game also ends when the deck or
+// max_turns runs out, won by the highest score unless said otherwise.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WinCondition {
+    // The game ends when the lead has victory_threshold points at the start of a trading
+    // round. The highest score wins.
+    #[default]
+    Points,
+    // The game runs until the deck or max_turns runs out, and the highest score wins.
+    HighestScore,
+    // The game ends when the lead holds at least one good of every category, other than
+    // money, at the start of a trading round, and the lead wins.
+    FullSet,
+    // The game lasts this many turns, and the highest score wins.
+    FixedTurns(i32),
+}
+
+// Where the goods and money of a player who resigns go.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ResignedGoods {
+    // Out of the game.
+    #[default]
+    Removed,
+    // Dealt out a good at a time to the players still in the game, from the next seat on, with
+    // the money split evenly.
+    Redistributed,
+    // Shuffled into the deck as plain goods. The money goes to the bank.
+    ReturnedToDeck,
+}
+
+// What happens to a player who runs out of money.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BankruptcyRule {
+    // Nothing; bankrupt players keep playing.
+    #[default]
+    Ignore,
+    // Bankrupt players leave the game. Their goods stay with them but they cannot win.
+    Eliminate,
+    // Bankrupt players skip their turns as lead until they have money again.
+    Skip,
+    // Bankrupt players receive bailout_money and lose bailout_penalty points.
+    Bailout,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameRules {
+    #[serde(default = "default_victory_threshold")]
+    pub(crate) victory_threshold: f64,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+    #[serde(default = "default_start_money")]
+    pub(crate) start_money: f64,
+    #[serde(default = "default_deck_size")]
+    deck_size: usize,
+
+    // The categories of goods, not counting money.
+    #[serde(default = "default_categories")]
+    pub categories: Vec<String>,
+
+    // How many goods of each category the deck holds. Categories left out get an even share
+    // of deck_size.
+    #[serde(default)]
+    pub deck_counts: BTreeMap<String, usize>,
+
+    // What the deck holds, in place of deck_size and deck_counts. See deck.rs.
+    #[serde(default)]
+    pub deck: Option<DeckSpec>,
+
+    // What happens when the deck runs out. See deck.rs.
+    #[serde(default)]
+    pub deck_exhaustion: Option<DeckExhaustion>,
+
+    // Shuffle the discard pile into a new deck when the deck runs out, as deck_exhaustion
+    // reshuffle does.
+    #[serde(default)]
+    pub reshuffle_discards: bool,
+
+    // Cards added to the deck besides plain goods. See cards.rs.
+    #[serde(default)]
+    pub special_cards: Vec<SpecialCards>,
+
+    // Point values on a preference card, one per category, dealt to the categories in a
+    // random order.
+    #[serde(default = "default_preference_values")]
+    pub preference_values: Vec<i32>,
+
+    // If set, players are instead dealt cards from this deck of candidate preference cards,
+    // without replacement. Each card gives a value for every category. They are kept by name,
+    // as the rules are read before any game has category ids.
+    #[serde(default)]
+    pub preference_cards: Option<Vec<BTreeMap<String, f64>>>,
+
+    // Players are dealt this many preference cards and keep the one their strategy chooses.
+    #[serde(default = "default_preference_hand_size")]
+    pub preference_hand_size: usize,
+
+    // Deal preference_cards with replacement, so players may hold the same card. Cards made
+    // from preference_values repeat anyway when hands hold one card, and otherwise only with
+    // this set.
+    #[serde(default)]
+    pub allow_duplicate_preferences: bool,
+
+    // How preference cards are made. See preferences.rs.
+    #[serde(default)]
+    pub preference_generator: Option<PreferenceModel>,
+
+    // House rule: redeal the preference cards until players' advantage indices differ by at
+    // most this. See advantage.rs.
+    #[serde(default)]
+    pub max_advantage_spread: Option<f64>,
+
+    #[serde(default = "default_max_turns")]
+    pub(crate) max_turns: i32,
+
+    #[serde(default)]
+    pub bankruptcy: BankruptcyRule,
+    #[serde(default)]
+    pub resigned_goods: ResignedGoods,
+    #[serde(default = "default_bailout_money")]
+    bailout_money: f64,
+    #[serde(default = "default_bailout_penalty")]
+    bailout_penalty: f64,
+
+    #[serde(default)]
+    pub loans: Option<LoanRules>,
+
+    #[serde(default)]
+    pub events: Option<EventRules>,
+
+    // A category traded on turn t can next be traded by either side on turn t + this.
+    #[serde(default)]
+    pub trade_cooldown_turns: i32,
+
+    // Replaces draw-then-trade turns with action points.
+    #[serde(default)]
+    pub action_points: Option<ActionPointRules>,
+
+    // Shrinks the trading rounds allowed in standard turns as the game goes on. See blitz.rs.
+    #[serde(default)]
+    pub blitz: Option<BlitzRules>,
+
+    #[serde(default)]
+    pub trading: TradingProtocol,
+
+    // How many counter-offers may follow a rejected proposal. 0 allows none.
+    #[serde(default)]
+    pub max_negotiation_rounds: u32,
+
+    // Whether a round's accepted trades are carried out one at a time or settle together. See
+    // escrow.rs.
+    #[serde(default)]
+    pub trade_settlement: TradeSettlement,
+
+    // Lets players answering proposals take only some of the goods offered, giving back a
+    // pro-rated share of what was asked in whole goods and money. See Trade::partial.
+    // Counter-offers are still accepted or rejected whole.
+    #[serde(default)]
+    pub partial_acceptance: bool,
+
+    // What happens to a player whose proposal, or accepted trade, cannot be carried out.
+    #[serde(default)]
+    pub invalid_trades: InvalidTradePolicy,
+
+    // Drawn goods in these categories go to the highest sealed bid instead of the lead.
+    #[serde(default)]
+    pub contested: Option<ContestRules>,
+
+    // Every drawn good is auctioned instead of going to the lead. See bidding.rs.
+    #[serde(default)]
+    pub auction: Option<AuctionRules>,
+
+    #[serde(default)]
+    pub milestones: Vec<Milestone>,
+
+    #[serde(default)]
+    pub score_visibility: ScoreVisibility,
+
+    #[serde(default)]
+    pub preference_visibility: PreferenceVisibility,
+
+    #[serde(default)]
+    pub inventory_visibility: InventoryVisibility,
+
+    #[serde(default)]
+    pub trade_partners: TradePartners,
+
+    #[serde(default)]
+    pub trade_limits: TradeLimits,
+
+    // Diminishing returns on holding many goods of one category.
+    #[serde(default)]
+    pub utility_curve: UtilityCurve,
+
+    // How goods are turned into points. See scoring.rs.
+    #[serde(default)]
+    pub scoring: ScoringModel,
+}
+
+impl GameRules {
+    // Every category, money first.
+    pub fn all_categories(&self) -> Vec<&str> {
+        std::iter::once(MONEY.name().as_str())
+            .chain(self.categories.iter().map(String::as_str))
+            .collect()
+    }
+
+    // Sets up this thread's category ids for a game under these rules, before anything of the
+    // game is dealt. See category.rs.
+    pub fn start_categories(&self) {
+        category::start_game(&self.categories);
+    }
+
+    // The turn at which play stops if the deck has not run out.
+    fn turn_limit(&self) -> i32 {
+        match self.win_condition {
+            WinCondition::FixedTurns(turns) => turns.min(self.max_turns),
+            _ => self.max_turns,
+        }
+    }
+
+    pub fn deck_count(&self, category: &str) -> usize {
+        self.deck_counts
+            .get(category)
+            .copied()
+            .unwrap_or(self.deck_size / self.categories.len())
+    }
+
+    // The deck spec, or the counts deck_size and deck_counts give without one.
+    pub fn deck_spec(&self) -> DeckSpec {
+        match &self.deck {
+            Some(spec) => spec.clone(),
+            None => DeckSpec::Counts {
+                counts: self
+                    .categories
+                    .iter()
+                    .map(|category| (category.clone(), self.deck_count(category)))
+                    .collect(),
+            },
+        }
+    }
+
+    // What a good is worth in money on a typical preference card: the mean preference value.
+    // Partial acceptances settle the part of a good their rounding leaves at this price.
+    pub fn mean_good_value(&self) -> f64 {
+        let values = &self.preference_values;
+        values.iter().sum::<i32>() as f64 / values.len().max(1) as f64
+    }
+
+    // A card giving the categories these values, in order.
+    pub fn preference_card(&self, values: &[i32]) -> error::Result<Preferences> {
+        if values.len() != self.categories.len() {
+            return Err(HedonicaError::Unsupported {
+                message: format!(
+                    "there are {} preference values for {} categories; give one per category",
+                    values.len(),
+                    self.categories.len()
+                ),
+            });
+        }
+        let mut map = Preferences::new();
+        map.insert(MONEY, 1.);
+        self.categories
+            .iter()
+            .zip(values.iter())
+            .for_each(|(category, &v)| {
+                map.insert(category.clone(), v as f64);
+            });
+        Ok(map)
+    }
+
+    // A candidate preference card, with money worth 1 unless the card says otherwise.
+    pub fn with_money(&self, card: &BTreeMap<String, f64>) -> error::Result<Preferences> {
+        if let Some(category) = self.categories.iter().find(|c| !card.contains_key(*c)) {
+            return Err(HedonicaError::Unsupported {
+                message: format!("a preference card has no value for {}", category),
+            });
+        }
+        let mut card: Preferences = card.iter().map(|(name, &value)| (name, value)).collect();
+        card.entry_id(MONEY).or_insert(1.);
+        Ok(card)
+    }
+
+    // The deck_exhaustion policy, or the one reshuffle_discards implies without it.
+    pub fn deck_exhaustion(&self) -> DeckExhaustion {
+        match self.deck_exhaustion {
+            Some(policy) => policy,
+            None if self.reshuffle_discards => DeckExhaustion::Reshuffle,
+            None => DeckExhaustion::End,
+        }
+    }
+
+    // The preference model, or the one the rules imply without it.
+    pub fn preference_model(&self) -> PreferenceModel {
+        match (&self.preference_generator, &self.preference_cards) {
+            (Some(model), _) => model.clone(),
+            (None, Some(_)) => PreferenceModel::List,
+            (None, None) => PreferenceModel::Shuffle,
+        }
+    }
+
+    // The preference generator, or the one the rules imply without it.
+    pub fn preference_generator(&self) -> Box<dyn PreferenceGenerator> {
+        self.preference_model().generator()
+    }
+
+    pub fn random_preference_card(&self, rng: &mut impl Rng) -> error::Result<Preferences> {
+        self.preference_generator().card(self, rng)
+    }
+
+    // Strategy capabilities needed by the optional mechanics these rules enable.
+    pub fn required_capabilities(&self) -> Vec<Capability> {
+        let mut capabilities = Vec::new();
+        if self.loans.is_some() {
+            capabilities.push(Capability::Loans);
+        }
+        if self.events.is_some() {
+            capabilities.push(Capability::Insurance);
+        }
+        if self.action_points.is_some() {
+            capabilities.push(Capability::ActionPoints);
+        }
+        if self.trading == TradingProtocol::OrderBook {
+            capabilities.push(Capability::OrderBook);
+        }
+        if self.partial_acceptance {
+            capabilities.push(Capability::PartialAcceptance);
+        }
+        if self.contested.is_some() || self.auction.is_some() {
+            capabilities.push(Capability::SealedBids);
+        }
+        if self
+            .special_cards
+            .iter()
+            .any(|cards| cards.effect.is_some_and(|effect| effect.needs_decision()))
+        {
+            capabilities.push(Capability::SpecialCards);
+        }
+        capabilities
+    }
+}
+
+fn default_victory_threshold() -> f64 {
+    50.
+}
+fn default_start_money() -> f64 {
+    10.
+}
+fn default_categories() -> Vec<String> {
+    ["cars", "clothing", "food", "art", "travel"]
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+}
+fn default_preference_values() -> Vec<i32> {
+    vec![1, 2, 2, 5, 10]
+}
+fn default_deck_size() -> usize {
+    500
+}
+fn default_preference_hand_size() -> usize {
+    1
+}
+fn default_max_turns() -> i32 {
+    1000
+}
+fn default_bailout_money() -> f64 {
+    5.
+}
+fn default_bailout_penalty() -> f64 {
+    5.
+}
+
+// Why a game ended.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEnding {
+    // The lead met the win condition.
+    Victory,
+    TurnLimit,
+    // The deck ran out, and the final turns, if any, were played.
+    #[default]
+    DeckExhausted,
+    // Everyone else was eliminated.
+    LastPlayerStanding,
+    Aborted,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GameResult {
+    pub turns: i32,
+    pub winner: PlayerId,
+    pub scores: Vec<f64>,
+    // Groups of tied players, first place first.
+    pub placements: Vec<Vec<PlayerId>>,
+    pub trade_flows: Vec<TradeFlow>,
+
+    // Players in the order they were eliminated, and those of them who resigned.
+    pub eliminated: Vec<PlayerId>,
+    #[serde(default)]
+    pub resigned: Vec<PlayerId>,
+    pub bailouts: Vec<u32>,
+    pub loans_taken: Vec<u32>,
+    pub loan_defaults: Vec<u32>,
+    pub events: Vec<EventLedger>,
+    pub action_counts: ActionCounts,
+    pub pacing: Pacing,
+    pub liquidity: Liquidity,
+    pub trade_history: TradeHistory,
+    pub bids: Vec<BidRecord>,
+    pub milestones: Vec<MilestoneClaim>,
+    pub final_goods: Vec<GoodsSet>,
+    pub trade_errors: Vec<TradeErrorRecord>,
+    pub aborted: bool,
+    #[serde(default)]
+    pub ending: GameEnding,
+    // The turn the deck ran out, if it did. See deck.rs.
+    #[serde(default)]
+    pub deck_ran_out: Option<i32>,
+    pub seeds: RunSeeds,
+    // Every player's advantage index for the preferences they played with. See advantage.rs.
+    #[serde(default)]
+    pub advantage: Vec<f64>,
+    // On the last game of each match, with match_format. See series.rs.
+    #[serde(default)]
+    pub match_result: Option<MatchResult>,
+
+    // With collect_trajectories, a sample at the start of every turn and one at the end.
+    pub trajectory: Vec<TurnSample>,
+}
+
+// Every player's position at one point of a game, by seat.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TurnSample {
+    pub turn: i32,
+    pub scores: Vec<f64>,
+    // Goods held, not counting money.
+    pub goods: Vec<f64>,
+    pub money: Vec<f64>,
+}
+
+impl TurnSample {
+    fn of(game: &GameState) -> TurnSample {
+        TurnSample {
+            turn: game.current_turn,
+            scores: game.players.iter().map(PlayerState::score).collect(),
+            goods: game
+                .players
+                .iter()
+                .map(|p| {
+                    p.num_goods
+                        .ids()
+                        .filter(|&(category, _)| category != MONEY)
+                        .map(|(_, count)| count)
+                        .sum()
+                })
+                .collect(),
+            money: game.players.iter().map(|p| p.num_goods[MONEY]).collect(),
+        }
+    }
+}
+
+// How much changed hands over a game, for comparing trading protocols.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Liquidity {
+    pub trades: u32,
+    pub goods_volume: f64,
+    pub money_volume: f64,
+
+    // Mean gap between the best ask and the best bid, sampled after each order round.
+    pub mean_spread: Option<f64>,
+}
+
+impl Liquidity {
+    fn from_state(game: &GameState) -> Liquidity {
+        let mut liquidity = Liquidity::default();
+        for trade in game.completed_trades() {
+            liquidity.trades += 1;
+            liquidity.money_volume += trade.money.0.abs();
+            for (category, amount) in trade.from_proposor.ids().chain(trade.from_acceptor.ids()) {
+                if category == MONEY {
+                    liquidity.money_volume += amount.abs();
+                } else {
+                    liquidity.goods_volume += amount.abs();
+                }
+            }
+        }
+        for fill in &game.fills {
+            liquidity.trades += 1;
+            liquidity.goods_volume += fill.quantity;
+            liquidity.money_volume += fill.quantity * fill.price;
+        }
+        if game.spread_samples > 0 {
+            liquidity.mean_spread = Some(game.spread_total / game.spread_samples as f64);
+        }
+        liquidity
+    }
+
+    pub fn add(&mut self, other: &Liquidity) {
+        self.trades += other.trades;
+        self.goods_volume += other.goods_volume;
+        self.money_volume += other.money_volume;
+    }
+
+    // Per-game means over a batch, given total spreads from the games that sampled any.
+    pub fn summary(&self, games: u64, spreads: &[f64]) -> serde_json::Value {
+        let games = games.max(1) as f64;
+        serde_json::json!({
+            "trades_per_game": self.trades as f64 / games,
+            "goods_volume_per_game": self.goods_volume / games,
+            "money_volume_per_game": self.money_volume / games,
+            "mean_price": self.money_volume / self.goods_volume.max(1.),
+            "mean_spread": if spreads.is_empty() {
+                None
+            } else {
+                Some(spreads.iter().sum::<f64>() / spreads.len() as f64)
+            },
+        })
+    }
+}
+
+// Who traded with whom over a game, from its completed trades and order book fills.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TradeHistory {
+    // Trades each seat took part in, on either side.
+    pub trades_by_player: Vec<u32>,
+    // Goods that changed hands, not counting money.
+    pub goods_volume: f64,
+    // money_flows[a][b] is the money seat a paid seat b.
+    pub money_flows: Vec<Vec<f64>>,
+    pub trades_per_turn: f64,
+}
+
+impl TradeHistory {
+    fn from_state(game: &GameState) -> TradeHistory {
+        let num_players = game.players.len();
+        let mut history = TradeHistory {
+            trades_by_player: vec![0; num_players],
+            money_flows: vec![vec![0.; num_players]; num_players],
+            ..TradeHistory::default()
+        };
+        let mut trades = 0;
+        let mut record = |a: PlayerId, b: PlayerId, goods: f64, paid_by_a: f64, paid_by_b: f64| {
+            trades += 1;
+            history.trades_by_player[a] += 1;
+            history.trades_by_player[b] += 1;
+            history.goods_volume += goods;
+            history.money_flows[a][b] += paid_by_a;
+            history.money_flows[b][a] += paid_by_b;
+        };
+        let goods = |set: &GoodsSet| -> f64 {
+            set.ids()
+                .filter(|&(category, _)| category != MONEY)
+                .map(|(_, amount)| amount.abs())
+                .sum()
+        };
+        let money = |set: &GoodsSet| set.get_id(MONEY).unwrap_or(0.);
+        for trade in game.completed_trades() {
+            let (proposer_gives, accepter_gives) = (trade.proposer_gives(), trade.accepter_gives());
+            record(
+                trade.proposer,
+                trade.accepter,
+                goods(&proposer_gives) + goods(&accepter_gives),
+                money(&proposer_gives),
+                money(&accepter_gives),
+            );
+        }
+        for fill in &game.fills {
+            record(
+                fill.buyer,
+                fill.seller,
+                fill.quantity,
+                fill.quantity * fill.price,
+                0.,
+            );
+        }
+        history.trades_per_turn = trades as f64 / game.current_turn.max(1) as f64;
+        history
+    }
+}
+
+impl GameResult {
+    pub fn from_state(rules: &GameRules, game: GameState) -> GameResult {
+        let scores: Vec<f64> = game.players.iter().map(PlayerState::score).collect();
+        let can_win =
+            |pi: &PlayerId| game.num_active_players() == 0 || !game.players[*pi].eliminated;
+        let highest_score = (0..game.players.len())
+            .filter(can_win)
+            .max_by_key(|pi| NonNan::new(scores[*pi]).unwrap())
+            .unwrap();
+        let winner = match rules.win_condition {
+            WinCondition::FullSet => game.victor.unwrap_or(highest_score),
+            WinCondition::Points | WinCondition::HighestScore | WinCondition::FixedTurns(_) => {
+                highest_score
+            }
+        };
+        let preferences: Vec<&Preferences> = game.players.iter().map(|p| &p.preferences).collect();
+        let advantage = advantage::expected_supply(rules)
+            .map(|supply| advantage::indices(&supply, &preferences))
+            .unwrap_or_default();
+        let resigned = game
+            .eliminations
+            .iter()
+            .copied()
+            .filter(|&pi| game.players[pi].resigned)
+            .collect();
+        let ending = if game.aborted {
+            GameEnding::Aborted
+        } else if game.victor.is_some() {
+            GameEnding::Victory
+        } else if game.num_active_players() <= 1 {
+            GameEnding::LastPlayerStanding
+        } else if game.current_turn >= rules.turn_limit() {
+            GameEnding::TurnLimit
+        } else {
+            GameEnding::DeckExhausted
+        };
+        GameResult {
+            winner,
+            placements: placements(&game, &scores),
+            trade_flows: trade_flows::trade_flows(&game),
+            scores,
+            turns: game.current_turn,
+            bailouts: game.players.iter().map(|p| p.bailouts).collect(),
+            loans_taken: game.players.iter().map(|p| p.loans_taken).collect(),
+            loan_defaults: game.players.iter().map(|p| p.loan_defaults).collect(),
+            events: game.players.iter().map(|p| p.events.clone()).collect(),
+            liquidity: Liquidity::from_state(&game),
+            trade_history: TradeHistory::from_state(&game),
+            action_counts: game.action_counts,
+            pacing: game.pacing,
+            bids: game.bid_history,
+            milestones: game.milestone_claims,
+            final_goods: game.players.iter().map(|p| p.num_goods.clone()).collect(),
+            trade_errors: game.trade_errors,
+            aborted: game.aborted,
+            ending,
+            deck_ran_out: game.deck_ran_out,
+            seeds: game.seeds,
+            resigned,
+            eliminated: game.eliminations,
+            advantage,
+            match_result: None,
+            trajectory: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SimConfig {
+    // Master seed for the batch. When set, every run's deck and preference seeds are derived
+    // from it and the run number, in place of deck_shuffle_seed and preferences_seed.
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    #[serde(default)]
+    pub deck_shuffle_seed: u64,
+
+    #[serde(default = "default_preferences_seed")]
+    pub preferences_seed: u64,
+
+    #[serde(default)]
+    pub preference_sampling: PreferenceSampling,
+
+    // How the goods and event decks are shuffled before each game.
+    #[serde(default)]
+    pub shuffle: ShuffleModel,
+
+    #[serde(default = "default_num_players")]
+    pub num_players: usize,
+
+    #[serde(default = "default_num_runs")]
+    pub num_runs: i32,
+
+    #[serde(default)]
+    pub player_configs: Vec<PlayerConfig>,
+
+    // How long the command line pauses before every round, for people watching. Unset pauses
+    // only games a human plays in. The engine itself never pauses.
+    #[serde(default)]
+    pub turn_pause_millis: Option<u64>,
+
+    // Longest a strategy may take over one decision before the default is played instead.
+    // See timing.rs.
+    #[serde(default)]
+    pub decision_budget_millis: Option<u64>,
+
+    // Resign the seat of a strategy that fails, instead of aborting the game. See timing.rs.
+    #[serde(default)]
+    pub resign_failing: Option<ResignRules>,
+
+    // Don't show the game state before every round on the command line.
+    #[serde(default)]
+    pub hide_game_state: bool,
+
+    // Fail instead of substituting default behavior when a strategy lacks a capability.
+    #[serde(default)]
+    pub strict_capabilities: bool,
+
+    // JSON file of strategy profiles, read before the batch and rewritten after it.
+    #[serde(default)]
+    pub profile_store: Option<String>,
+
+    // Stop the batch once its metrics are confidently outside this region.
+    #[serde(default)]
+    pub early_stopping: Option<EarlyStopping>,
+
+    // With --record, also record every value drawn from the engine's random number
+    // generators. See rng.rs.
+    #[serde(default)]
+    pub audit_rng: bool,
+
+    // Points for each place, first place first, in the placement summary. Places past the end
+    // are worth nothing. When unset, places get Borda points.
+    #[serde(default)]
+    pub placement_points: Option<Vec<f64>>,
+
+    // Play the batch as matches of consecutive runs. See series.rs.
+    #[serde(default)]
+    pub match_format: Option<MatchFormat>,
+
+    // Swap one seat's strategy on some runs of the batch and compare the arms. See ablation.rs.
+    #[serde(default)]
+    pub ablation: Option<Ablation>,
+
+    // Record every player's score, goods and money each turn in GameResult::trajectory, with
+    // --collect-trajectories.
+    #[serde(default)]
+    pub collect_trajectories: bool,
+
+    // Report a histogram of turns per game, for the long games the mean and variance hide.
+    #[serde(default)]
+    pub turn_histogram: Option<HistogramConfig>,
+
+    // What the batch records and reports, besides what the command line adds. See observer.rs.
+    #[serde(default, deserialize_with = "observer::deserialize_pipeline")]
+    pub observers: Vec<ObserverConfig>,
+
+    // Where games are recorded, with --record.
+    #[serde(skip)]
+    pub recorder: Option<Recorder>,
+
+    // Called as games are played, e.g. to pace and show them on the command line.
+    #[serde(skip)]
+    pub hooks: Vec<Box<dyn Observer>>,
+}
+
+impl SimConfig {
+    // A config for games nobody watches, e.g. a strategy's lookahead.
+    pub fn headless() -> SimConfig {
+        let mut config: SimConfig = serde_json::from_str("{}").unwrap();
+        config.hide_game_state = true;
+        config
+    }
+}
+
+fn default_preferences_seed() -> u64 {
+    1
+}
+fn default_num_players() -> usize {
+    2
+}
+fn default_num_runs() -> i32 {
+    100
+}
+
+fn generate_deck(config: &SimConfig, rules: &GameRules) -> error::Result<Vec<Good>> {
+    let seed = rng::config_seed(config.deck_shuffle_seed);
+    let mut rng = AuditedRng::new(config, RngPurpose::Deck, seed);
+
+    let mut result = rules.deck_spec().cards(rules, &mut rng)?;
+    result.extend(cards::generate_special_cards(rules)?);
+    shuffle::shuffle(&mut result, &config.shuffle, &mut rng);
+    rng.record(config);
+    Ok(result)
+}
+
+// Each player's hand of preference cards, from the end of the deck.
+fn deal_preference_hands(
+    config: &SimConfig,
+    rules: &GameRules,
+    mut preferences_deck: Vec<Preferences>,
+) -> error::Result<Vec<Vec<Preferences>>> {
+    let num_cards = config.num_players * rules.preference_hand_size;
+    if preferences_deck.len() < num_cards {
+        return Err(HedonicaError::Unsupported {
+            message: format!(
+                "the preference deck has {} cards, fewer than the {} in every player's hand",
+                preferences_deck.len(),
+                num_cards
+            ),
+        });
+    }
+    Ok((0..config.num_players)
+        .map(|_| {
+            (0..rules.preference_hand_size)
+                .map(|_| preferences_deck.pop().unwrap())
+                .collect()
+        })
+        .collect())
+}
+
+// Extra starting money for each seat, times the seat number.
+// TODO(mgraczyk): Correct for advantage in going first.
+//                 This doesn't quite work.
+//                 With two players, we have to give p1 $2 extra.
+//                 With more, it becomes hard to give integer numbers.
+const OFFSET: [f64; 11] = [0., 2., 0., 0., 0., 0., 1., 1., 1., 1., 1.];
+
+// The most players a game can seat, one per entry of OFFSET.
+pub const MAX_PLAYERS: usize = OFFSET.len();
+
+// The money a player in this seat starts with.
+pub fn start_money(rules: &GameRules, player_num: PlayerId) -> f64 {
+    rules.start_money + OFFSET[player_num] * (player_num as f64)
+}
+
+// Players start with the first card of their hand, until they choose.
+fn generate_players(
+    config: &SimConfig,
+    rules: &GameRules,
+    hands: &[Vec<Preferences>],
+) -> Vec<PlayerState> {
+    (0..config.num_players)
+        .map(|player_num| {
+            let preferences = hands[player_num][0].clone();
+            let mut num_goods: GoodsSet = preferences
+                .keys()
+                .map(|category| (category.clone(), 0.))
+                .collect();
+            num_goods.insert(MONEY, start_money(rules, player_num));
+            PlayerState {
+                preferences,
+                num_goods,
+                eliminated: false,
+                resigned: false,
+                penalty: 0.,
+                bailouts: 0,
+                loans: Vec::new(),
+                loans_taken: 0,
+                loan_defaults: 0,
+                events: EventLedger::default(),
+                cooldowns: HashMap::new(),
+                face_down_goods: 0.,
+                utility_curve: rules.utility_curve,
+                scoring: rules.scoring.clone(),
+                shown_score: None,
+            }
+        })
+        .collect()
+}
+
+// Enough preference cards for every player's hand, preference_hand_size cards each.
+pub fn generate_preferences_deck(
+    config: &SimConfig,
+    rules: &GameRules,
+) -> error::Result<Vec<Preferences>> {
+    generate_preferences_deck_with(config, rules, &*rules.preference_generator())
+}
+
+// How many decks max_advantage_spread deals before giving up on finding one within it.
+pub const MAX_REDEALS: usize = 1000;
+
+pub fn generate_preferences_deck_with(
+    config: &SimConfig,
+    rules: &GameRules,
+    generator: &dyn PreferenceGenerator,
+) -> error::Result<Vec<Preferences>> {
+    rules.start_categories();
+    let seed = rng::config_seed(config.preferences_seed);
+    let mut rng = AuditedRng::new(config, RngPurpose::Preferences, seed);
+    let num_cards = config.num_players * rules.preference_hand_size;
+    let mut result = generator.deck(rules, num_cards, &mut rng)?;
+    if let Some(max_spread) = rules.max_advantage_spread {
+        // A deck the rules cannot build fails in generate_start_state, so any deal does here.
+        let supply = advantage::expected_supply(rules).unwrap_or_default();
+        let mut attempts = 1;
+        while starting_spread(config, rules, &supply, &result)? > max_spread {
+            if attempts == MAX_REDEALS {
+                return Err(HedonicaError::Unsupported {
+                    message: format!(
+                        "no deal in {} has an advantage spread within max_advantage_spread {}",
+                        MAX_REDEALS, max_spread
+                    ),
+                });
+            }
+            result = generator.deck(rules, num_cards, &mut rng)?;
+            attempts += 1;
+        }
+    }
+    rng.record(config);
+    Ok(result)
+}
+
+// The advantage spread between the cards players start with when dealt from this deck.
+pub fn starting_spread(
+    config: &SimConfig,
+    rules: &GameRules,
+    supply: &GoodsSet,
+    preferences_deck: &[Preferences],
+) -> error::Result<f64> {
+    let hands = deal_preference_hands(config, rules, preferences_deck.to_vec())?;
+    let starting: Vec<&Preferences> = hands.iter().map(|hand| &hand[0]).collect();
+    Ok(advantage::spread(&advantage::indices(supply, &starting)))
+}
+
+pub fn generate_start_state(
+    config: &SimConfig,
+    rules: &GameRules,
+    preferences_deck: Vec<Preferences>,
+) -> error::Result<GameState> {
+    rules.start_categories();
+    let hands = deal_preference_hands(config, rules, preferences_deck)?;
+    let players = generate_players(config, rules, &hands);
+    let deck = generate_deck(config, rules)?;
+    if deck.is_empty() {
+        return Err(HedonicaError::EmptyDeck);
+    }
+    Ok(GameState {
+        players,
+        deck,
+        discards: Vec::new(),
+        lead: 0,
+        current_turn: 0,
+        current_round: 0,
+        current_trade_proposals: HashMap::new(),
+        current_trades: Vec::new(),
+        past_trades: HashMap::new(),
+        eliminations: Vec::new(),
+        event_deck: rules
+            .events
+            .as_ref()
+            .map_or_else(Vec::new, |r| events::generate_event_deck(config, r)),
+        events_drawn: 0,
+        action_points_left: 0,
+        turn_actions: Vec::new(),
+        action_counts: ActionCounts::default(),
+        pacing: Pacing::default(),
+        order_book: OrderBook::default(),
+        fills: Vec::new(),
+        spread_total: 0.,
+        spread_samples: 0,
+        bid_history: Vec::new(),
+        milestone_claims: Vec::new(),
+        trade_errors: Vec::new(),
+        forfeited: Vec::new(),
+        aborted: false,
+        victor: None,
+        deck_ran_out: None,
+        final_turns_left: None,
+        seeds: RunSeeds::of(config),
+        preference_hands: if rules.preference_hand_size > 1 {
+            hands
+        } else {
+            Vec::new()
+        },
+        score_visibility: rules.score_visibility,
+        preference_visibility: rules.preference_visibility,
+        inventory_visibility: rules.inventory_visibility,
+        trade_partners: rules.trade_partners,
+        trade_limits: rules.trade_limits,
+    })
+}
+
+enum RoundOutcome {
+    Victory,
+    Proposals,
+    NoProposals,
+}
+
+// Passes on why a player made the decision they just made, if their strategy says, to the
+// replay and the hooks. Strategies are only asked when something would keep the answer.
+fn explain(
+    config: &SimConfig,
+    game: &GameState,
+    player: &dyn player::PlayerStrategy,
+    player_id: PlayerId,
+    decision: Decision,
+) {
+    if config.recorder.is_none() && config.hooks.is_empty() {
+        return;
+    }
+    let explanation = match player.explain_last_decision(decision) {
+        Some(explanation) => explanation,
+        None => return,
+    };
+    if let Some(recorder) = &config.recorder {
+        recorder.record(&ReplayEvent::Explanation {
+            turn: game.current_turn,
+            round: game.current_round,
+            player: player_id,
+            decision,
+            explanation: explanation.clone(),
+        });
+    }
+    for hook in &config.hooks {
+        hook.on_explanation(game, player_id, decision, &explanation);
+    }
+}
+
+// One round of trading: either the lead proposes to everyone else (even rounds), or everyone
+// else proposes to the lead (odd rounds), and the recipients accept or reject.
+fn play_round(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) -> RoundOutcome {
+    if start_round(config, rules, game) {
+        return RoundOutcome::Victory;
+    }
+    if rules.trading == TradingProtocol::OrderBook {
+        play_order_round(config, rules, game, players);
+        return RoundOutcome::NoProposals;
+    }
+    if game.trades_left() == Some(0) {
+        game.end_round(config, rules, Vec::new());
+        return RoundOutcome::NoProposals;
+    }
+
+    let lead_proposes = game.current_round % 2 == 0;
+    let mut proposals = if lead_proposes {
+        if game.forfeited.contains(&game.lead) {
+            HashMap::new()
+        } else {
+            let mut trades =
+                players[game.lead].propose_trades_as_lead(&game.observation(config, game.lead));
+            explain(
+                config,
+                game,
+                players[game.lead].as_ref(),
+                game.lead,
+                Decision::Propose,
+            );
+            // Offers to eliminated players are dropped, not held against the lead as invalid.
+            trades.retain(|&player_id, _| {
+                !game
+                    .players
+                    .get(player_id)
+                    .is_some_and(|player| player.eliminated)
+            });
+            trades
+        }
+    } else {
+        let mut trades = HashMap::new();
+        for (player_id, player) in players.iter_mut().enumerate() {
+            if player_id == game.lead
+                || game.players[player_id].eliminated
+                || game.forfeited.contains(&player_id)
+            {
+                continue;
+            }
+            let trade = player.propose_trade_as_non_lead(&game.observation(config, player_id));
+            explain(config, game, player.as_ref(), player_id, Decision::Propose);
+            if let Some(trade) = trade {
+                trades.insert(player_id, trade);
+            }
+        }
+        trades
+    };
+
+    // Proposals are keyed by the non-lead player in them.
+    let lead = game.lead;
+    let proposer_of = |player_id: PlayerId| if lead_proposes { lead } else { player_id };
+    let mut keys: Vec<PlayerId> = proposals.keys().copied().collect();
+    keys.sort_unstable();
+    let mut proposals_made = vec![0; game.players.len()];
+    let mut escrow = Escrow::new(game.players.len());
+    for player_id in keys {
+        let trade = &proposals[&player_id];
+        let (proposer, accepter) = if lead_proposes {
+            (game.lead, player_id)
+        } else {
+            (player_id, game.lead)
+        };
+        let validation = if trade.proposer != proposer || trade.accepter != accepter {
+            Err(TradeError::WrongParties)
+        } else if proposals_made[proposer] >= game.max_proposals() {
+            Err(TradeError::TooManyProposals {
+                max: game.trade_limits.max_proposals_per_round.unwrap(),
+            })
+        } else if rules.trade_settlement == TradeSettlement::Escrow {
+            validate_trade(game, trade).and_then(|()| escrow.reserve_proposal(game, trade))
+        } else {
+            validate_trade(game, trade)
+        };
+        proposals_made[proposer] += 1;
+        if let Err(error) = validation {
+            proposals.remove(&player_id);
+            game.invalid_trade(rules, proposer, error);
+        }
+    }
+    proposals.retain(|&player_id, _| !game.forfeited.contains(&proposer_of(player_id)));
+    if game.aborted {
+        proposals.clear();
+    }
+    if let Some(recorder) = &config.recorder {
+        recorder.record(&ReplayEvent::Proposals {
+            turn: game.current_turn,
+            round: game.current_round,
+            lead: game.lead,
+            proposals: proposals.clone().into_iter().collect(),
+        });
+    }
+    let mut keys: Vec<PlayerId> = proposals.keys().copied().collect();
+    keys.sort_unstable();
+    for player_id in keys {
+        for hook in &config.hooks {
+            hook.on_trade_proposed(game, &proposals[&player_id]);
+        }
+    }
+    game.current_trade_proposals = proposals;
+    let outcome = if game.current_trade_proposals.is_empty() {
+        RoundOutcome::NoProposals
+    } else {
+        RoundOutcome::Proposals
+    };
+
+    let answers: Vec<Acceptance> = if game.current_round % 2 == 0 {
+        game.current_trade_proposals
+            .iter()
+            .map(|(&player_id, trade)| {
+                let observation = game.observation(config, player_id);
+                let answer = if rules.partial_acceptance {
+                    players[player_id].answer_trade_as_non_lead(&observation, trade)
+                } else {
+                    players[player_id]
+                        .accept_trades_as_non_lead(&observation, trade)
+                        .into()
+                };
+                explain(
+                    config,
+                    game,
+                    players[player_id].as_ref(),
+                    player_id,
+                    Decision::Accept,
+                );
+                answer
+            })
+            .collect()
+    } else {
+        let observation = game.observation(config, game.lead);
+        let answers = if rules.partial_acceptance {
+            players[game.lead].answer_trades_as_lead(&observation)
+        } else {
+            let acceptances = players[game.lead].accept_trades_as_lead(&observation);
+            acceptances.into_iter().map(Acceptance::from).collect()
+        };
+        explain(
+            config,
+            game,
+            players[game.lead].as_ref(),
+            game.lead,
+            Decision::Accept,
+        );
+        answers
+    };
+    let (trade_acceptances, partial) = game.accept_answers(rules, answers);
+    if let Some(recorder) = &config.recorder {
+        recorder.record(&ReplayEvent::Decisions {
+            turn: game.current_turn,
+            round: game.current_round,
+            accepted: game
+                .current_trade_proposals
+                .keys()
+                .copied()
+                .zip(trade_acceptances.iter().copied())
+                .collect(),
+            partial,
+        });
+    }
+
+    let mut trade_acceptances = trade_acceptances;
+    if rules.max_negotiation_rounds > 0 {
+        negotiate(config, rules, game, players, &mut trade_acceptances);
+    }
+    let completed_before = game.current_trades.len();
+    game.end_round(config, rules, trade_acceptances);
+    for trade in &game.current_trades[completed_before..] {
+        for hook in &config.hooks {
+            hook.on_trade_accepted(game, trade);
+        }
+    }
+    outcome
+}
+
+// Haggles over the proposals rejected this round. The recipient of a rejected offer may send a
+// counter-offer back, which is answered like a proposal standing alone, until one is accepted,
+// one is not countered, or max_negotiation_rounds counter-offers have been made. An accepted
+// counter-offer takes the place of the proposal and is carried out with the round's trades.
+fn negotiate(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+    acceptances: &mut Vec<bool>,
+) {
+    let keys: Vec<PlayerId> = game.current_trade_proposals.keys().copied().collect();
+    acceptances.resize(keys.len(), false);
+    for (i, key) in keys.into_iter().enumerate() {
+        if acceptances[i] {
+            continue;
+        }
+        let mut offer = game.current_trade_proposals[&key].clone();
+        for _ in 0..rules.max_negotiation_rounds {
+            let responder = offer.accepter;
+            if game.aborted || game.forfeited.contains(&responder) {
+                break;
+            }
+            let counter = if responder == game.lead {
+                players[responder]
+                    .counter_offer_as_lead(&game.observation(config, responder), &offer)
+            } else {
+                players[responder]
+                    .counter_offer_as_non_lead(&game.observation(config, responder), &offer)
+            };
+            explain(
+                config,
+                game,
+                players[responder].as_ref(),
+                responder,
+                Decision::CounterOffer,
+            );
+            let mut counter = match counter {
+                Some(counter) => counter,
+                None => break,
+            };
+            counter.counter_offers = offer.counter_offers + 1;
+            let validation = if counter.proposer != responder || counter.accepter != offer.proposer
+            {
+                Err(TradeError::WrongParties)
+            } else {
+                validate_trade(game, &counter)
+            };
+            if let Err(error) = validation {
+                game.invalid_trade(rules, responder, error);
+                break;
+            }
+            for hook in &config.hooks {
+                hook.on_trade_proposed(game, &counter);
+            }
+
+            let accepted = if counter.accepter == game.lead {
+                let lead = game.lead;
+                let mut alone = HashMap::new();
+                alone.insert(key, counter.clone());
+                let proposals = std::mem::replace(&mut game.current_trade_proposals, alone);
+                let acceptances =
+                    players[lead].accept_trades_as_lead(&game.observation(config, lead));
+                explain(config, game, players[lead].as_ref(), lead, Decision::Accept);
+                game.current_trade_proposals = proposals;
+                acceptances.first() == Some(&true)
+            } else {
+                let accepted = players[counter.accepter].accept_trades_as_non_lead(
+                    &game.observation(config, counter.accepter),
+                    &counter,
+                );
+                let accepter = counter.accepter;
+                explain(
+                    config,
+                    game,
+                    players[accepter].as_ref(),
+                    accepter,
+                    Decision::Accept,
+                );
+                accepted
+            };
+            if let Some(recorder) = &config.recorder {
+                recorder.record(&ReplayEvent::CounterOffer {
+                    turn: game.current_turn,
+                    round: game.current_round,
+                    trade: counter.clone(),
+                    accepted,
+                });
+            }
+            if accepted {
+                game.current_trade_proposals.insert(key, counter);
+                acceptances[i] = true;
+                break;
+            }
+            offer = counter;
+        }
+    }
+}
+
+fn lead_has_won(rules: &GameRules, game: &GameState) -> bool {
+    match rules.win_condition {
+        WinCondition::Points => {
+            rules.score_visibility == ScoreVisibility::Public
+                && game.lead_player_state().score() >= rules.victory_threshold
+        }
+        WinCondition::FullSet => has_full_set(rules, game.lead_player_state()),
+        WinCondition::HighestScore | WinCondition::FixedTurns(_) => false,
+    }
+}
+
+fn has_full_set(rules: &GameRules, player: &PlayerState) -> bool {
+    rules
+        .categories
+        .iter()
+        .all(|category| player.num_goods.get(category).is_some_and(|&n| n >= 1.))
+}
+
+// Tells the hooks a round is starting. Returns true if the lead has already won.
+fn start_round(config: &SimConfig, rules: &GameRules, game: &GameState) -> bool {
+    for hook in &config.hooks {
+        hook.on_round_start(game);
+    }
+    lead_has_won(rules, game)
+}
+
+// One round on the order book: every active player, lead first, may replace their orders.
+fn play_order_round(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) {
+    let num_players = game.players.len();
+    for offset in 0..num_players {
+        let player_id = (game.lead + offset) % num_players;
+        if game.players[player_id].eliminated {
+            continue;
+        }
+        if let Some(orders) = players[player_id].place_orders(&game.observation(config, player_id))
+        {
+            game.place_orders(rules, player_id, orders);
+        }
+    }
+    game.end_order_round(config, rules);
+}
+
+// The lead draws a good, which is auctioned if the rules auction every good or it is
+// contested, and otherwise goes to the lead. Special cards take effect first, as cards.rs
+// describes.
+fn draw_good(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) {
+    let mut good = game.draw_card(config, rules).unwrap();
+    for hook in &config.hooks {
+        hook.on_draw(game, &good);
+    }
+    match good.effect {
+        Some(GoodEffect::Wild) => {
+            let category =
+                players[game.lead].name_wild_category(&game.observation(config, game.lead), &good);
+            if !rules.categories.contains(category.name()) {
+                crate::warn!(
+                    "Player {} named {} for a wild card, which is not a category",
+                    game.lead,
+                    category
+                );
+                game.discard(config, rules, good);
+                return;
+            }
+            good = Good {
+                category,
+                amount: good.amount,
+                effect: None,
+            };
+        }
+        Some(GoodEffect::Steal) => {
+            if let Some((victim, category)) =
+                players[game.lead].choose_steal(&game.observation(config, game.lead))
+            {
+                game.steal(victim, category);
+            }
+            game.discard(config, rules, good);
+            return;
+        }
+        Some(GoodEffect::DrawTwo) => {
+            for _ in 0..2 {
+                if game.deck.is_empty() {
+                    break;
+                }
+                draw_good(config, rules, game, players);
+            }
+            game.discard(config, rules, good);
+            return;
+        }
+        None => {}
+    }
+    let contested = rules
+        .contested
+        .as_ref()
+        .is_some_and(|contest| contest.categories.contains(good.category.name()));
+    let (bids, winner) = match &rules.auction {
+        Some(auction) if auction.format == AuctionFormat::English => {
+            english_auction(config, auction, game, players, &good)
+        }
+        Some(_) => sealed_bid_auction(config, game, players, &good),
+        None if contested => sealed_bid_auction(config, game, players, &good),
+        None => {
+            game.give_good(game.lead, &good);
+            return;
+        }
+    };
+    *game.players[winner].money_mut() -= bids[winner].unwrap_or(0.);
+    game.give_good(winner, &good);
+    game.bid_history.push(BidRecord {
+        turn: game.current_turn,
+        lead: game.lead,
+        category: good.category,
+        bids,
+        winner,
+    });
+}
+
+// Every player's sealed bid for the good, and the winner.
+fn sealed_bid_auction(
+    config: &SimConfig,
+    game: &GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+    good: &Good,
+) -> (Vec<Option<f64>>, PlayerId) {
+    let bids: Vec<Option<f64>> = (0..game.players.len())
+        .map(|player_id| {
+            let player = &game.players[player_id];
+            if player.eliminated {
+                return None;
+            }
+            let money = player.num_goods[MONEY].max(0.);
+            Some(
+                players[player_id]
+                    .bid(&game.observation(config, player_id), good)
+                    .max(0.)
+                    .min(money),
+            )
+        })
+        .collect();
+    let winner = bidding::winner(game.lead, &bids);
+    (bids, winner)
+}
+
+// Every player's highest bid for the good in an English auction, and the winner.
+fn english_auction(
+    config: &SimConfig,
+    rules: &AuctionRules,
+    game: &GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+    good: &Good,
+) -> (Vec<Option<f64>>, PlayerId) {
+    let num_players = game.players.len();
+    let mut bids: Vec<Option<f64>> = game
+        .players
+        .iter()
+        .map(|player| if player.eliminated { None } else { Some(0.) })
+        .collect();
+    let mut in_auction: Vec<bool> = bids.iter().map(Option::is_some).collect();
+    let (mut leader, mut price) = (game.lead, 0.);
+    loop {
+        let mut raised = false;
+        for offset in 1..=num_players {
+            let player_id = (game.lead + offset) % num_players;
+            if player_id == leader || !in_auction[player_id] {
+                continue;
+            }
+            let bid = price + rules.increment;
+            let money = game.players[player_id].num_goods[MONEY];
+            if bid <= money
+                && players[player_id].raise_bid(&game.observation(config, player_id), good, bid)
+            {
+                bids[player_id] = Some(bid);
+                (leader, price, raised) = (player_id, bid, true);
+            } else {
+                in_auction[player_id] = false;
+            }
+        }
+        if !raised {
+            return (bids, leader);
+        }
+    }
+}
+
+// Loans, insurance and events, which happen once at the start of each turn.
+fn resolve_turn_start(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) {
+    if let Some(loan_rules) = &rules.loans {
+        game.settle_loans(loan_rules);
+        let action = players[game.lead].loan_action(&game.observation(config, game.lead));
+        game.apply_loan_action(loan_rules, action);
+    }
+    if let Some(event_rules) = &rules.events {
+        if !game.lead_player_state().events.insured {
+            game.players[game.lead].events.insurance_offers += 1;
+            if players[game.lead].buy_insurance(&game.observation(config, game.lead)) {
+                events::buy_insurance(&mut game.players[game.lead], event_rules);
+            }
+        }
+        game.draw_event(event_rules);
+    }
+}
+
+// The standard turn: draw a good, then trade until the lead has nothing more to propose or the
+// blitz schedule allows no more rounds. Returns true if the lead won.
+fn play_standard_turn(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) -> bool {
+    if game.final_turns_left.is_none() {
+        draw_good(config, rules, game, players);
+    }
+    resolve_turn_start(config, rules, game, players);
+    game.claim_milestones(config, rules);
+    loop {
+        let round = game.current_round;
+        let rounds_allowed = rules
+            .blitz
+            .as_ref()
+            .and_then(|blitz| blitz.rounds_allowed(game.current_turn));
+        if rounds_allowed.is_some_and(|allowed| round >= allowed) {
+            game.pacing.cut_short += 1;
+            return lead_has_won(rules, game);
+        }
+        if game.trades_left() == Some(0) && rules.trading != TradingProtocol::OrderBook {
+            return lead_has_won(rules, game);
+        }
+        let outcome = play_round(config, rules, game, players);
+        if !matches!(outcome, RoundOutcome::Victory) {
+            game.pacing.trade_rounds += 1;
+        }
+        match outcome {
+            RoundOutcome::Victory => return true,
+            RoundOutcome::NoProposals if round > 0 && round % 2 == 0 => return false,
+            RoundOutcome::NoProposals if rules.trading == TradingProtocol::OrderBook => {
+                return lead_has_won(rules, game);
+            }
+            _ => {}
+        }
+        if game.lead_player_state().eliminated || turn_cut_short(game) {
+            return false;
+        }
+    }
+}
+
+// Whether an invalid trade has aborted the game or forfeited the lead's turn.
+fn turn_cut_short(game: &GameState) -> bool {
+    game.aborted || game.forfeited.contains(&game.lead)
+}
+
+// The action point turn: the lead spends points on actions until they end the turn or
+// cannot afford their chosen action. Returns true if the lead won.
+fn play_action_point_turn(
+    config: &SimConfig,
+    rules: &GameRules,
+    action_rules: &ActionPointRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) -> bool {
+    resolve_turn_start(config, rules, game, players);
+    game.action_points_left = action_rules.points_per_turn;
+    loop {
+        let action = players[game.lead].choose_action(&game.observation(config, game.lead));
+        explain(
+            config,
+            game,
+            players[game.lead].as_ref(),
+            game.lead,
+            Decision::Action,
+        );
+        let cost = action_rules.cost(&action);
+        if action == TurnAction::EndTurn || cost > game.action_points_left {
+            return false;
+        }
+        game.action_points_left -= cost;
+        game.turn_actions.push(action.clone());
+
+        match action {
+            TurnAction::EndTurn => {}
+            TurnAction::Draw => {
+                if game.deck.is_empty() {
+                    return false;
+                }
+                draw_good(config, rules, game, players);
+                game.claim_milestones(config, rules);
+                game.action_counts.draws += 1;
+            }
+            TurnAction::TradeRound => {
+                game.action_counts.trade_rounds += 1;
+                // A trade round is both halves of proposal trading, or one order round.
+                let rounds = match rules.trading {
+                    TradingProtocol::Proposals => 2,
+                    TradingProtocol::OrderBook => 1,
+                };
+                for _ in 0..rounds {
+                    if let RoundOutcome::Victory = play_round(config, rules, game, players) {
+                        return true;
+                    }
+                }
+            }
+            TurnAction::Convert { category } => {
+                game.convert(config, rules, category, action_rules.convert_price);
+                game.claim_milestones(config, rules);
+                game.action_counts.converts += 1;
+            }
+        }
+
+        if game.lead_player_state().eliminated || turn_cut_short(game) {
+            return false;
+        }
+        if lead_has_won(rules, game) {
+            return true;
+        }
+    }
+}
+
+// Whether the game has run out of turns, goods or players. Under deck_exhaustion final_turns,
+// the goods run out once the final turns are played.
+pub fn out_of_play(rules: &GameRules, game: &GameState) -> bool {
+    game.aborted
+        || game.current_turn >= rules.turn_limit()
+        || (game.deck.is_empty() && game.final_turns_left.unwrap_or(0) == 0)
+        || game.num_active_players() <= 1
+}
+
+// Plays the lead's turn and passes the lead on. Returns true if the lead won, which ends the
+// game. Callers should stop once out_of_play is true.
+pub fn step_turn(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) -> bool {
+    if let Some(recorder) = &config.recorder {
+        recorder.record(&ReplayEvent::TurnStart {
+            state: game.clone(),
+        });
+    }
+    for (player_id, player) in players.iter().enumerate() {
+        if player.resigns() {
+            game.resign(config, rules, player_id);
+        }
+    }
+    if game.num_active_players() <= 1 {
+        return false;
+    }
+    // A lead who just resigned passes the lead on, and the next player plays this turn, so
+    // resigning does not use up one of the game's turns.
+    if game.lead_player_state().eliminated {
+        game.pass_lead();
+    }
+    for hook in &config.hooks {
+        hook.on_turn_start(game);
+    }
+    if rules.bankruptcy == BankruptcyRule::Skip && game.lead_player_state().is_bankrupt() {
+        game.advance_endgame(rules);
+        game.end_lead_turn();
+        return false;
+    }
+    let victory = match &rules.action_points {
+        None => play_standard_turn(config, rules, game, players),
+        Some(action_rules) => play_action_point_turn(config, rules, action_rules, game, players),
+    };
+    game.advance_endgame(rules);
+    if victory {
+        game.victor = Some(game.lead);
+    } else {
+        game.end_lead_turn();
+    }
+    victory
+}
+
+pub fn play(
+    config: &SimConfig,
+    rules: &GameRules,
+    mut game: GameState,
+    players: &mut Vec<Box<dyn player::PlayerStrategy>>,
+) -> error::Result<GameResult> {
+    choose_preferences(config, &mut game, players)?;
+    if let Some(recorder) = &config.recorder {
+        recorder.start_game(&game);
+    }
+    let mut trajectory = Vec::new();
+    while !out_of_play(rules, &game) {
+        if config.collect_trajectories {
+            trajectory.push(TurnSample::of(&game));
+        }
+        if step_turn(config, rules, &mut game, players) {
+            break;
+        }
+    }
+    if config.collect_trajectories {
+        trajectory.push(TurnSample::of(&game));
+    }
+
+    let final_state = config.recorder.as_ref().map(|_| game.clone());
+    let mut result = GameResult::from_state(rules, game);
+    result.trajectory = trajectory;
+    if let (Some(recorder), Some(state)) = (&config.recorder, final_state) {
+        recorder.record(&ReplayEvent::GameEnd {
+            winner: result.winner,
+            scores: result.scores.clone(),
+            state,
+        });
+        recorder.flush();
+    }
+    for hook in &config.hooks {
+        hook.on_game_end(rules, &result);
+    }
+    Ok(result)
+}
+
+// Every player with a hand of preference cards keeps the one their strategy chooses.
+fn choose_preferences(
+    config: &SimConfig,
+    game: &mut GameState,
+    players: &mut [Box<dyn player::PlayerStrategy>],
+) -> error::Result<()> {
+    if game.preference_hands.is_empty() {
+        return Ok(());
+    }
+    let choices: Vec<usize> = game
+        .preference_hands
+        .iter()
+        .enumerate()
+        .map(|(player_id, hand)| {
+            let choice = players[player_id].choose_preferences(hand);
+            if choice >= hand.len() {
+                return Err(HedonicaError::InvalidChoice {
+                    player: player_id,
+                    what: "preference card",
+                    choice,
+                    choices: hand.len(),
+                });
+            }
+            Ok(choice)
+        })
+        .collect::<error::Result<_>>()?;
+    let hands = std::mem::take(&mut game.preference_hands);
+    if let Some(recorder) = &config.recorder {
+        recorder.record(&ReplayEvent::PreferenceChoices {
+            hands: hands.clone(),
+            choices: choices.clone(),
+        });
+    }
+    for ((player, mut hand), choice) in game.players.iter_mut().zip(hands).zip(choices) {
+        player.preferences = hand.swap_remove(choice);
+    }
+    Ok(())
+}
+
+// A game that could not be played to the end, e.g. because a strategy panicked, as a remote
+// bot does when it stops answering, or because the game ended in an impossible state.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AbortedGame {
+    pub reason: String,
+}
+
+// How many games of a batch were aborted, and why.
+#[derive(Serialize, Deserialize, Default)]
+pub struct AbortTally {
+    games: u32,
+    reasons: BTreeMap<String, u32>,
+}
+
+impl AbortTally {
+    pub fn add(&mut self, aborted: &AbortedGame) {
+        self.games += 1;
+        *self.reasons.entry(aborted.reason.clone()).or_insert(0) += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games == 0
+    }
+
+    // The abort rate is over every game attempted, the played ones and the aborted ones.
+    pub fn summary(&self, played: u64) -> serde_json::Value {
+        let attempted = played + self.games as u64;
+        serde_json::json!({
+            "aborts": {
+                "games": self.games,
+                "rate": self.games as f64 / attempted.max(1) as f64,
+                "reasons": self.reasons,
+            }
+        })
+    }
+}
+
+// What is impossible about the end of a game, if anything.
+fn invariant_violation(result: &GameResult) -> Option<String> {
+    if let Some(player) = result.scores.iter().position(|score| !score.is_finite()) {
+        return Some(format!(
+            "player {} has score {}",
+            player, result.scores[player]
+        ));
+    }
+    for (player, goods) in result.final_goods.iter().enumerate() {
+        for (category, amount) in goods.ids() {
+            if !amount.is_finite() || (category != MONEY && amount < 0.) {
+                return Some(format!("player {} holds {} {}", player, amount, category));
+            }
+        }
+    }
+    None
+}
+
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown panic")
+    }
+}
+
+thread_local! {
+    // Whether this thread is playing a game in try_play.
+    static TRYING: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+// Installs, once per process, a panic hook that is quiet on threads in try_play and defers to
+// the previous hook elsewhere. The hook is global, so swapping it per game would race between
+// threads playing games at once.
+fn install_quiet_hook() {
+    static INSTALLED: std::sync::Once = std::sync::Once::new();
+    INSTALLED.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !TRYING.with(|flag| flag.get()) {
+                hook(info);
+            }
+        }));
+    });
+}
+
+// Resets the strategies and plays like play, but a game that panics or breaks an invariant is
+// returned as an AbortedGame instead of ending the batch. The panic is not printed, since the
+// reason says what it was.
+pub fn try_play(
+    config: &SimConfig,
+    rules: &GameRules,
+    game: GameState,
+    players: &mut Vec<Box<dyn player::PlayerStrategy>>,
+) -> Result<GameResult, AbortedGame> {
+    install_quiet_hook();
+    let trying = TRYING.with(|flag| flag.replace(true));
+    let played = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        players.iter_mut().for_each(|player| player.reset());
+        play(config, rules, game, players)
+    }));
+    TRYING.with(|flag| flag.set(trying));
+    let result = played
+        .map_err(|payload| AbortedGame {
+            reason: format!("panic: {}", panic_message(&*payload)),
+        })?
+        .map_err(|error| AbortedGame {
+            reason: format!("error: {}", error),
+        })?;
+    match invariant_violation(&result) {
+        Some(violation) => Err(AbortedGame {
+            reason: format!("invariant: {}", violation),
+        }),
+        None => Ok(result),
+    }
+}
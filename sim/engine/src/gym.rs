@@ -0,0 +1,565 @@
+// HedonicaEnv exposes the simulator as a reinforcement learning environment, in the style of a
+// gym: reset starts a game, step applies the agent's action and returns what it observes next,
+// the reward, and whether the game is over. Observations and actions are flat numbers, so a
+// training framework can drive the env through bindings or a bridge such as `sim gym`, without
+// knowing the game's types.
+//
+// The agent plays one seat. The other seats play the strategies of the sim config, and the
+// agent's own player config is its fallback for the decisions it is not asked about (loans,
+// insurance, action points, orders, bids and counter-offers). Each game runs on a worker
+// thread, which waits for the agent at every trade decision of its seat.
+//
+// Actions are indices into one discrete space of size action_size():
+//   0                 - Propose nothing, or reject.
+//   1 + ...           - A one-for-one trade with the player `offset` seats after the agent,
+//                       giving one good of category `give` for one of category `get`, at
+//                       1 + ((offset - 1) * C + give) * (C - 1) + (get, skipping give), where
+//                       C is the number of categories.
+// When accepting, any action naming a player accepts their proposal, so the lead accepts at
+// most one proposal per round. legal_actions() marks the actions that make sense now, and
+// actions outside it are taken as 0.
+//
+// An observation has observation_size() values, all raw counts and points:
+//   decision kind, one-hot (4): propose as lead, propose as non-lead, accept as lead, accept
+//                               as non-lead
+//   lead seat, one-hot by offset from the agent (N)
+//   current turn, current round, goods left in the deck
+//   per player, the agent first: money, goods by category (C), score, whether the score is
+//                               visible, preferences by category (C)
+//   per player, the agent first: what their proposal to the agent would change in the
+//                               agent's goods (C) and money, when accepting
+// Hidden scores and preferences are 0, and face-down goods are estimated as for strategies.
+//
+// The reward of a step is the change in the agent's score. The observation when the game ends
+// is all zeros, and last_result() has its result.
+use crate::error;
+use crate::game::{self, GameResult, GameRules, SimConfig};
+use crate::player::{self, Capability, PlayerStrategy};
+use crate::rng::SeedSchedule;
+use crate::types::*;
+use crate::view::Observation;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Decision {
+    ProposeAsLead,
+    ProposeAsNonLead,
+    AcceptAsLead,
+    AcceptAsNonLead,
+}
+
+impl Decision {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// The flat encodings for a number of players and the rules' categories.
+#[derive(Clone)]
+pub struct Encoding {
+    num_players: usize,
+    categories: Vec<String>,
+}
+
+// A decoded trade action: the partner's offset from the agent and the categories traded.
+struct TradeAction {
+    offset: usize,
+    give: usize,
+    get: usize,
+}
+
+impl Encoding {
+    pub fn new(num_players: usize, rules: &GameRules) -> Encoding {
+        Encoding {
+            num_players,
+            categories: rules.categories.clone(),
+        }
+    }
+
+    pub fn action_size(&self) -> usize {
+        let c = self.categories.len();
+        1 + (self.num_players - 1) * c * (c - 1)
+    }
+
+    pub fn observation_size(&self) -> usize {
+        let c = self.categories.len();
+        4 + self.num_players + 3 + self.num_players * (2 * c + 3) + self.num_players * (c + 1)
+    }
+
+    fn decode(&self, action: usize) -> Option<TradeAction> {
+        let c = self.categories.len();
+        if action == 0 || action >= self.action_size() {
+            return None;
+        }
+        let index = action - 1;
+        let (offset, pair) = (index / (c * (c - 1)) + 1, index % (c * (c - 1)));
+        let (give, get) = (pair / (c - 1), pair % (c - 1));
+        let get = if get >= give { get + 1 } else { get };
+        Some(TradeAction { offset, give, get })
+    }
+
+    fn encode_action(&self, offset: usize, give: usize, get: usize) -> usize {
+        let c = self.categories.len();
+        let get = if get > give { get - 1 } else { get };
+        1 + ((offset - 1) * c + give) * (c - 1) + get
+    }
+
+    fn seat(&self, agent: PlayerId, offset: usize) -> PlayerId {
+        (agent + offset) % self.num_players
+    }
+
+    fn offset(&self, agent: PlayerId, player: PlayerId) -> usize {
+        (player + self.num_players - agent) % self.num_players
+    }
+
+    // The proposal the action makes, if it is one the agent may make in this decision.
+    fn trade(&self, game: &Observation, decision: Decision, action: usize) -> Option<Trade> {
+        let agent = game.viewer();
+        let action = self.decode(action)?;
+        let partner = self.seat(agent, action.offset);
+        if decision == Decision::ProposeAsNonLead && partner != game.lead {
+            return None;
+        }
+        let give = &self.categories[action.give];
+        let get = &self.categories[action.get];
+        let view = game.view();
+        let affordable = view.inventory(agent).get(give).copied().unwrap_or(0.) >= 1.
+            && view
+                .estimated_inventory(partner)
+                .get(get)
+                .copied()
+                .unwrap_or(0.)
+                >= 1.;
+        let tradable = [(agent, give), (partner, get), (agent, get), (partner, give)]
+            .iter()
+            .all(|&(player, category)| {
+                game.players[player].can_trade(CategoryId::intern(category), game.current_turn)
+            });
+        if game.players[partner].eliminated || !affordable || !tradable {
+            return None;
+        }
+        let one = |category: &String| std::iter::once((category.clone(), 1.)).collect();
+        Some(Trade::new(agent, partner, one(give), one(get)))
+    }
+
+    // The proposals open to the agent in an accept decision, by proposer.
+    fn proposals(
+        &self,
+        game: &Observation,
+        decision: Decision,
+        trade: Option<&Trade>,
+    ) -> HashMap<PlayerId, Trade> {
+        match decision {
+            Decision::AcceptAsLead => game
+                .current_trade_proposals
+                .iter()
+                .map(|(&player, trade)| (player, trade.clone()))
+                .collect(),
+            Decision::AcceptAsNonLead => {
+                trade.map(|t| (t.proposer, t.clone())).into_iter().collect()
+            }
+            _ => HashMap::new(),
+        }
+    }
+
+    pub fn legal_actions(
+        &self,
+        game: &Observation,
+        decision: Decision,
+        trade: Option<&Trade>,
+    ) -> Vec<bool> {
+        let agent = game.viewer();
+        let mut legal = vec![false; self.action_size()];
+        legal[0] = true;
+        match decision {
+            Decision::ProposeAsLead | Decision::ProposeAsNonLead => {
+                for (action, legal) in legal.iter_mut().enumerate().skip(1) {
+                    *legal = self.trade(game, decision, action).is_some();
+                }
+            }
+            Decision::AcceptAsLead | Decision::AcceptAsNonLead => {
+                for proposer in self.proposals(game, decision, trade).keys() {
+                    legal[self.encode_action(self.offset(agent, *proposer), 0, 1)] = true;
+                }
+            }
+        }
+        legal
+    }
+
+    pub fn observation(
+        &self,
+        game: &Observation,
+        decision: Decision,
+        trade: Option<&Trade>,
+    ) -> Vec<f64> {
+        let agent = game.viewer();
+        let view = game.view();
+        let mut values = Vec::with_capacity(self.observation_size());
+        values.extend((0..4).map(|kind| (kind == decision.index()) as u8 as f64));
+        values.extend(
+            (0..self.num_players)
+                .map(|offset| (self.seat(agent, offset) == game.lead) as u8 as f64),
+        );
+        values.extend(&[
+            game.current_turn as f64,
+            game.current_round as f64,
+            game.deck_composition().values().sum(),
+        ]);
+        for offset in 0..self.num_players {
+            let player = self.seat(agent, offset);
+            let inventory = view.estimated_inventory(player);
+            values.push(inventory.get("money").copied().unwrap_or(0.));
+            values.extend(
+                self.categories
+                    .iter()
+                    .map(|c| inventory.get(c).copied().unwrap_or(0.)),
+            );
+            values.push(view.score(player).unwrap_or(0.));
+            values.push(view.score(player).is_some() as u8 as f64);
+            let preferences = view.preferences(player);
+            values.extend(
+                self.categories
+                    .iter()
+                    .map(|c| preferences.map_or(0., |p| p[c])),
+            );
+        }
+        let proposals = self.proposals(game, decision, trade);
+        for offset in 0..self.num_players {
+            let player = self.seat(agent, offset);
+            let (received, given) = match proposals.get(&player) {
+                Some(trade) => (trade.proposer_gives(), trade.accepter_gives()),
+                None => (GoodsSet::new(), GoodsSet::new()),
+            };
+            let net = |c: &str| {
+                received.get(c).copied().unwrap_or(0.) - given.get(c).copied().unwrap_or(0.)
+            };
+            values.extend(self.categories.iter().map(|c| net(c)));
+            values.push(net("money"));
+        }
+        values
+    }
+}
+
+// What the worker thread needs from the agent.
+struct Request {
+    observation: Vec<f64>,
+    legal_actions: Vec<bool>,
+    score: f64,
+}
+
+enum Message {
+    Decide(Request),
+    Done(Box<GameResult>),
+}
+
+// The agent's seat in a game on the worker thread. Once the env stops listening, e.g. after
+// a reset, the fallback strategy plays out the game.
+struct AgentSeat {
+    encoding: Encoding,
+    fallback: Box<dyn PlayerStrategy>,
+    requests: Sender<Message>,
+    actions: Receiver<usize>,
+}
+
+impl AgentSeat {
+    fn ask(
+        &mut self,
+        game: &Observation,
+        decision: Decision,
+        trade: Option<&Trade>,
+    ) -> Option<usize> {
+        let legal_actions = self.encoding.legal_actions(game, decision, trade);
+        let request = Request {
+            observation: self.encoding.observation(game, decision, trade),
+            score: game.players[game.viewer()].score(),
+            legal_actions: legal_actions.clone(),
+        };
+        self.requests.send(Message::Decide(request)).ok()?;
+        let action = self.actions.recv().ok()?;
+        Some(if legal_actions.get(action) == Some(&true) {
+            action
+        } else {
+            0
+        })
+    }
+
+    fn accepts(&self, game: &Observation, action: usize, proposer: PlayerId) -> bool {
+        self.encoding
+            .decode(action)
+            .is_some_and(|action| self.encoding.seat(game.viewer(), action.offset) == proposer)
+    }
+}
+
+impl PlayerStrategy for AgentSeat {
+    fn init(&mut self, player_id: PlayerId, value: &serde_json::Value) -> Result<(), String> {
+        self.fallback.init(player_id, value)
+    }
+
+    fn reset(&mut self) {
+        self.fallback.reset();
+    }
+
+    fn set_rules(&mut self, rules: &GameRules) {
+        self.fallback.set_rules(rules);
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.fallback.capabilities()
+    }
+
+    fn max_players(&self) -> Option<usize> {
+        self.fallback.max_players()
+    }
+
+    fn min_players(&self) -> Option<usize> {
+        self.fallback.min_players()
+    }
+
+    fn choose_preferences(&mut self, hand: &[Preferences]) -> usize {
+        self.fallback.choose_preferences(hand)
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        match self.ask(game_state, Decision::ProposeAsLead, None) {
+            Some(action) => self
+                .encoding
+                .trade(game_state, Decision::ProposeAsLead, action)
+                .map(|trade| (trade.accepter, trade))
+                .into_iter()
+                .collect(),
+            None => self.fallback.propose_trades_as_lead(game_state),
+        }
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        match self.ask(game_state, Decision::ProposeAsNonLead, None) {
+            Some(action) => self
+                .encoding
+                .trade(game_state, Decision::ProposeAsNonLead, action),
+            None => self.fallback.propose_trade_as_non_lead(game_state),
+        }
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        match self.ask(game_state, Decision::AcceptAsLead, None) {
+            Some(action) => game_state
+                .current_trade_proposals
+                .keys()
+                .map(|&proposer| self.accepts(game_state, action, proposer))
+                .collect(),
+            None => self.fallback.accept_trades_as_lead(game_state),
+        }
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        match self.ask(game_state, Decision::AcceptAsNonLead, Some(trade)) {
+            Some(action) => self.accepts(game_state, action, trade.proposer),
+            None => self.fallback.accept_trades_as_non_lead(game_state, trade),
+        }
+    }
+
+    fn counter_offer_as_lead(&mut self, game_state: &Observation, trade: &Trade) -> Option<Trade> {
+        self.fallback.counter_offer_as_lead(game_state, trade)
+    }
+
+    fn counter_offer_as_non_lead(
+        &mut self,
+        game_state: &Observation,
+        trade: &Trade,
+    ) -> Option<Trade> {
+        self.fallback.counter_offer_as_non_lead(game_state, trade)
+    }
+
+    fn loan_action(&mut self, game_state: &Observation) -> LoanAction {
+        self.fallback.loan_action(game_state)
+    }
+
+    fn buy_insurance(&mut self, game_state: &Observation) -> bool {
+        self.fallback.buy_insurance(game_state)
+    }
+
+    fn choose_action(&mut self, game_state: &Observation) -> TurnAction {
+        self.fallback.choose_action(game_state)
+    }
+
+    fn place_orders(&mut self, game_state: &Observation) -> Option<Vec<crate::orderbook::Order>> {
+        self.fallback.place_orders(game_state)
+    }
+
+    fn bid(&mut self, game_state: &Observation, good: &Good) -> f64 {
+        self.fallback.bid(game_state, good)
+    }
+
+    fn raise_bid(&mut self, game_state: &Observation, good: &Good, price: f64) -> bool {
+        self.fallback.raise_bid(game_state, good, price)
+    }
+}
+
+// The game being played, from the env's side of the worker thread.
+struct Session {
+    requests: Receiver<Message>,
+    actions: Sender<usize>,
+    // The decision the agent has been asked for, until the game ends.
+    pending: Option<Request>,
+}
+
+pub struct Step {
+    pub observation: Vec<f64>,
+    pub reward: f64,
+    pub done: bool,
+}
+
+pub struct HedonicaEnv {
+    config: serde_json::Value,
+    rules: GameRules,
+    seat: PlayerId,
+    encoding: Encoding,
+    games: u64,
+    session: Option<Session>,
+    score: f64,
+    result: Option<GameResult>,
+}
+
+impl HedonicaEnv {
+    // An env in which the agent plays `seat` of games set up by the config and rules. Games
+    // are seeded as the runs of a batch, so a seeded env replays the same games in the same
+    // order.
+    pub fn new(config: &SimConfig, rules: &GameRules, seat: PlayerId) -> HedonicaEnv {
+        assert!(
+            seat < config.num_players,
+            "The agent's seat must be one of the players"
+        );
+        assert!(
+            rules.categories.len() >= 2,
+            "The env needs at least two categories to trade"
+        );
+        HedonicaEnv {
+            config: serde_json::to_value(config).unwrap(),
+            rules: rules.clone(),
+            seat,
+            encoding: Encoding::new(config.num_players, rules),
+            games: 0,
+            session: None,
+            score: 0.,
+            result: None,
+        }
+    }
+
+    pub fn encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    pub fn action_size(&self) -> usize {
+        self.encoding.action_size()
+    }
+
+    pub fn observation_size(&self) -> usize {
+        self.encoding.observation_size()
+    }
+
+    // Starts a new game, abandoning any game in progress, and returns the first observation.
+    pub fn reset(&mut self) -> Vec<f64> {
+        let mut config: SimConfig = serde_json::from_value(self.config.clone()).unwrap();
+        SeedSchedule::new(&config)
+            .seeds(self.games as i32)
+            .apply(&mut config);
+        self.games += 1;
+
+        let (request_sender, requests) = mpsc::channel();
+        let (actions, action_receiver) = mpsc::channel();
+        let (rules, seat, encoding) = (self.rules.clone(), self.seat, self.encoding.clone());
+        thread::spawn(move || {
+            // The env has no way to report an error, so one ends the game, as a panic would.
+            let play = || -> error::Result<GameResult> {
+                let mut players = player::load_strategies(&config, &rules)?;
+                let fallback = players.remove(seat);
+                players.insert(
+                    seat,
+                    Box::new(AgentSeat {
+                        encoding,
+                        fallback,
+                        requests: request_sender.clone(),
+                        actions: action_receiver,
+                    }),
+                );
+                players.iter_mut().for_each(|player| player.reset());
+                let preferences = game::generate_preferences_deck(&config, &rules)?;
+                let game = game::generate_start_state(&config, &rules, preferences)?;
+                game::play(&config, &rules, game, &mut players)
+            };
+            let result = play().unwrap_or_else(|error| panic!("{}", error));
+            // Nobody is listening if the env was reset.
+            let _ = request_sender.send(Message::Done(Box::new(result)));
+        });
+        self.session = Some(Session {
+            requests,
+            actions,
+            pending: None,
+        });
+        self.score = 0.;
+        self.result = None;
+        self.next().observation
+    }
+
+    // Applies the action to the pending decision and plays on to the agent's next one.
+    pub fn step(&mut self, action: usize) -> Step {
+        let session = self.session.as_mut().expect("Call reset before step");
+        assert!(
+            session.pending.take().is_some(),
+            "The game is over, call reset"
+        );
+        session
+            .actions
+            .send(action)
+            .expect("The game stopped without finishing");
+        self.next()
+    }
+
+    // Waits for the agent's next decision or the end of the game.
+    fn next(&mut self) -> Step {
+        let session = self.session.as_mut().unwrap();
+        let message = session
+            .requests
+            .recv()
+            .expect("The game stopped without finishing");
+        let (observation, score, done) = match message {
+            Message::Decide(request) => {
+                let observation = request.observation.clone();
+                let score = request.score;
+                session.pending = Some(request);
+                (observation, score, false)
+            }
+            Message::Done(result) => {
+                let score = result.scores[self.seat];
+                self.result = Some(*result);
+                (vec![0.; self.observation_size()], score, true)
+            }
+        };
+        let reward = score - self.score;
+        self.score = score;
+        Step {
+            observation,
+            reward,
+            done,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.session
+            .as_ref()
+            .is_none_or(|session| session.pending.is_none())
+    }
+
+    // Which actions make sense for the pending decision. None once the game is over.
+    pub fn legal_actions(&self) -> Option<&[bool]> {
+        let request = self.session.as_ref()?.pending.as_ref()?;
+        Some(&request.legal_actions)
+    }
+
+    // The result of the last game, once it is over.
+    pub fn last_result(&self) -> Option<&GameResult> {
+        self.result.as_ref()
+    }
+}
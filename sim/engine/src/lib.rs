@@ -0,0 +1,41 @@
+// The Hedonica rules engine: game state, the turn loop, and the PlayerStrategy interface and
+// registry that strategies plug into. It has no terminal or command line dependencies, so
+// servers and other frontends can embed it.
+pub mod ablation;
+pub mod advantage;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod bidding;
+pub mod blitz;
+pub mod cards;
+pub mod category;
+pub mod deck;
+pub mod error;
+pub mod escrow;
+pub mod events;
+pub mod game;
+pub mod gym;
+pub mod log;
+pub mod lookahead;
+pub mod narrate;
+pub mod non_nan;
+pub mod observer;
+pub mod oracle;
+pub mod orderbook;
+pub mod player;
+pub mod preferences;
+pub mod pricing;
+pub mod replay;
+pub mod rng;
+pub mod sampling;
+pub mod schema;
+pub mod scoring;
+pub mod series;
+pub mod shuffle;
+pub mod stats;
+pub mod suggest;
+pub mod timing;
+pub mod trade_flows;
+pub mod types;
+pub mod validate;
+pub mod view;
@@ -0,0 +1,137 @@
+// GameEngine steps through hypothetical futures of a game, for strategies and tools that search
+// ahead, such as PlayerMCTS and replay annotation.
+//
+// The engine owns a game state and a strategy per seat, which play every turn it is asked to
+// play. Moves the searcher wants to try are applied with step, and snapshot and restore rewind
+// to an earlier position so several moves can be tried from it. Restoring resets the seat
+// strategies, since their memory of the abandoned future no longer applies.
+//
+// Nothing is recorded or shown: the engine plays with SimConfig::headless().
+use crate::error;
+use crate::game::{self, GameRules, GameState, SimConfig, TradeError};
+use crate::player::{self, PlayerStrategy};
+use crate::types::Trade;
+
+pub enum Action {
+    // Carry out a trade now, between any two players.
+    Trade(Trade),
+    // End the lead's turn without playing the rest of it.
+    EndTurn,
+    // Play the lead's turn with the seat strategies.
+    PlayTurn,
+}
+
+// A position to return to with GameEngine::restore.
+#[derive(Clone)]
+pub struct Snapshot {
+    game: GameState,
+}
+
+impl From<GameState> for Snapshot {
+    fn from(game: GameState) -> Snapshot {
+        Snapshot { game }
+    }
+}
+
+pub struct GameEngine {
+    config: SimConfig,
+    rules: GameRules,
+    players: Vec<Box<dyn PlayerStrategy>>,
+    game: GameState,
+}
+
+impl GameEngine {
+    pub fn new(rules: GameRules, players: Vec<Box<dyn PlayerStrategy>>, game: GameState) -> Self {
+        assert_eq!(
+            players.len(),
+            game.players.len(),
+            "GameEngine needs a strategy for every seat"
+        );
+        GameEngine {
+            config: SimConfig::headless(),
+            rules,
+            players,
+            game,
+        }
+    }
+
+    // An engine with the registered strategy `policy` in every seat.
+    pub fn with_policy(rules: &GameRules, policy: &str, game: GameState) -> error::Result<Self> {
+        let players = (0..game.players.len())
+            .map(|seat| {
+                let mut strategy = player::new_strategy(policy, seat, &serde_json::Value::Null)?;
+                strategy.set_rules(rules);
+                Ok(strategy)
+            })
+            .collect::<error::Result<_>>()?;
+        Ok(GameEngine::new(rules.clone(), players, game))
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.game
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    // Whether the game has been won or has run out of play. Stepping a finished game does
+    // nothing.
+    pub fn is_over(&self) -> bool {
+        self.game.victor.is_some() || game::out_of_play(&self.rules, &self.game)
+    }
+
+    // Applies the action and returns the game after it. A trade that is not valid in the
+    // current position is refused and leaves the game as it was.
+    pub fn step(&mut self, action: &Action) -> Result<&GameState, TradeError> {
+        if self.is_over() {
+            return Ok(&self.game);
+        }
+        match action {
+            Action::Trade(trade) => {
+                game::validate_trade(&self.game, trade)?;
+                self.game.apply_trade(&self.rules, trade);
+            }
+            Action::EndTurn => self.game.end_turn(),
+            Action::PlayTurn => {
+                game::step_turn(&self.config, &self.rules, &mut self.game, &mut self.players);
+            }
+        }
+        Ok(&self.game)
+    }
+
+    // Plays up to `turns` turns, stopping early if the game ends.
+    pub fn play_turns(&mut self, turns: usize) -> &GameState {
+        for _ in 0..turns {
+            if self.is_over() {
+                break;
+            }
+            self.step(&Action::PlayTurn).unwrap();
+        }
+        &self.game
+    }
+
+    // Plays until the game ends.
+    pub fn play_out(&mut self) -> &GameState {
+        while !self.is_over() {
+            self.step(&Action::PlayTurn).unwrap();
+        }
+        &self.game
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            game: self.game.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.reset(snapshot.clone());
+    }
+
+    // Starts over from the snapshot without copying it, e.g. from a freshly determinized game.
+    pub fn reset(&mut self, snapshot: Snapshot) {
+        self.game = snapshot.game;
+        self.players.iter_mut().for_each(|player| player.reset());
+    }
+}
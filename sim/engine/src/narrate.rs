@@ -0,0 +1,136 @@
+// Games told as they are played, a plain line per event, with --narrate, for reading a game
+// without wading through the game state JSON that is shown otherwise, e.g.
+//
+//   Turn 12: P2 leads.
+//   Turn 12: P2 draws art.
+//   Turn 12: P2 offers 1 art for 2 money to P0 - accepted.
+//   Turn 12: P0 offers 1 food to P2 - declined.
+//   Turn 12: P2 counters with 1 food for 1 money - accepted.
+//   Game over after 15 turns: P1 wins with 52 points (P0 40, P2 31).
+//
+// Offers are told with their answers, so they are held until the round's trades are carried
+// out, and told when the next round, turn or game end starts. An offer is accepted if it was
+// carried out, and declined otherwise, including offers the engine turned away, e.g. for
+// breaking a trade limit. Lines go to the log at the info level, like the game state, so they
+// never mix with the results printed to stdout.
+use crate::game::{GameResult, GameRules, GameState};
+use crate::observer::Observer;
+use crate::types::{Good, GoodsSet, Trade};
+use std::cell::RefCell;
+
+#[derive(Default)]
+pub struct Narrator {
+    // This round's offers and whether each was carried out, in the order they were made.
+    offers: RefCell<Vec<(i32, Trade, bool)>>,
+}
+
+// An amount without trailing zeros, e.g. 2 or 1.5.
+fn amount(amount: f64) -> String {
+    let rounded = (amount * 100.).round() / 100.;
+    format!("{}", rounded)
+}
+
+// Goods and money, e.g. "1 art and 2 money", or "nothing".
+fn goods(set: &GoodsSet) -> String {
+    let parts: Vec<String> = set
+        .iter()
+        .filter(|(category, &count)| *category != "money" && count != 0.)
+        .chain(
+            set.iter()
+                .filter(|(category, &count)| *category == "money" && count != 0.),
+        )
+        .map(|(category, &count)| format!("{} {}", amount(count), category))
+        .collect();
+    match parts.len() {
+        0 => String::from("nothing"),
+        1 => parts[0].clone(),
+        n => format!("{} and {}", parts[..n - 1].join(", "), parts[n - 1]),
+    }
+}
+
+fn offer(trade: &Trade) -> String {
+    let gives = trade.proposer_gives();
+    let wants = trade.accepter_gives();
+    let terms = if wants.values().all(|&count| count == 0.) {
+        goods(&gives)
+    } else {
+        format!("{} for {}", goods(&gives), goods(&wants))
+    };
+    if trade.counter_offers > 0 {
+        format!("P{} counters with {}", trade.proposer, terms)
+    } else {
+        format!(
+            "P{} offers {} to P{}",
+            trade.proposer, terms, trade.accepter
+        )
+    }
+}
+
+impl Narrator {
+    pub fn new() -> Narrator {
+        Narrator::default()
+    }
+
+    fn tell_offers(&self) {
+        for (turn, trade, accepted) in self.offers.borrow_mut().drain(..) {
+            let answer = if accepted { "accepted" } else { "declined" };
+            crate::info!("Turn {}: {} - {}.", turn, offer(&trade), answer);
+        }
+    }
+}
+
+impl Observer for Narrator {
+    fn on_turn_start(&self, game: &GameState) {
+        self.tell_offers();
+        crate::info!("Turn {}: P{} leads.", game.current_turn, game.lead);
+    }
+
+    fn on_draw(&self, game: &GameState, good: &Good) {
+        let card = if good.is_plain() {
+            good.category.to_string()
+        } else if good.effect.is_some() {
+            format!("a {} card", good.category.name().replace('_', " "))
+        } else {
+            format!("a {} {} card", amount(good.amount), good.category)
+        };
+        crate::info!("Turn {}: P{} draws {}.", game.current_turn, game.lead, card);
+    }
+
+    fn on_round_start(&self, _game: &GameState) {
+        self.tell_offers();
+    }
+
+    fn on_trade_proposed(&self, game: &GameState, trade: &Trade) {
+        self.offers
+            .borrow_mut()
+            .push((game.current_turn, trade.clone(), false));
+    }
+
+    fn on_trade_accepted(&self, _game: &GameState, trade: &Trade) {
+        let mut offers = self.offers.borrow_mut();
+        if let Some(offer) = offers
+            .iter_mut()
+            .find(|(_, offered, accepted)| !accepted && offered == trade)
+        {
+            offer.2 = true;
+        }
+    }
+
+    fn on_game_end(&self, _rules: &GameRules, result: &GameResult) {
+        self.tell_offers();
+        let others: Vec<String> = result
+            .scores
+            .iter()
+            .enumerate()
+            .filter(|&(pi, _)| pi != result.winner)
+            .map(|(pi, &score)| format!("P{} {}", pi, amount(score)))
+            .collect();
+        crate::info!(
+            "Game over after {} turns: P{} wins with {} points ({}).",
+            result.turns,
+            result.winner,
+            amount(result.scores[result.winner]),
+            others.join(", ")
+        );
+    }
+}
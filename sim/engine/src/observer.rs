@@ -0,0 +1,151 @@
+// The observers a batch runs, declared in the sim config so a config file fully describes
+// what a batch records and reports, e.g.
+//   observers: ["progress", { type: "replay", path: "out/games.jsonl" }, "trajectories"]
+//
+// Each observer is what a command line flag turns on. Flags add to the pipeline, and a flag
+// replaces a configured observer of the same type:
+//   progress                - The progress bar, on a terminal with game states hidden.
+//   replay {path}           - Records every game to a replay file, as --record.
+//   output {path}           - Writes per-game results, as --output.
+//   trajectories            - Reports per-turn trajectories, as --collect-trajectories.
+//   cohorts {by}            - Reports outcomes by starting conditions, as --cohort-by.
+//   log                     - Logs every turn, proposal, trade and game end, as --log-events.
+//   narrate                 - Narrates every game a line per event, as --narrate.
+// Observers without parameters may be written as just their type. A config without observers
+// gets the default pipeline, which is only the progress bar.
+//
+// Frontends watch games as they are played with an Observer in SimConfig::hooks, e.g. the
+// EventLog below, a pacer, or a metrics collector. The engine calls every hook at fixed points
+// of the game and otherwise neither pauses nor prints, so batches without hooks play at full
+// speed. Hooks only watch: they see the full game, hidden scores included, and cannot change
+// it. Hooks that keep state do so behind a RefCell, as the Recorder does.
+use crate::game::{GameResult, GameRules, GameState};
+use crate::replay::Decision;
+use crate::types::{Good, PlayerId, Trade};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObserverConfig {
+    Progress,
+    Replay { path: String },
+    Output { path: String },
+    Trajectories,
+    Cohorts { by: String },
+    Log,
+    Narrate,
+}
+
+// Reads a list of observers, each an object with a type or a bare type name.
+pub fn deserialize_pipeline<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<ObserverConfig>, D::Error> {
+    Vec::<Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|observer| {
+            let observer = match observer {
+                Value::String(name) => serde_json::json!({ "type": name }),
+                observer => observer,
+            };
+            serde_json::from_value(observer).map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
+
+// Whether the pipeline shows the progress bar. Logged or narrated events would tear it.
+pub fn shows_progress(observers: &[ObserverConfig]) -> bool {
+    (observers.is_empty() || observers.contains(&ObserverConfig::Progress))
+        && !observers.contains(&ObserverConfig::Log)
+        && !observers.contains(&ObserverConfig::Narrate)
+}
+
+pub trait Observer: Send {
+    // Before the first game of every batch, with the rules the batch plays by.
+    fn on_batch_start(&self, _rules: &GameRules) {}
+
+    // Before every turn, once the lead has passed.
+    fn on_turn_start(&self, _game: &GameState) {}
+
+    // For every card the lead draws from the deck, before it takes effect.
+    fn on_draw(&self, _game: &GameState, _good: &Good) {}
+
+    // Before every round of trading.
+    fn on_round_start(&self, _game: &GameState) {}
+
+    // For every valid proposal and counter-offer, before it is answered.
+    fn on_trade_proposed(&self, _game: &GameState, _trade: &Trade) {}
+
+    // For every proposal or counter-offer carried out, once the goods and money have moved.
+    // Order book fills are not trades.
+    fn on_trade_accepted(&self, _game: &GameState, _trade: &Trade) {}
+
+    // For every decision a strategy explains, just after it is made.
+    fn on_explanation(
+        &self,
+        _game: &GameState,
+        _player: PlayerId,
+        _decision: Decision,
+        _explanation: &str,
+    ) {
+    }
+
+    // After every game, with its result.
+    fn on_game_end(&self, _rules: &GameRules, _result: &GameResult) {}
+}
+
+// Logs every turn, proposal, trade, explanation and game end at the info level, a line each.
+pub struct EventLog;
+
+impl Observer for EventLog {
+    fn on_turn_start(&self, game: &GameState) {
+        crate::info!(
+            "--- Turn {}, player {} leads ---",
+            game.current_turn,
+            game.lead
+        );
+    }
+
+    fn on_trade_proposed(&self, game: &GameState, trade: &Trade) {
+        crate::info!(
+            "Round {}: player {} offers player {} {} for {}",
+            game.current_round,
+            trade.proposer,
+            trade.accepter,
+            serde_json::to_string(&trade.proposer_gives()).unwrap(),
+            serde_json::to_string(&trade.accepter_gives()).unwrap(),
+        );
+    }
+
+    fn on_trade_accepted(&self, _game: &GameState, trade: &Trade) {
+        crate::info!(
+            "Player {} accepts player {}'s offer",
+            trade.accepter,
+            trade.proposer
+        );
+    }
+
+    fn on_explanation(
+        &self,
+        game: &GameState,
+        player: PlayerId,
+        decision: Decision,
+        explanation: &str,
+    ) {
+        crate::info!(
+            "Round {}: player {} explains {:?}: {}",
+            game.current_round,
+            player,
+            decision,
+            explanation
+        );
+    }
+
+    fn on_game_end(&self, _rules: &GameRules, result: &GameResult) {
+        crate::info!(
+            "Player {} wins with scores {}",
+            result.winner,
+            serde_json::to_string(&result.scores).unwrap()
+        );
+    }
+}
@@ -0,0 +1,204 @@
+// The best trade between the viewer and one other player, for strategies that search over
+// trades rather than take the one-good suggestions in suggest.
+//
+// best_trade enumerates every swap of goods bundles in which each side gives at most
+// max_bundle goods, and returns the one that gains the viewer the most while the other side
+// loses nothing. With payments, the viewer also pays or asks for the money that leaves the
+// other side exactly as well off as before.
+//
+// Values are what the viewer can see, as in pricing: the other side's card when it is visible
+// and the mean card value otherwise. Valuation::True reads every card instead, for callers
+// holding the full game, such as analytics. Strategies are handed masked games and must use
+// Valuation::Estimated.
+//
+// A bundle is valued by how giving or receiving it changes a score, by the rules' scorer and
+// utility curve, so a trade's gain is what one side's bundle gains less what the other's costs.
+// That is exact for scorers that score each category on its own, which is all but set bonuses.
+//
+// The other side's bundles are searched best for the viewer first, so the search for each of
+// the viewer's bundles stops at the first one the other side accepts, or once none left could
+// beat the best trade so far.
+use crate::pricing;
+use crate::suggest::TradeSuggestion;
+use crate::types::*;
+use crate::view::PlayerView;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Valuation {
+    #[default]
+    Estimated,
+    True,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct OracleLimits {
+    // The most goods either side gives in one trade.
+    pub max_bundle: usize,
+
+    // Balance trades with money.
+    #[serde(default)]
+    pub payments: bool,
+
+    #[serde(default)]
+    pub valuation: Valuation,
+}
+
+impl Default for OracleLimits {
+    fn default() -> OracleLimits {
+        OracleLimits {
+            max_bundle: 2,
+            payments: false,
+            valuation: Valuation::Estimated,
+        }
+    }
+}
+
+// Goods one side gives, with what they are worth to the viewer and to the other player.
+struct Bundle {
+    goods: GoodsSet,
+    to_viewer: f64,
+    to_other: f64,
+}
+
+// Every bundle of at most max_bundle goods from the inventory, including the empty one.
+fn bundles(inventory: &[(&String, usize)], max_bundle: usize) -> Vec<GoodsSet> {
+    fn extend(
+        inventory: &[(&String, usize)],
+        left: usize,
+        bundle: &mut GoodsSet,
+        out: &mut Vec<GoodsSet>,
+    ) {
+        let Some(((category, held), rest)) = inventory.split_first() else {
+            out.push(bundle.clone());
+            return;
+        };
+        extend(rest, left, bundle, out);
+        for count in 1..=(*held).min(left) {
+            bundle.insert((*category).clone(), count as f64);
+            extend(rest, left - count, bundle, out);
+        }
+        bundle.remove(*category);
+    }
+
+    let mut out = Vec::new();
+    extend(inventory, max_bundle, &mut GoodsSet::new(), &mut out);
+    out
+}
+
+fn disjoint(a: &GoodsSet, b: &GoodsSet) -> bool {
+    a.keys().all(|category| !b.contains_key(category))
+}
+
+// The trade with `other` that gains the viewer the most without costing `other`, if any gains
+// the viewer anything.
+pub fn best_trade(
+    view: &PlayerView,
+    other: PlayerId,
+    limits: &OracleLimits,
+) -> Option<TradeSuggestion> {
+    let me = view.viewer();
+    let game = view.game();
+    if other == me || game.player_state(other).eliminated {
+        return None;
+    }
+    let turn = game.current_turn;
+    let value = |player_id: PlayerId, category: &str| match limits.valuation {
+        Valuation::Estimated => pricing::estimated_value(view, player_id, category),
+        Valuation::True => game.player_state(player_id).preferences()[category],
+    };
+    let gain = |player_id: PlayerId, received: &GoodsSet, given: &GoodsSet| match limits.valuation {
+        Valuation::Estimated => pricing::estimated_gain(view, player_id, received, given),
+        Valuation::True => game.player_state(player_id).score_change(received, given),
+    };
+    let side = |holder: PlayerId| -> Vec<Bundle> {
+        let mut tradable: Vec<(&String, usize)> = view
+            .inventory(holder)
+            .ids()
+            .filter(|&(category, held)| {
+                category != MONEY
+                    && held >= 1.
+                    && game.player_state(me).can_trade(category, turn)
+                    && game.player_state(other).can_trade(category, turn)
+            })
+            .map(|(category, held)| (category.name(), held as usize))
+            .collect();
+        tradable.sort();
+        bundles(&tradable, limits.max_bundle)
+            .into_iter()
+            .map(|goods| {
+                // What the holder would lose giving the bundle up, or another would gain.
+                let none = GoodsSet::new();
+                let worth = |player_id| -> f64 {
+                    if player_id == holder {
+                        -gain(player_id, &none, &goods)
+                    } else {
+                        gain(player_id, &goods, &none)
+                    }
+                };
+                Bundle {
+                    to_viewer: worth(me),
+                    to_other: worth(other),
+                    goods,
+                }
+            })
+            .collect()
+    };
+    let money = |player_id: PlayerId| view.inventory(player_id)["money"];
+    let payments = limits.payments && value(other, "money") > 0.;
+    // A unit of the other side's value, paid in money, costs the viewer this much of theirs.
+    let rate = value(me, "money") / value(other, "money");
+
+    // A trade gains the viewer net(taken) - net(given), with what they pay for the other
+    // side's value counted in when there are payments.
+    let mut mine = side(me);
+    let mut theirs = side(other);
+    let net = |bundle: &Bundle| {
+        if payments {
+            bundle.to_viewer - bundle.to_other * rate
+        } else {
+            bundle.to_viewer
+        }
+    };
+    mine.sort_by(|a, b| net(a).partial_cmp(&net(b)).unwrap());
+    theirs.sort_by(|a, b| net(b).partial_cmp(&net(a)).unwrap());
+
+    let mut best: Option<TradeSuggestion> = None;
+    for given in &mine {
+        for taken in &theirs {
+            let bound = net(taken) - net(given);
+            if bound <= best.as_ref().map_or(0., |best| best.gain) {
+                break;
+            }
+            if (given.goods.is_empty() && taken.goods.is_empty())
+                || !disjoint(&given.goods, &taken.goods)
+            {
+                continue;
+            }
+            let partner_gain = given.to_other - taken.to_other;
+            let trade = Trade::new(me, other, given.goods.clone(), taken.goods.clone());
+            let (trade, partner_gain) = if payments {
+                // Paid by the viewer, so the other side gains nothing and loses nothing.
+                let payment = -partner_gain / value(other, "money");
+                if (payment > 0. && money(me) < payment)
+                    || (payment < 0. && money(other) < -payment)
+                {
+                    continue;
+                }
+                (trade.with_payment(payment), 0.)
+            } else if partner_gain < 0. {
+                continue;
+            } else {
+                (trade, partner_gain)
+            };
+            best = Some(TradeSuggestion {
+                trade,
+                gain: bound,
+                partner_gain,
+            });
+            break;
+        }
+    }
+    best
+}
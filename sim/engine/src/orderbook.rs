@@ -0,0 +1,212 @@
+// A posted-offer market, an alternative to trading by proposals.
+//
+// Players keep standing buy and sell orders for goods on a public book. Whenever a player
+// updates their orders, each new order is matched against the resting orders on the other
+// side, best price first and then oldest first, and trades at the resting order's price.
+// Whatever is left of the new order rests on the book. A new order only matches orders of the
+// players its owner may trade with, under the rules' trade_partners. Orders are only as good as
+// the money and goods behind them when they are matched: an order that can no longer be funded
+// is dropped.
+use crate::category::{CategoryId, MONEY};
+use crate::game::PlayerState;
+use crate::types::PlayerId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Order {
+    pub category: CategoryId,
+    pub side: Side,
+    // Money per good.
+    pub price: f64,
+    pub quantity: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RestingOrder {
+    pub id: u64,
+    pub player: PlayerId,
+    pub order: Order,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Fill {
+    pub buyer: PlayerId,
+    pub seller: PlayerId,
+    pub category: CategoryId,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct OrderBook {
+    pub orders: Vec<RestingOrder>,
+    next_id: u64,
+}
+
+fn is_valid(order: &Order) -> bool {
+    order.category != MONEY && order.price > 0. && order.quantity >= 1.
+}
+
+// How many goods can change hands between these two players at this price.
+fn executable_quantity(
+    players: &[PlayerState],
+    buyer: PlayerId,
+    seller: PlayerId,
+    category: CategoryId,
+    price: f64,
+    wanted: f64,
+) -> f64 {
+    let affordable = (players[buyer].num_goods[MONEY] / price).floor();
+    let held = players[seller].num_goods.get_id(category).unwrap_or(0.);
+    wanted.min(affordable).min(held).floor().max(0.)
+}
+
+impl OrderBook {
+    pub fn remove_player(&mut self, player: PlayerId) {
+        self.orders.retain(|resting| resting.player != player);
+    }
+
+    // Replaces the player's standing orders with new ones, matching each against the orders of
+    // their partners on the book.
+    pub fn replace_orders(
+        &mut self,
+        players: &mut [PlayerState],
+        player: PlayerId,
+        partners: &[PlayerId],
+        orders: Vec<Order>,
+    ) -> Vec<Fill> {
+        self.remove_player(player);
+        let mut fills = Vec::new();
+        for order in orders.into_iter().filter(is_valid) {
+            self.add_order(players, player, partners, order, &mut fills);
+        }
+        fills
+    }
+
+    fn best_match(&self, partners: &[PlayerId], order: &Order) -> Option<usize> {
+        let crosses = |resting: &RestingOrder| {
+            partners.contains(&resting.player)
+                && resting.order.category == order.category
+                && resting.order.side != order.side
+                && match order.side {
+                    Side::Buy => resting.order.price <= order.price,
+                    Side::Sell => resting.order.price >= order.price,
+                }
+        };
+        let better = |a: &RestingOrder, b: &RestingOrder| match order.side {
+            Side::Buy => a.order.price < b.order.price,
+            Side::Sell => a.order.price > b.order.price,
+        };
+        let mut best: Option<usize> = None;
+        for (i, resting) in self.orders.iter().enumerate() {
+            if crosses(resting) && best.is_none_or(|b| better(resting, &self.orders[b])) {
+                best = Some(i);
+            }
+        }
+        best
+    }
+
+    fn add_order(
+        &mut self,
+        players: &mut [PlayerState],
+        player: PlayerId,
+        partners: &[PlayerId],
+        mut order: Order,
+        fills: &mut Vec<Fill>,
+    ) {
+        while order.quantity >= 1. {
+            let i = match self.best_match(partners, &order) {
+                Some(i) => i,
+                None => break,
+            };
+            let resting = &self.orders[i];
+            let (buyer, seller) = match order.side {
+                Side::Buy => (player, resting.player),
+                Side::Sell => (resting.player, player),
+            };
+            let price = resting.order.price;
+            let wanted = order.quantity.min(resting.order.quantity);
+            let quantity =
+                executable_quantity(players, buyer, seller, order.category, price, wanted);
+            if quantity < 1. {
+                // Drop whichever side can no longer be funded. If it is the new order, stop.
+                let resting_funds_it = match resting.order.side {
+                    Side::Buy => players[resting.player].num_goods[MONEY] >= price,
+                    Side::Sell => {
+                        players[resting.player]
+                            .num_goods
+                            .get_id(order.category)
+                            .unwrap_or(0.)
+                            >= 1.
+                    }
+                };
+                if resting_funds_it {
+                    return;
+                }
+                self.orders.remove(i);
+                continue;
+            }
+
+            *players[buyer].money_mut() -= price * quantity;
+            *players[seller].money_mut() += price * quantity;
+            players[seller].num_goods[order.category] -= quantity;
+            *players[buyer]
+                .num_goods
+                .entry_id(order.category)
+                .or_insert(0.) += quantity;
+            fills.push(Fill {
+                buyer,
+                seller,
+                category: order.category,
+                quantity,
+                price,
+            });
+
+            order.quantity -= quantity;
+            self.orders[i].order.quantity -= quantity;
+            if self.orders[i].order.quantity < 1. {
+                self.orders.remove(i);
+            }
+        }
+
+        if order.quantity >= 1. {
+            self.orders.push(RestingOrder {
+                id: self.next_id,
+                player,
+                order,
+            });
+            self.next_id += 1;
+        }
+    }
+
+    // The gap between the best ask and the best bid in each category with both.
+    pub fn spreads(&self) -> Vec<f64> {
+        let mut categories: Vec<CategoryId> =
+            self.orders.iter().map(|r| r.order.category).collect();
+        categories.sort_by_key(|category| category.name());
+        categories.dedup();
+        categories
+            .into_iter()
+            .filter_map(|category| {
+                let prices = |side: Side| {
+                    self.orders
+                        .iter()
+                        .filter(move |r| r.order.category == category && r.order.side == side)
+                        .map(|r| r.order.price)
+                };
+                let best_bid = prices(Side::Buy)
+                    .fold(None, |m: Option<f64>, p| Some(m.map_or(p, |m| m.max(p))))?;
+                let best_ask = prices(Side::Sell)
+                    .fold(None, |m: Option<f64>, p| Some(m.map_or(p, |m| m.min(p))))?;
+                Some(best_ask - best_bid)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,546 @@
+extern crate lazy_static;
+use crate::cards;
+use crate::error::{HedonicaError, Result};
+use crate::game::{GameRules, SimConfig};
+use crate::orderbook::Order;
+use crate::replay::Decision;
+use crate::schema;
+use crate::types::*;
+use crate::view::Observation;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type StrategyConstructor = Arc<dyn Fn() -> Box<dyn PlayerStrategy> + Send + Sync>;
+
+lazy_static! {
+    static ref REGISTRY: Mutex<HashMap<String, StrategyConstructor>> = Mutex::new(HashMap::new());
+}
+// The strategy of seats the sim config has no player config for.
+pub const DEFAULT_PLAYER_TYPE: &str = "PlayerNoTrades";
+
+// Optional game mechanics that need strategy hooks beyond proposing and accepting trades.
+// Variants are added alongside the GameRules options that enable them.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Capability {
+    // PlayerStrategy::loan_action.
+    Loans,
+    // PlayerStrategy::buy_insurance.
+    Insurance,
+    // PlayerStrategy::choose_action.
+    ActionPoints,
+    // PlayerStrategy::place_orders.
+    OrderBook,
+    // PlayerStrategy::bid.
+    SealedBids,
+    // PlayerStrategy::name_wild_category and PlayerStrategy::choose_steal.
+    SpecialCards,
+    // PlayerStrategy::answer_trades_as_lead and PlayerStrategy::answer_trade_as_non_lead.
+    PartialAcceptance,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 7] = [
+        Capability::Loans,
+        Capability::Insurance,
+        Capability::ActionPoints,
+        Capability::OrderBook,
+        Capability::SealedBids,
+        Capability::SpecialCards,
+        Capability::PartialAcceptance,
+    ];
+}
+
+// What the engine does on behalf of a strategy that lacks a capability the rules need. Every
+// capability has one, the trait's default for its hooks.
+fn capability_fallback(capability: Capability) -> &'static str {
+    match capability {
+        Capability::Loans => "never borrowing",
+        Capability::Insurance => "never buying insurance",
+        Capability::ActionPoints => "drawing once, then trading with the remaining points",
+        Capability::OrderBook => "placing no orders",
+        Capability::SealedBids => "bidding nothing",
+        Capability::SpecialCards => {
+            "naming the category valued most, and stealing it from whoever shows the most"
+        }
+        Capability::PartialAcceptance => "accepting or rejecting whole trades",
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlayerConfig {
+    player_type: String,
+
+    // Names this seat's strategy in reports and profile stores. Defaults to player_type.
+    #[serde(default)]
+    label: Option<String>,
+
+    #[serde(default)]
+    config: serde_json::Value,
+}
+
+impl PlayerConfig {
+    pub fn player_type(&self) -> &str {
+        &self.player_type
+    }
+}
+
+pub trait PlayerStrategy {
+    // Initialize the player from the given config, or say why the config cannot be used.
+    fn init(
+        &mut self,
+        player_id: PlayerId,
+        value: &serde_json::Value,
+    ) -> std::result::Result<(), String>;
+
+    // Reset the player to the most recent init() state.
+    fn reset(&mut self);
+
+    // A JSON Schema for the config passed to init, checked before init is called. See
+    // schema.rs for the keywords supported. The default, {}, allows any config.
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({})
+    }
+
+    // Called after init with the rules of the coming games, for strategies that simulate them.
+    fn set_rules(&mut self, _rules: &GameRules) {}
+
+    // Restore a profile saved by an earlier batch, e.g. models of known opponents.
+    // seat_labels names the strategy in each seat of the coming games.
+    fn load_profile(&mut self, _profile: &serde_json::Value, _seat_labels: &[String]) {}
+
+    // A profile to persist for the next batch, keyed by this strategy's label.
+    fn save_profile(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    // The optional mechanics this strategy implements hooks for.
+    fn capabilities(&self) -> Vec<Capability> {
+        Vec::new()
+    }
+
+    // The most players a game this strategy plays may seat, for strategies that only search
+    // small games. None plays games of any size.
+    fn max_players(&self) -> Option<usize> {
+        None
+    }
+
+    // The fewest players a game this strategy plays must seat, for strategies that only play
+    // against a given number of opponents. None plays games of any size.
+    fn min_players(&self) -> Option<usize> {
+        None
+    }
+
+    // The index of the preference card to keep from a hand dealt at the start of a game,
+    // when the rules deal more than one.
+    fn choose_preferences(&mut self, _hand: &[Preferences]) -> usize {
+        0
+    }
+
+    // The decisions below are shown the game as this player observes it, without what the
+    // rules hide from them.
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade>;
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade>;
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool>;
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool;
+
+    // Called in place of the two above when the rules allow partial acceptance, and answered
+    // the same way, except that a proposal may be accepted in part. Defaults to accepting or
+    // rejecting whole trades as those decide.
+    fn answer_trades_as_lead(&mut self, game_state: &Observation) -> Vec<Acceptance> {
+        let acceptances = self.accept_trades_as_lead(game_state);
+        acceptances.into_iter().map(Acceptance::from).collect()
+    }
+    fn answer_trade_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> Acceptance {
+        self.accept_trades_as_non_lead(game_state, trade).into()
+    }
+
+    // Called with an offer this player just rejected, when the rules allow counter-offers. A
+    // trade returned goes back to the offer's proposer, with this player as the proposer.
+    fn counter_offer_as_lead(
+        &mut self,
+        _game_state: &Observation,
+        _trade: &Trade,
+    ) -> Option<Trade> {
+        None
+    }
+    fn counter_offer_as_non_lead(
+        &mut self,
+        _game_state: &Observation,
+        _trade: &Trade,
+    ) -> Option<Trade> {
+        None
+    }
+
+    // Called at the start of each turn as lead when the rules enable loans.
+    fn loan_action(&mut self, _game_state: &Observation) -> LoanAction {
+        LoanAction::None
+    }
+
+    // Called at the start of each turn as lead, while uninsured, when event cards are on.
+    fn buy_insurance(&mut self, _game_state: &Observation) -> bool {
+        false
+    }
+
+    // Called repeatedly during a turn as lead when the rules use action points.
+    fn choose_action(&mut self, game_state: &Observation) -> TurnAction {
+        if game_state.turn_actions.is_empty() {
+            TurnAction::Draw
+        } else {
+            TurnAction::TradeRound
+        }
+    }
+
+    // Called once per order round when the rules use the order book. Some(orders) replaces
+    // the player's standing orders, None leaves them on the book.
+    fn place_orders(&mut self, _game_state: &Observation) -> Option<Vec<Order>> {
+        None
+    }
+
+    // A sealed money bid for a good the lead just drew, when it is contested or auctioned.
+    fn bid(&mut self, _game_state: &Observation, _good: &Good) -> f64 {
+        0.
+    }
+
+    // Whether to outbid the standing bid in an English auction for a good the lead just drew,
+    // by bidding `price`. By default, while the price is within the sealed bid.
+    fn raise_bid(&mut self, game_state: &Observation, good: &Good, price: f64) -> bool {
+        price <= self.bid(game_state, good)
+    }
+
+    // The category of a wild card this player just drew as lead. See cards.rs.
+    fn name_wild_category(&mut self, game_state: &Observation, _good: &Good) -> CategoryId {
+        cards::favorite_category(game_state.player_state(game_state.viewer()).preferences())
+    }
+
+    // The player and category to take a good from, for a steal card this player just drew
+    // as lead. None steals nothing.
+    fn choose_steal(&mut self, game_state: &Observation) -> Option<(PlayerId, CategoryId)> {
+        cards::default_steal(game_state)
+    }
+
+    // Whether this player resigns, asked at the start of every turn. A player who resigns is
+    // out of the game, as if eliminated, and their goods go where the rules' resigned_goods
+    // says. timing.rs resigns a seat whose strategy keeps failing.
+    fn resigns(&self) -> bool {
+        false
+    }
+
+    // Why the strategy made the decision it just made, for debugging. Asked just after each
+    // proposal, acceptance, counter-offer or turn action when games are recorded or watched,
+    // and kept in the replay. A strategy that remembers only its last reason should answer
+    // None when that reason was for another kind of decision, rather than repeat it.
+    fn explain_last_decision(&self, _decision: Decision) -> Option<String> {
+        None
+    }
+}
+
+// Points gained by receiving one set of goods for another, by these preferences.
+pub fn score_delta(preferences: &Preferences, received: &GoodsSet, given: &GoodsSet) -> f64 {
+    let value = |goods: &GoodsSet| {
+        goods
+            .ids()
+            .map(|(id, count)| count * preferences[id])
+            .sum::<f64>()
+    };
+    value(received) - value(given)
+}
+
+pub fn can_afford(holdings: &GoodsSet, goods: &GoodsSet) -> bool {
+    goods
+        .ids()
+        .all(|(id, count)| holdings.get_id(id).unwrap_or(0.) >= count)
+}
+
+// Removes goods from holdings, e.g. to track what outstanding proposals have committed.
+pub fn take(holdings: &mut GoodsSet, goods: &GoodsSet) {
+    for (id, count) in goods.ids() {
+        *holdings.get_id_mut(id).unwrap() -= count;
+    }
+}
+
+// The lead's proposals to each partner in turn, up to the rules' max_proposals, from propose.
+// Every proposal may be accepted, so together they must be affordable: propose is handed the
+// viewer's holdings less what the earlier proposals give.
+pub fn propose_within_holdings(
+    game_state: &Observation,
+    mut propose: impl FnMut(PlayerId, &GoodsSet) -> Option<Trade>,
+) -> HashMap<PlayerId, Trade> {
+    let mut holdings = game_state
+        .player_state(game_state.viewer())
+        .num_goods
+        .clone();
+    let mut trades = HashMap::new();
+    for other in game_state.trade_partners_of(game_state.viewer()) {
+        if trades.len() >= game_state.max_proposals() {
+            break;
+        }
+        if let Some(trade) = propose(other, &holdings) {
+            take(&mut holdings, &trade.proposer_gives());
+            trades.insert(other, trade);
+        }
+    }
+    trades
+}
+
+// Built-in strategies register a constructor function from a #[ctor]. Strategies loaded at
+// runtime, such as plugins, may register closures.
+pub fn register_strategy(
+    player_type: &str,
+    constructor: impl Fn() -> Box<dyn PlayerStrategy> + Send + Sync + 'static,
+) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .insert(player_type.to_string(), Arc::new(constructor));
+}
+
+// The strategies a strategy library adds, which the simulator registers once it is loaded. A
+// library has its own copy of this crate, with its own registry, so it registers through this
+// instead of register_strategy or a #[ctor]. See export_strategies!.
+#[derive(Default)]
+pub struct Registry {
+    strategies: Vec<(String, StrategyConstructor)>,
+}
+
+impl Registry {
+    pub fn register(
+        &mut self,
+        player_type: &str,
+        constructor: impl Fn() -> Box<dyn PlayerStrategy> + Send + Sync + 'static,
+    ) {
+        self.strategies
+            .push((player_type.to_string(), Arc::new(constructor)));
+    }
+
+    // Registers every strategy added, returning their names.
+    pub fn install(self) -> Vec<String> {
+        let mut registry = REGISTRY.lock().unwrap();
+        self.strategies
+            .into_iter()
+            .map(|(player_type, constructor)| {
+                registry.insert(player_type.clone(), constructor);
+                player_type
+            })
+            .collect()
+    }
+}
+
+// Identifies the build of this crate a strategy library was compiled against. Libraries pass
+// Rust types across, so they only load into a simulator built from the same engine version
+// with the same compiler.
+pub const STRATEGY_LIB_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+// Exports the entry points the simulator's --strategy-lib looks for from a cdylib, given a
+// `fn register(registry: &mut Registry)` that adds the library's strategies, e.g.
+//   hedonica_engine::export_strategies!(register);
+#[macro_export]
+macro_rules! export_strategies {
+    ($register:path) => {
+        #[no_mangle]
+        pub extern "C" fn hedonica_strategy_lib_version() -> *const ::std::os::raw::c_char {
+            $crate::player::STRATEGY_LIB_VERSION.as_ptr() as *const ::std::os::raw::c_char
+        }
+
+        #[no_mangle]
+        pub fn hedonica_register(registry: &mut $crate::player::Registry) {
+            $register(registry)
+        }
+    };
+}
+
+fn constructor(player_type: &str) -> Result<StrategyConstructor> {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .get(player_type)
+        .cloned()
+        .ok_or_else(|| HedonicaError::UnknownStrategy {
+            player_type: player_type.to_string(),
+        })
+}
+
+// Whether a strategy is registered under this name.
+pub fn check_registered(player_type: &str) -> Result<()> {
+    constructor(player_type).map(|_| ())
+}
+
+// The names of every registered strategy, in order.
+pub fn registered_strategies() -> Vec<String> {
+    let mut names: Vec<String> = REGISTRY.lock().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+// The schema a registered strategy publishes for its config.
+pub fn config_schema(player_type: &str) -> Result<serde_json::Value> {
+    Ok(constructor(player_type)?().config_schema())
+}
+
+// The most players a registered strategy plays with, if it is limited.
+pub fn max_players(player_type: &str) -> Result<Option<usize>> {
+    Ok(constructor(player_type)?().max_players())
+}
+
+// The fewest players a registered strategy plays with, if it is limited.
+pub fn min_players(player_type: &str) -> Result<Option<usize>> {
+    Ok(constructor(player_type)?().min_players())
+}
+
+// A fresh, initialized instance of a registered strategy.
+pub fn new_strategy(
+    player_type: &str,
+    player_id: PlayerId,
+    config: &serde_json::Value,
+) -> Result<Box<dyn PlayerStrategy>> {
+    let mut strategy = constructor(player_type)?();
+    strategy
+        .init(player_id, config)
+        .map_err(|error| HedonicaError::InvalidStrategyConfig {
+            player: player_id,
+            player_type: player_type.to_string(),
+            errors: vec![error],
+        })?;
+    Ok(strategy)
+}
+
+pub fn strategy_labels(config: &SimConfig) -> Vec<String> {
+    (0..config.num_players)
+        .map(|i| match config.player_configs.get(i) {
+            Some(c) => c.label.clone().unwrap_or_else(|| c.player_type.clone()),
+            None => String::from(DEFAULT_PLAYER_TYPE),
+        })
+        .collect()
+}
+
+pub fn load_strategies(
+    config: &SimConfig,
+    rules: &GameRules,
+) -> Result<Vec<Box<dyn PlayerStrategy>>> {
+    // Strategies may keep goods sets from set_rules on, under the ids the games will use.
+    rules.start_categories();
+    let configs = &config.player_configs;
+    if configs.len() > config.num_players {
+        return Err(HedonicaError::TooManyPlayerConfigs {
+            configs: configs.len(),
+            num_players: config.num_players,
+        });
+    }
+    (0..config.num_players)
+        .map(|i| match configs.get(i) {
+            Some(player_config) => {
+                let strategy = load_strategy(player_config, i, rules, config.strict_capabilities)?;
+                check_players(
+                    i,
+                    player_config.player_type(),
+                    strategy.as_ref(),
+                    config.num_players,
+                )?;
+                Ok(strategy)
+            }
+            None => {
+                // default
+                let mut strategy = constructor(DEFAULT_PLAYER_TYPE)?();
+                strategy.set_rules(rules);
+                check_capabilities(
+                    i,
+                    DEFAULT_PLAYER_TYPE,
+                    strategy.as_ref(),
+                    rules,
+                    config.strict_capabilities,
+                )?;
+                Ok(strategy)
+            }
+        })
+        .collect()
+}
+
+// The strategy a player config describes, set up to play the given seat under these rules.
+pub fn load_strategy(
+    player_config: &PlayerConfig,
+    player_id: PlayerId,
+    rules: &GameRules,
+    strict_capabilities: bool,
+) -> Result<Box<dyn PlayerStrategy>> {
+    // A seat without a config gets the strategy's defaults.
+    if !player_config.config.is_null() {
+        let schema = config_schema(&player_config.player_type)?;
+        schema::validate(&schema, &player_config.config, "config").map_err(|errors| {
+            HedonicaError::InvalidStrategyConfig {
+                player: player_id,
+                player_type: player_config.player_type.clone(),
+                errors,
+            }
+        })?;
+    }
+    let mut strategy = new_strategy(&player_config.player_type, player_id, &player_config.config)?;
+    strategy.set_rules(rules);
+    check_capabilities(
+        player_id,
+        player_config.player_type(),
+        strategy.as_ref(),
+        rules,
+        strict_capabilities,
+    )?;
+    Ok(strategy)
+}
+
+// Whether the strategy plays games of this many players.
+pub fn check_players(
+    player_id: PlayerId,
+    player_type: &str,
+    strategy: &dyn PlayerStrategy,
+    num_players: usize,
+) -> Result<()> {
+    if let Some(max_players) = strategy.max_players().filter(|&max| num_players > max) {
+        return Err(HedonicaError::TooManyPlayers {
+            player: player_id,
+            player_type: player_type.to_string(),
+            max_players,
+            num_players,
+        });
+    }
+    if let Some(min_players) = strategy.min_players().filter(|&min| num_players < min) {
+        return Err(HedonicaError::TooFewPlayers {
+            player: player_id,
+            player_type: player_type.to_string(),
+            min_players,
+            num_players,
+        });
+    }
+    Ok(())
+}
+
+// Whether the strategy supports what the rules need, or, unless strict, the engine can fall
+// back for it.
+pub fn check_capabilities(
+    player_id: PlayerId,
+    player_type: &str,
+    strategy: &dyn PlayerStrategy,
+    rules: &GameRules,
+    strict: bool,
+) -> Result<()> {
+    let supported = strategy.capabilities();
+    for capability in rules.required_capabilities() {
+        if supported.contains(&capability) {
+            continue;
+        }
+        if strict {
+            return Err(HedonicaError::MissingCapability {
+                player: player_id,
+                player_type: player_type.to_string(),
+                capability,
+            });
+        }
+        crate::warn!(
+            "player {} ({}) does not support {:?}, falling back to: {}",
+            player_id,
+            player_type,
+            capability,
+            capability_fallback(capability)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,274 @@
+// How preference cards are made, as GameRules.preference_generator specifies it. G2 asks
+// whether deals are fair, and that depends a great deal on how alike the players' preferences
+// are, so the generator is part of the rules and a sweep can vary it. A generator is one of
+//
+//   {type: "shuffle"}
+//       Every card gives the categories preference_values in a random order, each order equally
+//       likely.
+//   {type: "dirichlet", concentration: 1, total: 20}
+//       Every card splits `total` points, by default the sum of preference_values, between the
+//       categories in proportions drawn from a symmetric Dirichlet distribution. Below 1, the
+//       concentration piles the points on few categories; above 1, it spreads them evenly.
+//       Values are not rounded.
+//   {type: "anti_correlated"}
+//       Cards give preference_values in rotations of one random order of the categories, spread
+//       as evenly as the number of cards allows, so players want different things. With no
+//       more cards than categories, no two cards share a top category.
+//   {type: "list"}
+//       Cards from preference_cards, without replacement unless allow_duplicate_preferences.
+//
+// Without a generator, cards come from preference_cards if the rules have any, or are shuffled
+// otherwise. Other generators can be written against PreferenceGenerator and dealt with
+// generate_preferences_deck_with.
+use crate::error::{HedonicaError, Result};
+use crate::game::GameRules;
+use crate::types::Preferences;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PreferenceModel {
+    Shuffle,
+    Dirichlet {
+        #[serde(default = "default_concentration")]
+        concentration: f64,
+        #[serde(default)]
+        total: Option<f64>,
+    },
+    AntiCorrelated,
+    List,
+}
+
+fn default_concentration() -> f64 {
+    1.
+}
+
+pub trait PreferenceGenerator {
+    // The cards for every player's hand, `num_cards` in all. Players are dealt from the end.
+    fn deck(
+        &self,
+        rules: &GameRules,
+        num_cards: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<Preferences>>;
+
+    // One card, as a player guessing another's hidden preferences would draw it.
+    fn card(&self, rules: &GameRules, rng: &mut dyn RngCore) -> Result<Preferences>;
+}
+
+impl PreferenceModel {
+    pub fn generator(&self) -> Box<dyn PreferenceGenerator> {
+        match *self {
+            PreferenceModel::Shuffle => Box::new(UniformShuffle),
+            PreferenceModel::Dirichlet {
+                concentration,
+                total,
+            } => Box::new(DirichletRandom {
+                concentration,
+                total,
+            }),
+            PreferenceModel::AntiCorrelated => Box::new(AntiCorrelated),
+            PreferenceModel::List => Box::new(ExplicitList),
+        }
+    }
+}
+
+pub struct UniformShuffle;
+
+impl PreferenceGenerator for UniformShuffle {
+    fn deck(
+        &self,
+        rules: &GameRules,
+        num_cards: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<Preferences>> {
+        let mut values = rules.preference_values.clone();
+        let mut deck: Vec<Preferences> = Vec::with_capacity(num_cards);
+        for attempt in 0.. {
+            if deck.len() == num_cards {
+                break;
+            }
+            if attempt == 1000 * num_cards {
+                return Err(HedonicaError::Unsupported {
+                    message: String::from(
+                        "there are too few distinct preference cards for every player's hand; \
+                         shrink the hands or set allow_duplicate_preferences",
+                    ),
+                });
+            }
+            values.shuffle(rng);
+            let card = rules.preference_card(&values)?;
+            // A hand of several cards should offer a choice, and only one player may hold each
+            // card, unless duplicates are allowed.
+            let duplicate = rules.preference_hand_size > 1
+                && !rules.allow_duplicate_preferences
+                && deck.contains(&card);
+            if !duplicate {
+                deck.push(card);
+            }
+        }
+        Ok(deck)
+    }
+
+    fn card(&self, rules: &GameRules, rng: &mut dyn RngCore) -> Result<Preferences> {
+        let mut values = rules.preference_values.clone();
+        values.shuffle(rng);
+        rules.preference_card(&values)
+    }
+}
+
+pub struct DirichletRandom {
+    pub concentration: f64,
+    // The sum of preference_values if None.
+    pub total: Option<f64>,
+}
+
+impl PreferenceGenerator for DirichletRandom {
+    fn deck(
+        &self,
+        rules: &GameRules,
+        num_cards: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<Preferences>> {
+        (0..num_cards).map(|_| self.card(rules, rng)).collect()
+    }
+
+    fn card(&self, rules: &GameRules, rng: &mut dyn RngCore) -> Result<Preferences> {
+        if self.concentration <= 0. {
+            return Err(HedonicaError::Unsupported {
+                message: format!(
+                    "the Dirichlet concentration must be positive, not {}",
+                    self.concentration
+                ),
+            });
+        }
+        let total = self
+            .total
+            .unwrap_or_else(|| rules.preference_values.iter().sum::<i32>() as f64);
+        let weights: Vec<f64> = (0..rules.categories.len())
+            .map(|_| gamma(self.concentration, rng))
+            .collect();
+        let sum: f64 = weights.iter().sum();
+        let mut card = Preferences::new();
+        card.insert("money", 1.);
+        for (category, weight) in rules.categories.iter().zip(weights) {
+            card.insert(category, total * weight / sum);
+        }
+        Ok(card)
+    }
+}
+
+pub struct AntiCorrelated;
+
+impl PreferenceGenerator for AntiCorrelated {
+    fn deck(
+        &self,
+        rules: &GameRules,
+        num_cards: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<Preferences>> {
+        let num_categories = rules.categories.len();
+        let mut values = rules.preference_values.clone();
+        values.sort_unstable_by(|a, b| b.cmp(a));
+        let mut order: Vec<usize> = (0..num_categories).collect();
+        order.shuffle(rng);
+        (0..num_cards)
+            .map(|card| {
+                let rotation = card * num_categories / num_cards;
+                let mut card_values = vec![0; num_categories];
+                for (rank, &value) in values.iter().enumerate() {
+                    card_values[order[(rank + rotation) % num_categories]] = value;
+                }
+                rules.preference_card(&card_values)
+            })
+            .collect()
+    }
+
+    // Alone, an anti-correlated card is a uniformly shuffled one.
+    fn card(&self, rules: &GameRules, rng: &mut dyn RngCore) -> Result<Preferences> {
+        UniformShuffle.card(rules, rng)
+    }
+}
+
+pub struct ExplicitList;
+
+impl ExplicitList {
+    fn cards(rules: &GameRules) -> Result<&[BTreeMap<String, f64>]> {
+        rules
+            .preference_cards
+            .as_deref()
+            .ok_or_else(|| HedonicaError::Unsupported {
+                message: String::from("list preferences need preference_cards to deal from"),
+            })
+    }
+}
+
+impl PreferenceGenerator for ExplicitList {
+    fn deck(
+        &self,
+        rules: &GameRules,
+        num_cards: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<Preferences>> {
+        let cards = ExplicitList::cards(rules)?;
+        if rules.allow_duplicate_preferences {
+            return (0..num_cards)
+                .map(|_| ExplicitList.card(rules, rng))
+                .collect();
+        }
+        if cards.len() < num_cards {
+            return Err(HedonicaError::Unsupported {
+                message: format!(
+                    "the players' hands take {} preference cards, but {} are listed",
+                    num_cards,
+                    cards.len()
+                ),
+            });
+        }
+        cards
+            .choose_multiple(rng, num_cards)
+            .map(|card| rules.with_money(card))
+            .collect()
+    }
+
+    fn card(&self, rules: &GameRules, rng: &mut dyn RngCore) -> Result<Preferences> {
+        let card =
+            ExplicitList::cards(rules)?
+                .choose(rng)
+                .ok_or_else(|| HedonicaError::Unsupported {
+                    message: String::from("there are no preference_cards to deal from"),
+                })?;
+        rules.with_money(card)
+    }
+}
+
+// A Gamma(shape, 1) draw, by Marsaglia and Tsang's method. Shapes below 1 are boosted by one
+// and scaled back by U^(1/shape).
+fn gamma(shape: f64, rng: &mut dyn RngCore) -> f64 {
+    if shape < 1. {
+        let u: f64 = rng.gen();
+        return gamma(shape + 1., rng) * u.powf(1. / shape);
+    }
+    let d = shape - 1. / 3.;
+    let c = 1. / (9. * d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = (1. + c * x).powi(3);
+        if v <= 0. {
+            continue;
+        }
+        let u: f64 = rng.gen();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+// By the Box-Muller transform.
+fn standard_normal(rng: &mut dyn RngCore) -> f64 {
+    let u1: f64 = 1. - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2. * u1.ln()).sqrt() * (2. * std::f64::consts::PI * u2).cos()
+}
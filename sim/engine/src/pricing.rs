@@ -0,0 +1,88 @@
+// What goods are worth to each player, as one player can estimate it, and the prices that
+// follow. A player's own values come from their preference card. Other players' come from
+// theirs when it is visible, and otherwise from the mean point value on the viewer's own card,
+// which is the mean over cards when every card holds the same values.
+//
+// Gains and prices are score changes, by the rules' scorer and utility curve against what the
+// player is seen or estimated to hold, so under a curve a good is worth less to a player who
+// already has many.
+use crate::types::*;
+use crate::view::PlayerView;
+use std::borrow::Cow;
+
+pub fn mean_preference_value(view: &PlayerView) -> f64 {
+    let card = view.preferences(view.viewer()).unwrap();
+    let values: Vec<f64> = card
+        .iter()
+        .filter(|(category, _)| *category != "money")
+        .map(|(_, &value)| value)
+        .collect();
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+// What the viewer expects a category is worth to a player.
+pub fn estimated_value(view: &PlayerView, player_id: PlayerId, category: &str) -> f64 {
+    if category == "money" {
+        return 1.;
+    }
+    match view.preferences(player_id) {
+        Some(preferences) => preferences[category],
+        None => mean_preference_value(view),
+    }
+}
+
+// The preference card the viewer expects a player holds: theirs when visible, and otherwise
+// the viewer's with every category at the mean value.
+fn estimated_card<'a>(view: &PlayerView<'a>, player_id: PlayerId) -> Cow<'a, Preferences> {
+    if let Some(preferences) = view.preferences(player_id) {
+        return Cow::Borrowed(preferences);
+    }
+    let mean = mean_preference_value(view);
+    let mut card = view.preferences(view.viewer()).unwrap().clone();
+    for (category, value) in card.iter_mut() {
+        *value = if category == "money" { 1. } else { mean };
+    }
+    Cow::Owned(card)
+}
+
+// How the viewer expects a player's score to change on receiving one set of goods for another,
+// as PlayerState::score_change.
+pub fn estimated_gain(
+    view: &PlayerView,
+    player_id: PlayerId,
+    received: &GoodsSet,
+    given: &GoodsSet,
+) -> f64 {
+    let player = view.game().player_state(player_id);
+    let card = estimated_card(view, player_id);
+    let held = view.estimated_inventory(player_id);
+    let mut after = held.clone();
+    for (id, count) in received.ids() {
+        *after.entry_id(id).or_insert(0.) += count;
+    }
+    for (id, count) in given.ids() {
+        *after.entry_id(id).or_insert(0.) -= count;
+    }
+    let scorer = player.scoring.scorer();
+    scorer.worth(&card, player.utility_curve, &after)
+        - scorer.worth(&card, player.utility_curve, &held)
+}
+
+// The price of one good from `seller` to `buyer` that splits the gain from trading it evenly:
+// the mean of what receiving it gains the buyer and what giving it up costs the seller.
+pub fn midpoint_price(view: &PlayerView, buyer: PlayerId, seller: PlayerId, category: &str) -> f64 {
+    let mut good = GoodsSet::new();
+    good.insert(category, 1.);
+    let none = GoodsSet::new();
+    (estimated_gain(view, buyer, &good, &none) - estimated_gain(view, seller, &none, &good)) / 2.
+}
+
+// What a trade is expected to gain its proposer and its accepter.
+pub fn estimated_gains(view: &PlayerView, trade: &Trade) -> (f64, f64) {
+    let proposer_gives = trade.proposer_gives();
+    let accepter_gives = trade.accepter_gives();
+    (
+        estimated_gain(view, trade.proposer, &accepter_gives, &proposer_gives),
+        estimated_gain(view, trade.accepter, &proposer_gives, &accepter_gives),
+    )
+}
@@ -0,0 +1,286 @@
+// Recording games to a replay file and playing them back.
+//
+// A replay file is JSON lines, one ReplayEvent per line. Each recorded game starts with the
+// full game state, records the state again at the start of every turn along with every round
+// of proposals, the decisions on them and any counter-offers, and ends with the final state
+// and winner. States are recorded as the engine holds them, so hidden preferences and
+// face-down goods are included.
+// Strategies that explain their decisions have each explanation recorded just after the
+// decision is made, before the event that holds it.
+// Annotated replays also hold evaluations after those states. See annotate.rs.
+use crate::game::GameState;
+use crate::rng::RngPurpose;
+use crate::types::{PlayerId, Preferences, Trade};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::{thread, time};
+
+// Externally tagged, since the states hold maps keyed by integers, which serde cannot read
+// back inside internally tagged enums.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayEvent {
+    GameStart {
+        game: u32,
+        state: GameState,
+    },
+    TurnStart {
+        state: GameState,
+    },
+    Proposals {
+        turn: i32,
+        round: i32,
+        lead: PlayerId,
+        proposals: BTreeMap<PlayerId, Trade>,
+    },
+    // Whether each proposal in the round was accepted, keyed by the non-lead player it was
+    // made to or by, and the trades that proposals accepted in part were cut down to, with
+    // partial_acceptance.
+    Decisions {
+        turn: i32,
+        round: i32,
+        accepted: BTreeMap<PlayerId, bool>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        partial: BTreeMap<PlayerId, Trade>,
+    },
+    // A counter-offer to a rejected trade, and whether the other side accepted it.
+    CounterOffer {
+        turn: i32,
+        round: i32,
+        trade: Trade,
+        accepted: bool,
+    },
+    // The preference cards dealt to each player, and the index of the one each kept, with
+    // preference_hand_size above 1. Recorded just before game_start.
+    PreferenceChoices {
+        hands: Vec<Vec<Preferences>>,
+        choices: Vec<usize>,
+    },
+    // Values drawn from one of the engine's random number generators, with audit_rng.
+    RandomDraws {
+        purpose: RngPurpose,
+        seed: Option<u64>,
+        values: Vec<u64>,
+    },
+    GameEnd {
+        winner: PlayerId,
+        scores: Vec<f64>,
+        state: GameState,
+    },
+    // Why a player made a decision, in the words of their strategy's explain_last_decision.
+    Explanation {
+        turn: i32,
+        round: i32,
+        player: PlayerId,
+        decision: Decision,
+        explanation: String,
+    },
+    // Each player's chance of winning from the state just before, added by the annotator.
+    Evaluation {
+        turn: i32,
+        rollouts: u32,
+        win_probabilities: Vec<f64>,
+    },
+}
+
+// The decisions strategies are asked to explain.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Propose,
+    Accept,
+    CounterOffer,
+    Action,
+}
+
+pub struct Recorder {
+    writer: RefCell<BufWriter<File>>,
+    games: RefCell<u32>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> std::io::Result<Recorder> {
+        Ok(Recorder {
+            writer: RefCell::new(BufWriter::new(File::create(path)?)),
+            games: RefCell::new(0),
+        })
+    }
+
+    pub fn start_game(&self, state: &GameState) {
+        let game = *self.games.borrow();
+        *self.games.borrow_mut() += 1;
+        self.record(&ReplayEvent::GameStart {
+            game,
+            state: state.clone(),
+        });
+    }
+
+    pub fn record(&self, event: &ReplayEvent) {
+        let mut writer = self.writer.borrow_mut();
+        serde_json::to_writer(&mut *writer, event).expect("Could not write replay");
+        writeln!(writer).expect("Could not write replay");
+    }
+
+    pub fn flush(&self) {
+        self.writer
+            .borrow_mut()
+            .flush()
+            .expect("Could not write replay");
+    }
+}
+
+pub fn write(path: &Path, events: &[ReplayEvent]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+pub fn read(path: &Path) -> std::io::Result<Vec<ReplayEvent>> {
+    BufReader::new(File::open(path)?)
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|line| {
+            serde_json::from_str(&line?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        })
+        .collect()
+}
+
+// Prints a recorded game event by event, pausing between turns as a live game would.
+pub fn play_back(events: &[ReplayEvent], pause_millis: u64, hide_game_state: bool) {
+    for event in events {
+        match event {
+            ReplayEvent::GameStart { game, state } => {
+                println!("=== Game {} ===", game);
+                if !hide_game_state {
+                    println!("{}", serde_json::to_string_pretty(state).unwrap());
+                }
+            }
+            ReplayEvent::TurnStart { state } => {
+                if pause_millis > 0 {
+                    thread::sleep(time::Duration::from_millis(pause_millis));
+                }
+                println!(
+                    "--- Turn {}, player {} leads ---",
+                    state.current_turn, state.lead
+                );
+                if !hide_game_state {
+                    println!("{}", serde_json::to_string_pretty(state).unwrap());
+                }
+            }
+            ReplayEvent::Proposals {
+                round, proposals, ..
+            } => {
+                for trade in proposals.values() {
+                    println!(
+                        "Round {}: player {} offers player {} {} for {}",
+                        round,
+                        trade.proposer,
+                        trade.accepter,
+                        serde_json::to_string(&trade.proposer_gives()).unwrap(),
+                        serde_json::to_string(&trade.accepter_gives()).unwrap(),
+                    );
+                }
+            }
+            ReplayEvent::Decisions {
+                round,
+                accepted,
+                partial,
+                ..
+            } => {
+                for (player_id, accepted) in accepted {
+                    let answer = match partial.get(player_id) {
+                        Some(trade) => format!(
+                            "accepted in part, for {} and {}",
+                            serde_json::to_string(&trade.proposer_gives()).unwrap(),
+                            serde_json::to_string(&trade.accepter_gives()).unwrap(),
+                        ),
+                        None if *accepted => String::from("accepted"),
+                        None => String::from("rejected"),
+                    };
+                    println!(
+                        "Round {}: trade with player {} {}",
+                        round, player_id, answer
+                    );
+                }
+            }
+            ReplayEvent::CounterOffer {
+                round,
+                trade,
+                accepted,
+                ..
+            } => {
+                println!(
+                    "Round {}: player {} counters, offering player {} {} for {}, {}",
+                    round,
+                    trade.proposer,
+                    trade.accepter,
+                    serde_json::to_string(&trade.proposer_gives()).unwrap(),
+                    serde_json::to_string(&trade.accepter_gives()).unwrap(),
+                    if *accepted { "accepted" } else { "rejected" }
+                );
+            }
+            ReplayEvent::PreferenceChoices { hands, choices } => {
+                for (player_id, (hand, choice)) in hands.iter().zip(choices).enumerate() {
+                    println!(
+                        "Player {} keeps preference card {} of {}",
+                        player_id,
+                        choice + 1,
+                        hand.len()
+                    );
+                }
+            }
+            ReplayEvent::RandomDraws {
+                purpose,
+                seed,
+                values,
+            } => {
+                println!(
+                    "{} draws for {:?}, seeded {}",
+                    values.len(),
+                    purpose,
+                    seed.map_or_else(
+                        || String::from("by the OS"),
+                        |seed| format!("with {}", seed)
+                    )
+                );
+            }
+            ReplayEvent::Explanation {
+                round,
+                player,
+                decision,
+                explanation,
+                ..
+            } => {
+                println!(
+                    "Round {}: player {} explains {:?}: {}",
+                    round, player, decision, explanation
+                );
+            }
+            ReplayEvent::GameEnd { winner, scores, .. } => {
+                println!(
+                    "Player {} wins with scores {}",
+                    winner,
+                    serde_json::to_string(scores).unwrap()
+                );
+            }
+            ReplayEvent::Evaluation {
+                win_probabilities, ..
+            } => {
+                let chances: Vec<String> = win_probabilities
+                    .iter()
+                    .enumerate()
+                    .map(|(player, p)| format!("player {} {:.0}%", player, 100. * p))
+                    .collect();
+                println!("Chances to win: {}", chances.join(", "));
+            }
+        }
+    }
+}
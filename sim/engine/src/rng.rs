@@ -0,0 +1,194 @@
+// The engine's random number generators.
+//
+// All of a game's randomness (dealing the deck, preference cards and the event deck,
+// reshuffling the deck mid-game, and redealing the deck a player is shown of a face-down table)
+// comes from an AuditedRng tagged with its purpose. None of them is ever handed to a strategy.
+// With audit_rng and --record, every value drawn is written to the replay file with its purpose
+// and seed, so a game's randomness can be attributed and checked against its seeds. A game's
+// deals are recorded just before its game_start event, and reshuffles and redeals where they
+// happen. The spectator's table view is not recorded. A seed of None means the generator was
+// seeded from the OS.
+//
+// Each run of a batch is dealt from its own seeds, from a SeedSchedule, and a game's seeds are
+// recorded in its result so it can be reproduced on its own.
+use crate::game::SimConfig;
+use crate::replay::ReplayEvent;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RngPurpose {
+    Deck,
+    Preferences,
+    EventDeck,
+    // Shuffling goods back into the deck mid-game.
+    Reshuffle,
+    // Redealing the deck a viewer of a face-down table is shown.
+    Redeal,
+}
+
+pub struct AuditedRng {
+    inner: StdRng,
+    purpose: RngPurpose,
+    seed: Option<u64>,
+    // Values drawn since the last record, when auditing.
+    draws: Option<Vec<u64>>,
+}
+
+impl AuditedRng {
+    pub fn new(config: &SimConfig, purpose: RngPurpose, seed: Option<u64>) -> AuditedRng {
+        let inner = match seed {
+            None => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+            Some(seed) => SeedableRng::seed_from_u64(seed),
+        };
+        let audit = config.audit_rng && config.recorder.is_some();
+        AuditedRng {
+            inner,
+            purpose,
+            seed,
+            draws: if audit { Some(Vec::new()) } else { None },
+        }
+    }
+
+    // A generator whose draws are never recorded, for views of the game no replay holds.
+    pub fn unaudited(purpose: RngPurpose, seed: Option<u64>) -> AuditedRng {
+        let inner = match seed {
+            None => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+            Some(seed) => SeedableRng::seed_from_u64(seed),
+        };
+        AuditedRng {
+            inner,
+            purpose,
+            seed,
+            draws: None,
+        }
+    }
+
+    // Writes the values drawn since the last record to the replay file.
+    pub fn record(&mut self, config: &SimConfig) {
+        let draws = match &mut self.draws {
+            Some(draws) if !draws.is_empty() => std::mem::take(draws),
+            _ => return,
+        };
+        if let Some(recorder) = &config.recorder {
+            recorder.record(&ReplayEvent::RandomDraws {
+                purpose: self.purpose,
+                seed: self.seed,
+                values: draws,
+            });
+        }
+    }
+
+    fn log(&mut self, value: u64) {
+        if let Some(draws) = &mut self.draws {
+            draws.push(value);
+        }
+    }
+}
+
+// A config seed of 0 means seed from the OS.
+pub fn config_seed(seed: u64) -> Option<u64> {
+    if seed == 0 {
+        None
+    } else {
+        Some(seed)
+    }
+}
+
+// The seeds one game of a batch is dealt with, recorded in its GameResult. A game can be
+// replayed alone by setting them as the config's seeds, with num_runs 1 and no master seed.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct RunSeeds {
+    pub deck_shuffle_seed: u64,
+    pub preferences_seed: u64,
+}
+
+impl RunSeeds {
+    pub fn of(config: &SimConfig) -> RunSeeds {
+        RunSeeds {
+            deck_shuffle_seed: config.deck_shuffle_seed,
+            preferences_seed: config.preferences_seed,
+        }
+    }
+
+    pub fn apply(&self, config: &mut SimConfig) {
+        config.deck_shuffle_seed = self.deck_shuffle_seed;
+        config.preferences_seed = self.preferences_seed;
+    }
+}
+
+// Gives each run of a batch its own seeds. With a master seed, they are hashed from it and the
+// run number. Otherwise the config's seeds are offset by the run number, and a seed of 0 is
+// replaced by one drawn from the OS, so the recorded seeds reproduce the game either way.
+pub struct SeedSchedule {
+    master: Option<u64>,
+    base: RunSeeds,
+}
+
+// SplitMix64's output function, which spreads nearby inputs over the whole range.
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// Never 0, which would mean seeding from the OS.
+fn nonzero(seed: u64) -> u64 {
+    seed.max(1)
+}
+
+impl SeedSchedule {
+    pub fn new(config: &SimConfig) -> SeedSchedule {
+        SeedSchedule {
+            master: config.seed,
+            base: RunSeeds::of(config),
+        }
+    }
+
+    pub fn seeds(&self, run: i32) -> RunSeeds {
+        let derive = |seed: u64, purpose: RngPurpose| match self.master {
+            Some(master) => nonzero(mix(mix(master ^ purpose as u64) ^ run as u64)),
+            None if seed == 0 => nonzero(rand::thread_rng().gen()),
+            None => seed.wrapping_add(run as u64),
+        };
+        RunSeeds {
+            deck_shuffle_seed: derive(self.base.deck_shuffle_seed, RngPurpose::Deck),
+            preferences_seed: derive(self.base.preferences_seed, RngPurpose::Preferences),
+        }
+    }
+
+    // Puts the config's own seeds back after the batch.
+    pub fn restore(&self, config: &mut SimConfig) {
+        self.base.apply(config);
+    }
+}
+
+impl RngCore for AuditedRng {
+    fn next_u32(&mut self) -> u32 {
+        let value = self.inner.next_u32();
+        self.log(value as u64);
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let value = self.inner.next_u64();
+        self.log(value);
+        value
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest);
+        for chunk in dest.chunks(8) {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            self.log(u64::from_le_bytes(bytes));
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
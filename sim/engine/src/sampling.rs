@@ -0,0 +1,193 @@
+// How preference cards are dealt across the runs of a batch.
+//
+// Random dealing rarely produces some of the deals we care most about for G2, like every
+// player wanting the same category most. Stratified dealing groups deals by how players'
+// top categories collide, deals each stratum equally often, and weights every run by
+// P(stratum) / P(stratum is chosen) so weighted aggregates estimate the same quantities as
+// random dealing would.
+use crate::error::{HedonicaError, Result};
+use crate::game::*;
+use crate::preferences::PreferenceModel;
+use crate::rng;
+use crate::rng::{AuditedRng, RngPurpose};
+use crate::types::Preferences;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PreferenceSampling {
+    #[default]
+    Random,
+    Stratified,
+}
+
+pub struct Deal {
+    pub preferences: Vec<Preferences>,
+    pub weight: f64,
+    pub stratum: Option<String>,
+}
+
+// Players grouped by top category, e.g. [2, 1] means two players share a top category and
+// the third wants something else most.
+struct Stratum {
+    group_sizes: Vec<usize>,
+    probability: f64,
+}
+
+impl Stratum {
+    fn label(&self) -> String {
+        let sizes: Vec<String> = self.group_sizes.iter().map(|s| s.to_string()).collect();
+        sizes.join("+")
+    }
+}
+
+pub struct PreferenceDealer {
+    strata: Vec<Stratum>,
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+// Integer partitions of n into at most max_parts parts, largest part first.
+fn partitions(n: usize, max_part: usize, max_parts: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    if max_parts == 0 {
+        return Vec::new();
+    }
+    (1..=max_part.min(n))
+        .rev()
+        .flat_map(|first| {
+            partitions(n - first, first, max_parts - 1)
+                .into_iter()
+                .map(move |mut rest| {
+                    rest.insert(0, first);
+                    rest
+                })
+        })
+        .collect()
+}
+
+// Each player's top category is uniform and independent under random dealing, so the
+// probability of a collision pattern is the number of ways to seat players into groups of
+// these sizes and give each group its own category, over num_categories^num_players.
+fn stratum_probability(group_sizes: &[usize], num_categories: usize) -> f64 {
+    let num_players: usize = group_sizes.iter().sum();
+    let mut seatings = factorial(num_players);
+    for size in group_sizes {
+        seatings /= factorial(*size);
+    }
+    let mut size = 0;
+    let mut repeats = 0;
+    for &s in group_sizes {
+        repeats = if s == size { repeats + 1 } else { 1 };
+        size = s;
+        seatings /= repeats as f64;
+    }
+    let category_choices: f64 = (0..group_sizes.len())
+        .map(|i| (num_categories - i) as f64)
+        .product();
+    seatings * category_choices / (num_categories as f64).powi(num_players as i32)
+}
+
+// What in the rules keeps stratified sampling from dealing them, each as the rules key to change
+// and why.
+pub fn stratified_conflicts(rules: &GameRules) -> Vec<(&'static str, &'static str)> {
+    let shuffled = match rules.preference_generator {
+        Some(PreferenceModel::Shuffle) => true,
+        Some(_) => false,
+        None => rules.preference_cards.is_none(),
+    };
+    let mut conflicts = Vec::new();
+    if !shuffled {
+        conflicts.push((
+            "rules.preference_generator",
+            "stratified preference sampling needs shuffled preference_values",
+        ));
+    }
+    if rules.max_advantage_spread.is_some() {
+        conflicts.push((
+            "rules.max_advantage_spread",
+            "stratified preference sampling cannot redeal for max_advantage_spread",
+        ));
+    }
+    if rules.preference_hand_size != 1 {
+        conflicts.push((
+            "rules.preference_hand_size",
+            "stratified preference sampling deals one card per player, not hands",
+        ));
+    }
+    conflicts
+}
+
+impl PreferenceDealer {
+    pub fn new(config: &SimConfig, rules: &GameRules) -> Result<PreferenceDealer> {
+        if config.preference_sampling == PreferenceSampling::Stratified {
+            if let Some((_, conflict)) = stratified_conflicts(rules).first() {
+                return Err(HedonicaError::Unsupported {
+                    message: conflict.to_string(),
+                });
+            }
+        }
+        let num_categories = rules.categories.len();
+        let strata = match config.preference_sampling {
+            PreferenceSampling::Random => Vec::new(),
+            PreferenceSampling::Stratified => {
+                partitions(config.num_players, config.num_players, num_categories)
+                    .into_iter()
+                    .map(|group_sizes| Stratum {
+                        probability: stratum_probability(&group_sizes, num_categories),
+                        group_sizes,
+                    })
+                    .collect()
+            }
+        };
+        Ok(PreferenceDealer { strata })
+    }
+
+    // Each deal is drawn from the run's own preferences seed, so the run's recorded seeds and
+    // run number reproduce it.
+    pub fn deal(&mut self, config: &SimConfig, rules: &GameRules, run: i32) -> Result<Deal> {
+        rules.start_categories();
+        if self.strata.is_empty() {
+            return Ok(Deal {
+                preferences: generate_preferences_deck(config, rules)?,
+                weight: 1.,
+                stratum: None,
+            });
+        }
+
+        let stratum = &self.strata[run as usize % self.strata.len()];
+        let weight = stratum.probability * self.strata.len() as f64;
+
+        let top_value = *rules.preference_values.iter().max().unwrap();
+        let mut other_values: Vec<i32> = rules.preference_values.clone();
+        other_values.remove(other_values.iter().position(|&v| v == top_value).unwrap());
+
+        let seed = rng::config_seed(config.preferences_seed);
+        let mut rng = AuditedRng::new(config, RngPurpose::Preferences, seed);
+        let mut categories: Vec<usize> = (0..rules.categories.len()).collect();
+        categories.shuffle(&mut rng);
+
+        let mut preferences = Vec::new();
+        for (group, &size) in stratum.group_sizes.iter().enumerate() {
+            for _ in 0..size {
+                other_values.shuffle(&mut rng);
+                let mut values = other_values.clone();
+                values.insert(categories[group], top_value);
+                preferences.push(rules.preference_card(&values)?);
+            }
+        }
+        preferences.shuffle(&mut rng);
+        rng.record(config);
+
+        Ok(Deal {
+            preferences,
+            weight,
+            stratum: Some(stratum.label()),
+        })
+    }
+}
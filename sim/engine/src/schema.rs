@@ -0,0 +1,97 @@
+// Checks JSON values against the subset of JSON Schema that strategies publish for their
+// configs, so a typo in a bot parameter fails the batch instead of being silently ignored.
+//
+// Supported keywords:
+//   type                 - One type name or a list of them: object, array, string, number,
+//                          integer, boolean or null.
+//   properties           - Schemas for an object's keys.
+//   additionalProperties - false to reject keys not in properties.
+//   required             - Keys an object must have.
+//   items                - The schema for every element of an array.
+//   enum                 - The values allowed.
+//   minimum, maximum     - Inclusive bounds on numbers.
+// Other keywords, such as description and default, are ignored. The empty schema {} allows
+// anything.
+use serde_json::Value;
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.),
+        name => type_name(value) == name,
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+        errors.push(format!(
+            "{} should be {}, not {}",
+            path,
+            types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            errors.push(format!("{} should be one of {}", path, schema["enum"]));
+        }
+    }
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema["minimum"].as_f64().filter(|&min| n < min) {
+            errors.push(format!("{} should be at least {}", path, min));
+        }
+        if let Some(max) = schema["maximum"].as_f64().filter(|&max| n > max) {
+            errors.push(format!("{} should be at most {}", path, max));
+        }
+    }
+    if let Value::Array(items) = value {
+        for (i, item) in items.iter().enumerate() {
+            check(&schema["items"], item, &format!("{}[{}]", path, i), errors);
+        }
+    }
+    if let Value::Object(fields) = value {
+        for key in schema["required"].as_array().into_iter().flatten() {
+            if let Some(key) = key.as_str().filter(|key| !fields.contains_key(*key)) {
+                errors.push(format!("{} is missing {}", path, key));
+            }
+        }
+        for (key, field) in fields {
+            let field_path = format!("{}.{}", path, key);
+            match schema["properties"].get(key) {
+                Some(field_schema) => check(field_schema, field, &field_path, errors),
+                None if schema["additionalProperties"] == Value::Bool(false) => {
+                    errors.push(format!("{} is not a known key", field_path))
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+// Every way the value breaks the schema, e.g. "config.rollout is not a known key", with
+// paths starting from `name`.
+pub fn validate(schema: &Value, value: &Value, name: &str) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    check(schema, value, name, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
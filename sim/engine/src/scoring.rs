@@ -0,0 +1,127 @@
+// How goods are turned into points, as GameRules.scoring specifies it. Rule variants that
+// differ only in scoring are a scorer each, rather than a change to the engine. A scorer is one
+// of
+//
+//   {type: "linear"}
+//       Every good is worth its category's preference value, through the utility curve. The
+//       default, and the scoring of the physical game with the constant curve.
+//   {type: "set_bonus", bonus: 10}
+//       Linear, plus `bonus` points for every complete set: one good of every category the
+//       player's card values.
+//   {type: "diminishing", factor: 0.8}
+//       Each good of a category is worth `factor` times the one before, the first being worth
+//       the preference value, in place of the utility curve.
+//   {type: "weighted", weights: {art: 2, cars: 0.5}}
+//       Linear, with each category's points multiplied by its weight. Categories left out
+//       weigh 1.
+//
+// Money always counts at face value. Scorers give points for goods held; PlayerState::score
+// takes off penalties and debt. Every player's state carries the rules' scorer, and
+// strategies can reach theirs through Observation::scorer, or value any goods for any player
+// whose preferences they can see with PlayerView::worth.
+use crate::category::MONEY;
+use crate::game::UtilityCurve;
+use crate::types::{GoodsSet, Preferences};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub trait Scorer {
+    // Points for holding `goods`, for a player with these preferences and utility curve.
+    fn worth(&self, preferences: &Preferences, curve: UtilityCurve, goods: &GoodsSet) -> f64;
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScoringModel {
+    #[default]
+    Linear,
+    SetBonus(SetBonus),
+    Diminishing(Diminishing),
+    Weighted(Weighted),
+}
+
+impl ScoringModel {
+    pub fn scorer(&self) -> &dyn Scorer {
+        match self {
+            ScoringModel::Linear => &Linear,
+            ScoringModel::SetBonus(scorer) => scorer,
+            ScoringModel::Diminishing(scorer) => scorer,
+            ScoringModel::Weighted(scorer) => scorer,
+        }
+    }
+}
+
+pub struct Linear;
+
+impl Scorer for Linear {
+    fn worth(&self, preferences: &Preferences, curve: UtilityCurve, goods: &GoodsSet) -> f64 {
+        goods
+            .ids()
+            .map(|(id, count)| preferences[id] * curve.value(id, count))
+            .sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SetBonus {
+    pub bonus: f64,
+}
+
+impl Scorer for SetBonus {
+    fn worth(&self, preferences: &Preferences, curve: UtilityCurve, goods: &GoodsSet) -> f64 {
+        let sets = preferences
+            .ids()
+            .filter(|&(id, value)| id != MONEY && value > 0.)
+            .map(|(id, _)| goods.get_id(id).unwrap_or(0.).floor())
+            .fold(f64::MAX, f64::min);
+        let sets = if sets == f64::MAX { 0. } else { sets.max(0.) };
+        Linear.worth(preferences, curve, goods) + self.bonus * sets
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Diminishing {
+    pub factor: f64,
+}
+
+impl Scorer for Diminishing {
+    fn worth(&self, preferences: &Preferences, _curve: UtilityCurve, goods: &GoodsSet) -> f64 {
+        // The geometric sum 1 + factor + factor^2 + ..., continued smoothly for fractional
+        // counts.
+        let goods_worth = |count: f64| {
+            if count <= 0. || self.factor >= 1. {
+                count
+            } else {
+                (1. - self.factor.max(0.).powf(count)) / (1. - self.factor.max(0.))
+            }
+        };
+        goods
+            .ids()
+            .map(|(id, count)| match id {
+                MONEY => preferences[id] * count,
+                _ => preferences[id] * goods_worth(count),
+            })
+            .sum()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Weighted {
+    // By name, as the rules are read before any game has category ids.
+    pub weights: BTreeMap<String, f64>,
+}
+
+impl Scorer for Weighted {
+    fn worth(&self, preferences: &Preferences, curve: UtilityCurve, goods: &GoodsSet) -> f64 {
+        goods
+            .ids()
+            .map(|(id, count)| {
+                let weight = match id {
+                    MONEY => 1.,
+                    _ => self.weights.get(id.name()).copied().unwrap_or(1.),
+                };
+                weight * preferences[id] * curve.value(id, count)
+            })
+            .sum()
+    }
+}
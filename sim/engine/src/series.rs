@@ -0,0 +1,257 @@
+// Matches: the same players play a series of games, as a group plays several games in a night,
+// with a match score carried from game to game. SimConfig.match_format turns a batch into
+// matches of consecutive runs, e.g.
+//
+//   match_format: {games: 3, scoring: "wins"}
+//
+// The lead rotates from game to game of a match, so in game k of a match seat k leads first,
+// wrapping around. Each game scores every player:
+//
+//   wins       - 1 for a win, shared by tied winners.
+//   placements - Points for their place, by placement_points or Borda points, as in the
+//                placement summary.
+//   scores     - Their final score.
+//
+// A match is best of `games`: it ends once no player can catch the leader in the games left,
+// unless play_out is set. Scores are unbounded, so matches scored by them always play out. The
+// player with the highest match score wins the match, the one with more game wins on a tie,
+// then the lowest seat. The last game of every match carries its MatchResult, and the batch
+// report says how often the match went to the winner of each game, and how often the match
+// winner came back from behind after the first game.
+//
+// Aborted games do not count toward a match: the next run replays the same game of the match,
+// with the same seat leading, so every match still rotates the lead evenly. A batch that ends
+// partway through a match reports the games of that match as unfinished_games, and leaves them
+// out of the match statistics.
+use crate::game::{GameResult, GameState, SimConfig};
+use crate::types::PlayerId;
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchScoring {
+    #[default]
+    Wins,
+    Placements,
+    Scores,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchFormat {
+    #[serde(deserialize_with = "deserialize_games")]
+    pub games: u32,
+    #[serde(default)]
+    pub scoring: MatchScoring,
+    #[serde(default)]
+    pub play_out: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchResult {
+    pub games: u32,
+    pub winner: PlayerId,
+    pub match_scores: Vec<f64>,
+    // Every game's winner, in order.
+    pub game_winners: Vec<PlayerId>,
+    // The match score after each game.
+    pub standings: Vec<Vec<f64>>,
+}
+
+// The match being played.
+#[derive(Serialize, Deserialize, Default)]
+pub struct MatchState {
+    game_winners: Vec<PlayerId>,
+    standings: Vec<Vec<f64>>,
+}
+
+// A match of no games would have no winner.
+fn deserialize_games<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom(
+            "match_format.games must be at least 1",
+        )),
+        games => Ok(games),
+    }
+}
+
+// Points for each place, first place first, as in the placement summary.
+fn place_points(config: &SimConfig, num_players: usize) -> Vec<f64> {
+    (0..num_players)
+        .map(|place| match &config.placement_points {
+            Some(points) => points.get(place).copied().unwrap_or(0.),
+            None => (num_players - 1 - place) as f64,
+        })
+        .collect()
+}
+
+impl MatchFormat {
+    // What a game is worth to each player.
+    fn game_points(&self, config: &SimConfig, result: &GameResult) -> Vec<f64> {
+        let num_players = result.scores.len();
+        let mut points = vec![0.; num_players];
+        match self.scoring {
+            MatchScoring::Wins => {
+                let winners = &result.placements[0];
+                if winners.contains(&result.winner) {
+                    for &pi in winners {
+                        points[pi] = 1. / winners.len() as f64;
+                    }
+                } else {
+                    points[result.winner] = 1.;
+                }
+            }
+            MatchScoring::Placements => {
+                let place_points = place_points(config, num_players);
+                let mut place = 0;
+                for group in &result.placements {
+                    let places = &place_points[place..place + group.len()];
+                    let mean = places.iter().sum::<f64>() / group.len() as f64;
+                    for &pi in group {
+                        points[pi] = mean;
+                    }
+                    place += group.len();
+                }
+            }
+            MatchScoring::Scores => points.copy_from_slice(&result.scores),
+        }
+        points
+    }
+
+    // The most a player can gain on another in one game, if bounded.
+    fn max_swing(&self, config: &SimConfig, num_players: usize) -> Option<f64> {
+        match self.scoring {
+            MatchScoring::Wins => Some(1.),
+            MatchScoring::Placements => {
+                let points = place_points(config, num_players);
+                let highest = points.iter().cloned().fold(f64::MIN, f64::max);
+                let lowest = points.iter().cloned().fold(f64::MAX, f64::min);
+                Some(highest - lowest)
+            }
+            MatchScoring::Scores => None,
+        }
+    }
+}
+
+impl MatchState {
+    // Seats the next game of the match, rotating the lead. Only finished games advance the
+    // rotation, so a game replacing an aborted one keeps its lead.
+    pub fn start_game(&self, game: &mut GameState) {
+        game.lead = self.game_winners.len() % game.players.len();
+    }
+
+    // Games played of a match that has not ended.
+    pub fn unfinished_games(&self) -> usize {
+        self.game_winners.len()
+    }
+
+    // Adds a game of the match, with the match's result if the game ended it.
+    pub fn add(
+        &mut self,
+        config: &SimConfig,
+        format: &MatchFormat,
+        result: &GameResult,
+    ) -> Option<MatchResult> {
+        let points = format.game_points(config, result);
+        let scores: Vec<f64> = match self.standings.last() {
+            Some(last) => last.iter().zip(&points).map(|(a, b)| a + b).collect(),
+            None => points,
+        };
+        self.game_winners.push(result.winner);
+        self.standings.push(scores.clone());
+
+        let played = self.game_winners.len() as u32;
+        let wins = |pi: PlayerId| self.game_winners.iter().filter(|&&w| w == pi).count();
+        let winner = (0..scores.len())
+            .max_by(|&a, &b| {
+                scores[a]
+                    .total_cmp(&scores[b])
+                    .then(wins(a).cmp(&wins(b)))
+                    .then(b.cmp(&a))
+            })
+            .unwrap();
+        let decided = !format.play_out
+            && format.max_swing(config, scores.len()).is_some_and(|swing| {
+                let left = format.games.saturating_sub(played) as f64;
+                (0..scores.len())
+                    .filter(|&pi| pi != winner)
+                    .all(|pi| scores[pi] + swing * left < scores[winner])
+            });
+        if played < format.games && !decided {
+            return None;
+        }
+        let state = std::mem::take(self);
+        Some(MatchResult {
+            games: played,
+            winner,
+            match_scores: scores,
+            game_winners: state.game_winners,
+            standings: state.standings,
+        })
+    }
+}
+
+// Matches over a batch.
+#[derive(Serialize, Deserialize, Default)]
+pub struct MatchTally {
+    matches: u64,
+    games: u64,
+    wins_by_seat: Vec<u64>,
+    // Matches won by the winner of each game, by game.
+    won_by_game_winner: Vec<u64>,
+    // Matches that reached each game.
+    reached: Vec<u64>,
+    // Matches won by a player who was not leading after the first game.
+    comebacks: u64,
+}
+
+impl MatchTally {
+    pub fn add(&mut self, result: &MatchResult) {
+        let num_players = result.match_scores.len();
+        self.matches += 1;
+        self.games += result.games as u64;
+        self.wins_by_seat.resize(num_players, 0);
+        self.wins_by_seat[result.winner] += 1;
+        let games = result.game_winners.len();
+        if self.reached.len() < games {
+            self.reached.resize(games, 0);
+            self.won_by_game_winner.resize(games, 0);
+        }
+        for (game, &winner) in result.game_winners.iter().enumerate() {
+            self.reached[game] += 1;
+            if winner == result.winner {
+                self.won_by_game_winner[game] += 1;
+            }
+        }
+        let first = &result.standings[0];
+        let leading = first.iter().cloned().fold(f64::MIN, f64::max);
+        if first[result.winner] < leading {
+            self.comebacks += 1;
+        }
+    }
+
+    pub fn comeback_rate(&self) -> f64 {
+        self.comebacks as f64 / self.matches.max(1) as f64
+    }
+
+    // The summary, given the match still being played when the batch ended.
+    pub fn summary(&self, in_progress: &MatchState) -> serde_json::Value {
+        let matches = self.matches.max(1) as f64;
+        serde_json::json!({
+            "matches": self.matches,
+            "unfinished_games": in_progress.unfinished_games(),
+            "mean_games": self.games as f64 / matches,
+            "match_win_rate_by_seat": self
+                .wins_by_seat
+                .iter()
+                .map(|&w| w as f64 / matches)
+                .collect::<Vec<f64>>(),
+            "game_winner_match_rate": self
+                .won_by_game_winner
+                .iter()
+                .zip(&self.reached)
+                .map(|(&won, &reached)| won as f64 / reached.max(1) as f64)
+                .collect::<Vec<f64>>(),
+            "comeback_rate": self.comeback_rate(),
+        })
+    }
+}
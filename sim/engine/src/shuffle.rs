@@ -0,0 +1,117 @@
+// How decks are shuffled. The physical game is shuffled by hand, and hand shuffles leave
+// decks far from uniformly mixed, so the imperfect models here let us check whether that
+// matters. New decks start sorted by category, as a fresh box of cards would.
+//
+//   perfect - A uniform random permutation (Fisher-Yates).
+//   riffle  - `passes` riffle shuffles under the Gilbert-Shannon-Reeds model: cut the deck
+//             binomially, then drop cards from each half with probability proportional to
+//             its size. Seven passes mix a 52 card deck well.
+//   overhand - `passes` overhand shuffles: the deck is split into packets averaging
+//              `packet_size` cards, whose order is reversed.
+//   pile    - `passes` pile shuffles: cards are dealt round-robin onto `piles` piles, which
+//             are stacked in a random order.
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShuffleModel {
+    #[default]
+    Perfect,
+    Riffle {
+        #[serde(default = "default_riffle_passes")]
+        passes: u32,
+    },
+    Overhand {
+        #[serde(default = "default_overhand_passes")]
+        passes: u32,
+        #[serde(default = "default_packet_size")]
+        packet_size: f64,
+    },
+    Pile {
+        #[serde(default = "default_pile_passes")]
+        passes: u32,
+        #[serde(default = "default_piles")]
+        piles: usize,
+    },
+}
+
+fn default_riffle_passes() -> u32 {
+    7
+}
+fn default_overhand_passes() -> u32 {
+    10
+}
+fn default_packet_size() -> f64 {
+    5.
+}
+fn default_pile_passes() -> u32 {
+    1
+}
+fn default_piles() -> usize {
+    5
+}
+
+pub fn shuffle<T>(cards: &mut Vec<T>, model: &ShuffleModel, rng: &mut impl Rng) {
+    match *model {
+        ShuffleModel::Perfect => cards.shuffle(rng),
+        ShuffleModel::Riffle { passes } => {
+            for _ in 0..passes {
+                riffle(cards, rng);
+            }
+        }
+        ShuffleModel::Overhand {
+            passes,
+            packet_size,
+        } => {
+            for _ in 0..passes {
+                overhand(cards, packet_size, rng);
+            }
+        }
+        ShuffleModel::Pile { passes, piles } => {
+            for _ in 0..passes {
+                pile(cards, piles, rng);
+            }
+        }
+    }
+}
+
+fn riffle<T>(cards: &mut Vec<T>, rng: &mut impl Rng) {
+    let cut = (0..cards.len()).filter(|_| rng.gen_bool(0.5)).count();
+    let mut right: Vec<T> = cards.split_off(cut);
+    let mut left: Vec<T> = std::mem::take(cards);
+    // Build the result from the bottom up, so take from the ends of the halves.
+    let mut result = Vec::with_capacity(left.len() + right.len());
+    while !left.is_empty() || !right.is_empty() {
+        let from_left = rng.gen_range(0, left.len() + right.len()) < left.len();
+        result.push(if from_left { left.pop() } else { right.pop() }.unwrap());
+    }
+    result.reverse();
+    *cards = result;
+}
+
+fn overhand<T>(cards: &mut Vec<T>, packet_size: f64, rng: &mut impl Rng) {
+    let cut_chance = (1. / packet_size.max(1.)).min(1.);
+    let mut packets: Vec<Vec<T>> = Vec::new();
+    let mut packet = Vec::new();
+    for card in cards.drain(..) {
+        if !packet.is_empty() && rng.gen_bool(cut_chance) {
+            packets.push(std::mem::take(&mut packet));
+        }
+        packet.push(card);
+    }
+    packets.push(packet);
+    *cards = packets.into_iter().rev().flatten().collect();
+}
+
+fn pile<T>(cards: &mut Vec<T>, piles: usize, rng: &mut impl Rng) {
+    let mut stacks: Vec<Vec<T>> = (0..piles.max(1)).map(|_| Vec::new()).collect();
+    let num_stacks = stacks.len();
+    for (i, card) in cards.drain(..).enumerate() {
+        stacks[i % num_stacks].push(card);
+    }
+    // Dealing onto a pile reverses it.
+    stacks.iter_mut().for_each(|stack| stack.reverse());
+    stacks.shuffle(rng);
+    *cards = stacks.into_iter().flatten().collect();
+}
@@ -0,0 +1,714 @@
+use average::*;
+use average::{Max, Min, Quantile, Variance};
+
+use crate::error::HedonicaError;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Stats from Stats::with_quantiles() also keep the median, p90 and p99, for the few that report
+// them. These are streaming P² estimates, so they take constant space but are only approximate,
+// and rough for fewer than a few dozen samples.
+pub struct Stats {
+    min: Min,
+    max: Max,
+    var: Variance,
+    quantiles: Option<Quantiles>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Quantiles {
+    median: Quantile,
+    p90: Quantile,
+    p99: Quantile,
+}
+
+impl Stats {
+    fn new() -> Stats {
+        Stats {
+            min: Min::default(),
+            max: Max::default(),
+            var: Variance::default(),
+            quantiles: None,
+        }
+    }
+
+    pub fn with_quantiles() -> Stats {
+        Stats {
+            quantiles: Some(Quantiles {
+                median: Quantile::new(0.5),
+                p90: Quantile::new(0.9),
+                p99: Quantile::new(0.99),
+            }),
+            ..Stats::new()
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        self.min.add(x);
+        self.max.add(x);
+        self.var.add(x);
+        if let Some(quantiles) = &mut self.quantiles {
+            quantiles.median.add(x);
+            quantiles.p90.add(x);
+            quantiles.p99.add(x);
+        }
+    }
+
+    fn min(&self) -> f64 {
+        self.min.min()
+    }
+
+    fn max(&self) -> f64 {
+        self.max.max()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.var.mean()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.var.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.var.is_empty()
+    }
+
+    // Standard error of the mean.
+    fn mean_error(&self) -> f64 {
+        self.var.error()
+    }
+
+    pub fn var(&self) -> f64 {
+        self.var.population_variance()
+    }
+
+    // None unless the stats were made with_quantiles().
+    pub fn median(&self) -> Option<f64> {
+        self.quantiles
+            .as_ref()
+            .map(|quantiles| quantiles.median.quantile())
+    }
+
+    pub fn p90(&self) -> Option<f64> {
+        self.quantiles
+            .as_ref()
+            .map(|quantiles| quantiles.p90.quantile())
+    }
+
+    pub fn p99(&self) -> Option<f64> {
+        self.quantiles
+            .as_ref()
+            .map(|quantiles| quantiles.p99.quantile())
+    }
+
+    // The two-sided normal confidence interval for the mean at the given level, e.g. 0.95.
+    pub fn mean_interval(&self, level: f64) -> (f64, f64) {
+        let margin = normal_quantile(0.5 + level / 2.) * self.mean_error();
+        (self.mean() - margin, self.mean() + margin)
+    }
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats::new()
+    }
+}
+
+impl_from_iterator!(Stats);
+
+impl serde::Serialize for Stats {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = if self.quantiles.is_some() { 7 } else { 4 };
+        let mut state = serializer.serialize_struct("Stats", fields)?;
+        state.serialize_field("min", &self.min())?;
+        state.serialize_field("max", &self.max())?;
+        state.serialize_field("mean", &self.mean())?;
+        state.serialize_field("var", &self.var())?;
+        if let Some(quantiles) = &self.quantiles {
+            state.serialize_field("median", &quantiles.median.quantile())?;
+            state.serialize_field("p90", &quantiles.p90.quantile())?;
+            state.serialize_field("p99", &quantiles.p99.quantile())?;
+        }
+        state.end()
+    }
+}
+
+// Stats serialize as their summary. These serialize them as they are kept, so a checkpoint can
+// restore them, with #[serde(with = "stats::state")] or "stats::state_vec". Min and max are
+// left out while there are no samples, since JSON has no infinities.
+#[derive(Serialize, Deserialize)]
+struct StatsState {
+    min: Option<f64>,
+    max: Option<f64>,
+    var: Variance,
+    #[serde(default)]
+    quantiles: Option<Quantiles>,
+}
+
+pub mod state {
+    use super::*;
+
+    pub fn serialize<S: serde::Serializer>(
+        stats: &Stats,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let kept = StatsState {
+            min: Some(stats.min()).filter(|_| !stats.is_empty()),
+            max: Some(stats.max()).filter(|_| !stats.is_empty()),
+            var: stats.var.clone(),
+            quantiles: stats.quantiles.clone(),
+        };
+        kept.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Stats, D::Error> {
+        let kept = StatsState::deserialize(deserializer)?;
+        Ok(Stats {
+            min: kept.min.map_or_else(Min::default, Min::from_value),
+            max: kept.max.map_or_else(Max::default, Max::from_value),
+            var: kept.var,
+            quantiles: kept.quantiles,
+        })
+    }
+}
+
+pub mod state_vec {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Kept<'a>(#[serde(with = "state")] &'a Stats);
+
+    #[derive(Deserialize)]
+    struct Restored(#[serde(with = "state")] Stats);
+
+    pub fn serialize<S: serde::Serializer>(
+        stats: &[Stats],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(stats.iter().map(Kept))
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Stats>, D::Error> {
+        let restored = Vec::<Restored>::deserialize(deserializer)?;
+        Ok(restored.into_iter().map(|Restored(stats)| stats).collect())
+    }
+}
+
+// How a Histogram bins its samples, e.g. SimConfig.turn_histogram:
+//   turn_histogram: {bin_width: 5, max: 100}
+// Bins are [k * bin_width, (k + 1) * bin_width). Samples at or past `max` share one open last
+// bin, so a few very long games don't stretch the histogram over hundreds of empty bins.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistogramConfig {
+    #[serde(default = "default_bin_width")]
+    pub bin_width: f64,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+fn default_bin_width() -> f64 {
+    1.
+}
+
+// Counts of samples by bin, kept as they are counted. summary() lists only the bins with
+// samples, so a sparse tail doesn't list a bin for every gap; a bin's `from` shows where it is.
+#[derive(Serialize, Deserialize)]
+pub struct Histogram {
+    config: HistogramConfig,
+    counts: BTreeMap<i64, u64>,
+    overflow: u64,
+}
+
+#[derive(Serialize)]
+pub struct HistogramBin {
+    pub from: f64,
+    // None for the open bin past the config's max.
+    pub to: Option<f64>,
+    pub count: u64,
+}
+
+impl Histogram {
+    pub fn new(config: HistogramConfig) -> Result<Histogram, HedonicaError> {
+        if config.bin_width.is_nan() || config.bin_width <= 0. {
+            return Err(HedonicaError::parse(
+                "histogram",
+                format!("bin_width {} is not positive", config.bin_width),
+            ));
+        }
+        Ok(Histogram {
+            config,
+            counts: BTreeMap::new(),
+            overflow: 0,
+        })
+    }
+
+    pub fn add(&mut self, x: f64) {
+        if self.config.max.is_some_and(|max| x >= max) {
+            self.overflow += 1;
+        } else {
+            *self
+                .counts
+                .entry((x / self.config.bin_width).floor() as i64)
+                .or_insert(0) += 1;
+        }
+    }
+
+    pub fn summary(&self) -> Vec<HistogramBin> {
+        let width = self.config.bin_width;
+        let mut bins: Vec<HistogramBin> = self
+            .counts
+            .iter()
+            .map(|(&bin, &count)| HistogramBin {
+                from: bin as f64 * width,
+                to: Some((bin + 1) as f64 * width),
+                count,
+            })
+            .collect();
+        if let (Some(max), true) = (self.config.max, self.overflow > 0) {
+            bins.push(HistogramBin {
+                from: max,
+                to: None,
+                count: self.overflow,
+            });
+        }
+        bins
+    }
+}
+
+// Interim looks at a running batch use a Haybittle-Peto style boundary: we only stop once an
+// estimate is this many standard errors outside the acceptable region, which keeps the overall
+// error rate close to that of a single test at the end of the batch.
+fn default_sequential_z() -> f64 {
+    3.
+}
+fn default_min_runs() -> u64 {
+    30
+}
+
+// The acceptable region for a batch's metrics. A batch whose metrics are confidently outside
+// the region is stopped early, since more samples would not change the verdict.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EarlyStopping {
+    #[serde(default = "default_min_runs")]
+    pub min_runs: u64,
+
+    #[serde(default = "default_sequential_z")]
+    pub z: f64,
+
+    #[serde(default)]
+    pub min_mean_turns: Option<f64>,
+
+    #[serde(default)]
+    pub max_mean_turns: Option<f64>,
+
+    // No player should win more often than this.
+    #[serde(default)]
+    pub max_win_rate: Option<f64>,
+}
+
+impl EarlyStopping {
+    // Returns the reason to stop, if the metrics so far are confidently unacceptable.
+    pub fn check(&self, turns: &Stats, wins_by_player: &BTreeMap<usize, i32>) -> Option<String> {
+        let runs = turns.len();
+        if runs < self.min_runs {
+            return None;
+        }
+
+        let mean = turns.mean();
+        let margin = self.z * turns.mean_error();
+        if let Some(min) = self.min_mean_turns {
+            if mean + margin < min {
+                return Some(format!("mean turns {:.1} < {}", mean, min));
+            }
+        }
+        if let Some(max) = self.max_mean_turns {
+            if mean - margin > max {
+                return Some(format!("mean turns {:.1} > {}", mean, max));
+            }
+        }
+        if let Some(max) = self.max_win_rate {
+            for (player, &wins) in wins_by_player {
+                let rate = wins as f64 / runs as f64;
+                let margin = self.z * (rate * (1. - rate) / runs as f64).sqrt();
+                if rate - margin > max {
+                    return Some(format!("player {} win rate {:.3} > {}", player, rate, max));
+                }
+            }
+        }
+        None
+    }
+}
+
+// Aggregates over runs that carry importance weights, e.g. from stratified preference deals.
+#[derive(Default)]
+pub struct WeightedResults {
+    total_weight: f64,
+    total_squared_weight: f64,
+    weighted_turns: f64,
+    wins_by_player: BTreeMap<usize, f64>,
+    runs_by_stratum: BTreeMap<String, i32>,
+}
+
+// WeightedResults as kept, for checkpoints, with #[serde(with = "WeightedResultsState")].
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "WeightedResults")]
+pub struct WeightedResultsState {
+    total_weight: f64,
+    total_squared_weight: f64,
+    weighted_turns: f64,
+    wins_by_player: BTreeMap<usize, f64>,
+    runs_by_stratum: BTreeMap<String, i32>,
+}
+
+impl WeightedResults {
+    pub fn add(&mut self, winner: usize, turns: f64, weight: f64, stratum: Option<&str>) {
+        self.total_weight += weight;
+        self.total_squared_weight += weight * weight;
+        self.weighted_turns += weight * turns;
+        *self.wins_by_player.entry(winner).or_insert(0.) += weight;
+        if let Some(stratum) = stratum {
+            *self.runs_by_stratum.entry(stratum.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    fn win_rates(&self) -> BTreeMap<usize, f64> {
+        self.wins_by_player
+            .iter()
+            .map(|(&player, &wins)| (player, wins / self.total_weight))
+            .collect()
+    }
+
+    // Kish's effective sample size.
+    fn effective_runs(&self) -> f64 {
+        self.total_weight * self.total_weight / self.total_squared_weight
+    }
+}
+
+impl serde::Serialize for WeightedResults {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("WeightedResults", 4)?;
+        state.serialize_field("win_rates", &self.win_rates())?;
+        state.serialize_field("mean_turns", &(self.weighted_turns / self.total_weight))?;
+        state.serialize_field("effective_runs", &self.effective_runs())?;
+        state.serialize_field("runs_by_stratum", &self.runs_by_stratum)?;
+        state.end()
+    }
+}
+
+// The standard normal quantile, by Acklam's rational approximation (relative error < 1.2e-9).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+    if p < 0.02425 {
+        tail((-2. * p.ln()).sqrt())
+    } else if p > 1. - 0.02425 {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    }
+}
+
+// The Wilson score interval for a rate of successes out of trials at the given level, which
+// stays inside [0, 1] and is sensible for rates near 0 or 1.
+pub fn rate_interval(successes: f64, trials: f64, level: f64) -> (f64, f64) {
+    if trials <= 0. {
+        return (0., 1.);
+    }
+    let z = normal_quantile(0.5 + level / 2.);
+    let rate = successes / trials;
+    let denominator = 1. + z * z / trials;
+    let center = (rate + z * z / (2. * trials)) / denominator;
+    let margin =
+        z * (rate * (1. - rate) / trials + z * z / (4. * trials * trials)).sqrt() / denominator;
+    (center - margin, center + margin)
+}
+
+// The exact two-sided binomial test of a rate of successes out of trials differing from `null`,
+// e.g. of one strategy beating another more or less often than half the time: the chance of a
+// count no likelier than the one seen. Small p-values mean the difference is unlikely to be
+// noise.
+pub fn binomial_p_value(successes: u64, trials: u64, null: f64) -> f64 {
+    if trials == 0 {
+        return 1.;
+    }
+    if null <= 0. || null >= 1. {
+        let certain = if null <= 0. { 0 } else { trials };
+        return if successes == certain { 1. } else { 0. };
+    }
+    let n = trials as f64;
+    let ln_probability = |k: u64| {
+        let k = k as f64;
+        ln_gamma(n + 1.) - ln_gamma(k + 1.) - ln_gamma(n - k + 1.)
+            + k * null.ln()
+            + (n - k) * (1. - null).ln()
+    };
+    // Counts as likely as the one seen, up to rounding, count toward the p-value.
+    let seen = ln_probability(successes) + 1e-7;
+    let p_value: f64 = (0..=trials)
+        .map(ln_probability)
+        .filter(|&ln_p| ln_p <= seen)
+        .map(f64::exp)
+        .sum();
+    p_value.min(1.)
+}
+
+// Sample sizes for two-sided tests at a significance level and power. A single batch is
+// tested against a fixed value, e.g. a fair win rate; comparing two batches, such as two
+// rule sets, needs runs_per_arm in each.
+pub struct PowerAnalysis {
+    pub alpha: f64,
+    pub power: f64,
+}
+
+#[derive(Serialize)]
+pub struct RequiredRuns {
+    pub runs: u64,
+    pub runs_per_arm: u64,
+}
+
+impl PowerAnalysis {
+    fn z_sum(&self) -> f64 {
+        normal_quantile(1. - self.alpha / 2.) + normal_quantile(self.power)
+    }
+
+    // Runs to detect a shift of `effect` in a mean with this per-run variance.
+    pub fn for_mean(&self, variance: f64, effect: f64) -> RequiredRuns {
+        let runs = (self.z_sum() / effect).powi(2) * variance;
+        RequiredRuns {
+            runs: runs.ceil() as u64,
+            runs_per_arm: (2. * runs).ceil() as u64,
+        }
+    }
+
+    // Runs to detect a win rate `effect` away from `rate`. The variance of a win is taken at
+    // whichever of the two rates is nearer a half, so the estimate errs on the large side.
+    pub fn for_rate(&self, rate: f64, effect: f64) -> RequiredRuns {
+        let worst = |p: f64| p.clamp(0., 1.) * (1. - p.clamp(0., 1.));
+        let variance = worst(rate)
+            .max(worst(rate + effect))
+            .max(worst(rate - effect));
+        self.for_mean(variance, effect)
+    }
+}
+
+// ln Γ(x), by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const G: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let series = G.iter().enumerate().fold(1.000000000190015, |sum, (i, g)| {
+        sum + g / (x + 1. + i as f64)
+    });
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+// The regularized upper incomplete gamma function Q(a, x), by its series below a + 1 and its
+// continued fraction above.
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0. {
+        return 1.;
+    }
+    let scale = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1. {
+        let (mut term, mut sum, mut n) = (1. / a, 1. / a, a);
+        for _ in 0..500 {
+            n += 1.;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        1. - sum * scale
+    } else {
+        // Lentz's method.
+        let tiny = 1e-300;
+        let mut b = x + 1. - a;
+        let mut c = 1. / tiny;
+        let mut d = 1. / b;
+        let mut h = d;
+        for i in 1..500 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.;
+            d = an * d + b;
+            d = if d.abs() < tiny { tiny } else { d };
+            c = b + an / c;
+            c = if c.abs() < tiny { tiny } else { c };
+            d = 1. / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.).abs() < 1e-15 {
+                break;
+            }
+        }
+        h * scale
+    }
+}
+
+// The chance of a chi-squared statistic at least this large, with this many degrees of freedom.
+pub fn chi_squared_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    gamma_q(degrees_of_freedom as f64 / 2., statistic / 2.)
+}
+
+// The two-sided p-value of two samples' means differing, by Welch's z-test, from each sample's
+// (mean, variance, size). A squared standard normal is chi-squared with one degree of freedom.
+pub fn mean_difference_p_value(first: (f64, f64, u64), second: (f64, f64, u64)) -> f64 {
+    let (mean_a, var_a, n_a) = first;
+    let (mean_b, var_b, n_b) = second;
+    let error = (var_a / n_a.max(1) as f64 + var_b / n_b.max(1) as f64).sqrt();
+    if error == 0. {
+        return if mean_a == mean_b { 1. } else { 0. };
+    }
+    chi_squared_p_value(((mean_a - mean_b) / error).powi(2), 1)
+}
+
+// The p-value of a chi-squared test of homogeneity, of whether the rows of a table of counts,
+// e.g. wins by seat under each of two rule sets, come from the same distribution. Columns with
+// no counts are left out.
+pub fn homogeneity_p_value(counts: &[Vec<u64>]) -> f64 {
+    let count = |row: &Vec<u64>, column: usize| row.get(column).copied().unwrap_or(0) as f64;
+    let rows: Vec<&Vec<u64>> = counts
+        .iter()
+        .filter(|row| row.iter().sum::<u64>() > 0)
+        .collect();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let columns: Vec<usize> = (0..width)
+        .filter(|&c| rows.iter().any(|row| count(row, c) > 0.))
+        .collect();
+    if rows.len() < 2 || columns.len() < 2 {
+        return 1.;
+    }
+    let total: f64 = rows.iter().flat_map(|row| row.iter()).sum::<u64>() as f64;
+    let mut statistic = 0.;
+    for row in &rows {
+        let row_total = row.iter().sum::<u64>() as f64;
+        for &c in &columns {
+            let expected = row_total * rows.iter().map(|row| count(row, c)).sum::<f64>() / total;
+            statistic += (count(row, c) - expected).powi(2) / expected;
+        }
+    }
+    chi_squared_p_value(statistic, (rows.len() - 1) * (columns.len() - 1))
+}
+
+// Wins and scores by seat, with a chi-squared test of whether every seat wins equally often.
+// Compare seats with the same strategy, otherwise this measures the strategies too.
+pub struct SeatFairness {
+    wins: Vec<u64>,
+    scores: Vec<Stats>,
+}
+
+// SeatFairness as kept, for checkpoints, with #[serde(with = "SeatFairnessState")].
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "SeatFairness")]
+pub struct SeatFairnessState {
+    wins: Vec<u64>,
+    #[serde(with = "state_vec")]
+    scores: Vec<Stats>,
+}
+
+impl SeatFairness {
+    pub fn new(num_players: usize) -> SeatFairness {
+        SeatFairness {
+            wins: vec![0; num_players],
+            scores: (0..num_players).map(|_| Stats::default()).collect(),
+        }
+    }
+
+    pub fn add(&mut self, winner: usize, scores: &[f64]) {
+        self.wins[winner] += 1;
+        self.scores
+            .iter_mut()
+            .zip(scores)
+            .for_each(|(stats, &score)| stats.add(score));
+    }
+
+    fn chi_squared(&self) -> f64 {
+        let games: u64 = self.wins.iter().sum();
+        let expected = games as f64 / self.wins.len() as f64;
+        self.wins
+            .iter()
+            .map(|&w| (w as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+}
+
+impl serde::Serialize for SeatFairness {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let games = self.wins.iter().sum::<u64>().max(1) as f64;
+        let degrees_of_freedom = self.wins.len() - 1;
+        let chi_squared = self.chi_squared();
+        let mut state = serializer.serialize_struct("SeatFairness", 5)?;
+        state.serialize_field(
+            "win_rate_by_seat",
+            &self
+                .wins
+                .iter()
+                .map(|&w| w as f64 / games)
+                .collect::<Vec<f64>>(),
+        )?;
+        state.serialize_field(
+            "mean_score_by_seat",
+            &self.scores.iter().map(Stats::mean).collect::<Vec<f64>>(),
+        )?;
+        state.serialize_field(
+            "score_error_by_seat",
+            &self
+                .scores
+                .iter()
+                .map(Stats::mean_error)
+                .collect::<Vec<f64>>(),
+        )?;
+        state.serialize_field("chi_squared", &chi_squared)?;
+        state.serialize_field(
+            "p_value",
+            &chi_squared_p_value(chi_squared, degrees_of_freedom),
+        )?;
+        state.end()
+    }
+}
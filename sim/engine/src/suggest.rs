@@ -0,0 +1,112 @@
+// Trades worth proposing, for hints, user interfaces and bots.
+//
+// Suggestions are one-good trades the viewer could propose now: a swap of one of their goods
+// for one of another player's, or one good bought or sold for money at the midpoint price.
+// Only trades that both sides are expected to gain from are suggested, judged by what the
+// viewer can see of the other side's values.
+use crate::pricing;
+use crate::types::*;
+use crate::view::PlayerView;
+use serde::Serialize;
+
+#[derive(Serialize, Clone)]
+pub struct TradeSuggestion {
+    pub trade: Trade,
+    // Expected score changes for the viewer, who proposes the trade, and for the other side.
+    pub gain: f64,
+    pub partner_gain: f64,
+}
+
+impl TradeSuggestion {
+    pub fn surplus(&self) -> f64 {
+        self.gain + self.partner_gain
+    }
+}
+
+fn single(category: CategoryId, count: f64) -> GoodsSet {
+    let mut goods = GoodsSet::new();
+    goods.insert(category, count);
+    goods
+}
+
+// Every mutually beneficial trade the viewer could propose to `other` within the rules'
+// trade_limits, in no particular order.
+pub fn trades_with(view: &PlayerView, other: PlayerId) -> Vec<TradeSuggestion> {
+    let me = view.viewer();
+    let game = view.game();
+    if other == me || game.player_state(other).eliminated {
+        return Vec::new();
+    }
+    let turn = game.current_turn;
+    let tradable = |category: CategoryId| {
+        category != MONEY
+            && game.player_state(me).can_trade(category, turn)
+            && game.player_state(other).can_trade(category, turn)
+    };
+    let mine = view.inventory(me);
+    let theirs = view.inventory(other);
+
+    let mut trades = Vec::new();
+    for (wanted, count) in theirs.ids() {
+        if count < 1. || !tradable(wanted) {
+            continue;
+        }
+        let price = pricing::midpoint_price(view, me, other, wanted.name());
+        if mine[MONEY] >= price {
+            trades.push(
+                Trade::new(me, other, GoodsSet::new(), single(wanted, 1.)).with_payment(price),
+            );
+        }
+        for (offered, held) in mine.ids() {
+            if held >= 1. && tradable(offered) && offered != wanted {
+                trades.push(Trade::new(
+                    me,
+                    other,
+                    single(offered, 1.),
+                    single(wanted, 1.),
+                ));
+            }
+        }
+    }
+    for (offered, held) in mine.ids() {
+        if held < 1. || !tradable(offered) {
+            continue;
+        }
+        let price = pricing::midpoint_price(view, other, me, offered.name());
+        if theirs[MONEY] >= price {
+            trades.push(
+                Trade::new(me, other, single(offered, 1.), GoodsSet::new()).with_payment(-price),
+            );
+        }
+    }
+
+    trades
+        .into_iter()
+        .filter(|trade| game.trade_size_allowed(trade))
+        .map(|trade| {
+            let (gain, partner_gain) = pricing::estimated_gains(view, &trade);
+            TradeSuggestion {
+                trade,
+                gain,
+                partner_gain,
+            }
+        })
+        .filter(|suggestion| suggestion.gain > 0. && suggestion.partner_gain > 0.)
+        .collect()
+}
+
+// The k mutually beneficial trades with the largest combined expected gain, across every
+// other player. Ties go to the trade better for the viewer.
+pub fn suggest_trades(view: &PlayerView, k: usize) -> Vec<TradeSuggestion> {
+    let mut suggestions: Vec<TradeSuggestion> = (0..view.game().players.len())
+        .flat_map(|other| trades_with(view, other))
+        .collect();
+    suggestions.sort_by(|a, b| {
+        b.surplus()
+            .partial_cmp(&a.surplus())
+            .unwrap()
+            .then_with(|| b.gain.partial_cmp(&a.gain).unwrap())
+    });
+    suggestions.truncate(k);
+    suggestions
+}
@@ -0,0 +1,353 @@
+// Wall-clock time spent in each strategy's decisions, and SimConfig.decision_budget_millis.
+//
+// instrument() wraps every seat's strategy so each decision is timed. A decision that takes
+// longer than the budget is thrown away and the engine's default is played instead, as for a
+// strategy without the capability: no proposals, rejecting every trade, and so on. The budget
+// cannot interrupt a strategy, so a slow decision still takes as long as it takes, but a bot
+// gains nothing by thinking past it, and tournaments between slow bots stay comparable.
+// Setting up a strategy, between games and before the batch, is not timed.
+//
+// A decision that panics, e.g. a remote bot that disconnected, aborts the game, and the next
+// game likely too. With SimConfig.resign_failing set, the seat resigns instead, and the game
+// goes on without it: a panicking decision, or max_over_budget decisions over the budget in
+// one game, resign the seat at the start of the next turn, and the defaults are played for it
+// until then. The seat plays again from the next game.
+use crate::game::{self, GameRules, SimConfig};
+use crate::orderbook::Order;
+use crate::player::{Capability, PlayerStrategy};
+use crate::replay::Decision;
+use crate::stats::Stats;
+use crate::types::*;
+use crate::view::Observation;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// One seat's decision times.
+#[derive(Serialize)]
+pub struct DecisionTimes {
+    pub millis: Stats,
+    pub total_seconds: f64,
+    // Decisions replaced by the default for taking longer than the budget.
+    pub over_budget: u64,
+    // With resign_failing, decisions that panicked, and games the seat resigned.
+    pub failures: u64,
+    pub resignations: u64,
+}
+
+impl Default for DecisionTimes {
+    fn default() -> DecisionTimes {
+        DecisionTimes {
+            millis: Stats::with_quantiles(),
+            total_seconds: 0.,
+            over_budget: 0,
+            failures: 0,
+            resignations: 0,
+        }
+    }
+}
+
+// When a failing strategy's seat resigns, as SimConfig.resign_failing.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ResignRules {
+    // Decisions over the budget in one game before the seat resigns. Unset, slow decisions
+    // never resign it.
+    #[serde(default)]
+    pub max_over_budget: Option<u32>,
+}
+
+// The trait's defaults, played in place of decisions over the budget.
+struct Defaults;
+
+impl PlayerStrategy for Defaults {
+    fn init(&mut self, _player_id: PlayerId, _value: &serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn reset(&mut self) {}
+
+    fn propose_trades_as_lead(&mut self, _game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        HashMap::new()
+    }
+
+    fn propose_trade_as_non_lead(&mut self, _game_state: &Observation) -> Option<Trade> {
+        None
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        vec![false; game_state.current_trade_proposals.len()]
+    }
+
+    fn accept_trades_as_non_lead(&mut self, _game_state: &Observation, _trade: &Trade) -> bool {
+        false
+    }
+}
+
+struct Timed {
+    inner: Box<dyn PlayerStrategy>,
+    budget: Option<Duration>,
+    resign: Option<ResignRules>,
+    times: Arc<Mutex<DecisionTimes>>,
+    // This game's decisions over the budget, and whether the seat has resigned it.
+    over_budget: u32,
+    resigned: bool,
+    // Why the last decision was the default rather than the strategy's, if it was.
+    defaulted: Option<&'static str>,
+}
+
+impl Timed {
+    // The strategy's decision, or the default if it took longer than the budget, failed, or
+    // the seat has resigned.
+    fn decide<T>(
+        &mut self,
+        decision: impl FnOnce(&mut dyn PlayerStrategy) -> T,
+        default: impl FnOnce(&mut dyn PlayerStrategy) -> T,
+    ) -> T {
+        if self.resigned {
+            self.defaulted = Some("the seat has resigned");
+            return default(&mut Defaults);
+        }
+        self.defaulted = None;
+        let start = Instant::now();
+        let inner = &mut self.inner;
+        let decided = match &self.resign {
+            Some(_) => panic::catch_unwind(AssertUnwindSafe(|| decision(inner.as_mut())))
+                .map_err(|payload| game::panic_message(&*payload)),
+            None => Ok(decision(inner.as_mut())),
+        };
+        let elapsed = start.elapsed();
+        let over_budget = self.budget.is_some_and(|budget| elapsed > budget);
+        let mut times = self.times.lock().unwrap();
+        times.millis.add(elapsed.as_secs_f64() * 1000.);
+        times.total_seconds += elapsed.as_secs_f64();
+        let decided = match decided {
+            Ok(decided) => decided,
+            Err(message) => {
+                crate::warn!("A strategy failed and resigns its seat: {}", message);
+                times.failures += 1;
+                times.resignations += 1;
+                self.resigned = true;
+                self.defaulted = Some("the strategy failed");
+                drop(times);
+                return default(&mut Defaults);
+            }
+        };
+        if !over_budget {
+            return decided;
+        }
+        times.over_budget += 1;
+        self.over_budget += 1;
+        let max = self
+            .resign
+            .as_ref()
+            .and_then(|resign| resign.max_over_budget);
+        if max.is_some_and(|max| self.over_budget >= max) {
+            crate::warn!(
+                "A strategy went over the decision budget {} times and resigns its seat",
+                self.over_budget
+            );
+            times.resignations += 1;
+            self.resigned = true;
+        }
+        self.defaulted = Some("the strategy went over the decision budget");
+        drop(times);
+        default(&mut Defaults)
+    }
+}
+
+impl PlayerStrategy for Timed {
+    fn init(&mut self, player_id: PlayerId, value: &serde_json::Value) -> Result<(), String> {
+        self.inner.init(player_id, value)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.over_budget = 0;
+        self.resigned = false;
+        self.defaulted = None;
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        self.inner.config_schema()
+    }
+
+    fn set_rules(&mut self, rules: &GameRules) {
+        self.inner.set_rules(rules);
+    }
+
+    fn load_profile(&mut self, profile: &serde_json::Value, seat_labels: &[String]) {
+        self.inner.load_profile(profile, seat_labels);
+    }
+
+    fn save_profile(&self) -> Option<serde_json::Value> {
+        self.inner.save_profile()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    fn max_players(&self) -> Option<usize> {
+        self.inner.max_players()
+    }
+
+    fn min_players(&self) -> Option<usize> {
+        self.inner.min_players()
+    }
+
+    fn choose_preferences(&mut self, hand: &[Preferences]) -> usize {
+        self.decide(
+            |s| s.choose_preferences(hand),
+            |d| d.choose_preferences(hand),
+        )
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        self.decide(
+            |s| s.propose_trades_as_lead(game_state),
+            |d| d.propose_trades_as_lead(game_state),
+        )
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        self.decide(
+            |s| s.propose_trade_as_non_lead(game_state),
+            |d| d.propose_trade_as_non_lead(game_state),
+        )
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        self.decide(
+            |s| s.accept_trades_as_lead(game_state),
+            |d| d.accept_trades_as_lead(game_state),
+        )
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        self.decide(
+            |s| s.accept_trades_as_non_lead(game_state, trade),
+            |d| d.accept_trades_as_non_lead(game_state, trade),
+        )
+    }
+
+    fn answer_trades_as_lead(&mut self, game_state: &Observation) -> Vec<Acceptance> {
+        self.decide(
+            |s| s.answer_trades_as_lead(game_state),
+            |d| d.answer_trades_as_lead(game_state),
+        )
+    }
+
+    fn answer_trade_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> Acceptance {
+        self.decide(
+            |s| s.answer_trade_as_non_lead(game_state, trade),
+            |d| d.answer_trade_as_non_lead(game_state, trade),
+        )
+    }
+
+    fn counter_offer_as_lead(&mut self, game_state: &Observation, trade: &Trade) -> Option<Trade> {
+        self.decide(
+            |s| s.counter_offer_as_lead(game_state, trade),
+            |d| d.counter_offer_as_lead(game_state, trade),
+        )
+    }
+
+    fn counter_offer_as_non_lead(
+        &mut self,
+        game_state: &Observation,
+        trade: &Trade,
+    ) -> Option<Trade> {
+        self.decide(
+            |s| s.counter_offer_as_non_lead(game_state, trade),
+            |d| d.counter_offer_as_non_lead(game_state, trade),
+        )
+    }
+
+    fn loan_action(&mut self, game_state: &Observation) -> LoanAction {
+        self.decide(|s| s.loan_action(game_state), |d| d.loan_action(game_state))
+    }
+
+    fn buy_insurance(&mut self, game_state: &Observation) -> bool {
+        self.decide(
+            |s| s.buy_insurance(game_state),
+            |d| d.buy_insurance(game_state),
+        )
+    }
+
+    fn choose_action(&mut self, game_state: &Observation) -> TurnAction {
+        self.decide(
+            |s| s.choose_action(game_state),
+            |d| d.choose_action(game_state),
+        )
+    }
+
+    fn place_orders(&mut self, game_state: &Observation) -> Option<Vec<Order>> {
+        self.decide(
+            |s| s.place_orders(game_state),
+            |d| d.place_orders(game_state),
+        )
+    }
+
+    fn bid(&mut self, game_state: &Observation, good: &Good) -> f64 {
+        self.decide(|s| s.bid(game_state, good), |d| d.bid(game_state, good))
+    }
+
+    fn raise_bid(&mut self, game_state: &Observation, good: &Good, price: f64) -> bool {
+        self.decide(
+            |s| s.raise_bid(game_state, good, price),
+            |d| d.raise_bid(game_state, good, price),
+        )
+    }
+
+    fn name_wild_category(&mut self, game_state: &Observation, good: &Good) -> CategoryId {
+        self.decide(
+            |s| s.name_wild_category(game_state, good),
+            |d| d.name_wild_category(game_state, good),
+        )
+    }
+
+    fn choose_steal(&mut self, game_state: &Observation) -> Option<(PlayerId, CategoryId)> {
+        self.decide(
+            |s| s.choose_steal(game_state),
+            |d| d.choose_steal(game_state),
+        )
+    }
+
+    fn resigns(&self) -> bool {
+        self.resigned || self.inner.resigns()
+    }
+
+    fn explain_last_decision(&self, decision: Decision) -> Option<String> {
+        match self.defaulted {
+            Some(reason) => Some(format!("The engine's default, since {}", reason)),
+            None => self.inner.explain_last_decision(decision),
+        }
+    }
+}
+
+// Wraps each seat's strategy to time its decisions, hold them to the config's budget and resign
+// it if it fails, returning each seat's times.
+pub fn instrument(
+    players: &mut [Box<dyn PlayerStrategy>],
+    config: &SimConfig,
+) -> Vec<Arc<Mutex<DecisionTimes>>> {
+    let budget = config.decision_budget_millis.map(Duration::from_millis);
+    players
+        .iter_mut()
+        .map(|player| {
+            let times = Arc::new(Mutex::new(DecisionTimes::default()));
+            let inner = std::mem::replace(player, Box::new(Defaults));
+            *player = Box::new(Timed {
+                inner,
+                budget,
+                resign: config.resign_failing.clone(),
+                times: times.clone(),
+                over_budget: 0,
+                resigned: false,
+                defaulted: None,
+            });
+            times
+        })
+        .collect()
+}
@@ -0,0 +1,44 @@
+// Value flows between seats from completed trades.
+//
+// Every completed trade, including order book fills, is a value flow between two seats: each
+// side gains the points it receives less the points it gives, by its own preference card.
+use crate::game::GameState;
+use crate::player::score_delta;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TradeFlow {
+    pub proposer: PlayerId,
+    pub accepter: PlayerId,
+    pub proposer_gain: f64,
+    pub accepter_gain: f64,
+}
+
+pub fn trade_flows(game: &GameState) -> Vec<TradeFlow> {
+    let gain = |player: PlayerId, received: &GoodsSet, given: &GoodsSet| {
+        score_delta(game.player_state(player).preferences(), received, given)
+    };
+    let trades = game.completed_trades().map(|trade| {
+        let (proposer_gives, accepter_gives) = (trade.proposer_gives(), trade.accepter_gives());
+        TradeFlow {
+            proposer: trade.proposer,
+            accepter: trade.accepter,
+            proposer_gain: gain(trade.proposer, &accepter_gives, &proposer_gives),
+            accepter_gain: gain(trade.accepter, &proposer_gives, &accepter_gives),
+        }
+    });
+    let fills = game.fills.iter().map(|fill| {
+        let mut goods = GoodsSet::new();
+        goods.insert(fill.category, fill.quantity);
+        let mut money = GoodsSet::new();
+        money.insert(MONEY, fill.quantity * fill.price);
+        TradeFlow {
+            proposer: fill.buyer,
+            accepter: fill.seller,
+            proposer_gain: gain(fill.buyer, &goods, &money),
+            accepter_gain: gain(fill.seller, &money, &goods),
+        }
+    });
+    trades.chain(fills).collect()
+}
@@ -0,0 +1,300 @@
+use crate::cards::GoodEffect;
+pub use crate::category::{CategoryId, CategoryMap, MONEY};
+use crate::game::TradeError;
+use serde::{Deserialize, Serialize, Serializer};
+
+// Keyed by category name, and stored by interned category id. See category.rs.
+pub type Preferences = CategoryMap;
+pub type GoodsSet = CategoryMap;
+pub type PlayerId = usize;
+
+// A card in the deck. Plain goods are written as their category name, and special cards as
+// an object, e.g. {category: "art", amount: 2} or {category: "draw_two", effect: "draw_two"}.
+// See cards.rs.
+#[derive(Deserialize, Clone)]
+#[serde(from = "GoodSpec")]
+pub struct Good {
+    // For cards with an effect, the effect's name.
+    pub category: CategoryId,
+    // How much of its category the card is worth, e.g. 2 for a double or 1.5 for fine goods.
+    pub amount: f64,
+    pub effect: Option<GoodEffect>,
+}
+
+impl Good {
+    pub fn is_plain(&self) -> bool {
+        self.amount == 1. && self.effect.is_none()
+    }
+}
+
+impl From<CategoryId> for Good {
+    fn from(category: CategoryId) -> Good {
+        Good {
+            category,
+            amount: 1.,
+            effect: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GoodSpec {
+    Plain(String),
+    Special {
+        category: String,
+        #[serde(default = "default_amount")]
+        amount: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        effect: Option<GoodEffect>,
+    },
+}
+
+fn default_amount() -> f64 {
+    1.
+}
+
+impl From<GoodSpec> for Good {
+    fn from(spec: GoodSpec) -> Good {
+        match spec {
+            GoodSpec::Plain(category) => Good::from(CategoryId::intern(&category)),
+            GoodSpec::Special {
+                category,
+                amount,
+                effect,
+            } => Good {
+                category: CategoryId::intern(&category),
+                amount,
+                effect,
+            },
+        }
+    }
+}
+
+// An amount of money, kept apart from goods.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, PartialOrd, Debug, Default)]
+pub struct Money(pub f64);
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Trade {
+    pub proposer: PlayerId,
+    pub accepter: PlayerId,
+    pub from_proposor: GoodsSet,
+    pub from_acceptor: GoodsSet,
+
+    // Paid by the proposer to the accepter along with the goods. A negative payment is paid by
+    // the accepter. Older clients put money in the goods sets instead, which still works.
+    #[serde(default)]
+    pub money: Money,
+
+    // How many counter-offers came before this one in its negotiation. Opening proposals are 0.
+    #[serde(default)]
+    pub counter_offers: u32,
+}
+
+// An answer to a proposal, with the rules' partial_acceptance.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Acceptance {
+    Accept,
+    Reject,
+    // Take only these of the goods the proposer offers, giving back a pro-rated share of what
+    // was asked. See Trade::partial.
+    Partial(GoodsSet),
+}
+
+impl From<bool> for Acceptance {
+    fn from(accept: bool) -> Acceptance {
+        if accept {
+            Acceptance::Accept
+        } else {
+            Acceptance::Reject
+        }
+    }
+}
+
+// A lead's decision about the bank at the start of their turn.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LoanAction {
+    None,
+    Borrow,
+    // Repay the oldest outstanding loan early.
+    Repay,
+}
+
+// An action the lead can spend action points on, when the rules use them.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TurnAction {
+    // Draw a good from the deck.
+    Draw,
+    // One round of proposals from the lead, then one from everyone else.
+    TradeRound,
+    // Sell a good to the bank for money.
+    Convert { category: CategoryId },
+    EndTurn,
+}
+
+impl Trade {
+    // A trade of goods. Money in either goods set is taken out and becomes the payment.
+    pub fn new(
+        proposer: PlayerId,
+        accepter: PlayerId,
+        mut from_proposor: GoodsSet,
+        mut from_acceptor: GoodsSet,
+    ) -> Trade {
+        let payment =
+            from_proposor.remove(&MONEY).unwrap_or(0.) - from_acceptor.remove(&MONEY).unwrap_or(0.);
+        Trade {
+            proposer,
+            accepter,
+            from_proposor,
+            from_acceptor,
+            money: Money(0.),
+            counter_offers: 0,
+        }
+        .with_payment(payment)
+    }
+
+    // The same trade with the proposer paying `amount`. A negative amount is paid to them.
+    pub fn with_payment(mut self, amount: f64) -> Trade {
+        self.money = Money(amount);
+        self
+    }
+
+    // Everything the proposer gives, with their share of the payment as money.
+    pub fn proposer_gives(&self) -> GoodsSet {
+        with_money(&self.from_proposor, self.money.0.max(0.))
+    }
+
+    // Everything the accepter gives, with their share of the payment as money.
+    pub fn accepter_gives(&self) -> GoodsSet {
+        with_money(&self.from_acceptor, (-self.money.0).max(0.))
+    }
+
+    // What `player` must hold to carry the trade out: what they give, and what they take with a
+    // negative amount, which moves the other way.
+    pub fn owed_by(&self, player: PlayerId) -> GoodsSet {
+        let (gives, receives) = if player == self.proposer {
+            (self.proposer_gives(), self.accepter_gives())
+        } else {
+            (self.accepter_gives(), self.proposer_gives())
+        };
+        let mut owed = GoodsSet::new();
+        for (category, amount) in gives.ids().filter(|&(_, a)| a > 0.) {
+            *owed.entry_id(category).or_insert(0.) += amount;
+        }
+        for (category, amount) in receives.ids().filter(|&(_, a)| a < 0.) {
+            *owed.entry_id(category).or_insert(0.) -= amount;
+        }
+        owed
+    }
+
+    // The goods categories that change hands, not counting money.
+    pub fn goods_categories(&self) -> impl Iterator<Item = CategoryId> + '_ {
+        self.from_proposor
+            .ids()
+            .chain(self.from_acceptor.ids())
+            .filter(|&(category, amount)| category != MONEY && amount != 0.)
+            .map(|(category, _)| category)
+    }
+
+    // The trade with the accepter taking only `taken` of the proposer's goods, and the
+    // accepter's goods and the payment scaled by the share of the proposer's goods taken, e.g.
+    // taking two of three art offered for 3 money and 3 food leaves 2 art for 2 money and 2
+    // food. Goods change hands whole: only whole goods the proposer offered can be taken, and
+    // the accepter's scaled goods are rounded, with what rounding takes off or adds paid for in
+    // money at good_price a good. Money cannot be taken, as the payment is scaled with the rest.
+    pub fn partial(&self, taken: &GoodsSet, good_price: f64) -> Result<Trade, TradeError> {
+        for (category, amount) in taken.ids() {
+            if amount.fract() != 0. {
+                return Err(TradeError::InvalidAmount { category, amount });
+            }
+            let offered = if category == MONEY {
+                0.
+            } else {
+                self.from_proposor.get_id(category).unwrap_or(0.)
+            };
+            if !(0. ..=offered).contains(&amount) {
+                return Err(TradeError::NotOffered {
+                    category,
+                    taken: amount,
+                    offered,
+                });
+            }
+        }
+        let units = |goods: &GoodsSet| -> f64 {
+            goods
+                .ids()
+                .filter(|&(category, _)| category != MONEY)
+                .map(|(_, amount)| amount)
+                .sum()
+        };
+        let offered = units(&self.from_proposor);
+        let share = if offered > 0. {
+            units(taken) / offered
+        } else {
+            0.
+        };
+        // What the accepter keeps back by rounding their goods down, or gives over by rounding
+        // them up, in money.
+        let mut rounded_off = 0.;
+        let mut from_acceptor = GoodsSet::new();
+        for (category, amount) in self.from_acceptor.ids() {
+            let scaled = amount * share;
+            let whole = if category == MONEY {
+                scaled
+            } else {
+                scaled.round()
+            };
+            rounded_off += (scaled - whole) * good_price;
+            from_acceptor.insert(category, whole);
+        }
+        let mut from_proposor = taken.clone();
+        if let Some(money) = self.from_proposor.get_id(MONEY) {
+            from_proposor.insert(MONEY, money * share);
+        }
+        Ok(Trade {
+            from_proposor,
+            from_acceptor,
+            money: Money(self.money.0 * share - rounded_off),
+            ..self.clone()
+        })
+    }
+
+    // How many goods change hands, both sides together, not counting money.
+    pub fn goods_count(&self) -> f64 {
+        self.from_proposor
+            .ids()
+            .chain(self.from_acceptor.ids())
+            .filter(|&(category, _)| category != MONEY)
+            .map(|(_, amount)| amount.abs())
+            .sum()
+    }
+}
+
+fn with_money(goods: &GoodsSet, amount: f64) -> GoodsSet {
+    let mut goods = goods.clone();
+    if amount > 0. {
+        *goods.entry_id(MONEY).or_insert(0.) += amount;
+    }
+    goods
+}
+
+impl Serialize for Good {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.is_plain() {
+            return serializer.serialize_str(self.category.name());
+        }
+        GoodSpec::Special {
+            category: self.category.name().clone(),
+            amount: self.amount,
+            effect: self.effect,
+        }
+        .serialize(serializer)
+    }
+}
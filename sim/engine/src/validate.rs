@@ -0,0 +1,519 @@
+// Checks that a sim config and game rules which parse can also be played, for the
+// validate-config subcommand and before a batch is played. Many of these settings would only
+// fail partway through a batch, in whatever first needs them; checked up front, each problem
+// names the --set key to change.
+//
+// Errors are settings no game can be played with: too many players for the starting money
+// table, preference cards the generator or stratified sampling cannot deal, a deck with nothing
+// in it, a max_advantage_spread no deal is within, or a victory_threshold that no player could
+// score even holding every good and all the money.
+// Warnings are settings that play, but likely not as meant, such as a deck too small for
+// players to draw the goods they need to win before it runs out, or a batch that ends partway
+// through a match.
+use crate::advantage;
+use crate::deck::DeckExhaustion;
+use crate::error;
+use crate::game::{self, GameRules, SimConfig, WinCondition, MAX_PLAYERS};
+use crate::player;
+use crate::preferences::PreferenceModel;
+use crate::sampling::{self, PreferenceSampling};
+use crate::types::{GoodsSet, Preferences};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use std::fmt;
+
+// Preference cards sampled to estimate what a good is worth to a typical player.
+const SAMPLED_CARDS: usize = 100;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // The setting to change, as a --set key, e.g. rules.deck_size.
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.key, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn error(&mut self, key: &str, message: String) {
+        self.push(Severity::Error, key, message);
+    }
+
+    fn warning(&mut self, key: &str, message: String) {
+        self.push(Severity::Warning, key, message);
+    }
+
+    fn push(&mut self, severity: Severity, key: &str, message: String) {
+        self.0.push(Diagnostic {
+            severity,
+            key: key.to_string(),
+            message,
+        });
+    }
+
+    fn has_errors(&self) -> bool {
+        self.0.iter().any(|d| d.severity == Severity::Error)
+    }
+}
+
+// Every problem found, errors and warnings in the order they were checked. Checks that need
+// the preference cards or the deck are skipped when those have errors of their own.
+pub fn check(config: &SimConfig, rules: &GameRules) -> Vec<Diagnostic> {
+    let mut diagnostics = Diagnostics::default();
+    check_players(config, &mut diagnostics);
+    check_matches(config, &mut diagnostics);
+    let mut preferences = Diagnostics::default();
+    check_preferences(config, rules, &mut preferences);
+    let cards_dealable = !preferences.has_errors();
+    diagnostics.0.extend(preferences.0);
+    let supply = check_deck(rules, &mut diagnostics);
+    if let (Some(supply), true) = (supply, cards_dealable) {
+        if config.num_players > 0 && config.num_players <= MAX_PLAYERS {
+            check_advantage_spread(config, rules, &supply, &mut diagnostics);
+            check_victory(config, rules, &supply, &mut diagnostics);
+        }
+    }
+    diagnostics.0
+}
+
+// Whether a batch can be played with these settings, before any game starts: the errors check
+// finds, as one error, or Ok with the warnings logged.
+pub fn playable(config: &SimConfig, rules: &GameRules) -> error::Result<()> {
+    let (errors, warnings): (Vec<Diagnostic>, Vec<Diagnostic>) = check(config, rules)
+        .into_iter()
+        .partition(|d| d.severity == Severity::Error);
+    for warning in &warnings {
+        crate::warn!("{}", warning);
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let errors: Vec<String> = errors
+        .iter()
+        .map(|d| format!("{}: {}", d.key, d.message))
+        .collect();
+    Err(error::HedonicaError::Unsupported {
+        message: format!(
+            "the sim config and game rules cannot be played: {}",
+            errors.join("; ")
+        ),
+    })
+}
+
+fn check_matches(config: &SimConfig, diagnostics: &mut Diagnostics) {
+    let format = match &config.match_format {
+        Some(format) => format,
+        None => return,
+    };
+    if format.games == 0 {
+        diagnostics.error(
+            "sim.match_format.games",
+            String::from("a match needs at least one game"),
+        );
+    } else if config.num_runs % format.games as i32 != 0 {
+        diagnostics.warning(
+            "sim.num_runs",
+            format!(
+                "{} runs are not a whole number of {}-game matches; the last match may be left \
+                 unfinished and out of the match statistics",
+                config.num_runs, format.games
+            ),
+        );
+    }
+}
+
+fn check_players(config: &SimConfig, diagnostics: &mut Diagnostics) {
+    if config.num_players == 0 {
+        diagnostics.error(
+            "sim.num_players",
+            String::from("a game needs at least one player"),
+        );
+    } else if config.num_players > MAX_PLAYERS {
+        diagnostics.error(
+            "sim.num_players",
+            format!(
+                "the starting money table seats at most {} players, not {}",
+                MAX_PLAYERS, config.num_players
+            ),
+        );
+    }
+    if config.player_configs.len() > config.num_players {
+        diagnostics.error(
+            "sim.player_configs",
+            format!(
+                "there are {} player configs for {} players; remove some or raise num_players",
+                config.player_configs.len(),
+                config.num_players
+            ),
+        );
+    }
+    for (seat, player_config) in config.player_configs.iter().enumerate() {
+        let key = format!("sim.player_configs.{}", seat);
+        match player::max_players(player_config.player_type()) {
+            Err(e) => diagnostics.error(&key, e.to_string()),
+            Ok(Some(max_players)) if config.num_players > max_players => diagnostics.error(
+                &key,
+                format!(
+                    "{} only plays games of up to {} players; seat another strategy or lower \
+                     num_players",
+                    player_config.player_type(),
+                    max_players
+                ),
+            ),
+            Ok(_) => {}
+        }
+        match player::min_players(player_config.player_type()) {
+            Ok(Some(min_players)) if config.num_players < min_players => diagnostics.error(
+                &key,
+                format!(
+                    "{} only plays games of at least {} players; seat another strategy or raise \
+                     num_players",
+                    player_config.player_type(),
+                    min_players
+                ),
+            ),
+            _ => {}
+        }
+    }
+}
+
+fn check_preferences(config: &SimConfig, rules: &GameRules, diagnostics: &mut Diagnostics) {
+    if let Some(max_spread) = rules.max_advantage_spread.filter(|&spread| spread < 0.) {
+        diagnostics.error(
+            "rules.max_advantage_spread",
+            format!("the spread cannot be negative, not {}", max_spread),
+        );
+    }
+    if config.preference_sampling == PreferenceSampling::Stratified {
+        for (key, conflict) in sampling::stratified_conflicts(rules) {
+            diagnostics.error(
+                key,
+                format!(
+                    "{}; change it or set sim.preference_sampling to random",
+                    conflict
+                ),
+            );
+        }
+    }
+    if rules.categories.is_empty() {
+        diagnostics.error(
+            "rules.categories",
+            String::from("there must be at least one category"),
+        );
+        return;
+    }
+    if rules.preference_hand_size == 0 {
+        diagnostics.error(
+            "rules.preference_hand_size",
+            String::from("every player needs at least one preference card"),
+        );
+        return;
+    }
+    let num_cards = config.num_players * rules.preference_hand_size;
+    match rules.preference_model() {
+        PreferenceModel::Shuffle | PreferenceModel::AntiCorrelated => {
+            if rules.preference_values.len() != rules.categories.len() {
+                diagnostics.error(
+                    "rules.preference_values",
+                    format!(
+                        "there are {} preference values for {} categories; give one per category",
+                        rules.preference_values.len(),
+                        rules.categories.len()
+                    ),
+                );
+                return;
+            }
+            let distinct = distinct_orders(&rules.preference_values);
+            if rules.preference_model() == PreferenceModel::Shuffle
+                && rules.preference_hand_size > 1
+                && !rules.allow_duplicate_preferences
+                && distinct < num_cards as f64
+            {
+                diagnostics.error(
+                    "rules.preference_hand_size",
+                    format!(
+                        "the preference values make only {} distinct cards, fewer than the {} \
+                         in every player's hand; shrink the hands or set \
+                         allow_duplicate_preferences",
+                        distinct, num_cards
+                    ),
+                );
+            }
+        }
+        PreferenceModel::Dirichlet { concentration, .. } => {
+            if concentration <= 0. {
+                diagnostics.error(
+                    "rules.preference_generator.concentration",
+                    format!("the concentration must be positive, not {}", concentration),
+                );
+            }
+        }
+        PreferenceModel::List => {
+            let cards = match &rules.preference_cards {
+                Some(cards) => cards,
+                None => {
+                    diagnostics.error(
+                        "rules.preference_cards",
+                        String::from("list preferences need preference_cards to deal from"),
+                    );
+                    return;
+                }
+            };
+            for (index, card) in cards.iter().enumerate() {
+                let missing: Vec<&str> = rules
+                    .categories
+                    .iter()
+                    .filter(|category| !card.contains_key(category.as_str()))
+                    .map(String::as_str)
+                    .collect();
+                if !missing.is_empty() {
+                    diagnostics.error(
+                        &format!("rules.preference_cards.{}", index),
+                        format!("the card has no value for {}", missing.join(", ")),
+                    );
+                }
+            }
+            if cards.is_empty() || (!rules.allow_duplicate_preferences && cards.len() < num_cards) {
+                diagnostics.error(
+                    "rules.preference_cards",
+                    format!(
+                        "the players' hands take {} preference cards, but {} are listed; add \
+                         cards or set allow_duplicate_preferences",
+                        num_cards,
+                        cards.len()
+                    ),
+                );
+            }
+        }
+    }
+}
+
+// How many different orders the values can be dealt to the categories in.
+fn distinct_orders(values: &[i32]) -> f64 {
+    let factorial = |n: usize| (1..=n).map(|k| k as f64).product::<f64>();
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mut orders = factorial(sorted.len());
+    for run in sorted.chunk_by(|a, b| a == b) {
+        orders /= factorial(run.len());
+    }
+    orders
+}
+
+// The goods the deck holds on average, or None if it cannot be built or holds none.
+fn check_deck(rules: &GameRules, diagnostics: &mut Diagnostics) -> Option<GoodsSet> {
+    if let Err(e) = rules
+        .deck_spec()
+        .cards(rules, &mut StdRng::seed_from_u64(0))
+    {
+        diagnostics.error("rules.deck", e.to_string());
+        return None;
+    }
+    let mut supply = match advantage::expected_supply(rules) {
+        Ok(supply) => supply,
+        Err(e) => {
+            diagnostics.error("rules.deck", e.to_string());
+            return None;
+        }
+    };
+    supply.retain(|category, _| rules.categories.contains(category));
+    if supply.values().sum::<f64>() <= 0. {
+        diagnostics.error(
+            "rules.deck_size",
+            String::from("the deck holds no goods; raise deck_size or give the deck some cards"),
+        );
+        return None;
+    }
+    Some(supply)
+}
+
+// Games redeal preference cards until the spread is within max_advantage_spread, giving up
+// after game::MAX_REDEALS deals; this samples as many to find whether any would be.
+fn check_advantage_spread(
+    config: &SimConfig,
+    rules: &GameRules,
+    supply: &GoodsSet,
+    diagnostics: &mut Diagnostics,
+) {
+    let max_spread = match rules.max_advantage_spread {
+        Some(max_spread) => max_spread,
+        None => return,
+    };
+    let mut rng = StdRng::seed_from_u64(0);
+    let generator = rules.preference_generator();
+    let num_cards = config.num_players * rules.preference_hand_size;
+    let spreads: error::Result<Vec<f64>> = (0..game::MAX_REDEALS)
+        .map(|_| {
+            let deck = generator.deck(rules, num_cards, &mut rng)?;
+            game::starting_spread(config, rules, supply, &deck)
+        })
+        .collect();
+    // Cards the generator cannot deal are check_preferences' errors.
+    let closest = match spreads {
+        Ok(spreads) => spreads.into_iter().fold(f64::INFINITY, f64::min),
+        Err(_) => return,
+    };
+    if closest > max_spread {
+        diagnostics.error(
+            "rules.max_advantage_spread",
+            format!(
+                "none of {} sampled deals has a spread within {}, the closest is {:.3}; raise \
+                 it or remove it",
+                game::MAX_REDEALS,
+                max_spread,
+                closest
+            ),
+        );
+    }
+}
+
+fn check_victory(
+    config: &SimConfig,
+    rules: &GameRules,
+    supply: &GoodsSet,
+    diagnostics: &mut Diagnostics,
+) {
+    if rules.win_condition != WinCondition::Points {
+        return;
+    }
+    let scorer = rules.scoring.scorer();
+    let money: f64 = (0..config.num_players)
+        .map(|seat| game::start_money(rules, seat))
+        .sum();
+    let best = best_cards(rules, supply)
+        .iter()
+        .map(|card| scorer.worth(card, rules.utility_curve, supply))
+        .fold(0., f64::max)
+        + money;
+    if rules.victory_threshold > best {
+        diagnostics.error(
+            "rules.victory_threshold",
+            format!(
+                "no player can reach {} points: holding every good in the deck and all {} of \
+                 the money scores at most {:.1}",
+                rules.victory_threshold, money, best
+            ),
+        );
+        return;
+    }
+    if rules.victory_threshold <= rules.start_money {
+        diagnostics.warning(
+            "rules.victory_threshold",
+            format!(
+                "players start with {} money, so the first lead wins at {} points before \
+                 anyone trades",
+                rules.start_money, rules.victory_threshold
+            ),
+        );
+        return;
+    }
+
+    // Each turn the lead draws one good, so a game reaching the threshold by draws alone lasts
+    // about num_players times the goods a typical player needs.
+    let goods: f64 = supply.values().sum();
+    let mut rng = StdRng::seed_from_u64(0);
+    let generator = rules.preference_generator();
+    let cards: error::Result<Vec<Preferences>> = (0..SAMPLED_CARDS)
+        .map(|_| generator.card(rules, &mut rng))
+        .collect();
+    let cards = match cards {
+        Ok(cards) => cards,
+        Err(_) => return,
+    };
+    let points_per_good = cards
+        .iter()
+        .map(|card| {
+            supply
+                .ids()
+                .map(|(id, count)| card[id] * count)
+                .sum::<f64>()
+                / goods
+        })
+        .sum::<f64>()
+        / SAMPLED_CARDS as f64;
+    if points_per_good <= 0. {
+        return;
+    }
+    let needed = ((rules.victory_threshold - rules.start_money) / points_per_good).ceil();
+    let draws = needed * config.num_players as f64;
+    if rules.deck_exhaustion() == DeckExhaustion::End && goods < draws {
+        diagnostics.warning(
+            "rules.deck_size",
+            format!(
+                "a typical player draws about {} goods to reach {} points, {} for {} players, but \
+                 the deck holds {}; most games will end when it runs out",
+                needed, rules.victory_threshold, draws, config.num_players, goods
+            ),
+        );
+    }
+    if (rules.max_turns as f64) < draws {
+        diagnostics.warning(
+            "rules.max_turns",
+            format!(
+                "a typical game takes about {} turns for {} players to draw the goods to reach \
+                 {} points, more than max_turns {}",
+                draws, config.num_players, rules.victory_threshold, rules.max_turns
+            ),
+        );
+    }
+}
+
+// The cards that value the deck's goods most for each way the rules make cards, for an upper
+// bound on any player's score.
+fn best_cards(rules: &GameRules, supply: &GoodsSet) -> Vec<Preferences> {
+    let count = |category: &String| supply.get(category).copied().unwrap_or(0.);
+    let mut by_supply: Vec<&String> = rules.categories.iter().collect();
+    by_supply.sort_by(|a, b| count(b).total_cmp(&count(a)));
+    match rules.preference_model() {
+        // The highest values on the most plentiful categories.
+        PreferenceModel::Shuffle | PreferenceModel::AntiCorrelated => {
+            let mut values = rules.preference_values.clone();
+            values.sort_unstable_by(|a, b| b.cmp(a));
+            let mut card: Preferences = by_supply
+                .iter()
+                .zip(&values)
+                .map(|(category, &value)| (category.as_str(), value as f64))
+                .collect();
+            card.insert("money", 1.);
+            vec![card]
+        }
+        // Every point on the most plentiful category.
+        PreferenceModel::Dirichlet { total, .. } => {
+            let total = total.unwrap_or_else(|| rules.preference_values.iter().sum::<i32>() as f64);
+            let mut card: Preferences = rules
+                .categories
+                .iter()
+                .map(|category| (category.as_str(), 0.))
+                .collect();
+            card.insert(by_supply[0], total);
+            card.insert("money", 1.);
+            vec![card]
+        }
+        PreferenceModel::List => rules
+            .preference_cards
+            .iter()
+            .flatten()
+            .filter_map(|card| rules.with_money(card).ok())
+            .collect(),
+    }
+}
@@ -0,0 +1,161 @@
+// What one player may see of the game.
+//
+// Players see everyone's inventories but only their own preference card, unless the rules'
+// preference_visibility reveals them all, and the engine hands strategies a copy of the game
+// with the other preference cards removed. With public scoring, every score is still open,
+// kept in the copy as shown_score. With hidden scoring, as in the physical game, only their
+// own is. Strategies should read preferences and scores through a PlayerView rather than from
+// PlayerState, so they work under any of these rules.
+//
+// With face-down inventories, the copy shows only how many goods each other player holds, and
+// inventory() shows just their money. estimated_inventory() guesses the rest.
+//
+// Strategies are handed that copy as an Observation. The goods and event decks are private to
+// the engine, so strategies cannot read their order, and strategies that simulate the game
+// should start from determinize(), which reshuffles them. Bots outside the simulator are sent
+// the decks sorted, keeping their contents, which players could count from the rules and the
+// draws so far, but not what comes next.
+//
+// Whom the viewer may trade with under the rules' trade_partners is trade_partners_of(), and
+// may_trade() for any two players. The rules' trade_limits are copied into the game too, with
+// trades_left(), max_proposals() and trade_size_allowed() to check a trade against them.
+use crate::game::{GameState, ScoreVisibility};
+use crate::scoring::Scorer;
+use crate::types::*;
+use serde::{Serialize, Serializer};
+use std::borrow::Cow;
+use std::ops::Deref;
+
+pub struct PlayerView<'a> {
+    game: &'a GameState,
+    viewer: PlayerId,
+}
+
+impl<'a> PlayerView<'a> {
+    pub fn new(game: &'a GameState, viewer: PlayerId) -> PlayerView<'a> {
+        PlayerView { game, viewer }
+    }
+
+    pub fn game(&self) -> &'a GameState {
+        self.game
+    }
+
+    pub fn viewer(&self) -> PlayerId {
+        self.viewer
+    }
+
+    fn can_see_preferences(&self, player_id: PlayerId) -> bool {
+        player_id == self.viewer || self.game.preferences_revealed()
+    }
+
+    // The goods the viewer can see the player holding.
+    pub fn inventory(&self, player_id: PlayerId) -> &'a GoodsSet {
+        &self.game.player_state(player_id).num_goods
+    }
+
+    // How many goods, not counting money, the player holds, whether or not they are face down.
+    pub fn goods_count(&self, player_id: PlayerId) -> f64 {
+        let player = self.game.player_state(player_id);
+        player
+            .num_goods
+            .iter()
+            .filter(|(category, _)| *category != "money")
+            .map(|(_, count)| count)
+            .sum::<f64>()
+            + player.face_down_goods
+    }
+
+    // The expected inventory of the player. Face-down goods are split between categories in
+    // proportion to the deck, which is dealt from every good the viewer cannot see, or evenly
+    // once the deck is empty.
+    pub fn estimated_inventory(&self, player_id: PlayerId) -> GoodsSet {
+        let player = self.game.player_state(player_id);
+        let mut estimate = player.num_goods.clone();
+        if player.face_down_goods <= 0. {
+            return estimate;
+        }
+        let mut composition = self.game.deck_composition();
+        if composition.is_empty() {
+            composition = player
+                .num_goods
+                .keys()
+                .filter(|category| *category != "money")
+                .map(|category| (category.clone(), 1.))
+                .collect();
+        }
+        let unseen: f64 = composition.values().sum();
+        for (category, count) in composition {
+            *estimate.entry(category).or_insert(0.) += player.face_down_goods * count / unseen;
+        }
+        estimate
+    }
+
+    pub fn preferences(&self, player_id: PlayerId) -> Option<&'a Preferences> {
+        if self.can_see_preferences(player_id) {
+            Some(self.game.player_state(player_id).preferences())
+        } else {
+            None
+        }
+    }
+
+    pub fn score(&self, player_id: PlayerId) -> Option<f64> {
+        let player = self.game.player_state(player_id);
+        if player_id == self.viewer || self.game.score_visibility == ScoreVisibility::Public {
+            Some(player.shown_score.unwrap_or_else(|| player.score()))
+        } else {
+            None
+        }
+    }
+
+    // Points the player would have for holding these goods, by the rules' scorer, before
+    // penalties and debt.
+    pub fn worth(&self, player_id: PlayerId, goods: &GoodsSet) -> Option<f64> {
+        if self.can_see_preferences(player_id) {
+            Some(self.game.player_state(player_id).worth(goods))
+        } else {
+            None
+        }
+    }
+}
+
+// The game as one player is shown it when asked for a decision, from GameState::observation.
+// It reads like the GameState it derefs to.
+pub struct Observation<'a> {
+    game: Cow<'a, GameState>,
+    viewer: PlayerId,
+}
+
+impl<'a> Observation<'a> {
+    // The game must already be masked for the viewer.
+    pub(crate) fn new(game: Cow<'a, GameState>, viewer: PlayerId) -> Observation<'a> {
+        Observation { game, viewer }
+    }
+
+    pub fn viewer(&self) -> PlayerId {
+        self.viewer
+    }
+
+    pub fn view(&self) -> PlayerView<'_> {
+        PlayerView::new(&self.game, self.viewer)
+    }
+
+    // The rules' scorer, which turns the viewer's goods into points with their preferences and
+    // utility curve. See scoring.rs.
+    pub fn scorer(&self) -> &dyn Scorer {
+        self.game.player_state(self.viewer).scoring.scorer()
+    }
+}
+
+impl Deref for Observation<'_> {
+    type Target = GameState;
+
+    fn deref(&self) -> &GameState {
+        &self.game
+    }
+}
+
+impl Serialize for Observation<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.game.with_sorted_decks().serialize(serializer)
+    }
+}
@@ -0,0 +1,143 @@
+// A "rational economist". It accepts exactly the trades that raise its utility, and proposes
+// trades that should raise both sides' utility: one good for another, or a good for money at
+// the midpoint of the two valuations. Other players' valuations come from their preference
+// cards when those are public, and otherwise from the mean point value of a card.
+use ctor::ctor;
+
+use hedonica_engine::game::GameState;
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::suggest;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+use std::collections::{HashMap, HashSet};
+
+struct PlayerExpectedUtility {
+    my_id: PlayerId,
+
+    // (turn, other player, category wanted, category offered) for proposals already made.
+    proposed: HashSet<(i32, PlayerId, String, String)>,
+}
+
+fn proposal_key(turn: i32, trade: &Trade) -> (i32, PlayerId, String, String) {
+    let category = |goods: GoodsSet| goods.keys().next().cloned().unwrap_or_default();
+    (
+        turn,
+        trade.accepter,
+        category(trade.accepter_gives()),
+        category(trade.proposer_gives()),
+    )
+}
+
+impl PlayerExpectedUtility {
+    // The mutually beneficial trade with `other` that gains me the most, paid for out of
+    // `holdings`.
+    fn best_trade(
+        &self,
+        game_state: &GameState,
+        other: PlayerId,
+        holdings: &GoodsSet,
+    ) -> Option<Trade> {
+        let view = game_state.view(self.my_id);
+        let turn = game_state.current_turn;
+        suggest::trades_with(&view, other)
+            .into_iter()
+            .filter(|suggestion| {
+                can_afford(holdings, &suggestion.trade.proposer_gives())
+                    && !self
+                        .proposed
+                        .contains(&proposal_key(turn, &suggestion.trade))
+            })
+            .max_by(|a, b| {
+                a.gain
+                    .partial_cmp(&b.gain)
+                    .unwrap()
+                    .then_with(|| a.partner_gain.partial_cmp(&b.partner_gain).unwrap())
+            })
+            .map(|suggestion| suggestion.trade)
+    }
+
+    fn propose(
+        &mut self,
+        game_state: &GameState,
+        other: PlayerId,
+        holdings: &GoodsSet,
+    ) -> Option<Trade> {
+        let trade = self.best_trade(game_state, other, holdings)?;
+        self.proposed
+            .insert(proposal_key(game_state.current_turn, &trade));
+        Some(trade)
+    }
+
+    fn wants(&self, game_state: &GameState, trade: &Trade, holdings: &GoodsSet) -> bool {
+        can_afford(holdings, &trade.accepter_gives())
+            && game_state
+                .player_state(self.my_id)
+                .score_change(&trade.proposer_gives(), &trade.accepter_gives())
+                > 0.
+    }
+}
+
+impl PlayerStrategy for PlayerExpectedUtility {
+    fn init(&mut self, player_id: PlayerId, _value: &serde_json::Value) -> Result<(), String> {
+        self.my_id = player_id;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.proposed.clear();
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({ "type": "object", "additionalProperties": false })
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        propose_within_holdings(game_state, |other, holdings| {
+            self.propose(game_state, other, holdings)
+        })
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        if !game_state.may_trade(self.my_id, game_state.lead) {
+            return None;
+        }
+        let holdings = game_state.player_state(self.my_id).num_goods.clone();
+        self.propose(game_state, game_state.lead, &holdings)
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        let mut holdings = game_state.player_state(self.my_id).num_goods.clone();
+        game_state
+            .current_trade_proposals
+            .values()
+            .map(|trade| {
+                let accept = self.wants(game_state, trade, &holdings);
+                if accept {
+                    take(&mut holdings, &trade.accepter_gives());
+                }
+                accept
+            })
+            .collect()
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        self.wants(
+            game_state,
+            trade,
+            &game_state.player_state(self.my_id).num_goods,
+        )
+    }
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(PlayerExpectedUtility {
+        my_id: 0,
+        proposed: HashSet::new(),
+    })
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerExpectedUtility", create)
+}
@@ -0,0 +1,341 @@
+// Trades for whatever most improves its own score right now, judged only by its own
+// preferences. For each good another player holds, it offers either money or its least
+// valuable good, whichever leaves it better off, and it accepts any trade that raises its
+// score. It never repeats a proposal within a turn, so its turns as lead end once everything
+// it wants has been turned down. When the rules allow counter-offers, it answers an offer it
+// turns down by asking for the same goods plus enough money to come out ahead. In auctions it
+// bids offer_fraction of what the good is worth to it. It explains each trade decision by the
+// points it expected to gain.
+//
+// Config:
+//   offer_fraction - Money offered or bid for a good, as a fraction of what the good is
+//                    worth to this player. Defaults to 0.5.
+use ctor::ctor;
+
+use hedonica_engine::game::{GameState, PlayerState};
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::replay::Decision;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+use std::collections::{HashMap, HashSet};
+
+struct PlayerGreedy {
+    my_id: PlayerId,
+    offer_fraction: f64,
+
+    // (turn, other player, category wanted, category offered) for proposals already made.
+    proposed: HashSet<(i32, PlayerId, String, String)>,
+    // Why it made its last trade decision, and which kind of decision that was.
+    reason: Option<(Decision, String)>,
+}
+
+fn default_offer_fraction() -> f64 {
+    0.5
+}
+
+impl PlayerGreedy {
+    fn me<'a>(&self, game_state: &'a GameState) -> &'a PlayerState {
+        game_state.player_state(self.my_id)
+    }
+
+    // The best single trade with `other`, paid for out of `holdings`.
+    fn best_trade(
+        &self,
+        game_state: &GameState,
+        other: PlayerId,
+        holdings: &GoodsSet,
+    ) -> Option<Trade> {
+        let me = self.me(game_state);
+        let view = game_state.view(self.my_id);
+        let preferences = view.preferences(self.my_id).unwrap();
+        let turn = game_state.current_turn;
+        let tradable = |player: &PlayerState, category: &str| {
+            category != "money" && player.can_trade(CategoryId::intern(category), turn)
+        };
+
+        // My least valuable good, offered in exchange for anything worth more.
+        let cheapest_good = holdings
+            .iter()
+            .filter(|(category, &count)| count >= 1. && tradable(me, category))
+            .min_by(|a, b| {
+                preferences[a.0]
+                    .partial_cmp(&preferences[b.0])
+                    .unwrap()
+                    .then_with(|| a.0.cmp(b.0))
+            })
+            .map(|(category, _)| category.clone());
+
+        let mut best: Option<(f64, Trade)> = None;
+        for (category, &count) in view.inventory(other) {
+            if count < 1.
+                || !tradable(me, category)
+                || !tradable(game_state.player_state(other), category)
+            {
+                continue;
+            }
+            let mut wanted = GoodsSet::new();
+            wanted.insert(category.clone(), 1.);
+
+            let mut offers = Vec::new();
+            let price = (preferences[category] * self.offer_fraction)
+                .floor()
+                .max(1.);
+            offers.push((String::from("money"), price));
+            if let Some(good) = &cheapest_good {
+                offers.push((good.clone(), 1.));
+            }
+            for (offered, amount) in offers {
+                let key = (turn, other, category.clone(), offered.clone());
+                if self.proposed.contains(&key) {
+                    continue;
+                }
+                let mut payment = GoodsSet::new();
+                payment.insert(offered, amount);
+                let delta = me.score_change(&wanted, &payment);
+                if delta <= 0. || !can_afford(holdings, &payment) {
+                    continue;
+                }
+                let trade = Trade::new(self.my_id, other, payment, wanted.clone());
+                if !game_state.trade_size_allowed(&trade) {
+                    continue;
+                }
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_delta, _)| delta > *best_delta)
+                {
+                    best = Some((delta, trade));
+                }
+            }
+        }
+        best.map(|(_, trade)| trade)
+    }
+
+    fn remember(&mut self, game_state: &GameState, trade: &Trade) {
+        let category = |goods: &GoodsSet| goods.keys().next().cloned().unwrap_or_default();
+        self.proposed.insert((
+            game_state.current_turn,
+            trade.accepter,
+            category(&trade.accepter_gives()),
+            category(&trade.proposer_gives()),
+        ));
+    }
+
+    // The offer with its goods turned around and the offer's proposer paying enough more to
+    // make it worth a point to this player, if they have the money.
+    fn counter(&self, game_state: &GameState, offer: &Trade) -> Option<Trade> {
+        let view = game_state.view(self.my_id);
+        let preferences = view.preferences(self.my_id).unwrap();
+        if !can_afford(&self.me(game_state).num_goods, &offer.accepter_gives()) {
+            return None;
+        }
+        let shortfall = 1.
+            - self
+                .me(game_state)
+                .score_change(&offer.proposer_gives(), &offer.accepter_gives());
+        let asked = offer.money.0 + (shortfall / preferences["money"]).ceil().max(1.);
+        if asked > view.inventory(offer.proposer)["money"] {
+            return None;
+        }
+        let counter = Trade::new(
+            self.my_id,
+            offer.proposer,
+            offer.from_acceptor.clone(),
+            offer.from_proposor.clone(),
+        );
+        Some(counter.with_payment(-asked))
+    }
+
+    // A trade from this player's side, e.g. `{"art":1} for {"money":3}, +2.5 points`.
+    fn describe(&self, game_state: &GameState, trade: &Trade) -> String {
+        let (gives, gets) = if trade.proposer == self.my_id {
+            (trade.proposer_gives(), trade.accepter_gives())
+        } else {
+            (trade.accepter_gives(), trade.proposer_gives())
+        };
+        format!(
+            "{} for {}, {:+.2} points",
+            serde_json::to_string(&gives).unwrap(),
+            serde_json::to_string(&gets).unwrap(),
+            self.me(game_state).score_change(&gets, &gives)
+        )
+    }
+
+    fn explain_answer(
+        &self,
+        game_state: &GameState,
+        trade: &Trade,
+        holdings: &GoodsSet,
+        accept: bool,
+    ) -> String {
+        let answer = if accept { "accepted" } else { "rejected" };
+        let affordable = can_afford(holdings, &trade.accepter_gives());
+        let trade = self.describe(game_state, trade);
+        if affordable {
+            format!("{} {}", answer, trade)
+        } else {
+            format!("{} {}, unaffordable", answer, trade)
+        }
+    }
+
+    fn explain_counter(&mut self, game_state: &GameState, counter: Option<&Trade>) {
+        let reason = match counter {
+            Some(counter) => format!("countered {}", self.describe(game_state, counter)),
+            None => String::from("could not ask enough money to come out ahead"),
+        };
+        self.reason = Some((Decision::CounterOffer, reason));
+    }
+
+    fn wants(&self, game_state: &GameState, trade: &Trade, holdings: &GoodsSet) -> bool {
+        can_afford(holdings, &trade.accepter_gives())
+            && self
+                .me(game_state)
+                .score_change(&trade.proposer_gives(), &trade.accepter_gives())
+                > 0.
+    }
+}
+
+impl PlayerStrategy for PlayerGreedy {
+    fn init(&mut self, player_id: PlayerId, value: &serde_json::Value) -> Result<(), String> {
+        self.my_id = player_id;
+        self.offer_fraction = value["offer_fraction"]
+            .as_f64()
+            .unwrap_or_else(default_offer_fraction);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.proposed.clear();
+        self.reason = None;
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "offer_fraction": {
+                    "type": "number",
+                    "minimum": 0,
+                    "description": "Money offered or bid for a good, as a fraction of its worth",
+                    "default": default_offer_fraction(),
+                },
+            },
+        })
+    }
+
+    // The default wild card and steal choices already take what this player values most.
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::SealedBids, Capability::SpecialCards]
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        let mut reasons = Vec::new();
+        let trades = propose_within_holdings(game_state, |other, holdings| {
+            let trade = self.best_trade(game_state, other, holdings)?;
+            self.remember(game_state, &trade);
+            reasons.push(format!(
+                "offered player {} {}",
+                other,
+                self.describe(game_state, &trade)
+            ));
+            Some(trade)
+        });
+        let reason = if reasons.is_empty() {
+            String::from("no trade would gain points")
+        } else {
+            reasons.join("; ")
+        };
+        self.reason = Some((Decision::Propose, reason));
+        trades
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        if !game_state.may_trade(self.my_id, game_state.lead) {
+            return None;
+        }
+        let holdings = &self.me(game_state).num_goods;
+        let trade = self.best_trade(game_state, game_state.lead, holdings);
+        let reason = match &trade {
+            Some(trade) => format!("offered {}", self.describe(game_state, trade)),
+            None => String::from("no trade with the lead would gain points"),
+        };
+        self.reason = Some((Decision::Propose, reason));
+        let trade = trade?;
+        self.remember(game_state, &trade);
+        Some(trade)
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        let mut holdings = self.me(game_state).num_goods.clone();
+        let mut reasons = Vec::new();
+        let accepts = game_state
+            .current_trade_proposals
+            .values()
+            .map(|trade| {
+                let accept = self.wants(game_state, trade, &holdings);
+                reasons.push(format!(
+                    "player {}: {}",
+                    trade.proposer,
+                    self.explain_answer(game_state, trade, &holdings, accept)
+                ));
+                if accept {
+                    take(&mut holdings, &trade.accepter_gives());
+                }
+                accept
+            })
+            .collect();
+        self.reason = Some((Decision::Accept, reasons.join("; ")));
+        accepts
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        let holdings = &self.me(game_state).num_goods;
+        let accept = self.wants(game_state, trade, holdings);
+        let reason = self.explain_answer(game_state, trade, holdings, accept);
+        self.reason = Some((Decision::Accept, reason));
+        accept
+    }
+
+    fn counter_offer_as_lead(&mut self, game_state: &Observation, trade: &Trade) -> Option<Trade> {
+        let counter = self.counter(game_state, trade);
+        self.explain_counter(game_state, counter.as_ref());
+        counter
+    }
+
+    fn counter_offer_as_non_lead(
+        &mut self,
+        game_state: &Observation,
+        trade: &Trade,
+    ) -> Option<Trade> {
+        let counter = self.counter(game_state, trade);
+        self.explain_counter(game_state, counter.as_ref());
+        counter
+    }
+
+    fn bid(&mut self, game_state: &Observation, good: &Good) -> f64 {
+        let preferences = self.me(game_state).preferences();
+        self.offer_fraction * preferences[&good.category] * good.amount / preferences["money"]
+    }
+
+    fn explain_last_decision(&self, decision: Decision) -> Option<String> {
+        match &self.reason {
+            Some((made, reason)) if *made == decision => Some(reason.clone()),
+            _ => None,
+        }
+    }
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(PlayerGreedy {
+        my_id: 0,
+        offer_fraction: default_offer_fraction(),
+        proposed: HashSet::new(),
+        reason: None,
+    })
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerGreedy", create)
+}
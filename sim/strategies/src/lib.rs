@@ -0,0 +1,18 @@
+// The built-in computer strategies, and the remote strategies that play through bots outside
+// the simulator. Each strategy registers itself with hedonica_engine::player when the program
+// starts, so linking this crate is enough to make them available by name.
+mod expected_utility;
+mod greedy;
+mod mcts;
+mod minimax;
+mod noisy;
+#[cfg(all(feature = "plugins", unix))]
+pub mod plugins;
+pub mod protocol;
+mod rand_no_trades;
+pub mod remote;
+mod remote_ws;
+#[cfg(all(feature = "plugins", unix))]
+pub mod strategy_lib;
+mod subprocess;
+pub mod websocket;
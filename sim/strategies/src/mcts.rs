@@ -0,0 +1,396 @@
+// Flat Monte Carlo tree search. For each decision it lists candidate moves, declining to trade
+// among them, and spends a budget of rollouts on them, choosing which move to try next by
+// UCB1. A rollout applies the move to a determinized copy of the game, ends the current turn
+// and plays rollout_turns more turns with rollout_policy in every seat. The move with the
+// best mean score at the end of its rollouts is chosen. Proposals are scored as if accepted.
+//
+// Config:
+//   rollouts       - Rollouts per decision. Defaults to 64.
+//   rollout_turns  - Turns simulated per rollout. Defaults to 20.
+//   exploration    - UCB1 exploration constant, for rewards scaled to [0, 1]. Defaults to 1.4.
+//   max_candidates - Trades considered per decision, largest immediate gain first.
+//                    Defaults to 8.
+//   max_proposals  - Proposals made to each player per turn. Defaults to 3.
+//   rollout_policy - Strategy played in every seat during rollouts. Defaults to PlayerGreedy.
+//   seed           - Seeds the rollouts. 0, the default, seeds from the OS.
+use ctor::ctor;
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use hedonica_engine::game::{GameRules, GameState, PlayerState};
+use hedonica_engine::lookahead::{Action, GameEngine, Snapshot};
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+
+#[derive(Deserialize)]
+struct MctsConfig {
+    #[serde(default = "default_rollouts")]
+    rollouts: usize,
+    #[serde(default = "default_rollout_turns")]
+    rollout_turns: usize,
+    #[serde(default = "default_exploration")]
+    exploration: f64,
+    #[serde(default = "default_max_candidates")]
+    max_candidates: usize,
+    #[serde(default = "default_max_proposals")]
+    max_proposals: usize,
+    #[serde(default = "default_rollout_policy")]
+    rollout_policy: String,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_rollouts() -> usize {
+    64
+}
+fn default_rollout_turns() -> usize {
+    20
+}
+fn default_exploration() -> f64 {
+    1.4
+}
+fn default_max_candidates() -> usize {
+    8
+}
+fn default_max_proposals() -> usize {
+    3
+}
+fn default_rollout_policy() -> String {
+    String::from("PlayerGreedy")
+}
+
+struct PlayerMCTS {
+    my_id: PlayerId,
+    config: MctsConfig,
+    rules: Option<GameRules>,
+    rng: StdRng,
+
+    // Plays rollouts with rollout_policy in every seat.
+    rollout_engine: Option<GameEngine>,
+
+    // (turn, other player, category wanted, category offered) for proposals already made.
+    proposed: HashSet<(i32, PlayerId, String, String)>,
+}
+
+fn seeded_rng(seed: u64) -> StdRng {
+    match seed {
+        0 => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+        seed => SeedableRng::seed_from_u64(seed),
+    }
+}
+
+fn offered_category(goods: &GoodsSet) -> String {
+    goods.keys().next().cloned().unwrap_or_default()
+}
+
+// Whether both sides hold what the trade asks of them.
+fn fulfillable(game_state: &GameState, trade: &Trade) -> bool {
+    can_afford(
+        &game_state.player_state(trade.proposer).num_goods,
+        &trade.proposer_gives(),
+    ) && can_afford(
+        &game_state.player_state(trade.accepter).num_goods,
+        &trade.accepter_gives(),
+    )
+}
+
+impl PlayerMCTS {
+    fn me<'a>(&self, game_state: &'a GameState) -> &'a PlayerState {
+        game_state.player_state(self.my_id)
+    }
+
+    // One-for-one trades with `other` that raise my score immediately, paid for out of
+    // `holdings`, largest gain first.
+    fn candidate_trades(
+        &self,
+        game_state: &GameState,
+        other: PlayerId,
+        holdings: &GoodsSet,
+    ) -> Vec<Trade> {
+        let me = self.me(game_state);
+        let view = game_state.view(self.my_id);
+        let preferences = view.preferences(self.my_id).unwrap();
+        let turn = game_state.current_turn;
+        let tradable = |player: &PlayerState, category: &str| {
+            category != "money" && player.can_trade(CategoryId::intern(category), turn)
+        };
+
+        let mut candidates: Vec<(f64, Trade)> = Vec::new();
+        for (category, &count) in view.inventory(other) {
+            if count < 1.
+                || !tradable(me, category)
+                || !tradable(game_state.player_state(other), category)
+            {
+                continue;
+            }
+            let mut wanted = GoodsSet::new();
+            wanted.insert(category.clone(), 1.);
+
+            let value = preferences[category];
+            let mut offers = vec![
+                (String::from("money"), (value / 2.).floor().max(1.)),
+                (String::from("money"), (value - 1.).max(1.)),
+            ];
+            offers.dedup();
+            for (mine, &held) in holdings {
+                if held >= 1. && tradable(me, mine) {
+                    offers.push((mine.clone(), 1.));
+                }
+            }
+            for (offered, amount) in offers {
+                let key = (turn, other, category.clone(), offered.clone());
+                if self.proposed.contains(&key) {
+                    continue;
+                }
+                let mut payment = GoodsSet::new();
+                payment.insert(offered, amount);
+                let delta = me.score_change(&wanted, &payment);
+                if delta <= 0. || !can_afford(holdings, &payment) {
+                    continue;
+                }
+                let trade = Trade::new(self.my_id, other, payment, wanted.clone());
+                if game_state.trade_size_allowed(&trade) {
+                    candidates.push((delta, trade));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        candidates.truncate(self.config.max_candidates);
+        candidates.into_iter().map(|(_, trade)| trade).collect()
+    }
+
+    // Plays out one future after `trade` and returns my score at the end of it.
+    fn rollout(&mut self, game_state: &GameState, trade: Option<&Trade>) -> f64 {
+        let rules = self
+            .rules
+            .as_ref()
+            .expect("PlayerMCTS needs the game rules");
+        let sim = game_state.determinize(rules, self.my_id, &mut self.rng);
+        let engine = self.rollout_engine.as_mut().unwrap();
+        engine.reset(Snapshot::from(sim));
+        if let Some(trade) = trade {
+            engine.step(&Action::Trade(trade.clone())).ok();
+        }
+        engine.step(&Action::EndTurn).unwrap();
+        engine
+            .play_turns(self.config.rollout_turns)
+            .player_state(self.my_id)
+            .score()
+    }
+
+    // The index of the move with the best mean rollout score. Ties go to the earlier move.
+    fn best_move(&mut self, game_state: &GameState, moves: &[Option<Trade>]) -> usize {
+        if moves.len() <= 1 {
+            return 0;
+        }
+        let num_players = game_state.players.len();
+        if self
+            .rollout_engine
+            .as_ref()
+            .is_none_or(|engine| engine.state().players.len() != num_players)
+        {
+            let rules = self
+                .rules
+                .as_ref()
+                .expect("PlayerMCTS needs the game rules");
+            // The policy was checked against the registry with the config.
+            self.rollout_engine = Some(
+                GameEngine::with_policy(rules, &self.config.rollout_policy, game_state.clone())
+                    .unwrap_or_else(|error| panic!("{}", error)),
+            );
+        }
+
+        let mut totals = vec![0.; moves.len()];
+        let mut counts = vec![0u32; moves.len()];
+        let (mut lowest, mut highest) = (f64::INFINITY, f64::NEG_INFINITY);
+        for rollout in 0..self.config.rollouts {
+            let pick = if rollout < moves.len() {
+                rollout
+            } else {
+                let range = (highest - lowest).max(1e-9);
+                let ucb = |i: usize| {
+                    (totals[i] / counts[i] as f64 - lowest) / range
+                        + self.config.exploration
+                            * ((rollout as f64).ln() / counts[i] as f64).sqrt()
+                };
+                (0..moves.len())
+                    .max_by(|&a, &b| ucb(a).partial_cmp(&ucb(b)).unwrap())
+                    .unwrap()
+            };
+            let reward = self.rollout(game_state, moves[pick].as_ref());
+            totals[pick] += reward;
+            counts[pick] += 1;
+            lowest = lowest.min(reward);
+            highest = highest.max(reward);
+        }
+
+        let mean = |i: usize| totals[i] / counts[i] as f64;
+        let mut best = 0;
+        for i in (0..moves.len()).filter(|&i| counts[i] > 0) {
+            if counts[best] == 0 || mean(i) > mean(best) {
+                best = i;
+            }
+        }
+        best
+    }
+
+    // The best trade with `other`, or None if not trading looks best.
+    fn search_proposal(
+        &mut self,
+        game_state: &GameState,
+        other: PlayerId,
+        holdings: &GoodsSet,
+    ) -> Option<Trade> {
+        let turn = game_state.current_turn;
+        let proposals_made = self
+            .proposed
+            .iter()
+            .filter(|(t, p, _, _)| *t == turn && *p == other)
+            .count();
+        if proposals_made >= self.config.max_proposals {
+            return None;
+        }
+        let mut moves = vec![None];
+        moves.extend(
+            self.candidate_trades(game_state, other, holdings)
+                .into_iter()
+                .map(Some),
+        );
+        let best = self.best_move(game_state, &moves);
+        let trade = moves.swap_remove(best)?;
+        self.proposed.insert((
+            game_state.current_turn,
+            other,
+            offered_category(&trade.accepter_gives()),
+            offered_category(&trade.proposer_gives()),
+        ));
+        Some(trade)
+    }
+
+    fn search_acceptance(&mut self, game_state: &GameState, trade: &Trade) -> bool {
+        fulfillable(game_state, trade)
+            && self.best_move(game_state, &[None, Some(trade.clone())]) == 1
+    }
+}
+
+impl PlayerStrategy for PlayerMCTS {
+    fn init(&mut self, player_id: PlayerId, value: &serde_json::Value) -> Result<(), String> {
+        self.my_id = player_id;
+        let value = if value.is_null() {
+            serde_json::json!({})
+        } else {
+            value.clone()
+        };
+        self.config = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        if self.config.rollout_policy == "PlayerMCTS" {
+            return Err(String::from("PlayerMCTS cannot roll out with itself"));
+        }
+        self.rng = seeded_rng(self.config.seed);
+        self.rollout_engine = None;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.proposed.clear();
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "rollouts": { "type": "integer", "minimum": 1, "default": default_rollouts() },
+                "rollout_turns": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "default": default_rollout_turns(),
+                },
+                "exploration": {
+                    "type": "number",
+                    "minimum": 0,
+                    "default": default_exploration(),
+                },
+                "max_candidates": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "default": default_max_candidates(),
+                },
+                "max_proposals": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "default": default_max_proposals(),
+                },
+                "rollout_policy": {
+                    "enum": player::registered_strategies()
+                        .into_iter()
+                        .filter(|name| name != "PlayerMCTS")
+                        .collect::<Vec<String>>(),
+                    "default": default_rollout_policy(),
+                },
+                "seed": { "type": "integer", "minimum": 0, "default": 0 },
+            },
+        })
+    }
+
+    fn set_rules(&mut self, rules: &GameRules) {
+        self.rules = Some(rules.clone());
+        self.rollout_engine = None;
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        propose_within_holdings(game_state, |other, holdings| {
+            self.search_proposal(game_state, other, holdings)
+        })
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        if !game_state.may_trade(self.my_id, game_state.lead) {
+            return None;
+        }
+        let holdings = self.me(game_state).num_goods.clone();
+        self.search_proposal(game_state, game_state.lead, &holdings)
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        let mut holdings = self.me(game_state).num_goods.clone();
+        let proposals: Vec<Trade> = game_state
+            .current_trade_proposals
+            .values()
+            .cloned()
+            .collect();
+        proposals
+            .iter()
+            .map(|trade| {
+                let accept = can_afford(&holdings, &trade.accepter_gives())
+                    && self.search_acceptance(game_state, trade);
+                if accept {
+                    take(&mut holdings, &trade.accepter_gives());
+                }
+                accept
+            })
+            .collect()
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        self.search_acceptance(game_state, trade)
+    }
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(PlayerMCTS {
+        my_id: 0,
+        config: serde_json::from_str("{}").unwrap(),
+        rules: None,
+        rng: seeded_rng(0),
+        rollout_engine: None,
+        proposed: HashSet::new(),
+    })
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerMCTS", create)
+}
@@ -0,0 +1,397 @@
+// Alpha-beta minimax for two-player games, as a near-optimal opponent to measure how much
+// simple strategies can be exploited. Only winning counts with two players, so the search
+// treats the game as zero-sum and evaluates a position by my score minus the other player's.
+//
+// A ply is one player's chance to trade: they propose one of their candidate trades or pass,
+// and the other player accepts or declines. Plies alternate between the players, starting
+// with the player deciding now, and trades are applied and undone on a GameEngine. After
+// `depth` plies, or when the game ends, the position is evaluated; a won game is worth more
+// than any score difference. Turns are not played ahead, so the search weighs trades only by
+// what they do to the scores and to the trades that can follow in the same turn.
+//
+// The search sees everything. Under hidden scoring or face-down inventories it searches one
+// determinized copy of the game per decision instead, which is no longer perfect information.
+//
+// Config:
+//   depth          - Plies searched per decision. Defaults to 4.
+//   max_candidates - Trades each player considers per ply, largest immediate gain first.
+//                    Defaults to 6.
+//   seed           - Seeds determinization. 0, the default, seeds from the OS.
+use ctor::ctor;
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use hedonica_engine::game::{GameRules, GameState, InventoryVisibility, PlayerState};
+use hedonica_engine::lookahead::{Action, GameEngine, Snapshot};
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+
+#[derive(Deserialize)]
+struct MinimaxConfig {
+    #[serde(default = "default_depth")]
+    depth: usize,
+    #[serde(default = "default_max_candidates")]
+    max_candidates: usize,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_depth() -> usize {
+    4
+}
+fn default_max_candidates() -> usize {
+    6
+}
+
+// Worth more than any difference in scores.
+const WIN: f64 = 1e9;
+
+struct PlayerMinimax {
+    my_id: PlayerId,
+    config: MinimaxConfig,
+    rules: Option<GameRules>,
+    rng: StdRng,
+
+    // Steps the searched trades. Its seats never play, since turns are not played ahead.
+    engine: Option<GameEngine>,
+
+    // (turn, category wanted, category offered) for proposals already made, so a declined
+    // proposal is not made again every round.
+    proposed: HashSet<(i32, String, String)>,
+}
+
+fn seeded_rng(seed: u64) -> StdRng {
+    match seed {
+        0 => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+        seed => SeedableRng::seed_from_u64(seed),
+    }
+}
+
+fn offered_category(goods: &GoodsSet) -> String {
+    goods.keys().next().cloned().unwrap_or_default()
+}
+
+fn proposal_key(game_state: &GameState, trade: &Trade) -> (i32, String, String) {
+    (
+        game_state.current_turn,
+        offered_category(&trade.accepter_gives()),
+        offered_category(&trade.proposer_gives()),
+    )
+}
+
+// Whether both sides hold what the trade asks of them.
+fn fulfillable(game_state: &GameState, trade: &Trade) -> bool {
+    can_afford(
+        &game_state.player_state(trade.proposer).num_goods,
+        &trade.proposer_gives(),
+    ) && can_afford(
+        &game_state.player_state(trade.accepter).num_goods,
+        &trade.accepter_gives(),
+    )
+}
+
+// One-for-one trades `proposer` could offer `accepter` that raise the proposer's score
+// immediately, largest gain first. The game must show everyone's preferences and goods.
+fn candidate_trades(
+    game_state: &GameState,
+    proposer: PlayerId,
+    accepter: PlayerId,
+    max_candidates: usize,
+) -> Vec<Trade> {
+    let turn = game_state.current_turn;
+    let (me, other) = (
+        game_state.player_state(proposer),
+        game_state.player_state(accepter),
+    );
+    let tradable = |player: &PlayerState, category: &str| {
+        category != "money" && player.can_trade(CategoryId::intern(category), turn)
+    };
+    let mut candidates: Vec<(f64, Trade)> = Vec::new();
+    for (category, &count) in &other.num_goods {
+        if count < 1. || !tradable(me, category) || !tradable(other, category) {
+            continue;
+        }
+        let mut wanted = GoodsSet::new();
+        wanted.insert(category.clone(), 1.);
+
+        let value = me.score_change(&wanted, &GoodsSet::new());
+        let mut offers = vec![
+            (String::from("money"), (value / 2.).floor().max(1.)),
+            (String::from("money"), (value - 1.).max(1.)),
+        ];
+        offers.dedup();
+        for (mine, &held) in &me.num_goods {
+            if held >= 1. && tradable(me, mine) && mine != category {
+                offers.push((mine.clone(), 1.));
+            }
+        }
+        for (offered, amount) in offers {
+            let mut payment = GoodsSet::new();
+            payment.insert(offered, amount);
+            let delta = me.score_change(&wanted, &payment);
+            if delta <= 0. || !can_afford(&me.num_goods, &payment) {
+                continue;
+            }
+            let trade = Trade::new(proposer, accepter, payment, wanted.clone());
+            if game_state.trade_size_allowed(&trade) {
+                candidates.push((delta, trade));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+    candidates.truncate(max_candidates);
+    candidates.into_iter().map(|(_, trade)| trade).collect()
+}
+
+impl PlayerMinimax {
+    fn other(&self) -> PlayerId {
+        1 - self.my_id
+    }
+
+    fn evaluate(&self, game_state: &GameState) -> f64 {
+        match game_state.victor {
+            Some(victor) if victor == self.my_id => WIN,
+            Some(_) => -WIN,
+            None => {
+                game_state.player_state(self.my_id).score()
+                    - game_state.player_state(self.other()).score()
+            }
+        }
+    }
+
+    // Points the engine at the game this decision searches, with everything in it visible.
+    fn start_search(&mut self, game_state: &GameState) {
+        assert_eq!(
+            game_state.players.len(),
+            2,
+            "PlayerMinimax only plays two-player games"
+        );
+        let rules = self
+            .rules
+            .as_ref()
+            .expect("PlayerMinimax needs the game rules");
+        let game = if game_state.preferences_revealed()
+            && game_state.inventory_visibility == InventoryVisibility::Public
+        {
+            game_state.clone()
+        } else {
+            game_state.determinize(rules, self.my_id, &mut self.rng)
+        };
+        match self.engine.as_mut() {
+            Some(engine) => engine.reset(Snapshot::from(game)),
+            None => {
+                let seats = vec![create(), create()];
+                self.engine = Some(GameEngine::new(rules.clone(), seats, game));
+            }
+        }
+    }
+
+    // The value of the position after `accepter` accepts or declines `trade`, whichever is
+    // better for them, with `declined` the value of declining.
+    fn respond(
+        &mut self,
+        trade: &Trade,
+        declined: f64,
+        depth: usize,
+        alpha: f64,
+        beta: f64,
+    ) -> f64 {
+        let mine = trade.accepter == self.my_id;
+        let (alpha, beta) = if mine {
+            (alpha.max(declined), beta)
+        } else {
+            (alpha, beta.min(declined))
+        };
+        if alpha >= beta {
+            return declined;
+        }
+        let engine = self.engine.as_mut().unwrap();
+        let before = engine.snapshot();
+        if engine.step(&Action::Trade(trade.clone())).is_err() {
+            return declined;
+        }
+        let accepted = self.search(trade.accepter, depth, alpha, beta);
+        self.engine.as_mut().unwrap().restore(&before);
+        if mine {
+            accepted.max(declined)
+        } else {
+            accepted.min(declined)
+        }
+    }
+
+    // The value of the position with `depth` plies left, `proposer` to move.
+    fn search(&mut self, proposer: PlayerId, depth: usize, mut alpha: f64, mut beta: f64) -> f64 {
+        let engine = self.engine.as_ref().unwrap();
+        if depth == 0 || engine.is_over() {
+            return self.evaluate(engine.state());
+        }
+        let accepter = 1 - proposer;
+        let trades = if engine.state().may_trade(proposer, accepter) {
+            candidate_trades(
+                engine.state(),
+                proposer,
+                accepter,
+                self.config.max_candidates,
+            )
+        } else {
+            vec![]
+        };
+        let passed = self.search(accepter, depth - 1, alpha, beta);
+        let mut best = passed;
+        let maximizing = proposer == self.my_id;
+        for trade in &trades {
+            if maximizing {
+                alpha = alpha.max(best);
+            } else {
+                beta = beta.min(best);
+            }
+            if alpha >= beta {
+                break;
+            }
+            let value = self.respond(trade, passed, depth - 1, alpha, beta);
+            best = if maximizing {
+                best.max(value)
+            } else {
+                best.min(value)
+            };
+        }
+        best
+    }
+
+    // My best proposal to the other player, or None if passing is as good.
+    fn best_proposal(&mut self, game_state: &GameState) -> Option<Trade> {
+        if !game_state.may_trade(self.my_id, self.other()) || self.config.depth == 0 {
+            return None;
+        }
+        self.start_search(game_state);
+        let depth = self.config.depth;
+        let state = self.engine.as_ref().unwrap().state();
+        let trades: Vec<Trade> =
+            candidate_trades(state, self.my_id, self.other(), self.config.max_candidates)
+                .into_iter()
+                .filter(|trade| !self.proposed.contains(&proposal_key(game_state, trade)))
+                .collect();
+        let passed = self.search(self.other(), depth - 1, f64::NEG_INFINITY, f64::INFINITY);
+        let mut best = (passed, None);
+        for trade in trades {
+            let value = self.respond(&trade, passed, depth - 1, best.0, f64::INFINITY);
+            if value > best.0 {
+                best = (value, Some(trade));
+            }
+        }
+        let trade = best.1?;
+        self.proposed.insert(proposal_key(game_state, &trade));
+        Some(trade)
+    }
+
+    // Whether accepting `trade` is better for me than declining it. Ties decline.
+    fn accepts(&mut self, game_state: &GameState, trade: &Trade) -> bool {
+        if !fulfillable(game_state, trade) || self.config.depth == 0 {
+            return false;
+        }
+        self.start_search(game_state);
+        let depth = self.config.depth;
+        let declined = self.search(self.my_id, depth - 1, f64::NEG_INFINITY, f64::INFINITY);
+        let engine = self.engine.as_mut().unwrap();
+        if engine.step(&Action::Trade(trade.clone())).is_err() {
+            return false;
+        }
+        self.search(self.my_id, depth - 1, f64::NEG_INFINITY, f64::INFINITY) > declined
+    }
+}
+
+impl PlayerStrategy for PlayerMinimax {
+    fn init(&mut self, player_id: PlayerId, value: &serde_json::Value) -> Result<(), String> {
+        self.my_id = player_id;
+        let value = if value.is_null() {
+            serde_json::json!({})
+        } else {
+            value.clone()
+        };
+        self.config = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        self.rng = seeded_rng(self.config.seed);
+        self.engine = None;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.proposed.clear();
+    }
+
+    // The search alternates between two sides, this player and the other.
+    fn max_players(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn min_players(&self) -> Option<usize> {
+        Some(2)
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "depth": { "type": "integer", "minimum": 1, "default": default_depth() },
+                "max_candidates": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "default": default_max_candidates(),
+                },
+                "seed": { "type": "integer", "minimum": 0, "default": 0 },
+            },
+        })
+    }
+
+    fn set_rules(&mut self, rules: &GameRules) {
+        self.rules = Some(rules.clone());
+        self.engine = None;
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        let mut trades = HashMap::new();
+        if let Some(trade) = self.best_proposal(game_state) {
+            trades.insert(self.other(), trade);
+        }
+        trades
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        self.best_proposal(game_state)
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        let proposals: Vec<Trade> = game_state
+            .current_trade_proposals
+            .values()
+            .cloned()
+            .collect();
+        proposals
+            .iter()
+            .map(|trade| self.accepts(game_state, trade))
+            .collect()
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        self.accepts(game_state, trade)
+    }
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(PlayerMinimax {
+        my_id: 0,
+        config: serde_json::from_str("{}").unwrap(),
+        rules: None,
+        rng: seeded_rng(0),
+        engine: None,
+        proposed: HashSet::new(),
+    })
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerMinimax", create)
+}
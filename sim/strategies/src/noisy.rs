@@ -0,0 +1,404 @@
+// Wraps another registered strategy and makes mistakes, as people at the table do, so balance
+// can be checked against imperfect play. Every decision is the wrapped strategy's, except:
+// each acceptance is flipped with probability accept_flip_rate, and each chance to propose a
+// trade is taken with probability proposal_mistake_rate by a random one-for-one trade instead
+// of the wrapped strategy's proposal. A flipped partial acceptance is a rejection. Flipped
+// acceptances and random proposals are only made when the player holds what they give, so
+// mistakes never forfeit a turn. Decisions changed by a mistake are explained as mistakes.
+//
+// Config:
+//   player_type           - The strategy wrapped. Defaults to PlayerGreedy.
+//   config                - The wrapped strategy's config.
+//   accept_flip_rate      - Chance of accepting a trade the wrapped strategy rejects, or
+//                           rejecting one it accepts. Defaults to 0.05.
+//   proposal_mistake_rate - Chance of proposing a random trade to each player instead.
+//                           Defaults to 0.05.
+//   seed                  - Seeds the mistakes. 0, the default, seeds from the OS.
+use ctor::ctor;
+use rand::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use hedonica_engine::game::{GameRules, GameState};
+use hedonica_engine::orderbook::Order;
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::replay::Decision;
+use hedonica_engine::schema;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+
+#[derive(Deserialize)]
+struct NoisyConfig {
+    #[serde(default = "default_player_type")]
+    player_type: String,
+    #[serde(default)]
+    config: serde_json::Value,
+    #[serde(default = "default_rate")]
+    accept_flip_rate: f64,
+    #[serde(default = "default_rate")]
+    proposal_mistake_rate: f64,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_player_type() -> String {
+    String::from("PlayerGreedy")
+}
+fn default_rate() -> f64 {
+    0.05
+}
+
+struct PlayerNoisy {
+    my_id: PlayerId,
+    config: NoisyConfig,
+    inner: Box<dyn PlayerStrategy>,
+    rng: StdRng,
+    // Whether the last proposal or acceptance was changed by a mistake.
+    mistaken: bool,
+    // What a partial acceptance pays for part of a good, from the rules.
+    good_price: f64,
+}
+
+fn seeded_rng(seed: u64) -> StdRng {
+    match seed {
+        0 => SeedableRng::from_rng(rand::thread_rng()).unwrap(),
+        seed => SeedableRng::seed_from_u64(seed),
+    }
+}
+
+impl PlayerNoisy {
+    fn flip(&mut self) -> bool {
+        self.rng.gen_bool(self.config.accept_flip_rate)
+    }
+
+    // Whether a mistake flips an answer that accepted or rejected `trade`. Accepting by mistake
+    // needs the goods asked for in `holdings`.
+    fn flip_answer(&mut self, accepted: bool, holdings: &GoodsSet, trade: &Trade) -> bool {
+        let flip = self.flip() && (accepted || can_afford(holdings, &trade.accepter_gives()));
+        self.mistaken |= flip;
+        flip
+    }
+
+    // The wrapped strategy's answer, or the opposite whole answer after a mistake.
+    fn answer(&mut self, answer: Acceptance, holdings: &GoodsSet, trade: &Trade) -> Acceptance {
+        let accepted = answer != Acceptance::Reject;
+        if self.flip_answer(accepted, holdings, trade) {
+            (!accepted).into()
+        } else {
+            answer
+        }
+    }
+
+    fn blunder(&mut self) -> bool {
+        self.rng.gen_bool(self.config.proposal_mistake_rate)
+    }
+
+    // A one-for-one trade with `other` of a good paid for out of `holdings` for a good they
+    // show, chosen at random regardless of value.
+    fn random_trade(
+        &mut self,
+        game_state: &GameState,
+        other: PlayerId,
+        holdings: &GoodsSet,
+    ) -> Option<Trade> {
+        let turn = game_state.current_turn;
+        let me = game_state.player_state(self.my_id);
+        let them = game_state.player_state(other);
+        let mine: Vec<&String> = holdings
+            .iter()
+            .filter(|(category, &held)| {
+                *category != "money"
+                    && held >= 1.
+                    && me.can_trade(CategoryId::intern(category), turn)
+            })
+            .map(|(category, _)| category)
+            .collect();
+        let view = game_state.view(self.my_id);
+        let theirs: Vec<&String> = view
+            .inventory(other)
+            .iter()
+            .filter(|(category, &held)| {
+                *category != "money"
+                    && held >= 1.
+                    && them.can_trade(CategoryId::intern(category), turn)
+            })
+            .map(|(category, _)| category)
+            .collect();
+        let given = (*mine.choose(&mut self.rng)?).clone();
+        let wanted = theirs
+            .into_iter()
+            .filter(|&category| *category != given)
+            .collect::<Vec<&String>>()
+            .choose(&mut self.rng)
+            .map(|&category| category.clone())?;
+        let mut from_proposor = GoodsSet::new();
+        from_proposor.insert(given, 1.);
+        let mut from_acceptor = GoodsSet::new();
+        from_acceptor.insert(wanted, 1.);
+        Some(Trade::new(self.my_id, other, from_proposor, from_acceptor))
+            .filter(|trade| game_state.trade_size_allowed(trade))
+    }
+}
+
+impl PlayerStrategy for PlayerNoisy {
+    fn init(&mut self, player_id: PlayerId, value: &serde_json::Value) -> Result<(), String> {
+        self.my_id = player_id;
+        let value = if value.is_null() {
+            serde_json::json!({})
+        } else {
+            value.clone()
+        };
+        self.config = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        if self.config.player_type == "PlayerNoisy" {
+            return Err(String::from("PlayerNoisy cannot wrap itself"));
+        }
+        if !self.config.config.is_null() {
+            let schema =
+                player::config_schema(&self.config.player_type).map_err(|e| e.to_string())?;
+            schema::validate(&schema, &self.config.config, "config.config")
+                .map_err(|errors| errors.join(", "))?;
+        }
+        self.inner = player::new_strategy(&self.config.player_type, player_id, &self.config.config)
+            .map_err(|e| e.to_string())?;
+        self.rng = seeded_rng(self.config.seed);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn config_schema(&self) -> serde_json::Value {
+        let rate = |default: f64| {
+            serde_json::json!({
+                "type": "number",
+                "minimum": 0,
+                "maximum": 1,
+                "default": default,
+            })
+        };
+        serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "player_type": {
+                    "enum": player::registered_strategies()
+                        .into_iter()
+                        .filter(|name| name != "PlayerNoisy")
+                        .collect::<Vec<String>>(),
+                    "default": default_player_type(),
+                },
+                "config": {},
+                "accept_flip_rate": rate(default_rate()),
+                "proposal_mistake_rate": rate(default_rate()),
+                "seed": { "type": "integer", "minimum": 0, "default": 0 },
+            },
+        })
+    }
+
+    fn set_rules(&mut self, rules: &GameRules) {
+        self.good_price = rules.mean_good_value();
+        self.inner.set_rules(rules);
+    }
+
+    fn load_profile(&mut self, profile: &serde_json::Value, seat_labels: &[String]) {
+        self.inner.load_profile(profile, seat_labels);
+    }
+
+    fn save_profile(&self) -> Option<serde_json::Value> {
+        self.inner.save_profile()
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.inner.capabilities()
+    }
+
+    fn max_players(&self) -> Option<usize> {
+        self.inner.max_players()
+    }
+
+    fn min_players(&self) -> Option<usize> {
+        self.inner.min_players()
+    }
+
+    fn choose_preferences(&mut self, hand: &[Preferences]) -> usize {
+        self.inner.choose_preferences(hand)
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        let mut trades = self.inner.propose_trades_as_lead(game_state);
+        let my_id = self.my_id;
+        let blunders: Vec<PlayerId> = game_state
+            .trade_partners_of(my_id)
+            .into_iter()
+            .filter(|_| self.blunder())
+            .collect();
+        // Random trades are paid for out of what the proposals kept leave.
+        let mut holdings = game_state.player_state(self.my_id).num_goods.clone();
+        self.mistaken = !blunders.is_empty();
+        for other in &blunders {
+            trades.remove(other);
+        }
+        for trade in trades.values() {
+            take(&mut holdings, &trade.proposer_gives());
+        }
+        for other in blunders {
+            if trades.len() >= game_state.max_proposals() {
+                break;
+            }
+            if let Some(trade) = self.random_trade(game_state, other, &holdings) {
+                take(&mut holdings, &trade.proposer_gives());
+                trades.insert(other, trade);
+            }
+        }
+        trades
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        let trade = self.inner.propose_trade_as_non_lead(game_state);
+        self.mistaken = false;
+        if !game_state.may_trade(self.my_id, game_state.lead) || !self.blunder() {
+            return trade;
+        }
+        self.mistaken = true;
+        let holdings = game_state.player_state(self.my_id).num_goods.clone();
+        self.random_trade(game_state, game_state.lead, &holdings)
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        let accepts = self.inner.accept_trades_as_lead(game_state);
+        let mut holdings = game_state.player_state(self.my_id).num_goods.clone();
+        self.mistaken = false;
+        game_state
+            .current_trade_proposals
+            .values()
+            .zip(accepts)
+            .map(|(trade, accept)| {
+                let accept = accept != self.flip_answer(accept, &holdings, trade);
+                if accept && can_afford(&holdings, &trade.accepter_gives()) {
+                    take(&mut holdings, &trade.accepter_gives());
+                }
+                accept
+            })
+            .collect()
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        let accept = self.inner.accept_trades_as_non_lead(game_state, trade);
+        let holdings = &game_state.player_state(self.my_id).num_goods;
+        self.mistaken = false;
+        accept != self.flip_answer(accept, holdings, trade)
+    }
+
+    fn answer_trades_as_lead(&mut self, game_state: &Observation) -> Vec<Acceptance> {
+        let answers = self.inner.answer_trades_as_lead(game_state);
+        let mut holdings = game_state.player_state(self.my_id).num_goods.clone();
+        self.mistaken = false;
+        game_state
+            .current_trade_proposals
+            .values()
+            .zip(answers)
+            .map(|(trade, answer)| {
+                let answer = self.answer(answer, &holdings, trade);
+                let given = match &answer {
+                    Acceptance::Accept => trade.accepter_gives(),
+                    Acceptance::Reject => GoodsSet::new(),
+                    Acceptance::Partial(taken) => trade
+                        .partial(taken, self.good_price)
+                        .map(|trade| trade.accepter_gives())
+                        .unwrap_or_default(),
+                };
+                if can_afford(&holdings, &given) {
+                    take(&mut holdings, &given);
+                }
+                answer
+            })
+            .collect()
+    }
+
+    fn answer_trade_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> Acceptance {
+        let answer = self.inner.answer_trade_as_non_lead(game_state, trade);
+        let holdings = &game_state.player_state(self.my_id).num_goods;
+        self.mistaken = false;
+        self.answer(answer, holdings, trade)
+    }
+
+    fn counter_offer_as_lead(&mut self, game_state: &Observation, trade: &Trade) -> Option<Trade> {
+        self.mistaken = false;
+        self.inner.counter_offer_as_lead(game_state, trade)
+    }
+
+    fn counter_offer_as_non_lead(
+        &mut self,
+        game_state: &Observation,
+        trade: &Trade,
+    ) -> Option<Trade> {
+        self.mistaken = false;
+        self.inner.counter_offer_as_non_lead(game_state, trade)
+    }
+
+    fn loan_action(&mut self, game_state: &Observation) -> LoanAction {
+        self.inner.loan_action(game_state)
+    }
+
+    fn buy_insurance(&mut self, game_state: &Observation) -> bool {
+        self.inner.buy_insurance(game_state)
+    }
+
+    fn choose_action(&mut self, game_state: &Observation) -> TurnAction {
+        self.mistaken = false;
+        self.inner.choose_action(game_state)
+    }
+
+    fn place_orders(&mut self, game_state: &Observation) -> Option<Vec<Order>> {
+        self.inner.place_orders(game_state)
+    }
+
+    fn bid(&mut self, game_state: &Observation, good: &Good) -> f64 {
+        self.inner.bid(game_state, good)
+    }
+
+    fn raise_bid(&mut self, game_state: &Observation, good: &Good, price: f64) -> bool {
+        self.inner.raise_bid(game_state, good, price)
+    }
+
+    fn name_wild_category(&mut self, game_state: &Observation, good: &Good) -> CategoryId {
+        self.inner.name_wild_category(game_state, good)
+    }
+
+    fn choose_steal(&mut self, game_state: &Observation) -> Option<(PlayerId, CategoryId)> {
+        self.inner.choose_steal(game_state)
+    }
+
+    fn resigns(&self) -> bool {
+        self.inner.resigns()
+    }
+
+    fn explain_last_decision(&self, decision: Decision) -> Option<String> {
+        let inner = self.inner.explain_last_decision(decision);
+        if !self.mistaken {
+            return inner;
+        }
+        Some(match inner {
+            Some(inner) => format!("A deliberate mistake, overruling: {}", inner),
+            None => String::from("A deliberate mistake"),
+        })
+    }
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(PlayerNoisy {
+        my_id: 0,
+        config: serde_json::from_str("{}").unwrap(),
+        inner: player::new_strategy(&default_player_type(), 0, &serde_json::Value::Null).unwrap(),
+        rng: seeded_rng(0),
+        mistaken: false,
+        good_price: 0.,
+    })
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerNoisy", create)
+}
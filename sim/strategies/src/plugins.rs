@@ -0,0 +1,198 @@
+// Strategies loaded at startup from plugin libraries in a directory, so bots can be distributed
+// compiled, in any language with a C ABI, without forking the simulator. Only built with the
+// "plugins" feature, and only on unix.
+//
+// A plugin is a shared library exporting these functions, for PLUGIN_ABI_VERSION:
+//
+//   uint32_t hedonica_plugin_abi_version(void);
+//   // The names of the strategies the plugin provides, one per line.
+//   const char *hedonica_plugin_strategies(void);
+//   // A new bot playing the named strategy, or null if there is no such strategy.
+//   void *hedonica_plugin_create(const char *strategy);
+//   // The JSON response to one JSON request of protocol.rs. Freed with
+//   // hedonica_plugin_free_string. Null is a failure, which stops the batch.
+//   char *hedonica_plugin_exchange(void *bot, const char *request);
+//   void hedonica_plugin_free_string(char *response);
+//   void hedonica_plugin_destroy(void *bot);
+//
+// Each strategy is registered under its own name and played like the other remote strategies,
+// so it takes the config keys in remote.rs. A library that cannot be loaded or was built for
+// another ABI version is skipped with a warning. Loaded libraries stay loaded until exit.
+use crate::remote::{RemoteStrategy, Transport};
+use hedonica_engine::{player, warn};
+use serde_json::Value;
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_void};
+use std::path::Path;
+use std::sync::Arc;
+
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersion = unsafe extern "C" fn() -> u32;
+type Strategies = unsafe extern "C" fn() -> *const c_char;
+type Create = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+type Exchange = unsafe extern "C" fn(*mut c_void, *const c_char) -> *mut c_char;
+type FreeString = unsafe extern "C" fn(*mut c_char);
+type Destroy = unsafe extern "C" fn(*mut c_void);
+
+struct Plugin {
+    path: String,
+    create: Create,
+    exchange: Exchange,
+    free_string: FreeString,
+    destroy: Destroy,
+}
+
+// The library is never unloaded, and the functions are required to be callable from any
+// thread, one bot at a time.
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+struct PluginBot {
+    plugin: Arc<Plugin>,
+    bot: *mut c_void,
+}
+
+pub(crate) fn plugin_error(message: String) -> io::Error {
+    io::Error::other(message)
+}
+
+pub(crate) fn dl_error() -> String {
+    let error = unsafe { libc::dlerror() };
+    if error.is_null() {
+        String::from("unknown error")
+    } else {
+        unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Transport for PluginBot {
+    fn exchange(&mut self, request: &Value) -> io::Result<Value> {
+        let request = CString::new(request.to_string()).unwrap();
+        let response = unsafe { (self.plugin.exchange)(self.bot, request.as_ptr()) };
+        if response.is_null() {
+            return Err(plugin_error(format!(
+                "{} failed to answer",
+                self.plugin.path
+            )));
+        }
+        let text = unsafe { CStr::from_ptr(response) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { (self.plugin.free_string)(response) };
+        serde_json::from_str(&text).map_err(io::Error::from)
+    }
+}
+
+impl Drop for PluginBot {
+    fn drop(&mut self) {
+        unsafe { (self.plugin.destroy)(self.bot) };
+    }
+}
+
+fn connect(plugin: &Arc<Plugin>, strategy: &CStr) -> io::Result<Box<dyn Transport>> {
+    let bot = unsafe { (plugin.create)(strategy.as_ptr()) };
+    if bot.is_null() {
+        return Err(plugin_error(format!(
+            "{} could not create {}",
+            plugin.path,
+            strategy.to_string_lossy()
+        )));
+    }
+    Ok(Box::new(PluginBot {
+        plugin: plugin.clone(),
+        bot,
+    }))
+}
+
+// The function the library exports under this name, as type F.
+pub(crate) unsafe fn symbol<F: Copy>(library: *mut c_void, name: &str) -> Result<F, String> {
+    let c_name = CString::new(name).unwrap();
+    let symbol = libc::dlsym(library, c_name.as_ptr());
+    if symbol.is_null() {
+        Err(format!("missing {}", name))
+    } else {
+        Ok(std::mem::transmute_copy::<*mut c_void, F>(&symbol))
+    }
+}
+
+// Loads one plugin and registers its strategies, returning their names.
+fn load(path: &Path) -> Result<Vec<String>, String> {
+    let c_path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+    let library = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if library.is_null() {
+        return Err(dl_error());
+    }
+    let registered = unsafe { register(library, path) };
+    if registered.is_err() {
+        unsafe { libc::dlclose(library) };
+    }
+    registered
+}
+
+unsafe fn register(library: *mut c_void, path: &Path) -> Result<Vec<String>, String> {
+    let abi_version: AbiVersion = symbol(library, "hedonica_plugin_abi_version")?;
+    let version = abi_version();
+    if version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "built for plugin ABI version {}, not {}",
+            version, PLUGIN_ABI_VERSION
+        ));
+    }
+    let strategies: Strategies = symbol(library, "hedonica_plugin_strategies")?;
+    let plugin = Arc::new(Plugin {
+        path: path.display().to_string(),
+        create: symbol(library, "hedonica_plugin_create")?,
+        exchange: symbol(library, "hedonica_plugin_exchange")?,
+        free_string: symbol(library, "hedonica_plugin_free_string")?,
+        destroy: symbol(library, "hedonica_plugin_destroy")?,
+    });
+
+    let names: Vec<String> = CStr::from_ptr(strategies())
+        .to_string_lossy()
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect();
+    for name in &names {
+        let plugin = plugin.clone();
+        let strategy = CString::new(name.as_str()).unwrap();
+        let player_type = name.clone();
+        player::register_strategy(name, move || {
+            let plugin = plugin.clone();
+            let strategy = strategy.clone();
+            Box::new(RemoteStrategy::new(&player_type, move |_: &Value| {
+                connect(&plugin, &strategy)
+            }))
+        });
+    }
+    Ok(names)
+}
+
+// Loads every shared library in the directory, in name order, and returns the names of the
+// strategies registered.
+pub fn load_dir(dir: &Path) -> io::Result<Vec<String>> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    paths.retain(|path| {
+        matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("so") | Some("dylib")
+        )
+    });
+    paths.sort();
+
+    let mut registered = Vec::new();
+    for path in paths {
+        match load(&path) {
+            Ok(names) => registered.extend(names),
+            Err(error) => warn!("Skipping plugin {}: {}", path.display(), error),
+        }
+    }
+    Ok(registered)
+}
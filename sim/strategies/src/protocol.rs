@@ -0,0 +1,654 @@
+// Protocol spoken with strategies that run outside the simulator.
+//
+// The engine sends one request per line as JSON, and the bot answers each request with
+// exactly one line of JSON, over the bot's stdin and stdout for PlayerSubprocess. Every request
+// has a "type" field naming the PlayerStrategy method it corresponds to, and the response
+// carries that method's return value. Over WebSocket (PlayerRemoteWs), each request and
+// response is one text message instead of a line.
+//
+//   init                        -> {}
+//   reset                       -> {}
+//   choose_preferences          -> {"choice": integer}
+//   propose_trades_as_lead      -> {"trades": {player_id: Trade}}
+//   propose_trade_as_non_lead   -> {"trade": Trade | null}
+//   accept_trades_as_lead       -> {"acceptances": [bool]}
+//   accept_trades_as_non_lead   -> {"accept": bool}
+//   answer_trades_as_lead       -> {"answers": [Acceptance]}
+//   answer_trade_as_non_lead    -> {"answer": Acceptance}
+//   counter_offer_as_lead       -> {"trade": Trade | null}
+//   counter_offer_as_non_lead   -> {"trade": Trade | null}
+//   loan_action                 -> {"action": "none" | "borrow" | "repay"}
+//   buy_insurance               -> {"buy": bool}
+//   choose_action               -> {"action": TurnAction}
+//   place_orders                -> {"orders": [Order] | null}
+//   bid                         -> {"bid": number}
+//   raise_bid                   -> {"raise_price": bool}
+//   name_wild_category          -> {"category": string}
+//   choose_steal                -> {"steal": {"player": integer, "category": string} | null}
+//
+// counter_offer_as_lead and counter_offer_as_non_lead are sent only when the rules allow
+// counter-offers, with the trade the bot just rejected. A counter-offer is a trade back to that
+// trade's proposer, with the bot as the proposer.
+//
+// choose_preferences is sent at the start of a game only when the rules deal each player a
+// hand of several preference cards, with the hand, and answers with the index of the card to
+// keep.
+//
+// raise_bid is sent in English auctions, with the price the bot would bid to outbid the
+// standing bid.
+//
+// name_wild_category and choose_steal are sent when the bot draws a wild or steal card as
+// lead, if the rules deal special cards. Goods in the deck and in bid requests are a category
+// name, or an object for special cards (see cards.rs in the engine).
+//
+// accept_trades_as_lead and answer_trades_as_lead answer in the order the proposals appear in
+// game_state.current_trade_proposals.
+//
+// answer_trades_as_lead and answer_trade_as_non_lead are sent in place of the accept requests
+// when the rules allow partial acceptance and the bot lists the PartialAcceptance capability.
+// An Acceptance is "accept", "reject" or {"partial": GoodsSet}, the whole goods of the
+// proposer's offer the bot takes, for which it gives back a pro-rated share of what was asked,
+// rounded to whole goods with the rest settled in money.
+//
+// Any response may also carry an "explanation" string saying why the bot decided as it did,
+// which is kept in replays for the proposals, acceptances, counter-offers and turn actions.
+//
+// game_state is the game as the bot's seat observes it (see view.rs in the engine): preference
+// cards and goods the rules hide are masked, and the decks are sorted so their order is hidden.
+// Other players' preference cards are hidden unless the rules' preference_visibility reveals
+// them; their scores, if public, are in shown_score.
+use serde_json::{json, Map, Value};
+
+pub const PROTOCOL_VERSION: u32 = 7;
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/definitions/{}", name) })
+}
+
+fn array_of(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn map_of(values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values })
+}
+
+fn nullable(value: Value) -> Value {
+    json!({ "oneOf": [value, { "type": "null" }] })
+}
+
+fn object(properties: Value) -> Value {
+    let required: Vec<Value> = properties
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(|k| Value::String(k.clone()))
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+// A response that may also carry the bot's explanation of its decision.
+fn explained(mut response: Value) -> Value {
+    response["properties"]["explanation"] = json!({ "type": "string" });
+    response
+}
+
+// An object tagged with {"type": kind}, like serde's internally tagged enums.
+fn request(kind: &str, fields: Value) -> Value {
+    let mut properties = fields.as_object().cloned().unwrap_or_default();
+    properties.insert(String::from("type"), json!({ "const": kind }));
+    object(Value::Object(properties))
+}
+
+// JSON schema (draft-07) for every message in the protocol.
+pub fn schema() -> Value {
+    let mut definitions = Map::new();
+    let mut define = |name: &str, value: Value| definitions.insert(String::from(name), value);
+
+    define("PlayerId", json!({ "type": "integer", "minimum": 0 }));
+    define("GoodsSet", map_of(json!({ "type": "number" })));
+    define("Preferences", map_of(json!({ "type": "number" })));
+    let mut trade = object(json!({
+        "proposer": reference("PlayerId"),
+        "accepter": reference("PlayerId"),
+        "from_proposor": reference("GoodsSet"),
+        "from_acceptor": reference("GoodsSet"),
+        "money": { "type": "number" },
+        "counter_offers": { "type": "integer" },
+    }));
+    // Trades may leave out the payment and the counter-offer count, which default to 0.
+    trade["required"]
+        .as_array_mut()
+        .unwrap()
+        .retain(|field| field != "money" && field != "counter_offers");
+    define("Trade", trade);
+    define(
+        "GoodEffect",
+        json!({ "oneOf": [{ "const": "wild" }, { "const": "steal" }, { "const": "draw_two" }] }),
+    );
+    let mut special_good = object(json!({
+        "category": { "type": "string" },
+        "amount": { "type": "number" },
+        "effect": reference("GoodEffect"),
+    }));
+    special_good["required"] = json!(["category"]);
+    define("SpecialGood", special_good);
+    define(
+        "Good",
+        json!({ "oneOf": [{ "type": "string" }, reference("SpecialGood")] }),
+    );
+    define(
+        "Loan",
+        object(json!({
+            "owed": { "type": "number" },
+            "turns_left": { "type": "integer" },
+        })),
+    );
+    define(
+        "LoanAction",
+        json!({ "oneOf": [{ "const": "none" }, { "const": "borrow" }, { "const": "repay" }] }),
+    );
+    define(
+        "Event",
+        json!({ "oneOf": [{ "const": "calm" }, { "const": "tax" }, { "const": "spoilage" }] }),
+    );
+    define(
+        "EventLedger",
+        object(json!({
+            "insured": { "type": "boolean" },
+            "negative_events": { "type": "integer" },
+            "losses": { "type": "number" },
+            "insurance_offers": { "type": "integer" },
+            "insurance_bought": { "type": "integer" },
+            "premiums": { "type": "number" },
+            "claims": { "type": "integer" },
+            "losses_avoided": { "type": "number" },
+        })),
+    );
+    define("DrawAction", request("draw", json!({})));
+    define("TradeRoundAction", request("trade_round", json!({})));
+    define(
+        "ConvertAction",
+        request("convert", json!({ "category": { "type": "string" } })),
+    );
+    define("EndTurnAction", request("end_turn", json!({})));
+    define(
+        "TurnAction",
+        json!({ "oneOf": [
+            reference("DrawAction"),
+            reference("TradeRoundAction"),
+            reference("ConvertAction"),
+            reference("EndTurnAction"),
+        ]}),
+    );
+    define(
+        "ActionCounts",
+        object(json!({
+            "draws": { "type": "integer" },
+            "trade_rounds": { "type": "integer" },
+            "converts": { "type": "integer" },
+        })),
+    );
+    define(
+        "Side",
+        json!({ "oneOf": [{ "const": "buy" }, { "const": "sell" }] }),
+    );
+    define(
+        "Order",
+        object(json!({
+            "category": { "type": "string" },
+            "side": reference("Side"),
+            "price": { "type": "number" },
+            "quantity": { "type": "number" },
+        })),
+    );
+    define(
+        "RestingOrder",
+        object(json!({
+            "id": { "type": "integer" },
+            "player": reference("PlayerId"),
+            "order": reference("Order"),
+        })),
+    );
+    define(
+        "OrderBook",
+        object(json!({
+            "orders": array_of(reference("RestingOrder")),
+            "next_id": { "type": "integer" },
+        })),
+    );
+    define(
+        "Fill",
+        object(json!({
+            "buyer": reference("PlayerId"),
+            "seller": reference("PlayerId"),
+            "category": { "type": "string" },
+            "quantity": { "type": "number" },
+            "price": { "type": "number" },
+        })),
+    );
+    define(
+        "BidRecord",
+        object(json!({
+            "turn": { "type": "integer" },
+            "lead": reference("PlayerId"),
+            "category": { "type": "string" },
+            "bids": array_of(nullable(json!({ "type": "number" }))),
+            "winner": reference("PlayerId"),
+        })),
+    );
+    define(
+        "MilestoneClaim",
+        object(json!({
+            "milestone": { "type": "integer" },
+            "player": reference("PlayerId"),
+            "turn": { "type": "integer" },
+        })),
+    );
+    define(
+        "ScoreVisibility",
+        json!({ "oneOf": [{ "const": "public" }, { "const": "hidden" }] }),
+    );
+    define(
+        "PreferenceVisibility",
+        json!({ "oneOf": [{ "const": "hidden" }, { "const": "public" }] }),
+    );
+    define(
+        "InventoryVisibility",
+        json!({ "oneOf": [{ "const": "public" }, { "const": "counts_only" }] }),
+    );
+    define(
+        "TradePartners",
+        json!({ "oneOf": [
+            { "const": "anyone" },
+            { "const": "lead" },
+            { "const": "adjacent" },
+        ]}),
+    );
+    define(
+        "TradeLimits",
+        object(json!({
+            "max_goods_per_trade": nullable(json!({ "type": "integer", "minimum": 0 })),
+            "max_trades_per_turn": nullable(json!({ "type": "integer", "minimum": 0 })),
+            "max_proposals_per_round": nullable(json!({ "type": "integer", "minimum": 0 })),
+        })),
+    );
+    define(
+        "UtilityCurve",
+        json!({ "oneOf": [
+            { "const": "constant" },
+            object(json!({ "linear": { "type": "number" } })),
+            { "const": "sqrt" },
+            { "const": "log" },
+        ]}),
+    );
+    define(
+        "ScoringModel",
+        json!({ "oneOf": [
+            request("linear", json!({})),
+            request("set_bonus", json!({ "bonus": { "type": "number" } })),
+            request("diminishing", json!({ "factor": { "type": "number" } })),
+            request("weighted", json!({ "weights": reference("GoodsSet") })),
+        ]}),
+    );
+    define(
+        "TradeError",
+        json!({ "oneOf": [
+            request("unknown_player", json!({ "player": reference("PlayerId") })),
+            request("eliminated_player", json!({ "player": reference("PlayerId") })),
+            request("self_trade", json!({})),
+            request("wrong_parties", json!({})),
+            request("unknown_category", json!({ "category": { "type": "string" } })),
+            request(
+                "invalid_amount",
+                json!({ "category": { "type": "string" }, "amount": { "type": "number" } }),
+            ),
+            request(
+                "unaffordable",
+                json!({
+                    "player": reference("PlayerId"),
+                    "category": { "type": "string" },
+                    "needed": { "type": "number" },
+                    "held": { "type": "number" },
+                }),
+            ),
+            request("on_cooldown", json!({ "category": { "type": "string" } })),
+            request("not_partners", json!({})),
+            request(
+                "too_many_goods",
+                json!({ "goods": { "type": "number" }, "max": { "type": "integer" } }),
+            ),
+            request("too_many_trades", json!({ "max": { "type": "integer" } })),
+            request("too_many_proposals", json!({ "max": { "type": "integer" } })),
+            request(
+                "not_offered",
+                json!({
+                    "category": { "type": "string" },
+                    "taken": { "type": "number" },
+                    "offered": { "type": "number" },
+                }),
+            ),
+        ]}),
+    );
+    define(
+        "TradeErrorRecord",
+        object(json!({
+            "turn": { "type": "integer" },
+            "round": { "type": "integer" },
+            "player": reference("PlayerId"),
+            "error": reference("TradeError"),
+        })),
+    );
+    let mut player_state = object(json!({
+        "preferences": reference("Preferences"),
+        "num_goods": reference("GoodsSet"),
+        "eliminated": { "type": "boolean" },
+        "penalty": { "type": "number" },
+        "bailouts": { "type": "integer" },
+        "loans": array_of(reference("Loan")),
+        "loans_taken": { "type": "integer" },
+        "loan_defaults": { "type": "integer" },
+        "events": reference("EventLedger"),
+        "cooldowns": map_of(json!({ "type": "integer" })),
+        "face_down_goods": { "type": "number" },
+        "utility_curve": reference("UtilityCurve"),
+        "scoring": reference("ScoringModel"),
+        "shown_score": { "type": "number" },
+    }));
+    // Only other players' states carry shown_score, when their preference cards are hidden.
+    player_state["required"]
+        .as_array_mut()
+        .unwrap()
+        .retain(|field| field != "shown_score");
+    define("PlayerState", player_state);
+    define(
+        "GameState",
+        object(json!({
+            "deck": array_of(reference("Good")),
+            "discards": array_of(reference("Good")),
+            "players": array_of(reference("PlayerState")),
+            "lead": reference("PlayerId"),
+            "current_turn": { "type": "integer" },
+            "current_round": { "type": "integer" },
+            "current_trade_proposals": map_of(reference("Trade")),
+            "current_trades": array_of(reference("Trade")),
+            "past_trades": map_of(array_of(reference("Trade"))),
+            "eliminations": array_of(reference("PlayerId")),
+            "event_deck": array_of(reference("Event")),
+            "events_drawn": { "type": "integer" },
+            "action_points_left": { "type": "integer" },
+            "turn_actions": array_of(reference("TurnAction")),
+            "action_counts": reference("ActionCounts"),
+            "order_book": reference("OrderBook"),
+            "fills": array_of(reference("Fill")),
+            "spread_total": { "type": "number" },
+            "spread_samples": { "type": "integer" },
+            "bid_history": array_of(reference("BidRecord")),
+            "milestone_claims": array_of(reference("MilestoneClaim")),
+            "trade_errors": array_of(reference("TradeErrorRecord")),
+            "forfeited": array_of(reference("PlayerId")),
+            "aborted": { "type": "boolean" },
+            "score_visibility": reference("ScoreVisibility"),
+            "preference_visibility": reference("PreferenceVisibility"),
+            "inventory_visibility": reference("InventoryVisibility"),
+            "trade_partners": reference("TradePartners"),
+            "trade_limits": reference("TradeLimits"),
+        })),
+    );
+
+    define(
+        "InitRequest",
+        request(
+            "init",
+            json!({ "player_id": reference("PlayerId"), "config": {} }),
+        ),
+    );
+    define("ResetRequest", request("reset", json!({})));
+    define(
+        "ChoosePreferencesRequest",
+        request(
+            "choose_preferences",
+            json!({ "hand": array_of(reference("Preferences")) }),
+        ),
+    );
+    define(
+        "ProposeTradesAsLeadRequest",
+        request(
+            "propose_trades_as_lead",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "ProposeTradeAsNonLeadRequest",
+        request(
+            "propose_trade_as_non_lead",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "AcceptTradesAsLeadRequest",
+        request(
+            "accept_trades_as_lead",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "AcceptTradesAsNonLeadRequest",
+        request(
+            "accept_trades_as_non_lead",
+            json!({ "game_state": reference("GameState"), "trade": reference("Trade") }),
+        ),
+    );
+    define(
+        "AnswerTradesAsLeadRequest",
+        request(
+            "answer_trades_as_lead",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "AnswerTradeAsNonLeadRequest",
+        request(
+            "answer_trade_as_non_lead",
+            json!({ "game_state": reference("GameState"), "trade": reference("Trade") }),
+        ),
+    );
+    define(
+        "CounterOfferAsLeadRequest",
+        request(
+            "counter_offer_as_lead",
+            json!({ "game_state": reference("GameState"), "trade": reference("Trade") }),
+        ),
+    );
+    define(
+        "CounterOfferAsNonLeadRequest",
+        request(
+            "counter_offer_as_non_lead",
+            json!({ "game_state": reference("GameState"), "trade": reference("Trade") }),
+        ),
+    );
+    define(
+        "LoanActionRequest",
+        request(
+            "loan_action",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "BuyInsuranceRequest",
+        request(
+            "buy_insurance",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "ChooseActionRequest",
+        request(
+            "choose_action",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "PlaceOrdersRequest",
+        request(
+            "place_orders",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "BidRequest",
+        request(
+            "bid",
+            json!({ "game_state": reference("GameState"), "good": reference("Good") }),
+        ),
+    );
+    define(
+        "RaiseBidRequest",
+        request(
+            "raise_bid",
+            json!({
+                "game_state": reference("GameState"),
+                "good": reference("Good"),
+                "price": { "type": "number" },
+            }),
+        ),
+    );
+    define(
+        "NameWildCategoryRequest",
+        request(
+            "name_wild_category",
+            json!({ "game_state": reference("GameState"), "good": reference("Good") }),
+        ),
+    );
+    define(
+        "ChooseStealRequest",
+        request(
+            "choose_steal",
+            json!({ "game_state": reference("GameState") }),
+        ),
+    );
+    define(
+        "Request",
+        json!({ "oneOf": [
+            reference("InitRequest"),
+            reference("ResetRequest"),
+            reference("ChoosePreferencesRequest"),
+            reference("ProposeTradesAsLeadRequest"),
+            reference("ProposeTradeAsNonLeadRequest"),
+            reference("AcceptTradesAsLeadRequest"),
+            reference("AcceptTradesAsNonLeadRequest"),
+            reference("AnswerTradesAsLeadRequest"),
+            reference("AnswerTradeAsNonLeadRequest"),
+            reference("CounterOfferAsLeadRequest"),
+            reference("CounterOfferAsNonLeadRequest"),
+            reference("LoanActionRequest"),
+            reference("BuyInsuranceRequest"),
+            reference("ChooseActionRequest"),
+            reference("PlaceOrdersRequest"),
+            reference("BidRequest"),
+            reference("RaiseBidRequest"),
+            reference("NameWildCategoryRequest"),
+            reference("ChooseStealRequest"),
+        ]}),
+    );
+
+    define("AckResponse", object(json!({})));
+    define(
+        "ChoosePreferencesResponse",
+        object(json!({ "choice": { "type": "integer", "minimum": 0 } })),
+    );
+    define(
+        "ProposeTradesAsLeadResponse",
+        explained(object(json!({ "trades": map_of(reference("Trade")) }))),
+    );
+    define(
+        "ProposeTradeAsNonLeadResponse",
+        explained(object(json!({ "trade": nullable(reference("Trade")) }))),
+    );
+    define(
+        "AcceptTradesAsLeadResponse",
+        explained(object(
+            json!({ "acceptances": array_of(json!({ "type": "boolean" })) }),
+        )),
+    );
+    define(
+        "AcceptTradesAsNonLeadResponse",
+        explained(object(json!({ "accept": { "type": "boolean" } }))),
+    );
+    define(
+        "PartialAcceptance",
+        object(json!({ "partial": reference("GoodsSet") })),
+    );
+    define(
+        "Acceptance",
+        json!({ "oneOf": [
+            { "const": "accept" },
+            { "const": "reject" },
+            reference("PartialAcceptance"),
+        ]}),
+    );
+    define(
+        "AnswerTradesAsLeadResponse",
+        explained(object(
+            json!({ "answers": array_of(reference("Acceptance")) }),
+        )),
+    );
+    define(
+        "AnswerTradeAsNonLeadResponse",
+        explained(object(json!({ "answer": reference("Acceptance") }))),
+    );
+    define(
+        "CounterOfferResponse",
+        explained(object(json!({ "trade": nullable(reference("Trade")) }))),
+    );
+    define(
+        "LoanActionResponse",
+        object(json!({ "action": reference("LoanAction") })),
+    );
+    define(
+        "BuyInsuranceResponse",
+        object(json!({ "buy": { "type": "boolean" } })),
+    );
+    define(
+        "ChooseActionResponse",
+        explained(object(json!({ "action": reference("TurnAction") }))),
+    );
+    define(
+        "PlaceOrdersResponse",
+        object(json!({ "orders": nullable(array_of(reference("Order"))) })),
+    );
+    define(
+        "BidResponse",
+        object(json!({ "bid": { "type": "number" } })),
+    );
+    define(
+        "RaiseBidResponse",
+        object(json!({ "raise_price": { "type": "boolean" } })),
+    );
+    define(
+        "NameWildCategoryResponse",
+        object(json!({ "category": { "type": "string" } })),
+    );
+    define(
+        "Steal",
+        object(json!({
+            "player": reference("PlayerId"),
+            "category": { "type": "string" },
+        })),
+    );
+    define(
+        "ChooseStealResponse",
+        object(json!({ "steal": nullable(reference("Steal")) })),
+    );
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Hedonica strategy protocol",
+        "version": PROTOCOL_VERSION,
+        "definitions": definitions,
+    })
+}
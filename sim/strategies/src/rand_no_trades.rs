@@ -0,0 +1,44 @@
+use ctor::ctor;
+use hedonica_engine::player;
+use hedonica_engine::player::*;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+use std::collections::HashMap;
+
+struct PlayerNoTrades {}
+impl PlayerStrategy for PlayerNoTrades {
+    fn init(&mut self, _player_id: PlayerId, _value: &serde_json::Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn reset(&mut self) {}
+
+    fn config_schema(&self) -> serde_json::Value {
+        serde_json::json!({ "type": "object", "additionalProperties": false })
+    }
+
+    fn propose_trades_as_lead(&mut self, _game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        HashMap::new()
+    }
+
+    fn propose_trade_as_non_lead(&mut self, _game_state: &Observation) -> Option<Trade> {
+        None
+    }
+
+    fn accept_trades_as_lead(&mut self, _game_state: &Observation) -> Vec<bool> {
+        vec![false; _game_state.current_trade_proposals.len()]
+    }
+
+    fn accept_trades_as_non_lead(&mut self, _game_state: &Observation, _trade: &Trade) -> bool {
+        false
+    }
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(PlayerNoTrades {})
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerNoTrades", create)
+}
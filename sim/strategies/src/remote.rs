@@ -0,0 +1,250 @@
+// Strategies that run outside the simulator, speaking the protocol in protocol.rs over some
+// transport. Each PlayerStrategy call becomes one request, and the strategy waits for the
+// response. The transport is opened when the strategy is initialized.
+//
+// Config, besides whatever the transport needs:
+//   bot_config   - Passed to the bot in the init request.
+//   capabilities - The optional mechanics the bot implements, e.g. ["Loans", "OrderBook"].
+//                  Mechanics not listed get the engine's fallback behavior.
+//
+// A bot that breaks the protocol, or a transport that fails, stops the batch.
+use hedonica_engine::category;
+use hedonica_engine::orderbook::Order;
+use hedonica_engine::player::{Capability, PlayerStrategy};
+use hedonica_engine::replay::Decision;
+use hedonica_engine::types::*;
+use hedonica_engine::view::Observation;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io;
+
+pub trait Transport {
+    // Sends one request and waits for its response.
+    fn exchange(&mut self, request: &Value) -> io::Result<Value>;
+}
+
+pub type Connect = Box<dyn Fn(&Value) -> io::Result<Box<dyn Transport>>>;
+
+pub struct RemoteStrategy {
+    player_type: String,
+    connect: Connect,
+    transport: Option<Box<dyn Transport>>,
+    capabilities: Vec<Capability>,
+    // Schemas for the config keys the transport reads, for config_schema.
+    transport_keys: Value,
+    required_keys: Vec<&'static str>,
+    // The explanation the bot sent with its last response, if any.
+    explanation: Option<String>,
+}
+
+impl RemoteStrategy {
+    pub fn new(
+        player_type: &str,
+        connect: impl Fn(&Value) -> io::Result<Box<dyn Transport>> + 'static,
+    ) -> RemoteStrategy {
+        RemoteStrategy {
+            player_type: player_type.to_string(),
+            connect: Box::new(connect),
+            transport: None,
+            capabilities: Vec::new(),
+            transport_keys: json!({}),
+            required_keys: Vec::new(),
+            explanation: None,
+        }
+    }
+
+    // Declares the config keys the transport reads, as schemas by key, and which of them it
+    // cannot do without.
+    pub fn with_transport_keys(mut self, keys: Value, required: &[&'static str]) -> RemoteStrategy {
+        self.transport_keys = keys;
+        self.required_keys = required.to_vec();
+        self
+    }
+
+    fn call(&mut self, request: Value) -> Value {
+        let kind = request["type"].clone();
+        let player_type = &self.player_type;
+        let transport = self
+            .transport
+            .as_mut()
+            .unwrap_or_else(|| panic!("{} was used before init", player_type));
+        transport.exchange(&request).unwrap_or_else(|e| {
+            panic!("{} failed to answer a {} request: {}", player_type, kind, e)
+        })
+    }
+
+    // Calls the bot and reads one field of its response.
+    fn ask<T: DeserializeOwned>(&mut self, request: Value, field: &str) -> T {
+        let kind = request["type"].clone();
+        let mut response = self.call(request);
+        self.explanation = response["explanation"].as_str().map(String::from);
+        let value = response[field].take();
+        // A bot may not add categories, so an unknown one is a bad response.
+        category::known_only(|| serde_json::from_value(value)).unwrap_or_else(|e| {
+            panic!(
+                "{} sent a bad \"{}\" in its {} response: {}",
+                self.player_type, field, kind, e
+            )
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Steal {
+    player: PlayerId,
+    category: CategoryId,
+}
+
+fn with_state(kind: &str, game_state: &Observation) -> Value {
+    json!({ "type": kind, "game_state": game_state })
+}
+
+impl PlayerStrategy for RemoteStrategy {
+    fn init(&mut self, player_id: PlayerId, value: &Value) -> Result<(), String> {
+        let transport = (self.connect)(value)
+            .map_err(|e| format!("{} could not connect: {}", self.player_type, e))?;
+        self.transport = Some(transport);
+        self.capabilities =
+            serde_json::from_value(value["capabilities"].clone()).unwrap_or_default();
+        self.call(json!({
+            "type": "init",
+            "player_id": player_id,
+            "config": value["bot_config"],
+        }));
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.call(json!({ "type": "reset" }));
+    }
+
+    fn config_schema(&self) -> Value {
+        let mut properties = json!({
+            "bot_config": { "description": "Passed to the bot in the init request" },
+            "capabilities": {
+                "type": "array",
+                "items": { "enum": Capability::ALL },
+            },
+        });
+        for (key, schema) in self.transport_keys.as_object().into_iter().flatten() {
+            properties[key] = schema.clone();
+        }
+        json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": properties,
+            "required": self.required_keys,
+        })
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.capabilities.clone()
+    }
+
+    fn choose_preferences(&mut self, hand: &[Preferences]) -> usize {
+        self.ask(
+            json!({ "type": "choose_preferences", "hand": hand }),
+            "choice",
+        )
+    }
+
+    fn propose_trades_as_lead(&mut self, game_state: &Observation) -> HashMap<PlayerId, Trade> {
+        self.ask(with_state("propose_trades_as_lead", game_state), "trades")
+    }
+
+    fn propose_trade_as_non_lead(&mut self, game_state: &Observation) -> Option<Trade> {
+        self.ask(with_state("propose_trade_as_non_lead", game_state), "trade")
+    }
+
+    fn accept_trades_as_lead(&mut self, game_state: &Observation) -> Vec<bool> {
+        self.ask(
+            with_state("accept_trades_as_lead", game_state),
+            "acceptances",
+        )
+    }
+
+    fn accept_trades_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> bool {
+        let mut request = with_state("accept_trades_as_non_lead", game_state);
+        request["trade"] = json!(trade);
+        self.ask(request, "accept")
+    }
+
+    fn answer_trades_as_lead(&mut self, game_state: &Observation) -> Vec<Acceptance> {
+        if !self.capabilities.contains(&Capability::PartialAcceptance) {
+            let acceptances = self.accept_trades_as_lead(game_state);
+            return acceptances.into_iter().map(Acceptance::from).collect();
+        }
+        self.ask(with_state("answer_trades_as_lead", game_state), "answers")
+    }
+
+    fn answer_trade_as_non_lead(&mut self, game_state: &Observation, trade: &Trade) -> Acceptance {
+        if !self.capabilities.contains(&Capability::PartialAcceptance) {
+            return self.accept_trades_as_non_lead(game_state, trade).into();
+        }
+        let mut request = with_state("answer_trade_as_non_lead", game_state);
+        request["trade"] = json!(trade);
+        self.ask(request, "answer")
+    }
+
+    fn counter_offer_as_lead(&mut self, game_state: &Observation, trade: &Trade) -> Option<Trade> {
+        let mut request = with_state("counter_offer_as_lead", game_state);
+        request["trade"] = json!(trade);
+        self.ask(request, "trade")
+    }
+
+    fn counter_offer_as_non_lead(
+        &mut self,
+        game_state: &Observation,
+        trade: &Trade,
+    ) -> Option<Trade> {
+        let mut request = with_state("counter_offer_as_non_lead", game_state);
+        request["trade"] = json!(trade);
+        self.ask(request, "trade")
+    }
+
+    fn loan_action(&mut self, game_state: &Observation) -> LoanAction {
+        self.ask(with_state("loan_action", game_state), "action")
+    }
+
+    fn buy_insurance(&mut self, game_state: &Observation) -> bool {
+        self.ask(with_state("buy_insurance", game_state), "buy")
+    }
+
+    fn choose_action(&mut self, game_state: &Observation) -> TurnAction {
+        self.ask(with_state("choose_action", game_state), "action")
+    }
+
+    fn place_orders(&mut self, game_state: &Observation) -> Option<Vec<Order>> {
+        self.ask(with_state("place_orders", game_state), "orders")
+    }
+
+    fn bid(&mut self, game_state: &Observation, good: &Good) -> f64 {
+        let mut request = with_state("bid", game_state);
+        request["good"] = json!(good);
+        self.ask(request, "bid")
+    }
+
+    fn raise_bid(&mut self, game_state: &Observation, good: &Good, price: f64) -> bool {
+        let mut request = with_state("raise_bid", game_state);
+        request["good"] = json!(good);
+        request["price"] = json!(price);
+        self.ask(request, "raise_price")
+    }
+
+    fn name_wild_category(&mut self, game_state: &Observation, good: &Good) -> CategoryId {
+        let mut request = with_state("name_wild_category", game_state);
+        request["good"] = json!(good);
+        self.ask(request, "category")
+    }
+
+    fn choose_steal(&mut self, game_state: &Observation) -> Option<(PlayerId, CategoryId)> {
+        let steal: Option<Steal> = self.ask(with_state("choose_steal", game_state), "steal");
+        steal.map(|steal| (steal.player, steal.category))
+    }
+
+    fn explain_last_decision(&self, _decision: Decision) -> Option<String> {
+        self.explanation.clone()
+    }
+}
@@ -0,0 +1,48 @@
+// A bot served over WebSocket, e.g. one written in Python or JavaScript. Each request in
+// protocol.rs is sent as one text message, and the server answers it with one text message.
+// See remote.rs for the rest of the config.
+//
+// Config:
+//   url          - The ws:// endpoint to connect to.
+//   timeout_ms   - How long to wait for each response before failing. Waits forever if unset.
+use ctor::ctor;
+
+use crate::remote::{RemoteStrategy, Transport};
+use crate::websocket::WebSocket;
+use hedonica_engine::player;
+use hedonica_engine::player::PlayerStrategy;
+use serde_json::Value;
+use std::io;
+use std::time::Duration;
+
+impl Transport for WebSocket {
+    fn exchange(&mut self, request: &Value) -> io::Result<Value> {
+        self.send_text(&request.to_string())?;
+        serde_json::from_str(&self.read_text()?).map_err(io::Error::from)
+    }
+}
+
+fn connect(config: &Value) -> io::Result<Box<dyn Transport>> {
+    let url = config["url"]
+        .as_str()
+        .expect("PlayerRemoteWs needs a url in its config");
+    let timeout = config["timeout_ms"].as_u64().map(Duration::from_millis);
+    Ok(Box::new(WebSocket::connect(url, timeout)?))
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(
+        RemoteStrategy::new("PlayerRemoteWs", connect).with_transport_keys(
+            serde_json::json!({
+                "url": { "type": "string" },
+                "timeout_ms": { "type": "integer", "minimum": 0 },
+            }),
+            &["url"],
+        ),
+    )
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerRemoteWs", create)
+}
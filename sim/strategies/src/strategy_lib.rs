@@ -0,0 +1,57 @@
+// Strategies loaded from Rust libraries with --strategy-lib, so third-party bots written
+// against hedonica_engine can be distributed as compiled cdylibs without forking the
+// simulator. Unlike plugins.rs, the library's strategies run in process as PlayerStrategy
+// implementations, with no protocol in between. Only built with the "plugins" feature, and only
+// on unix.
+//
+// A library exports its entry points with hedonica_engine::export_strategies!, from a function
+// that adds its strategies to a player::Registry:
+//
+//   fn register(registry: &mut Registry) {
+//       registry.register("MyBot", || Box::new(MyBot::default()));
+//   }
+//   hedonica_engine::export_strategies!(register);
+//
+// Rust has no stable ABI, so the library must be built from the same version of
+// hedonica_engine, with the same compiler, as the simulator. Only the engine version is checked.
+// The library's copy of the engine keeps its own log level, so its bots log at the default
+// level. Loaded libraries stay loaded until exit.
+use crate::plugins::{dl_error, plugin_error, symbol};
+use hedonica_engine::player::{Registry, STRATEGY_LIB_VERSION};
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_void};
+use std::path::Path;
+
+type LibVersion = unsafe extern "C" fn() -> *const c_char;
+type Register = fn(&mut Registry);
+
+unsafe fn register(library: *mut c_void) -> Result<Vec<String>, String> {
+    let lib_version: LibVersion = symbol(library, "hedonica_strategy_lib_version")?;
+    let version = CStr::from_ptr(lib_version()).to_string_lossy();
+    let expected = STRATEGY_LIB_VERSION.trim_end_matches('\0');
+    if version != expected {
+        return Err(format!(
+            "built against hedonica_engine {}, not {}",
+            version, expected
+        ));
+    }
+    let register: Register = symbol(library, "hedonica_register")?;
+    let mut registry = Registry::default();
+    register(&mut registry);
+    Ok(registry.install())
+}
+
+// Loads a strategy library and registers its strategies, returning their names.
+pub fn load(path: &Path) -> io::Result<Vec<String>> {
+    let c_path = CString::new(path.to_string_lossy().as_bytes()).unwrap();
+    let library = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+    if library.is_null() {
+        return Err(plugin_error(dl_error()));
+    }
+    let registered = unsafe { register(library) };
+    if registered.is_err() {
+        unsafe { libc::dlclose(library) };
+    }
+    registered.map_err(plugin_error)
+}
@@ -0,0 +1,110 @@
+// A bot run as a child process, e.g. one written with the sdk from `sim generate-sdk`. Each
+// request in protocol.rs is written to the bot's stdin as one line of JSON, and the bot
+// answers it with one line on stdout. The bot's stderr is passed through. See remote.rs for the
+// rest of the config.
+//
+// Config:
+//   command    - The command line to run, as a list of arguments, e.g. ["python3", "bot.py"].
+//   timeout_ms - How long to wait for each response before failing. Waits forever if unset.
+use ctor::ctor;
+
+use crate::remote::{RemoteStrategy, Transport};
+use hedonica_engine::player;
+use hedonica_engine::player::PlayerStrategy;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+struct Subprocess {
+    child: Child,
+    stdin: ChildStdin,
+    // Lines from the bot's stdout, read on another thread so responses can time out.
+    lines: Receiver<io::Result<String>>,
+    timeout: Option<Duration>,
+}
+
+impl Transport for Subprocess {
+    fn exchange(&mut self, request: &Value) -> io::Result<Value> {
+        writeln!(self.stdin, "{}", request)?;
+        self.stdin.flush()?;
+        let line = match self.timeout {
+            None => self.lines.recv().map_err(|_| closed()),
+            Some(timeout) => self.lines.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no response within {} ms", timeout.as_millis()),
+                ),
+                RecvTimeoutError::Disconnected => closed(),
+            }),
+        }??;
+        serde_json::from_str(&line).map_err(io::Error::from)
+    }
+}
+
+fn closed() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "the bot closed its stdout")
+}
+
+impl Drop for Subprocess {
+    fn drop(&mut self) {
+        // The bot may already have exited.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn connect(config: &Value) -> io::Result<Box<dyn Transport>> {
+    let command: Vec<String> = serde_json::from_value(config["command"].clone())
+        .expect("PlayerSubprocess needs a command in its config, as a list of arguments");
+    let (program, args) = command
+        .split_first()
+        .expect("PlayerSubprocess needs a non-empty command");
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    let stdin = child.stdin.take().unwrap();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in stdout.lines() {
+            let line = line.map(|line| line.trim().to_string());
+            if matches!(&line, Ok(line) if line.is_empty()) {
+                continue;
+            }
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Box::new(Subprocess {
+        child,
+        stdin,
+        lines,
+        timeout: config["timeout_ms"].as_u64().map(Duration::from_millis),
+    }))
+}
+
+fn create() -> Box<dyn PlayerStrategy> {
+    Box::new(
+        RemoteStrategy::new("PlayerSubprocess", connect).with_transport_keys(
+            serde_json::json!({
+                "command": { "type": "array", "items": { "type": "string" } },
+                "timeout_ms": { "type": "integer", "minimum": 0 },
+            }),
+            &["command"],
+        ),
+    )
+}
+
+#[ctor]
+fn init() {
+    player::register_strategy("PlayerSubprocess", create)
+}